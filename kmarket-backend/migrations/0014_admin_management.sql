@@ -0,0 +1,19 @@
+-- Admin account management: roles, forced password resets, invitations
+
+ALTER TABLE admin_users ADD COLUMN IF NOT EXISTS role VARCHAR(32) NOT NULL DEFAULT 'admin';
+ALTER TABLE admin_users ADD COLUMN IF NOT EXISTS must_reset_password BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE admin_users ADD COLUMN IF NOT EXISTS password_changed_at TIMESTAMPTZ;
+ALTER TABLE admin_users ADD COLUMN IF NOT EXISTS last_login_at TIMESTAMPTZ;
+ALTER TABLE admin_users ADD COLUMN IF NOT EXISTS invited_by BIGINT REFERENCES admin_users(id) ON DELETE SET NULL;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'admin_users_set_updated_at') THEN
+        CREATE TRIGGER admin_users_set_updated_at
+        BEFORE UPDATE ON admin_users
+        FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+END$$;
+
+-- Login attempt listing reads audit_logs by action, newest first
+CREATE INDEX IF NOT EXISTS idx_audit_logs_action_created ON audit_logs(action, created_at DESC);
@@ -0,0 +1,44 @@
+-- Partner / bot API keys: scoped, HMAC-signed server-to-server access
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id BIGSERIAL PRIMARY KEY,
+    key_id VARCHAR(32) UNIQUE NOT NULL,           -- public identifier sent in X-Api-Key
+    secret VARCHAR(128) NOT NULL,                 -- HMAC signing secret; shown once at creation
+    name VARCHAR(128) NOT NULL,
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    status VARCHAR(32) NOT NULL DEFAULT 'active', -- active | revoked
+    rate_limit_per_min INTEGER NOT NULL DEFAULT 60 CHECK (rate_limit_per_min > 0),
+    request_count BIGINT NOT NULL DEFAULT 0,
+    last_used_at TIMESTAMPTZ,
+    created_by BIGINT REFERENCES admin_users(id) ON DELETE SET NULL,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'api_keys_set_updated_at') THEN
+        CREATE TRIGGER api_keys_set_updated_at
+        BEFORE UPDATE ON api_keys
+        FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+END$$;
+
+-- Daily usage counters per key
+CREATE TABLE IF NOT EXISTS api_key_usage_daily (
+    api_key_id BIGINT NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (api_key_id, day)
+);
+
+-- Nonces seen inside the signature time window (replay protection)
+CREATE TABLE IF NOT EXISTS api_key_nonces (
+    api_key_id BIGINT NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    nonce VARCHAR(64) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (api_key_id, nonce)
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_key_nonces_created ON api_key_nonces(created_at);
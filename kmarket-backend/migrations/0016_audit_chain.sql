@@ -0,0 +1,76 @@
+-- Tamper-evident audit trail: audit_logs rows are hash-chained and append-only.
+-- row_hash = sha256(prev_hash|chain_seq|actor_id|action|resource|resource_id|payload_json|created_at_micros)
+-- (mirrored by utils::audit::row_hash, which verifies the chain independently of these functions)
+
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS chain_seq BIGINT;
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS prev_hash VARCHAR(64);
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS row_hash VARCHAR(64);
+
+CREATE OR REPLACE FUNCTION audit_logs_row_hash(prev TEXT, seq BIGINT, actor BIGINT, act TEXT, res TEXT, res_id BIGINT, payload JSONB, created TIMESTAMPTZ)
+RETURNS TEXT AS $fn$
+    SELECT encode(sha256(convert_to(concat_ws('|',
+        prev, seq::text, actor::text, act, res,
+        COALESCE(res_id::text, ''), COALESCE(payload::text, ''),
+        (EXTRACT(EPOCH FROM created) * 1000000)::bigint::text), 'UTF8')), 'hex')
+$fn$ LANGUAGE sql IMMUTABLE;
+
+-- Backfill rows written before the chain existed, in id order
+DO $$
+DECLARE
+    r RECORD;
+    seq BIGINT := 0;
+    prev TEXT := '';
+BEGIN
+    IF EXISTS (SELECT 1 FROM audit_logs WHERE chain_seq IS NULL) THEN
+        SELECT COALESCE(MAX(chain_seq), 0) INTO seq FROM audit_logs;
+        SELECT COALESCE((SELECT row_hash FROM audit_logs WHERE chain_seq = seq), '') INTO prev;
+        FOR r IN SELECT * FROM audit_logs WHERE chain_seq IS NULL ORDER BY id LOOP
+            seq := seq + 1;
+            UPDATE audit_logs
+               SET chain_seq = seq,
+                   prev_hash = prev,
+                   row_hash = audit_logs_row_hash(prev, seq, r.actor_id, r.action, r.resource, r.resource_id, r.payload_json, r.created_at)
+             WHERE id = r.id
+            RETURNING row_hash INTO prev;
+        END LOOP;
+    END IF;
+END$$;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_audit_logs_chain_seq ON audit_logs(chain_seq);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_created ON audit_logs(created_at);
+
+-- Link each new row to the previous one. The advisory lock serializes writers until commit,
+-- so chain_seq (not id, which is allocated before the lock) gives the chain order.
+CREATE OR REPLACE FUNCTION audit_logs_chain() RETURNS TRIGGER AS $fn$
+DECLARE
+    last_seq BIGINT;
+    last_hash TEXT;
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtext('audit_logs_chain'));
+    SELECT chain_seq, row_hash INTO last_seq, last_hash FROM audit_logs WHERE chain_seq IS NOT NULL ORDER BY chain_seq DESC LIMIT 1;
+    NEW.chain_seq := COALESCE(last_seq, 0) + 1;
+    NEW.prev_hash := COALESCE(last_hash, '');
+    NEW.row_hash := audit_logs_row_hash(NEW.prev_hash, NEW.chain_seq, NEW.actor_id, NEW.action, NEW.resource, NEW.resource_id, NEW.payload_json, NEW.created_at);
+    RETURN NEW;
+END
+$fn$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION audit_logs_append_only() RETURNS TRIGGER AS $fn$
+BEGIN
+    RAISE EXCEPTION 'audit_logs is append-only (% rejected)', TG_OP;
+END
+$fn$ LANGUAGE plpgsql;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'audit_logs_chain_insert') THEN
+        CREATE TRIGGER audit_logs_chain_insert
+        BEFORE INSERT ON audit_logs
+        FOR EACH ROW EXECUTE PROCEDURE audit_logs_chain();
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'audit_logs_append_only') THEN
+        CREATE TRIGGER audit_logs_append_only
+        BEFORE UPDATE OR DELETE ON audit_logs
+        FOR EACH ROW EXECUTE PROCEDURE audit_logs_append_only();
+    END IF;
+END$$;
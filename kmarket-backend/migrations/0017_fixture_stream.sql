@@ -0,0 +1,2 @@
+-- Fixture SSE stream: incremental scans of markets by updated_at (Last-Event-ID resume)
+CREATE INDEX IF NOT EXISTS idx_markets_updated_at ON markets (updated_at, id);
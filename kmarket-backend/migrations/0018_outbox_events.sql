@@ -0,0 +1,19 @@
+-- Transactional outbox: domain events written in the same transaction as the change,
+-- then delivered to in-process subscribers by the outbox dispatcher (at-least-once)
+CREATE TABLE IF NOT EXISTS outbox_events (
+    id               BIGSERIAL PRIMARY KEY,
+    event_type       VARCHAR(64) NOT NULL,             -- e.g. order.placed, market.settled
+    aggregate_type   VARCHAR(32) NOT NULL,             -- orders | markets | users
+    aggregate_id     BIGINT NOT NULL,
+    payload          JSONB NOT NULL,
+    status           VARCHAR(16) NOT NULL DEFAULT 'pending',
+    attempts         INT NOT NULL DEFAULT 0,
+    next_attempt_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error       TEXT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at     TIMESTAMPTZ,
+    CONSTRAINT chk_outbox_status CHECK (status IN ('pending', 'delivered', 'failed'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_outbox_events_due ON outbox_events (next_attempt_at, id) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_outbox_events_aggregate ON outbox_events (aggregate_type, aggregate_id, id);
@@ -0,0 +1,47 @@
+-- Outbound webhooks: admin-managed subscriptions and a per-subscription delivery log
+
+CREATE TABLE IF NOT EXISTS webhook_subscriptions (
+    id BIGSERIAL PRIMARY KEY,
+    url TEXT NOT NULL,
+    event_types TEXT[] NOT NULL,                  -- market.opened | market.closed | market.settled | order.placed
+    secret VARCHAR(128) NOT NULL,                 -- HMAC signing secret; shown once at creation
+    api_key_id BIGINT REFERENCES api_keys(id) ON DELETE CASCADE, -- partner scope: order events for its referred users only
+    description VARCHAR(255),
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by BIGINT REFERENCES admin_users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'webhook_subscriptions_set_updated_at') THEN
+        CREATE TRIGGER webhook_subscriptions_set_updated_at
+        BEFORE UPDATE ON webhook_subscriptions
+        FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    subscription_id BIGINT NOT NULL REFERENCES webhook_subscriptions(id) ON DELETE CASCADE,
+    outbox_event_id BIGINT NOT NULL,              -- source event; one delivery per subscription and event
+    event_type VARCHAR(64) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_status_code INT,
+    last_error TEXT,
+    last_attempt_at TIMESTAMPTZ,
+    delivered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT chk_webhook_delivery_status CHECK (status IN ('pending', 'delivered', 'dead')),
+    CONSTRAINT uq_webhook_delivery_event UNIQUE (subscription_id, outbox_event_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries (next_attempt_at, id) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_subscription ON webhook_deliveries (subscription_id, id DESC);
+
+-- Partner that brought the user in (first order placed through that key)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS referrer_api_key_id BIGINT REFERENCES api_keys(id) ON DELETE SET NULL;
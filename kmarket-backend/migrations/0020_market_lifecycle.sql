@@ -0,0 +1,12 @@
+-- Market lifecycle scheduler: pending markets open at open_time, betting closes at close_time
+-- (state = 2), and markets past end_time without a result are flagged for admin attention
+
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS open_time TIMESTAMPTZ;         -- pending -> active at this time; NULL = opened manually
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS attention_reason VARCHAR(64);  -- e.g. unresolved_after_end
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS attention_at TIMESTAMPTZ;
+
+COMMENT ON COLUMN markets.state IS '1 = open for betting, 2 = closed for betting (close_time passed)';
+
+CREATE INDEX IF NOT EXISTS idx_markets_open_due ON markets (open_time) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_markets_close_due ON markets (close_time) WHERE status = 'active' AND state = 1;
+CREATE INDEX IF NOT EXISTS idx_markets_attention ON markets (attention_at) WHERE attention_at IS NOT NULL;
@@ -0,0 +1,46 @@
+-- Server-side live match simulation (demo flow pre -> live -> settled): the simulator advances
+-- clock, score and odds on a tick and settles the market from the final score
+
+CREATE TABLE IF NOT EXISTS match_simulations (
+    id              BIGSERIAL PRIMARY KEY,
+    market_id       BIGINT NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    sport           VARCHAR(32) NOT NULL,            -- football | basketball | american_football
+    status          VARCHAR(16) NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'settled', 'stopped')),
+    seed            BIGINT NOT NULL,                 -- RNG seed; tick n uses seed + n so a restart replays the same match
+    tick_secs       INT NOT NULL CHECK (tick_secs > 0),
+    duration_secs   INT NOT NULL CHECK (duration_secs > 0),
+    ticks           INT NOT NULL DEFAULT 0,
+    progress        DOUBLE PRECISION NOT NULL DEFAULT 0,  -- share of the match played, 0..1 (wall clock / duration)
+    period          INT NOT NULL DEFAULT 1,
+    minute          INT NOT NULL DEFAULT 0,
+    second          INT NOT NULL DEFAULT 0,
+    phase           VARCHAR(32) NOT NULL,
+    home_score      INT NOT NULL DEFAULT 0,
+    away_score      INT NOT NULL DEFAULT 0,
+    pre_odds_home_bps INT NOT NULL,                  -- market odds at kick-off; live odds drift from these
+    pre_odds_away_bps INT NOT NULL,
+    odds_home_bps   INT NOT NULL,
+    odds_away_bps   INT NOT NULL,
+    winning_option  SMALLINT,                        -- markets.winning_option applied at settlement
+    settled_orders  INT,
+    started_by      BIGINT,
+    started_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    next_tick_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at     TIMESTAMPTZ,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- at most one running simulation per market
+CREATE UNIQUE INDEX IF NOT EXISTS idx_match_simulations_running ON match_simulations (market_id) WHERE status = 'running';
+CREATE INDEX IF NOT EXISTS idx_match_simulations_due ON match_simulations (next_tick_at) WHERE status = 'running';
+CREATE INDEX IF NOT EXISTS idx_match_simulations_market ON match_simulations (market_id, id DESC);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'match_simulations_set_updated_at') THEN
+        CREATE TRIGGER match_simulations_set_updated_at
+        BEFORE UPDATE ON match_simulations
+        FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+END$$;
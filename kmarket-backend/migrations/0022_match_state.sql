@@ -0,0 +1,84 @@
+-- Live match state per fixture (clock, period, score, cards), written by admins, feeds and the
+-- match simulator. sports_fixtures_v uses it to decide live / half time / finished and exposes it
+-- as live_stats; markets without a state row keep the status-based classification.
+
+CREATE TABLE IF NOT EXISTS match_state (
+    market_id       BIGINT PRIMARY KEY REFERENCES markets(id) ON DELETE CASCADE,
+    phase           VARCHAR(16) NOT NULL DEFAULT 'pre' CHECK (phase IN ('pre', 'live', 'half_time', 'finished')),
+    period          INT NOT NULL DEFAULT 0 CHECK (period >= 0),
+    clock_seconds   INT NOT NULL DEFAULT 0 CHECK (clock_seconds >= 0),  -- match clock as displayed for the sport
+    home_score      INT NOT NULL DEFAULT 0 CHECK (home_score >= 0),
+    away_score      INT NOT NULL DEFAULT 0 CHECK (away_score >= 0),
+    home_red_cards  INT NOT NULL DEFAULT 0 CHECK (home_red_cards >= 0),
+    away_red_cards  INT NOT NULL DEFAULT 0 CHECK (away_red_cards >= 0),
+    source          VARCHAR(64) NOT NULL,                                -- admin | simulator | feed name
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'match_state_set_updated_at') THEN
+        CREATE TRIGGER match_state_set_updated_at
+        BEFORE UPDATE ON match_state
+        FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+END$$;
+
+-- Classification:
+-- - Settled/Cancelled market, or match_state finished → final
+-- - match_state live / half_time → live (liveStats.phase tells them apart)
+-- - match_state pre → pre
+-- - no match_state: Active → live, Pending → pre
+DROP VIEW IF EXISTS sports_fixtures_v;
+CREATE VIEW sports_fixtures_v AS
+SELECT
+    m.market_id,
+    (m.market_id)::TEXT AS id,
+    COALESCE(m.title, 'Fixture'::varchar) AS title,
+    CASE
+        WHEN m.title ILIKE '%NBA%' THEN 'NBA'
+        WHEN m.title ILIKE '%NFL%' THEN 'NFL'
+        WHEN m.title ILIKE '%Premier%' OR m.title ILIKE '%EPL%' THEN 'Premier League'
+        WHEN m.title ILIKE '%MLB%' THEN 'MLB'
+        WHEN m.title ILIKE '%UCL%' OR m.title ILIKE '%UEFA%' THEN 'UCL'
+        WHEN m.title ILIKE '%Tennis%' THEN 'Tennis'
+        ELSE 'Sports'
+    END AS sport,
+    CASE
+        WHEN m.title ILIKE '%EPL%' OR m.title ILIKE '%Premier%' THEN 'EPL'
+        WHEN m.title ILIKE '%UEFA%' OR m.title ILIKE '%UCL%' THEN 'UEFA Champions League'
+        ELSE NULL
+    END AS league,
+    COALESCE(NULLIF(m.home_name, ''), m.option_a, 'Home') AS home_team,
+    COALESCE(NULLIF(m.away_name, ''), m.option_b, 'Away') AS away_team,
+    m.start_time AS kickoff_time,
+    CASE
+        WHEN m.status IN ('settled', 'cancelled') THEN 'final'
+        WHEN ms.phase = 'finished' THEN 'final'
+        WHEN ms.phase IN ('live', 'half_time') THEN 'live'
+        WHEN ms.phase = 'pre' THEN 'pre'
+        WHEN m.status = 'active' THEN 'live'
+        ELSE 'pre'
+    END AS status,
+    jsonb_build_object(
+        'home', COALESCE(m.odds_home_bps, 0)::numeric / 10000.0,
+        'away', COALESCE(m.odds_away_bps, 0)::numeric / 10000.0
+    ) AS pre_odds,
+    jsonb_build_object(
+        'home', COALESCE(m.odds_home_bps, 0)::numeric / 10000.0,
+        'away', COALESCE(m.odds_away_bps, 0)::numeric / 10000.0
+    ) AS live_odds,
+    CASE WHEN ms.market_id IS NULL THEN NULL ELSE jsonb_build_object(
+        'phase', ms.phase,
+        'period', ms.period,
+        'clockSeconds', ms.clock_seconds,
+        'homeScore', ms.home_score,
+        'awayScore', ms.away_score,
+        'homeRedCards', ms.home_red_cards,
+        'awayRedCards', ms.away_red_cards,
+        'source', ms.source,
+        'updatedAt', ms.updated_at
+    ) END AS live_stats
+FROM markets m
+LEFT JOIN match_state ms ON ms.market_id = m.id
+WHERE m.status IN ('active', 'pending', 'settled', 'cancelled');
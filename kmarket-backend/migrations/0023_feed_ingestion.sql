@@ -0,0 +1,80 @@
+-- Sports feed ingestion: providers (file or HTTP), markets keyed by provider external id,
+-- provider team mappings, odds history, results and a per-run sync log
+
+CREATE TABLE IF NOT EXISTS feed_providers (
+    id               BIGSERIAL PRIMARY KEY,
+    name             VARCHAR(64) UNIQUE NOT NULL,         -- also markets.feed_provider and match_state.source
+    kind             VARCHAR(16) NOT NULL CHECK (kind IN ('file', 'http')),
+    source           TEXT NOT NULL,                       -- file name under FEED_DIR, or base URL
+    enabled          BOOLEAN NOT NULL DEFAULT TRUE,
+    interval_secs    INT NOT NULL DEFAULT 60 CHECK (interval_secs > 0),
+    last_sync_at     TIMESTAMPTZ,                         -- claimed by a sync (success or not)
+    last_success_at  TIMESTAMPTZ,
+    last_status      VARCHAR(16) NOT NULL DEFAULT 'never' CHECK (last_status IN ('never', 'ok', 'partial', 'error')),
+    last_error       TEXT,
+    created_by       BIGINT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'feed_providers_set_updated_at') THEN
+        CREATE TRIGGER feed_providers_set_updated_at
+        BEFORE UPDATE ON feed_providers
+        FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+END$$;
+
+-- Markets created or matched by a feed
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS feed_provider VARCHAR(64);
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS external_id VARCHAR(128);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_markets_feed_external ON markets (feed_provider, external_id) WHERE external_id IS NOT NULL;
+
+-- Business ids for feed-created markets, clear of hand-entered ones
+CREATE SEQUENCE IF NOT EXISTS feed_market_id_seq START WITH 10000000000;
+
+-- Provider team ids -> our display name/code; first sight inserts the feed's name, admins may correct it
+CREATE TABLE IF NOT EXISTS feed_team_mappings (
+    provider         VARCHAR(64) NOT NULL,
+    external_id      VARCHAR(128) NOT NULL,
+    name             VARCHAR(128) NOT NULL,
+    code             INT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (provider, external_id)
+);
+
+-- Every odds change applied to a market, from feeds or otherwise
+CREATE TABLE IF NOT EXISTS odds_history (
+    id               BIGSERIAL PRIMARY KEY,
+    market_id        BIGINT NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    source           VARCHAR(64) NOT NULL,
+    odds_home_bps    INT NOT NULL,
+    odds_away_bps    INT NOT NULL,
+    recorded_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_odds_history_market ON odds_history (market_id, recorded_at DESC);
+
+-- Final results reported by a provider; settlement stays an admin decision
+CREATE TABLE IF NOT EXISTS feed_results (
+    provider         VARCHAR(64) NOT NULL,
+    market_id        BIGINT NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    home_score       INT NOT NULL,
+    away_score       INT NOT NULL,
+    status           VARCHAR(16) NOT NULL CHECK (status IN ('final', 'cancelled')),
+    received_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (provider, market_id)
+);
+
+CREATE TABLE IF NOT EXISTS feed_sync_runs (
+    id               BIGSERIAL PRIMARY KEY,
+    provider_id      BIGINT NOT NULL REFERENCES feed_providers(id) ON DELETE CASCADE,
+    status           VARCHAR(16) NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'ok', 'partial', 'error')),
+    fixtures         INT NOT NULL DEFAULT 0,                -- markets created or updated
+    odds             INT NOT NULL DEFAULT 0,                -- odds changes recorded
+    results          INT NOT NULL DEFAULT 0,
+    errors           JSONB NOT NULL DEFAULT '[]'::jsonb,    -- [{stage, external_id, error}]
+    started_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at      TIMESTAMPTZ
+);
+CREATE INDEX IF NOT EXISTS idx_feed_sync_runs_provider ON feed_sync_runs (provider_id, id DESC);
@@ -0,0 +1,146 @@
+-- Sport / league / team catalog. Markets reference a league and two teams by foreign key;
+-- sports_fixtures_v reads sport and league from the catalog instead of guessing from the title.
+
+CREATE TABLE IF NOT EXISTS sports (
+    id               BIGSERIAL PRIMARY KEY,
+    code             VARCHAR(32) UNIQUE NOT NULL,          -- football, basketball, ... (simulator sport names)
+    name             VARCHAR(64) UNIQUE NOT NULL,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS leagues (
+    id               BIGSERIAL PRIMARY KEY,
+    sport_id         BIGINT NOT NULL REFERENCES sports(id),
+    code             VARCHAR(32) UNIQUE NOT NULL,          -- short code, e.g. EPL, NBA
+    name             VARCHAR(128) NOT NULL,
+    country          VARCHAR(64),
+    logo_url         TEXT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_leagues_sport ON leagues (sport_id);
+
+CREATE TABLE IF NOT EXISTS teams (
+    id               BIGSERIAL PRIMARY KEY,
+    sport_id         BIGINT NOT NULL REFERENCES sports(id),
+    name             VARCHAR(128) NOT NULL,
+    code             VARCHAR(16),                          -- short code, e.g. ARS
+    country          VARCHAR(64),
+    logo_url         TEXT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (sport_id, name)
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'sports_set_updated_at') THEN
+        CREATE TRIGGER sports_set_updated_at BEFORE UPDATE ON sports FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'leagues_set_updated_at') THEN
+        CREATE TRIGGER leagues_set_updated_at BEFORE UPDATE ON leagues FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'teams_set_updated_at') THEN
+        CREATE TRIGGER teams_set_updated_at BEFORE UPDATE ON teams FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+END$$;
+
+INSERT INTO sports (code, name) VALUES
+    ('football', 'Football'),
+    ('basketball', 'Basketball'),
+    ('american_football', 'American Football'),
+    ('baseball', 'Baseball'),
+    ('tennis', 'Tennis')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO leagues (sport_id, code, name, country)
+SELECT s.id, v.code, v.name, v.country
+FROM (VALUES
+    ('football', 'EPL', 'Premier League', 'England'),
+    ('football', 'UCL', 'UEFA Champions League', NULL),
+    ('basketball', 'NBA', 'NBA', 'USA'),
+    ('american_football', 'NFL', 'NFL', 'USA'),
+    ('baseball', 'MLB', 'MLB', 'USA'),
+    ('tennis', 'ATP', 'ATP Tour', NULL)
+) AS v (sport, code, name, country)
+JOIN sports s ON s.code = v.sport
+ON CONFLICT DO NOTHING;
+
+-- Deleting a league or team still referenced by a market is refused
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS league_id BIGINT REFERENCES leagues(id);
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS home_team_id BIGINT REFERENCES teams(id);
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS away_team_id BIGINT REFERENCES teams(id);
+CREATE INDEX IF NOT EXISTS idx_markets_league ON markets (league_id);
+CREATE INDEX IF NOT EXISTS idx_markets_home_team ON markets (home_team_id);
+CREATE INDEX IF NOT EXISTS idx_markets_away_team ON markets (away_team_id);
+
+-- One-off backfill with the rules the view used to apply on every read
+UPDATE markets m SET league_id = l.id
+FROM leagues l
+WHERE m.league_id IS NULL AND l.code = CASE
+    WHEN m.title ILIKE '%NBA%' THEN 'NBA'
+    WHEN m.title ILIKE '%NFL%' THEN 'NFL'
+    WHEN m.title ILIKE '%Premier%' OR m.title ILIKE '%EPL%' THEN 'EPL'
+    WHEN m.title ILIKE '%MLB%' THEN 'MLB'
+    WHEN m.title ILIKE '%UCL%' OR m.title ILIKE '%UEFA%' THEN 'UCL'
+    WHEN m.title ILIKE '%Tennis%' THEN 'ATP'
+END;
+
+-- Feed team ids can be pointed at catalog teams
+ALTER TABLE feed_team_mappings ADD COLUMN IF NOT EXISTS team_id BIGINT REFERENCES teams(id) ON DELETE SET NULL;
+
+-- Same classification as 0022; sport/league/teams now come from the catalog
+DROP VIEW IF EXISTS sports_fixtures_v;
+CREATE VIEW sports_fixtures_v AS
+SELECT
+    m.market_id,
+    (m.market_id)::TEXT AS id,
+    COALESCE(m.title, 'Fixture'::varchar) AS title,
+    COALESCE(s.name, 'Sports'::varchar) AS sport,
+    s.id AS sport_id,
+    s.code AS sport_code,
+    l.name AS league,
+    l.id AS league_id,
+    l.code AS league_code,
+    COALESCE(ht.name, NULLIF(m.home_name, ''), m.option_a, 'Home') AS home_team,
+    COALESCE(at.name, NULLIF(m.away_name, ''), m.option_b, 'Away') AS away_team,
+    m.home_team_id,
+    m.away_team_id,
+    ht.logo_url AS home_team_logo,
+    at.logo_url AS away_team_logo,
+    m.start_time AS kickoff_time,
+    CASE
+        WHEN m.status IN ('settled', 'cancelled') THEN 'final'
+        WHEN ms.phase = 'finished' THEN 'final'
+        WHEN ms.phase IN ('live', 'half_time') THEN 'live'
+        WHEN ms.phase = 'pre' THEN 'pre'
+        WHEN m.status = 'active' THEN 'live'
+        ELSE 'pre'
+    END AS status,
+    jsonb_build_object(
+        'home', COALESCE(m.odds_home_bps, 0)::numeric / 10000.0,
+        'away', COALESCE(m.odds_away_bps, 0)::numeric / 10000.0
+    ) AS pre_odds,
+    jsonb_build_object(
+        'home', COALESCE(m.odds_home_bps, 0)::numeric / 10000.0,
+        'away', COALESCE(m.odds_away_bps, 0)::numeric / 10000.0
+    ) AS live_odds,
+    CASE WHEN ms.market_id IS NULL THEN NULL ELSE jsonb_build_object(
+        'phase', ms.phase,
+        'period', ms.period,
+        'clockSeconds', ms.clock_seconds,
+        'homeScore', ms.home_score,
+        'awayScore', ms.away_score,
+        'homeRedCards', ms.home_red_cards,
+        'awayRedCards', ms.away_red_cards,
+        'source', ms.source,
+        'updatedAt', ms.updated_at
+    ) END AS live_stats
+FROM markets m
+LEFT JOIN leagues l ON l.id = m.league_id
+LEFT JOIN sports s ON s.id = l.sport_id
+LEFT JOIN teams ht ON ht.id = m.home_team_id
+LEFT JOIN teams at ON at.id = m.away_team_id
+LEFT JOIN match_state ms ON ms.market_id = m.id
+WHERE m.status IN ('active', 'pending', 'settled', 'cancelled');
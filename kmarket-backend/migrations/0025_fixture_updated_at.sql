@@ -0,0 +1,57 @@
+-- HTTP validators for fixtures (If-Modified-Since / Last-Modified): expose the newest updated_at
+-- of every row a fixture is built from. Same definition as 0024 plus the updated_at column.
+
+DROP VIEW IF EXISTS sports_fixtures_v;
+CREATE VIEW sports_fixtures_v AS
+SELECT
+    m.market_id,
+    (m.market_id)::TEXT AS id,
+    COALESCE(m.title, 'Fixture'::varchar) AS title,
+    COALESCE(s.name, 'Sports'::varchar) AS sport,
+    s.id AS sport_id,
+    s.code AS sport_code,
+    l.name AS league,
+    l.id AS league_id,
+    l.code AS league_code,
+    COALESCE(ht.name, NULLIF(m.home_name, ''), m.option_a, 'Home') AS home_team,
+    COALESCE(at.name, NULLIF(m.away_name, ''), m.option_b, 'Away') AS away_team,
+    m.home_team_id,
+    m.away_team_id,
+    ht.logo_url AS home_team_logo,
+    at.logo_url AS away_team_logo,
+    m.start_time AS kickoff_time,
+    CASE
+        WHEN m.status IN ('settled', 'cancelled') THEN 'final'
+        WHEN ms.phase = 'finished' THEN 'final'
+        WHEN ms.phase IN ('live', 'half_time') THEN 'live'
+        WHEN ms.phase = 'pre' THEN 'pre'
+        WHEN m.status = 'active' THEN 'live'
+        ELSE 'pre'
+    END AS status,
+    jsonb_build_object(
+        'home', COALESCE(m.odds_home_bps, 0)::numeric / 10000.0,
+        'away', COALESCE(m.odds_away_bps, 0)::numeric / 10000.0
+    ) AS pre_odds,
+    jsonb_build_object(
+        'home', COALESCE(m.odds_home_bps, 0)::numeric / 10000.0,
+        'away', COALESCE(m.odds_away_bps, 0)::numeric / 10000.0
+    ) AS live_odds,
+    CASE WHEN ms.market_id IS NULL THEN NULL ELSE jsonb_build_object(
+        'phase', ms.phase,
+        'period', ms.period,
+        'clockSeconds', ms.clock_seconds,
+        'homeScore', ms.home_score,
+        'awayScore', ms.away_score,
+        'homeRedCards', ms.home_red_cards,
+        'awayRedCards', ms.away_red_cards,
+        'source', ms.source,
+        'updatedAt', ms.updated_at
+    ) END AS live_stats,
+    GREATEST(m.updated_at, ms.updated_at, s.updated_at, l.updated_at, ht.updated_at, at.updated_at) AS updated_at
+FROM markets m
+LEFT JOIN leagues l ON l.id = m.league_id
+LEFT JOIN sports s ON s.id = l.sport_id
+LEFT JOIN teams ht ON ht.id = m.home_team_id
+LEFT JOIN teams at ON at.id = m.away_team_id
+LEFT JOIN match_state ms ON ms.market_id = m.id
+WHERE m.status IN ('active', 'pending', 'settled', 'cancelled');
@@ -0,0 +1,40 @@
+-- Exact money: payouts are kept at the full amount scale, like close_pnl, so that
+-- close_pnl = close_price - amount holds exactly (close_price was NUMERIC(18,8) and rounded).
+-- positions_v selects the column, so it is recreated around the type change.
+
+DROP VIEW IF EXISTS positions_v;
+
+ALTER TABLE orders ALTER COLUMN close_price TYPE NUMERIC(38,18);
+
+CREATE VIEW positions_v AS
+SELECT
+    o.id AS id,
+    o.user_id AS user_id,
+    o.market_id AS market_id,
+    u.address AS wallet_address,
+    m.market_address AS market_address,
+    NULL::TEXT AS bet_address,
+    o.id AS nonce,
+    CASE WHEN o.status = 'placed' THEN 'OPEN' ELSE 'CLOSE' END AS position_type,
+    CASE WHEN o.option = 0 THEN 1 ELSE 2 END AS selected_team,
+    o.amount::NUMERIC AS amount,
+    ROUND(o.odds * 10000)::INT AS multiplier_bps,
+    m.odds_home_bps AS odds_home_bps,
+    m.odds_away_bps AS odds_away_bps,
+    (o.amount * o.odds)::NUMERIC AS payout_expected,
+    CASE o.status WHEN 'placed' THEN 1 WHEN 'cancelled' THEN 4 WHEN 'settled' THEN 2 ELSE 1 END AS status,
+    FALSE AS is_claimed,
+    COALESCE(o.close_pnl, 0)::NUMERIC AS pnl,
+    0::NUMERIC AS fee_paid,
+    o.close_price,
+    o.close_pnl AS close_pnl,
+    o.created_at AS timestamp,
+    o.created_at AS created_at,
+    o.updated_at AS updated_at,
+    o.closed_at AS closed_at,
+    NULL::TEXT AS transaction_signature,
+    NULL::BIGINT AS block_slot,
+    'pending'::TEXT AS confirmation_status
+FROM orders o
+JOIN users u ON u.id = o.user_id
+JOIN markets m ON m.id = o.market_id;
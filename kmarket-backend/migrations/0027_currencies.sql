@@ -0,0 +1,124 @@
+-- Currencies. Every market is denominated in one currency and its orders inherit it; amounts stay
+-- NUMERIC(38,18) but must fit the currency's decimals. Balances, PnL and exposure limits are kept
+-- per user and currency; fx_rates (admin-set) convert currency totals for consolidated reporting.
+
+CREATE TABLE IF NOT EXISTS currencies (
+    code              VARCHAR(16) PRIMARY KEY,              -- USDC, SOL, POINTS
+    name              VARCHAR(64) NOT NULL,
+    decimals          SMALLINT NOT NULL CHECK (decimals BETWEEN 0 AND 18),
+    chain             VARCHAR(32),                          -- e.g. solana; NULL for off-chain units
+    mint_address      VARCHAR(128),                         -- SPL mint / token contract
+    max_user_exposure NUMERIC(38,18) CHECK (max_user_exposure >= 0), -- default cap on a user's open stakes; NULL = none
+    enabled           BOOLEAN NOT NULL DEFAULT TRUE,        -- disabled: no new markets in it
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (chain, mint_address)
+);
+
+CREATE TABLE IF NOT EXISTS fx_rates (
+    currency          VARCHAR(16) PRIMARY KEY REFERENCES currencies(code) ON DELETE CASCADE,
+    rate              NUMERIC(38,18) NOT NULL CHECK (rate > 0), -- value of one unit in the reporting currency (USD)
+    updated_by        BIGINT,                               -- admin_users.id
+    updated_at        TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS user_balances (
+    user_id           BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    currency          VARCHAR(16) NOT NULL REFERENCES currencies(code),
+    balance           NUMERIC(38,18) NOT NULL DEFAULT 0,
+    total_pnl         NUMERIC(38,18) NOT NULL DEFAULT 0,
+    exposure_limit    NUMERIC(38,18) CHECK (exposure_limit >= 0), -- overrides currencies.max_user_exposure
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, currency)
+);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'currencies_set_updated_at') THEN
+        CREATE TRIGGER currencies_set_updated_at BEFORE UPDATE ON currencies FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'user_balances_set_updated_at') THEN
+        CREATE TRIGGER user_balances_set_updated_at BEFORE UPDATE ON user_balances FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+END$$;
+
+INSERT INTO currencies (code, name, decimals, chain, mint_address) VALUES
+    ('USDC', 'USD Coin', 6, 'solana', 'EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v'),
+    ('SOL', 'Solana', 9, 'solana', 'So11111111111111111111111111111111111111112'),
+    ('POINTS', 'Points', 0, NULL, NULL)
+ON CONFLICT DO NOTHING;
+
+-- Existing unit-less markets and orders were USDC
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS currency VARCHAR(16) NOT NULL DEFAULT 'USDC' REFERENCES currencies(code);
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS currency VARCHAR(16);
+UPDATE orders o SET currency = m.currency FROM markets m WHERE m.id = o.market_id AND o.currency IS NULL;
+ALTER TABLE orders ALTER COLUMN currency SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_orders_user_currency_status ON orders (user_id, currency, status);
+
+-- Orders take their market's currency; the composite key keeps the two equal, so a market's
+-- currency cannot change once it has orders
+CREATE OR REPLACE FUNCTION orders_set_currency() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.currency IS NULL THEN
+        -- an unknown market still fails on the market foreign key, not on a NULL currency
+        NEW.currency := COALESCE((SELECT currency FROM markets WHERE id = NEW.market_id), 'USDC');
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'orders_set_currency') THEN
+        CREATE TRIGGER orders_set_currency BEFORE INSERT ON orders FOR EACH ROW EXECUTE PROCEDURE orders_set_currency();
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'markets_id_currency_key') THEN
+        ALTER TABLE markets ADD CONSTRAINT markets_id_currency_key UNIQUE (id, currency);
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'orders_market_currency_fkey') THEN
+        ALTER TABLE orders ADD CONSTRAINT orders_market_currency_fkey
+            FOREIGN KEY (market_id, currency) REFERENCES markets (id, currency) ON DELETE CASCADE;
+    END IF;
+END$$;
+
+-- Carry existing balances and PnL over as USDC
+INSERT INTO user_balances (user_id, currency, balance, total_pnl)
+SELECT id, 'USDC', COALESCE(balance, 0), COALESCE(total_pnl, 0)
+FROM users
+WHERE COALESCE(balance, 0) <> 0 OR COALESCE(total_pnl, 0) <> 0
+ON CONFLICT DO NOTHING;
+
+CREATE OR REPLACE VIEW positions_v AS
+SELECT
+    o.id AS id,
+    o.user_id AS user_id,
+    o.market_id AS market_id,
+    u.address AS wallet_address,
+    m.market_address AS market_address,
+    NULL::TEXT AS bet_address,
+    o.id AS nonce,
+    CASE WHEN o.status = 'placed' THEN 'OPEN' ELSE 'CLOSE' END AS position_type,
+    CASE WHEN o.option = 0 THEN 1 ELSE 2 END AS selected_team,
+    o.amount::NUMERIC AS amount,
+    ROUND(o.odds * 10000)::INT AS multiplier_bps,
+    m.odds_home_bps AS odds_home_bps,
+    m.odds_away_bps AS odds_away_bps,
+    (o.amount * o.odds)::NUMERIC AS payout_expected,
+    CASE o.status WHEN 'placed' THEN 1 WHEN 'cancelled' THEN 4 WHEN 'settled' THEN 2 ELSE 1 END AS status,
+    FALSE AS is_claimed,
+    COALESCE(o.close_pnl, 0)::NUMERIC AS pnl,
+    0::NUMERIC AS fee_paid,
+    o.close_price,
+    o.close_pnl AS close_pnl,
+    o.created_at AS timestamp,
+    o.created_at AS created_at,
+    o.updated_at AS updated_at,
+    o.closed_at AS closed_at,
+    NULL::TEXT AS transaction_signature,
+    NULL::BIGINT AS block_slot,
+    'pending'::TEXT AS confirmation_status,
+    o.currency AS currency
+FROM orders o
+JOIN users u ON u.id = o.user_id
+JOIN markets m ON m.id = o.market_id;
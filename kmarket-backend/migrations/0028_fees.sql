@@ -0,0 +1,91 @@
+-- Fees. A fee schedule charges a percentage of the stake, a percentage of the winnings and/or a
+-- flat fee; schedules are set globally, per user fee tier or per market (most specific wins).
+-- Terms are fixed on the order when it is placed; stake and flat fees are charged then or at
+-- settlement (withheld from the payout), winnings fees always at settlement. Every charge is a
+-- ledger entry and adds to orders.fee_paid.
+
+CREATE TABLE IF NOT EXISTS fee_schedules (
+    id                BIGSERIAL PRIMARY KEY,
+    scope             VARCHAR(16) NOT NULL CHECK (scope IN ('global', 'tier', 'market')),
+    market_id         BIGINT REFERENCES markets(id) ON DELETE CASCADE,
+    tier              VARCHAR(32),
+    currency          VARCHAR(16) REFERENCES currencies(code) ON DELETE CASCADE, -- NULL: orders in any currency
+    stake_rate        NUMERIC(10,8) NOT NULL DEFAULT 0 CHECK (stake_rate BETWEEN 0 AND 1),
+    winnings_rate     NUMERIC(10,8) NOT NULL DEFAULT 0 CHECK (winnings_rate BETWEEN 0 AND 1),
+    flat_fee          NUMERIC(38,18) NOT NULL DEFAULT 0 CHECK (flat_fee >= 0),
+    charge_at         VARCHAR(16) NOT NULL DEFAULT 'placement' CHECK (charge_at IN ('placement', 'settlement')),
+    enabled           BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((scope = 'market') = (market_id IS NOT NULL)),
+    CHECK ((scope = 'tier') = (tier IS NOT NULL)),
+    CHECK (flat_fee = 0 OR currency IS NOT NULL)             -- a flat fee is an amount of one currency
+);
+
+-- one schedule per scope, target and currency
+CREATE UNIQUE INDEX IF NOT EXISTS uq_fee_schedules_target
+    ON fee_schedules (scope, COALESCE(market_id, 0), COALESCE(tier, ''), COALESCE(currency, ''));
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'fee_schedules_set_updated_at') THEN
+        CREATE TRIGGER fee_schedules_set_updated_at BEFORE UPDATE ON fee_schedules FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+    END IF;
+END$$;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS fee_tier VARCHAR(32);
+
+-- Fee terms and charges on the order
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS fee_schedule_id BIGINT REFERENCES fee_schedules(id) ON DELETE SET NULL;
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS fee_paid NUMERIC(38,18) NOT NULL DEFAULT 0;
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS fee_due NUMERIC(38,18) NOT NULL DEFAULT 0;          -- stake/flat fee deferred to settlement
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS fee_winnings_rate NUMERIC(10,8) NOT NULL DEFAULT 0;
+
+-- Money movements per user and currency; amount is signed from the user's side (fees negative)
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    id                BIGSERIAL PRIMARY KEY,
+    user_id           BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    order_id          BIGINT REFERENCES orders(id) ON DELETE SET NULL,          -- orders.id
+    currency          VARCHAR(16) NOT NULL REFERENCES currencies(code),
+    kind              VARCHAR(32) NOT NULL,                                   -- fee
+    amount            NUMERIC(38,18) NOT NULL,
+    detail            JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_user_created ON ledger_entries (user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_order ON ledger_entries (order_id);
+
+CREATE OR REPLACE VIEW positions_v AS
+SELECT
+    o.id AS id,
+    o.user_id AS user_id,
+    o.market_id AS market_id,
+    u.address AS wallet_address,
+    m.market_address AS market_address,
+    NULL::TEXT AS bet_address,
+    o.id AS nonce,
+    CASE WHEN o.status = 'placed' THEN 'OPEN' ELSE 'CLOSE' END AS position_type,
+    CASE WHEN o.option = 0 THEN 1 ELSE 2 END AS selected_team,
+    o.amount::NUMERIC AS amount,
+    ROUND(o.odds * 10000)::INT AS multiplier_bps,
+    m.odds_home_bps AS odds_home_bps,
+    m.odds_away_bps AS odds_away_bps,
+    (o.amount * o.odds)::NUMERIC AS payout_expected,
+    CASE o.status WHEN 'placed' THEN 1 WHEN 'cancelled' THEN 4 WHEN 'settled' THEN 2 ELSE 1 END AS status,
+    FALSE AS is_claimed,
+    COALESCE(o.close_pnl, 0)::NUMERIC AS pnl,
+    o.fee_paid::NUMERIC AS fee_paid,
+    o.close_price,
+    o.close_pnl AS close_pnl,
+    o.created_at AS timestamp,
+    o.created_at AS created_at,
+    o.updated_at AS updated_at,
+    o.closed_at AS closed_at,
+    NULL::TEXT AS transaction_signature,
+    NULL::BIGINT AS block_slot,
+    'pending'::TEXT AS confirmation_status,
+    o.currency AS currency
+FROM orders o
+JOIN users u ON u.id = o.user_id
+JOIN markets m ON m.id = o.market_id;
@@ -0,0 +1,46 @@
+-- Payout claims. A settled order with a payout (close_price > 0) is claimable once: claiming
+-- credits the user's balance in the order's currency, or records the on-chain payout transaction.
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS claimed_at TIMESTAMPTZ;
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS claim_tx_signature VARCHAR(128);  -- on-chain payout; NULL when credited to the balance
+
+-- one payout transaction pays one claim
+CREATE UNIQUE INDEX IF NOT EXISTS uq_orders_claim_tx_signature ON orders (claim_tx_signature) WHERE claim_tx_signature IS NOT NULL;
+
+-- unclaimed liabilities
+CREATE INDEX IF NOT EXISTS idx_orders_unclaimed ON orders (currency, closed_at)
+    WHERE status = 'settled' AND claimed_at IS NULL AND close_price > 0;
+
+CREATE OR REPLACE VIEW positions_v AS
+SELECT
+    o.id AS id,
+    o.user_id AS user_id,
+    o.market_id AS market_id,
+    u.address AS wallet_address,
+    m.market_address AS market_address,
+    NULL::TEXT AS bet_address,
+    o.id AS nonce,
+    CASE WHEN o.status = 'placed' THEN 'OPEN' ELSE 'CLOSE' END AS position_type,
+    CASE WHEN o.option = 0 THEN 1 ELSE 2 END AS selected_team,
+    o.amount::NUMERIC AS amount,
+    ROUND(o.odds * 10000)::INT AS multiplier_bps,
+    m.odds_home_bps AS odds_home_bps,
+    m.odds_away_bps AS odds_away_bps,
+    (o.amount * o.odds)::NUMERIC AS payout_expected,
+    CASE o.status WHEN 'placed' THEN 1 WHEN 'cancelled' THEN 4 WHEN 'settled' THEN 2 ELSE 1 END AS status,
+    o.claimed_at IS NOT NULL AS is_claimed,
+    COALESCE(o.close_pnl, 0)::NUMERIC AS pnl,
+    o.fee_paid::NUMERIC AS fee_paid,
+    o.close_price,
+    o.close_pnl AS close_pnl,
+    o.created_at AS timestamp,
+    o.created_at AS created_at,
+    o.updated_at AS updated_at,
+    o.closed_at AS closed_at,
+    NULL::TEXT AS transaction_signature,
+    NULL::BIGINT AS block_slot,
+    'pending'::TEXT AS confirmation_status,
+    o.currency AS currency
+FROM orders o
+JOIN users u ON u.id = o.user_id
+JOIN markets m ON m.id = o.market_id;
@@ -0,0 +1,21 @@
+-- Idempotency-Key support for writes: the first request with a key records a fingerprint of
+-- itself and, once handled, its response; a retry with the same key and fingerprint gets that
+-- response back instead of running again.
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    id BIGSERIAL PRIMARY KEY,
+    scope VARCHAR(64) NOT NULL,                   -- whose key: 'admin:{id}' or 'public'
+    idempotency_key VARCHAR(255) NOT NULL,
+    method VARCHAR(16) NOT NULL,
+    path TEXT NOT NULL,
+    request_hash CHAR(64) NOT NULL,               -- sha256 of method, path, query and body
+    response_status SMALLINT,                     -- NULL while the first request is in flight
+    response_content_type VARCHAR(255),
+    response_body BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS uq_idempotency_keys_scope_key ON idempotency_keys (scope, idempotency_key);
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_expires_at ON idempotency_keys (expires_at);
@@ -0,0 +1,47 @@
+-- A fee charged at placement comes out of the stake: only the rest is at risk, so a winning
+-- payout is (amount - fee_at_placement) * odds. fee_paid also grows with settlement fees, so the
+-- placement part is kept on its own.
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS fee_at_placement NUMERIC(38,18) NOT NULL DEFAULT 0;
+
+UPDATE orders o SET fee_at_placement = l.fee
+FROM (
+    SELECT order_id, -SUM(amount) AS fee FROM ledger_entries
+    WHERE kind = 'fee' AND detail->>'charged_at' = 'placement' AND order_id IS NOT NULL
+    GROUP BY order_id
+) l
+WHERE o.id = l.order_id AND o.fee_at_placement = 0;
+
+CREATE OR REPLACE VIEW positions_v AS
+SELECT
+    o.id AS id,
+    o.user_id AS user_id,
+    o.market_id AS market_id,
+    u.address AS wallet_address,
+    m.market_address AS market_address,
+    NULL::TEXT AS bet_address,
+    o.id AS nonce,
+    CASE WHEN o.status = 'placed' THEN 'OPEN' ELSE 'CLOSE' END AS position_type,
+    CASE WHEN o.option = 0 THEN 1 ELSE 2 END AS selected_team,
+    o.amount::NUMERIC AS amount,
+    ROUND(o.odds * 10000)::INT AS multiplier_bps,
+    m.odds_home_bps AS odds_home_bps,
+    m.odds_away_bps AS odds_away_bps,
+    ((o.amount - o.fee_at_placement) * o.odds)::NUMERIC AS payout_expected,
+    CASE o.status WHEN 'placed' THEN 1 WHEN 'cancelled' THEN 4 WHEN 'settled' THEN 2 ELSE 1 END AS status,
+    o.claimed_at IS NOT NULL AS is_claimed,
+    COALESCE(o.close_pnl, 0)::NUMERIC AS pnl,
+    o.fee_paid::NUMERIC AS fee_paid,
+    o.close_price,
+    o.close_pnl AS close_pnl,
+    o.created_at AS timestamp,
+    o.created_at AS created_at,
+    o.updated_at AS updated_at,
+    o.closed_at AS closed_at,
+    NULL::TEXT AS transaction_signature,
+    NULL::BIGINT AS block_slot,
+    'pending'::TEXT AS confirmation_status,
+    o.currency AS currency
+FROM orders o
+JOIN users u ON u.id = o.user_id
+JOIN markets m ON m.id = o.market_id;
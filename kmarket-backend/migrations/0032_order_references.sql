@@ -0,0 +1,25 @@
+-- Server-allocated order ids and partner order references. Partner (and compat) orders used to
+-- take a hash of their external reference as orders.order_id, which could collide with another
+-- order's id; the server now allocates the id and stores the reference in its own unique column.
+
+CREATE SEQUENCE IF NOT EXISTS order_id_seq;
+
+-- The next free orders.order_id. Clients of POST /orders still choose theirs, so taken ids are skipped.
+CREATE OR REPLACE FUNCTION next_order_id() RETURNS BIGINT AS $fn$
+DECLARE
+    candidate BIGINT;
+BEGIN
+    LOOP
+        candidate := nextval('order_id_seq');
+        EXIT WHEN NOT EXISTS (SELECT 1 FROM orders WHERE order_id = candidate);
+    END LOOP;
+    RETURN candidate;
+END
+$fn$ LANGUAGE plpgsql;
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS api_key_id BIGINT REFERENCES api_keys(id) ON DELETE SET NULL;  -- partner key that placed it
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS client_order_id VARCHAR(64);                                   -- the partner's reference
+
+-- a partner reference names one order per key
+CREATE UNIQUE INDEX IF NOT EXISTS uq_orders_api_key_client_order_id ON orders (api_key_id, client_order_id)
+    WHERE client_order_id IS NOT NULL;
@@ -0,0 +1,74 @@
+-- order_audits gets the same tamper evidence as audit_logs (0016): rows are hash-chained and
+-- append-only, with a chain of their own.
+-- row_hash = sha256(prev_hash|chain_seq|order_id|action|detail|created_at_micros)
+-- (mirrored by utils::audit::order_audit_row_hash)
+
+ALTER TABLE order_audits ADD COLUMN IF NOT EXISTS chain_seq BIGINT;
+ALTER TABLE order_audits ADD COLUMN IF NOT EXISTS prev_hash VARCHAR(64);
+ALTER TABLE order_audits ADD COLUMN IF NOT EXISTS row_hash VARCHAR(64);
+
+CREATE OR REPLACE FUNCTION order_audits_row_hash(prev TEXT, seq BIGINT, ord BIGINT, act TEXT, detail JSONB, created TIMESTAMPTZ)
+RETURNS TEXT AS $fn$
+    SELECT encode(sha256(convert_to(concat_ws('|',
+        prev, seq::text, ord::text, act, COALESCE(detail::text, ''),
+        (EXTRACT(EPOCH FROM created) * 1000000)::bigint::text), 'UTF8')), 'hex')
+$fn$ LANGUAGE sql IMMUTABLE;
+
+-- Backfill existing rows, in id order
+DO $$
+DECLARE
+    r RECORD;
+    seq BIGINT := 0;
+    prev TEXT := '';
+BEGIN
+    IF EXISTS (SELECT 1 FROM order_audits WHERE chain_seq IS NULL) THEN
+        SELECT COALESCE(MAX(chain_seq), 0) INTO seq FROM order_audits;
+        SELECT COALESCE((SELECT row_hash FROM order_audits WHERE chain_seq = seq), '') INTO prev;
+        FOR r IN SELECT * FROM order_audits WHERE chain_seq IS NULL ORDER BY id LOOP
+            seq := seq + 1;
+            UPDATE order_audits
+               SET chain_seq = seq,
+                   prev_hash = prev,
+                   row_hash = order_audits_row_hash(prev, seq, r.order_id, r.action, r.detail, r.created_at)
+             WHERE id = r.id
+            RETURNING row_hash INTO prev;
+        END LOOP;
+    END IF;
+END$$;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_order_audits_chain_seq ON order_audits(chain_seq);
+
+CREATE OR REPLACE FUNCTION order_audits_chain() RETURNS TRIGGER AS $fn$
+DECLARE
+    last_seq BIGINT;
+    last_hash TEXT;
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtext('order_audits_chain'));
+    SELECT chain_seq, row_hash INTO last_seq, last_hash FROM order_audits WHERE chain_seq IS NOT NULL ORDER BY chain_seq DESC LIMIT 1;
+    NEW.chain_seq := COALESCE(last_seq, 0) + 1;
+    NEW.prev_hash := COALESCE(last_hash, '');
+    NEW.row_hash := order_audits_row_hash(NEW.prev_hash, NEW.chain_seq, NEW.order_id, NEW.action, NEW.detail, NEW.created_at);
+    RETURN NEW;
+END
+$fn$ LANGUAGE plpgsql;
+
+-- Shared by both audit tables
+CREATE OR REPLACE FUNCTION audit_logs_append_only() RETURNS TRIGGER AS $fn$
+BEGIN
+    RAISE EXCEPTION '% is append-only (% rejected)', TG_TABLE_NAME, TG_OP;
+END
+$fn$ LANGUAGE plpgsql;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'order_audits_chain_insert') THEN
+        CREATE TRIGGER order_audits_chain_insert
+        BEFORE INSERT ON order_audits
+        FOR EACH ROW EXECUTE PROCEDURE order_audits_chain();
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'order_audits_append_only') THEN
+        CREATE TRIGGER order_audits_append_only
+        BEFORE UPDATE OR DELETE ON order_audits
+        FOR EACH ROW EXECUTE PROCEDURE audit_logs_append_only();
+    END IF;
+END$$;
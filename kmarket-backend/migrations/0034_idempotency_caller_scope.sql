@@ -0,0 +1,7 @@
+-- Public idempotency keys are scoped per caller ('wallet:{address}' or 'ip:{addr}') instead of
+-- one shared 'public' space, so one client's key cannot replay another client's response.
+
+ALTER TABLE idempotency_keys ALTER COLUMN scope TYPE VARCHAR(255);
+
+-- keys written under the shared scope can no longer be matched to a caller
+DELETE FROM idempotency_keys WHERE scope = 'public';
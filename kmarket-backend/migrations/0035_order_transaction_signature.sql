@@ -0,0 +1,8 @@
+-- Compat orders carried their on-chain transaction as a hash folded into orders.order_id; the
+-- signature now has its own unique column and the order id comes from next_order_id().
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS transaction_signature VARCHAR(128);  -- placement transaction (compat)
+
+-- a placement transaction pays for one order
+CREATE UNIQUE INDEX IF NOT EXISTS uq_orders_transaction_signature ON orders (transaction_signature)
+    WHERE transaction_signature IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Outbox delivery is tracked per subscriber: a retry goes only to the subscribers that have
+-- not handled the event yet, instead of redelivering to all of them.
+ALTER TABLE outbox_events ADD COLUMN IF NOT EXISTS delivered_to TEXT[] NOT NULL DEFAULT '{}';  -- EventSubscriber::name()s done
@@ -1,4 +1,5 @@
 pub mod state;
 pub mod models;
 pub mod repository;
-pub mod utils;
\ No newline at end of file
+pub mod routes;
+pub mod utils;
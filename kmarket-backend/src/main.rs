@@ -1,13 +1,18 @@
 use actix_web::{App, HttpServer, web, middleware};
 use actix_files::Files;
 use actix_cors::Cors;
-use tracing_subscriber;
 
-mod state;
-mod routes;
-mod repository;
-mod models;
-mod utils;
+use std::sync::Arc;
+use std::time::Duration;
+
+use kmarket_backend::{routes, state, utils::{idempotency, rate_limit, request_id}};
+use kmarket_backend::utils::outbox::OutboxDispatcher;
+use kmarket_backend::utils::cache::CacheSubscriber;
+use kmarket_backend::utils::realtime::RealtimeSubscriber;
+use kmarket_backend::utils::feeds::FeedIngestor;
+use kmarket_backend::utils::scheduler::MarketScheduler;
+use kmarket_backend::utils::simulator::MatchSimulator;
+use kmarket_backend::utils::webhooks::{WebhookSubscriber, WebhookWorker};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -24,6 +29,32 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Failed to initialize AppState");
 
+    // Receive pushes raised on any instance (REALTIME_FANOUT=postgres)
+    app_state.realtime.spawn_listener();
+
+    // Deliver committed domain events (outbox_events) to in-process subscribers
+    let outbox_poll_ms = std::env::var("OUTBOX_POLL_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(500);
+    OutboxDispatcher::new(app_state.db_pool.clone())
+        .subscribe(Arc::new(RealtimeSubscriber::new(app_state.realtime.clone(), app_state.db_pool.clone())))
+        .subscribe(Arc::new(WebhookSubscriber::new(app_state.db_pool.clone())))
+        .subscribe(Arc::new(CacheSubscriber::new(app_state.cache.clone())))
+        .spawn(Duration::from_millis(outbox_poll_ms));
+    // Send queued webhook deliveries (retries with backoff, dead-letters after MAX_ATTEMPTS)
+    WebhookWorker::new(app_state.db_pool.clone()).spawn(Duration::from_secs(1));
+
+    // Open / close / flag markets by their schedule (0 disables, e.g. on read-only replicas)
+    let scheduler_secs = std::env::var("MARKET_SCHEDULER_INTERVAL_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(15);
+    if scheduler_secs > 0 {
+        MarketScheduler::new(app_state.db_pool.clone()).spawn(Duration::from_secs(scheduler_secs));
+    }
+    // Advance simulated matches (admin-started demo flow) and settle them at full time
+    MatchSimulator::new(app_state.db_pool.clone(), app_state.realtime.clone()).spawn(Duration::from_secs(1));
+    // Sync sports feed providers whose interval has elapsed (0 disables)
+    let feed_secs = std::env::var("FEED_SYNC_INTERVAL_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(30);
+    if feed_secs > 0 {
+        FeedIngestor::new(app_state.db_pool.clone()).spawn(Duration::from_secs(feed_secs));
+    }
+
     let server_addr = std::env::var("SERVER_ADDR")
         .unwrap_or_else(|_| "0.0.0.0:8080".to_string());
     tracing::info!("Server listening on http://{}", server_addr);
@@ -45,6 +76,7 @@ async fn main() -> std::io::Result<()> {
             .wrap(cors)
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
+            .wrap(middleware::from_fn(request_id::assign))
             // 静态壁纸服务：直接映射文件系统目录到 /api/wallpaper
             .service(
                 Files::new("/api/wallpaper", wallpaper_dir.clone())
@@ -53,6 +85,8 @@ async fn main() -> std::io::Result<()> {
             )
             .service(
                 web::scope("/api/v1")
+                    .wrap(middleware::from_fn(idempotency::handle))
+                    .wrap(middleware::from_fn(rate_limit::limit_public_reads))
                     .route("/markets", web::get().to(routes::markets::get_markets))
                     .route("/markets", web::post().to(routes::markets::create_market))
                     .route("/markets/{id}", web::get().to(routes::markets::get_market_detail))
@@ -70,18 +104,39 @@ async fn main() -> std::io::Result<()> {
                     .route("/users/{id}", web::put().to(routes::users::update_user_email))
                     .route("/users/{id}", web::delete().to(routes::users::delete_user))
                     .route("/sports/fixtures", web::get().to(routes::sports::get_fixtures))
+                    .route("/sports/fixtures/stream", web::get().to(routes::sports::stream_fixtures))
                     .route("/admin/auth/login", web::post().to(routes::admin_auth::login))
+                    .route("/admin/auth/mfa/verify", web::post().to(routes::admin_auth::verify_mfa))
+                    .route("/admin/auth/mfa/enroll", web::post().to(routes::admin_auth::enroll_mfa))
+                    .route("/admin/auth/mfa/activate", web::post().to(routes::admin_auth::activate_mfa))
+                    .route("/admin/auth/mfa/recovery-codes", web::post().to(routes::admin_auth::regenerate_recovery_codes))
+                    .route("/admin/auth/mfa/disable", web::post().to(routes::admin_auth::disable_mfa))
+                    // Admin accounts
+                    .route("/admin/admins", web::get().to(routes::admin_admins::list_admins))
+                    .route("/admin/admins", web::post().to(routes::admin_admins::invite_admin))
+                    .route("/admin/admins/login-attempts", web::get().to(routes::admin_admins::list_login_attempts))
+                    .route("/admin/admins/me/password", web::post().to(routes::admin_admins::change_own_password))
+                    .route("/admin/admins/{id}", web::get().to(routes::admin_admins::get_admin))
+                    .route("/admin/admins/{id}/role", web::put().to(routes::admin_admins::set_admin_role))
+                    .route("/admin/admins/{id}/status", web::put().to(routes::admin_admins::set_admin_status))
+                    .route("/admin/admins/{id}/reset-password", web::post().to(routes::admin_admins::force_password_reset))
                     // Admin markets
                     .route("/admin/markets", web::get().to(routes::admin_markets::list_markets))
                     .route("/admin/markets", web::post().to(routes::admin_markets::create_market))
                     .route("/admin/markets/{id}", web::put().to(routes::admin_markets::update_market))
                     .route("/admin/markets/{id}/deactivate", web::post().to(routes::admin_markets::deactivate_market))
                     .route("/admin/markets/{id}/settle", web::post().to(routes::admin_markets::settle_market))
+                    .route("/admin/markets/{id}/match-state", web::put().to(routes::admin_markets::update_match_state))
+                    .route("/admin/markets/{id}/simulation", web::get().to(routes::admin_simulations::get_simulation))
+                    .route("/admin/markets/{id}/simulation", web::post().to(routes::admin_simulations::start_simulation))
+                    .route("/admin/markets/{id}/simulation/stop", web::post().to(routes::admin_simulations::stop_simulation))
                     // Admin orders
                     .route("/admin/orders", web::get().to(routes::admin_orders::list_orders))
                     .route("/admin/orders/{id}", web::get().to(routes::admin_orders::get_order_detail))
                     .route("/admin/orders/{id}/cancel", web::post().to(routes::admin_orders::cancel_order))
                     .route("/admin/orders/{id}/settle", web::post().to(routes::admin_orders::settle_order))
+                    .route("/admin/orders/{id}/claim", web::post().to(routes::admin_orders::claim_order))
+                    .route("/admin/claims/unclaimed", web::get().to(routes::admin_orders::list_unclaimed))
                     // Admin users
                     .route("/admin/users", web::get().to(routes::admin_users::list_users))
                     .route("/admin/users/{id}", web::get().to(routes::admin_users::get_user_detail))
@@ -89,11 +144,71 @@ async fn main() -> std::io::Result<()> {
                     .route("/admin/users/{id}/blacklist", web::post().to(routes::admin_users::set_blacklist))
                     .route("/admin/users/{id}/whitelist", web::post().to(routes::admin_users::set_whitelist))
                     .route("/admin/users/{id}/stats", web::get().to(routes::admin_users::get_user_stats))
+                    .route("/admin/users/{id}/fee-tier", web::put().to(routes::admin_users::set_fee_tier))
+                    .route("/admin/users/{id}/balances", web::get().to(routes::admin_users::get_user_balances))
+                    .route("/admin/users/{id}/balances/{currency}/exposure-limit", web::put().to(routes::admin_users::set_exposure_limit))
                     // Admin carousel
                     .route("/admin/carousel", web::get().to(routes::admin_carousel::list_items))
                     .route("/admin/carousel", web::post().to(routes::admin_carousel::create_item))
                     .route("/admin/carousel/{id}", web::put().to(routes::admin_carousel::update_item))
                     .route("/admin/carousel/{id}", web::delete().to(routes::admin_carousel::delete_item))
+                    // Audit trail
+                    .route("/admin/audit-logs", web::get().to(routes::admin_audit::list_audit_logs))
+                    .route("/admin/audit-logs/verify", web::get().to(routes::admin_audit::verify_audit_chain))
+                    .route("/admin/order-audits", web::get().to(routes::admin_audit::list_order_audits))
+                    .route("/admin/order-audits/verify", web::get().to(routes::admin_audit::verify_order_audit_chain))
+                    // Partner API keys
+                    .route("/admin/api-keys", web::get().to(routes::admin_api_keys::list_keys))
+                    .route("/admin/api-keys", web::post().to(routes::admin_api_keys::create_key))
+                    .route("/admin/api-keys/{id}/revoke", web::post().to(routes::admin_api_keys::revoke_key))
+                    .route("/admin/api-keys/{id}/usage", web::get().to(routes::admin_api_keys::get_key_usage))
+                    // Outbound webhooks
+                    .route("/admin/webhooks", web::get().to(routes::admin_webhooks::list_subscriptions))
+                    .route("/admin/webhooks", web::post().to(routes::admin_webhooks::create_subscription))
+                    .route("/admin/webhooks/{id}", web::put().to(routes::admin_webhooks::update_subscription))
+                    .route("/admin/webhooks/{id}", web::delete().to(routes::admin_webhooks::delete_subscription))
+                    .route("/admin/webhooks/{id}/deliveries", web::get().to(routes::admin_webhooks::list_deliveries))
+                    .route("/admin/webhooks/{id}/deliveries/{delivery_id}/retry", web::post().to(routes::admin_webhooks::retry_delivery))
+                    // Sport / league / team catalog
+                    .route("/admin/sports", web::get().to(routes::admin_catalog::list_sports))
+                    .route("/admin/sports", web::post().to(routes::admin_catalog::create_sport))
+                    .route("/admin/sports/{id}", web::put().to(routes::admin_catalog::update_sport))
+                    .route("/admin/sports/{id}", web::delete().to(routes::admin_catalog::delete_sport))
+                    .route("/admin/leagues", web::get().to(routes::admin_catalog::list_leagues))
+                    .route("/admin/leagues", web::post().to(routes::admin_catalog::create_league))
+                    .route("/admin/leagues/{id}", web::put().to(routes::admin_catalog::update_league))
+                    .route("/admin/leagues/{id}", web::delete().to(routes::admin_catalog::delete_league))
+                    .route("/admin/teams", web::get().to(routes::admin_catalog::list_teams))
+                    .route("/admin/teams", web::post().to(routes::admin_catalog::create_team))
+                    .route("/admin/teams/{id}", web::put().to(routes::admin_catalog::update_team))
+                    .route("/admin/teams/{id}", web::delete().to(routes::admin_catalog::delete_team))
+                    // Currencies, FX rates and per-currency reporting
+                    .route("/admin/currencies", web::get().to(routes::admin_currencies::list_currencies))
+                    .route("/admin/currencies", web::post().to(routes::admin_currencies::create_currency))
+                    .route("/admin/currencies/{code}", web::put().to(routes::admin_currencies::update_currency))
+                    .route("/admin/fx-rates", web::get().to(routes::admin_currencies::list_fx_rates))
+                    .route("/admin/fx-rates/{code}", web::put().to(routes::admin_currencies::set_fx_rate))
+                    .route("/admin/fx-rates/{code}", web::delete().to(routes::admin_currencies::delete_fx_rate))
+                    .route("/admin/reports/currencies", web::get().to(routes::admin_currencies::currency_report))
+                    // Fee schedules and the ledger
+                    .route("/admin/fees", web::get().to(routes::admin_fees::list_fee_schedules))
+                    .route("/admin/fees", web::post().to(routes::admin_fees::create_fee_schedule))
+                    .route("/admin/fees/{id}", web::put().to(routes::admin_fees::update_fee_schedule))
+                    .route("/admin/fees/{id}", web::delete().to(routes::admin_fees::delete_fee_schedule))
+                    .route("/admin/ledger", web::get().to(routes::admin_fees::list_ledger))
+                    // Sports feeds
+                    .route("/admin/feeds", web::get().to(routes::admin_feeds::list_providers))
+                    .route("/admin/feeds", web::post().to(routes::admin_feeds::create_provider))
+                    .route("/admin/feeds/{id}", web::put().to(routes::admin_feeds::update_provider))
+                    .route("/admin/feeds/{id}/sync", web::post().to(routes::admin_feeds::sync_provider))
+                    .route("/admin/feeds/{id}/runs", web::get().to(routes::admin_feeds::list_runs))
+                    // Partner server-to-server API (HMAC-signed, see utils::api_keys)
+                    .service(
+                        web::scope("/partner")
+                            .route("/markets", web::get().to(routes::partner::list_markets))
+                            .route("/orders", web::post().to(routes::partner::place_order))
+                            .route("/reports/summary", web::get().to(routes::partner::report_summary))
+                    )
                     // 兼容输出：前端 database.ts 对齐结构
                     .service(
                         web::scope("/compat")
@@ -101,9 +216,12 @@ async fn main() -> std::io::Result<()> {
                             .route("/users/{address}/positions", web::get().to(routes::compat::get_frontend_positions))
                             .route("/positions", web::post().to(routes::compat::create_frontend_position))
                             .route("/positions/close", web::post().to(routes::compat::close_frontend_position))
+                            .route("/positions/{id}/claim", web::post().to(routes::compat::claim_frontend_position))
                     )
             )
             .route("/health", web::get().to(routes::health::health_check))
+            // Realtime push (market:{id}, fixtures:live)
+            .route("/ws", web::get().to(routes::ws::connect))
     })
     .bind(&server_addr)?
     .run()
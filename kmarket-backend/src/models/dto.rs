@@ -1,7 +1,8 @@
+use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-// Frontend-aligned Market DTO (database.ts)
+// Frontend-aligned Market DTO (database.ts); money fields are exact decimals serialized as strings
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FrontendMarket {
     pub id: i64,
@@ -17,16 +18,18 @@ pub struct FrontendMarket {
     pub result: i32,
     pub odds_home_bps: Option<i32>,
     pub odds_away_bps: Option<i32>,
-    pub max_exposure: f64,
-    pub current_exposure: f64,
-    pub total_volume: f64,
+    pub max_exposure: BigDecimal,
+    pub current_exposure: BigDecimal,
+    pub total_volume: BigDecimal,
     pub total_bets: i64,
+    /// Currency code of all amounts, e.g. USDC
+    pub currency: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub resolved_at: Option<DateTime<Utc>>,
 }
 
-// Frontend-aligned Position DTO (database.ts)
+// Frontend-aligned Position DTO (database.ts); money fields are exact decimals serialized as strings
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FrontendPosition {
     pub id: i64,
@@ -38,17 +41,19 @@ pub struct FrontendPosition {
     pub nonce: i64,
     pub position_type: String, // 'OPEN' | 'CLOSE'
     pub selected_team: i32,    // 1=Home, 2=Away
-    pub amount: f64,           // numeric as number
+    pub amount: BigDecimal,
+    /// Currency code of the money fields, e.g. USDC
+    pub currency: String,
     pub multiplier_bps: i32,
     pub odds_home_bps: Option<i32>,
     pub odds_away_bps: Option<i32>,
     pub payout_expected: Option<String>,
     pub status: i32,
     pub is_claimed: bool,
-    pub pnl: f64,
-    pub fee_paid: f64,
-    pub close_price: Option<f64>,
-    pub close_pnl: Option<f64>,
+    pub pnl: BigDecimal,
+    pub fee_paid: BigDecimal,
+    pub close_price: Option<BigDecimal>,
+    pub close_pnl: Option<BigDecimal>,
     pub timestamp: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+/// State changes other parts of the system react to (realtime push, cache invalidation, webhooks).
+/// Ids are primary keys: `id` of the aggregate itself, `market_id`/`user_id` as stored on orders.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    OrderPlaced { id: i64, market_id: i64, user_id: i64 },
+    OrderCancelled { id: i64, market_id: i64, user_id: i64 },
+    OrderSettled { id: i64, market_id: i64, user_id: i64 },
+    /// The payout of a settled order was claimed
+    OrderClaimed { id: i64, market_id: i64, user_id: i64 },
+    OrderDeleted { id: i64 },
+    MarketCreated { id: i64 },
+    MarketUpdated { id: i64, fields: Vec<String> },
+    MarketStatusChanged { id: i64, status: String },
+    MarketSettled { id: i64, winning_option: i16 },
+    /// Betting closed at close_time (markets.state = closed); status is unchanged
+    MarketClosed { id: i64 },
+    MarketDeleted { id: i64 },
+    /// Live clock/score/phase of the market's fixture changed (match_state)
+    MatchStateChanged { id: i64, phase: String },
+    UserStatusChanged { id: i64, status: String },
+    UserBlacklisted { id: i64, blacklisted: bool },
+    UserWhitelisted { id: i64, whitelisted: bool },
+}
+
+impl DomainEvent {
+    /// Stable dotted name stored in outbox_events.event_type
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            DomainEvent::OrderPlaced { .. } => "order.placed",
+            DomainEvent::OrderCancelled { .. } => "order.cancelled",
+            DomainEvent::OrderSettled { .. } => "order.settled",
+            DomainEvent::OrderClaimed { .. } => "order.claimed",
+            DomainEvent::OrderDeleted { .. } => "order.deleted",
+            DomainEvent::MarketCreated { .. } => "market.created",
+            DomainEvent::MarketUpdated { .. } => "market.updated",
+            DomainEvent::MarketStatusChanged { .. } => "market.status_changed",
+            DomainEvent::MarketSettled { .. } => "market.settled",
+            DomainEvent::MarketClosed { .. } => "market.closed",
+            DomainEvent::MarketDeleted { .. } => "market.deleted",
+            DomainEvent::MatchStateChanged { .. } => "match.state_changed",
+            DomainEvent::UserStatusChanged { .. } => "user.status_changed",
+            DomainEvent::UserBlacklisted { .. } => "user.blacklisted",
+            DomainEvent::UserWhitelisted { .. } => "user.whitelisted",
+        }
+    }
+
+    /// (table, primary key) of the entity the event is about
+    pub fn aggregate(&self) -> (&'static str, i64) {
+        match self {
+            DomainEvent::OrderPlaced { id, .. }
+            | DomainEvent::OrderCancelled { id, .. }
+            | DomainEvent::OrderSettled { id, .. }
+            | DomainEvent::OrderClaimed { id, .. }
+            | DomainEvent::OrderDeleted { id } => ("orders", *id),
+            DomainEvent::MarketCreated { id }
+            | DomainEvent::MarketUpdated { id, .. }
+            | DomainEvent::MarketStatusChanged { id, .. }
+            | DomainEvent::MarketSettled { id, .. }
+            | DomainEvent::MarketClosed { id }
+            | DomainEvent::MarketDeleted { id }
+            | DomainEvent::MatchStateChanged { id, .. } => ("markets", *id),
+            DomainEvent::UserStatusChanged { id, .. }
+            | DomainEvent::UserBlacklisted { id, .. }
+            | DomainEvent::UserWhitelisted { id, .. } => ("users", *id),
+        }
+    }
+}
+
+/// An outbox row as handed to subscribers
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxEvent {
+    pub id: i64,
+    pub event: DomainEvent,
+    /// 1 on first delivery; subscribers must tolerate redelivery
+    pub attempts: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
@@ -1,7 +1,13 @@
+use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::Type;
 
+/// markets.state: betting open / closed (close_time passed). Independent of status, which
+/// tracks the result lifecycle.
+pub const MARKET_STATE_OPEN: i32 = 1;
+pub const MARKET_STATE_CLOSED: i32 = 2;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Type)]
 #[sqlx(type_name = "market_status", rename_all = "lowercase")]
 pub enum MarketStatus {
@@ -11,6 +17,17 @@ pub enum MarketStatus {
     Cancelled,
 }
 
+impl MarketStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketStatus::Pending => "pending",
+            MarketStatus::Active => "active",
+            MarketStatus::Settled => "settled",
+            MarketStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Market {
     pub id: i64,
@@ -23,6 +40,8 @@ pub struct Market {
     pub end_time: DateTime<Utc>,
     pub status: MarketStatus,
     pub winning_option: Option<i16>,
+    /// Currency stakes and payouts are denominated in (currencies.code)
+    pub currency: String,
     pub version: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -32,7 +51,7 @@ pub struct Market {
 pub struct MarketStats {
     pub bets_a: i64,
     pub bets_b: i64,
-    pub amount_a: String,
-    pub amount_b: String,
+    pub amount_a: BigDecimal,
+    pub amount_b: BigDecimal,
     pub total_orders: i64,
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 pub mod market;
 pub mod order;
 pub mod user;
-pub mod dto;
\ No newline at end of file
+pub mod dto;
+pub mod event;
\ No newline at end of file
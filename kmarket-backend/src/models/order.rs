@@ -1,3 +1,4 @@
+use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::Type;
@@ -16,10 +17,15 @@ pub struct Order {
     pub order_id: i64,
     pub user_id: i64,
     pub market_id: i64,
-    pub amount: String, // NUMERIC as String to avoid precision issues
-    pub odds: String,
+    /// NUMERIC, exact; serialized as a string
+    pub amount: BigDecimal,
+    pub odds: BigDecimal,
     pub option: i16,
     pub status: OrderStatus,
+    /// The market's currency (currencies.code); amounts are in it
+    pub currency: String,
+    /// Fees charged so far (at placement and settlement)
+    pub fee_paid: BigDecimal,
     pub version: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
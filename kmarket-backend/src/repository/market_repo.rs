@@ -1,8 +1,11 @@
 use anyhow::Result;
+use bigdecimal::{BigDecimal, Zero};
 use sqlx::{PgPool, Row};
 
+use crate::models::event::DomainEvent;
 use crate::models::market::{Market, MarketStats, MarketStatus};
 use crate::utils::errors::{DataAccessError, translate_sqlx_error};
+use crate::utils::outbox;
 
 pub struct MarketRepository {
     db_pool: PgPool,
@@ -16,11 +19,11 @@ impl MarketRepository {
         let rows = sqlx::query_as::<_, Market>(
             r#"
             SELECT id, market_id, title, description, option_a, option_b,
-                   start_time, end_time, status, winning_option, version,
+                   start_time, end_time, status, winning_option, currency, version,
                    created_at, updated_at
             FROM markets
             WHERE status = 'active' AND end_time > NOW()
-            ORDER BY created_at DESC
+            ORDER BY created_at DESC, id DESC
             LIMIT $1 OFFSET $2
             "#
         )
@@ -38,7 +41,7 @@ impl MarketRepository {
         let row = sqlx::query_as::<_, Market>(
             r#"
             SELECT id, market_id, title, description, option_a, option_b,
-                   start_time, end_time, status, winning_option, version,
+                   start_time, end_time, status, winning_option, currency, version,
                    created_at, updated_at
             FROM markets
             WHERE market_id = $1
@@ -58,8 +61,8 @@ impl MarketRepository {
             SELECT
                 COUNT(*) FILTER (WHERE option = 0) as bets_a,
                 COUNT(*) FILTER (WHERE option = 1) as bets_b,
-                COALESCE(SUM(amount) FILTER (WHERE option = 0), 0)::TEXT as amount_a,
-                COALESCE(SUM(amount) FILTER (WHERE option = 1), 0)::TEXT as amount_b,
+                COALESCE(SUM(amount) FILTER (WHERE option = 0), 0) as amount_a,
+                COALESCE(SUM(amount) FILTER (WHERE option = 1), 0) as amount_b,
                 COUNT(*) as total_orders
             FROM orders WHERE market_id = $1
             "#
@@ -72,20 +75,21 @@ impl MarketRepository {
         Ok(MarketStats {
             bets_a: row.try_get::<i64, _>("bets_a").unwrap_or(0),
             bets_b: row.try_get::<i64, _>("bets_b").unwrap_or(0),
-            amount_a: row.try_get::<String, _>("amount_a").unwrap_or_else(|_| "0".into()),
-            amount_b: row.try_get::<String, _>("amount_b").unwrap_or_else(|_| "0".into()),
+            amount_a: row.try_get("amount_a").unwrap_or_else(|_| BigDecimal::zero()),
+            amount_b: row.try_get("amount_b").unwrap_or_else(|_| BigDecimal::zero()),
             total_orders: row.try_get::<i64, _>("total_orders").unwrap_or(0),
         })
     }
 
     pub async fn create(&self, m: CreateMarketRequest) -> Result<Market, DataAccessError> {
         if m.market_id <= 0 || m.title.trim().is_empty() { return Err(DataAccessError::InvalidArgument("market fields".into())); }
+        let mut tx = self.db_pool.begin().await.map_err(translate_sqlx_error)?;
         let rec = sqlx::query_as::<_, Market>(
             r#"
             INSERT INTO markets (market_id, title, description, option_a, option_b, start_time, end_time, status)
             VALUES ($1, $2, $3, $4, $5, $6, $7, 'pending')
             RETURNING id, market_id, title, description, option_a, option_b,
-                      start_time, end_time, status, winning_option, version,
+                      start_time, end_time, status, winning_option, currency, version,
                       created_at, updated_at
             "#
         )
@@ -96,32 +100,39 @@ impl MarketRepository {
         .bind(m.option_b)
         .bind(m.start_time)
         .bind(m.end_time)
-        .fetch_one(&self.db_pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(translate_sqlx_error)?;
+        outbox::enqueue(&mut *tx, &DomainEvent::MarketCreated { id: rec.id }).await.map_err(translate_sqlx_error)?;
+        tx.commit().await.map_err(translate_sqlx_error)?;
 
         Ok(rec)
     }
 
     pub async fn update_status_with_version(&self, id: i64, expected_version: i32, new_status: MarketStatus) -> Result<Market, DataAccessError> {
         if id <= 0 { return Err(DataAccessError::InvalidArgument("id".into())); }
+        let mut tx = self.db_pool.begin().await.map_err(translate_sqlx_error)?;
         let rec = sqlx::query_as::<_, Market>(
             r#"
             UPDATE markets
             SET status = $1, version = version + 1
             WHERE id = $2 AND version = $3
             RETURNING id, market_id, title, description, option_a, option_b,
-                      start_time, end_time, status, winning_option, version,
+                      start_time, end_time, status, winning_option, currency, version,
                       created_at, updated_at
             "#
         )
         .bind(new_status)
         .bind(id)
         .bind(expected_version)
-        .fetch_optional(&self.db_pool)
+        .fetch_optional(&mut *tx)
         .await
         .map_err(translate_sqlx_error)?
         .ok_or(DataAccessError::ConcurrencyConflict("markets".into()))?;
+        outbox::enqueue(&mut *tx, &DomainEvent::MarketStatusChanged { id: rec.id, status: new_status.as_str().to_string() })
+            .await
+            .map_err(translate_sqlx_error)?;
+        tx.commit().await.map_err(translate_sqlx_error)?;
 
         Ok(rec)
     }
@@ -136,7 +147,8 @@ impl MarketRepository {
             .await
             .map_err(translate_sqlx_error)?
             .rows_affected();
-        if rows_affected == 0 { return Err(DataAccessError::Database("market not found".into())); }
+        if rows_affected == 0 { return Err(DataAccessError::NotFound("market".into())); }
+        outbox::enqueue(&mut *tx, &DomainEvent::MarketDeleted { id }).await.map_err(translate_sqlx_error)?;
         tx.commit().await.map_err(translate_sqlx_error)?;
         Ok(())
     }
@@ -1,24 +1,157 @@
 use anyhow::Result;
-use sqlx::{PgPool, Row};
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool, Row};
 
+use crate::models::event::DomainEvent;
+use crate::models::market::MARKET_STATE_CLOSED;
 use crate::models::order::{Order, OrderStatus};
+use crate::utils::currency;
 use crate::utils::errors::{DataAccessError, translate_sqlx_error};
+use crate::utils::fees::{self, OrderFees};
+use crate::utils::ledger;
+use crate::utils::money::{self, Rounding};
+use crate::utils::outbox;
 
 pub struct OrderRepository { db_pool: PgPool }
 
+/// Reject bets on markets whose betting has closed (state closed, or close_time already passed
+/// before the scheduler got to it). Locks the market row so a concurrent close waits for us.
+/// Returns the market's currency and its rounding; None when the market does not exist.
+async fn ensure_market_open(conn: &mut PgConnection, market_id: i64) -> Result<Option<(String, Rounding)>, DataAccessError> {
+    let row = sqlx::query(
+        "SELECT m.state, m.close_time <= NOW() AS past_close, m.currency, c.decimals \
+         FROM markets m JOIN currencies c ON c.code = m.currency WHERE m.id = $1 FOR SHARE OF m"
+    )
+    .bind(market_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(translate_sqlx_error)?;
+    let Some(row) = row else { return Ok(None) }; // the insert reports the missing market
+    let closed = row.try_get::<Option<i32>, _>("state").ok().flatten() == Some(MARKET_STATE_CLOSED)
+        || row.try_get::<Option<bool>, _>("past_close").ok().flatten() == Some(true);
+    if closed { return Err(DataAccessError::MarketClosed(market_id)); }
+    let currency: String = row.try_get("currency").map_err(translate_sqlx_error)?;
+    let decimals: i16 = row.try_get("decimals").map_err(translate_sqlx_error)?;
+    Ok(Some((currency, Rounding::new(decimals))))
+}
+
+/// Reject an order that would take the user's open stakes in the currency past their limit
+/// (user_balances.exposure_limit, else currencies.max_user_exposure). Locks the user's balance
+/// row, so concurrent orders of one user in one currency are checked one at a time.
+async fn ensure_within_exposure(conn: &mut PgConnection, user_id: i64, currency: &str, amount: &BigDecimal) -> Result<(), DataAccessError> {
+    sqlx::query("INSERT INTO user_balances (user_id, currency) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(user_id)
+        .bind(currency)
+        .execute(&mut *conn)
+        .await
+        .map_err(translate_sqlx_error)?;
+    let limit: Option<BigDecimal> = sqlx::query_scalar(
+        "SELECT COALESCE(b.exposure_limit, c.max_user_exposure) FROM user_balances b JOIN currencies c ON c.code = b.currency \
+         WHERE b.user_id = $1 AND b.currency = $2 FOR UPDATE OF b"
+    )
+    .bind(user_id)
+    .bind(currency)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(translate_sqlx_error)?;
+    let Some(limit) = limit else { return Ok(()) };
+    let open: BigDecimal = sqlx::query_scalar("SELECT COALESCE(SUM(amount), 0) FROM orders WHERE user_id = $1 AND currency = $2 AND status = 'placed'")
+        .bind(user_id)
+        .bind(currency)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(translate_sqlx_error)?;
+    if open + amount > limit { return Err(DataAccessError::ExposureLimitExceeded(currency.to_string())); }
+    Ok(())
+}
+
+/// Checks before placing an order: market open, stake within the currency's decimals, exposure limit.
+/// Also fixes the order's fee terms.
+async fn admit(conn: &mut PgConnection, req: CreateOrderRequest) -> Result<(CreateOrderRequest, OrderFees), DataAccessError> {
+    let Some((currency, rounding)) = ensure_market_open(conn, req.market_id).await? else { return Ok((req, OrderFees::default())) };
+    let amount = rounding.stake(&req.amount).map_err(DataAccessError::InvalidArgument)?;
+    ensure_within_exposure(conn, req.user_id, &currency, &amount).await?;
+    let fees = fees::for_order(conn, req.market_id, req.user_id, &currency, &amount, rounding)
+        .await
+        .map_err(translate_sqlx_error)?;
+    Ok((CreateOrderRequest { amount, ..req }, fees))
+}
+
+/// Mark an order as paid on-chain by `tx_signature`, whoever owns it; the balance is left alone.
+/// Runs in the caller's transaction so the admin who reports the payout is audited with it.
+pub async fn record_onchain_payout(conn: &mut PgConnection, id: i64, tx_signature: &str) -> Result<Claim, DataAccessError> {
+    claim_payout(conn, id, None, Some(tx_signature)).await
+}
+
+/// Lock a settled winning order and mark it claimed: credited to the balance, or with
+/// `tx_signature` paid on-chain. `owner` restricts it to the user with that address.
+async fn claim_payout(conn: &mut PgConnection, id: i64, owner: Option<&str>, tx_signature: Option<&str>) -> Result<Claim, DataAccessError> {
+    if id <= 0 { return Err(DataAccessError::InvalidArgument("id".into())); }
+    let row = sqlx::query(
+        "SELECT o.user_id, o.market_id, o.status::TEXT AS status, o.close_price, o.currency, o.claimed_at \
+         FROM orders o JOIN users u ON u.id = o.user_id WHERE o.id = $1 AND ($2::TEXT IS NULL OR u.address = $2) FOR UPDATE OF o"
+    )
+    .bind(id)
+    .bind(owner)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(translate_sqlx_error)?
+    .ok_or(DataAccessError::NotFound("position".into()))?;
+    if row.try_get::<Option<DateTime<Utc>>, _>("claimed_at").map_err(translate_sqlx_error)?.is_some() {
+        return Err(DataAccessError::AlreadyClaimed(id));
+    }
+    let payout: Option<BigDecimal> = row.try_get("close_price").map_err(translate_sqlx_error)?;
+    let payout = match payout {
+        Some(p) if p > BigDecimal::zero() && row.try_get::<String, _>("status").map_err(translate_sqlx_error)? == "settled" => p,
+        _ => return Err(DataAccessError::NotClaimable(id)),
+    };
+    let user_id: i64 = row.try_get("user_id").map_err(translate_sqlx_error)?;
+    let market_id: i64 = row.try_get("market_id").map_err(translate_sqlx_error)?;
+    let currency: String = row.try_get("currency").map_err(translate_sqlx_error)?;
+
+    let claimed_at: DateTime<Utc> = sqlx::query_scalar("UPDATE orders SET claimed_at = NOW(), claim_tx_signature = $2 WHERE id = $1 RETURNING claimed_at")
+        .bind(id)
+        .bind(tx_signature)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(translate_sqlx_error)?;
+    let balance = match tx_signature {
+        Some(_) => None,
+        None => Some(sqlx::query_scalar::<_, BigDecimal>(
+            "INSERT INTO user_balances (user_id, currency, balance) VALUES ($1, $2, $3) \
+             ON CONFLICT (user_id, currency) DO UPDATE SET balance = user_balances.balance + EXCLUDED.balance RETURNING balance"
+        )
+        .bind(user_id)
+        .bind(&currency)
+        .bind(&payout)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(translate_sqlx_error)?),
+    };
+    ledger::record(conn, user_id, Some(id), &currency, ledger::KIND_PAYOUT, &payout, serde_json::json!({"transaction_signature": tx_signature}))
+        .await
+        .map_err(translate_sqlx_error)?;
+    outbox::enqueue(&mut *conn, &DomainEvent::OrderClaimed { id, market_id, user_id })
+        .await
+        .map_err(translate_sqlx_error)?;
+    Ok(Claim { id, user_id, currency, amount: payout, claimed_at, transaction_signature: tx_signature.map(str::to_string), balance })
+}
+
 impl OrderRepository {
     pub fn new(db_pool: PgPool) -> Self { Self { db_pool } }
 
     pub async fn create(&self, req: CreateOrderRequest) -> Result<Order, DataAccessError> {
-        if req.order_id <= 0 || req.user_id <= 0 || req.market_id <= 0 || req.amount <= 0.0 || req.odds <= 0.0 || (req.option != 0 && req.option != 1) {
-            return Err(DataAccessError::InvalidArgument("order fields".into()));
-        }
+        let req = req.validated()?;
+        let mut tx = self.db_pool.begin().await.map_err(translate_sqlx_error)?;
+        let (req, fees) = admit(&mut tx, req).await?;
         let rec = sqlx::query_as::<_, Order>(
             r#"
-            INSERT INTO orders (order_id, user_id, market_id, amount, odds, option, status)
-            VALUES ($1, $2, $3, $4, $5, $6, 'placed')
-            RETURNING id, order_id, user_id, market_id, amount::TEXT as amount, odds::TEXT as odds,
-                      option, status, version, created_at, updated_at
+            INSERT INTO orders (order_id, user_id, market_id, amount, odds, option, status,
+                                fee_schedule_id, fee_paid, fee_at_placement, fee_due, fee_winnings_rate)
+            VALUES ($1, $2, $3, $4, $5, $6, 'placed', $7, $8, $8, $9, $10)
+            RETURNING id, order_id, user_id, market_id, amount, odds,
+                      option, status, currency, fee_paid, version, created_at, updated_at
             "#
         )
         .bind(req.order_id)
@@ -27,26 +160,44 @@ impl OrderRepository {
         .bind(req.amount)
         .bind(req.odds)
         .bind(req.option)
-        .fetch_one(&self.db_pool)
+        .bind(fees.schedule_id)
+        .bind(&fees.paid)
+        .bind(&fees.due)
+        .bind(&fees.winnings_rate)
+        .fetch_one(&mut *tx)
         .await
         .map_err(translate_sqlx_error)?;
+        fees::record_placement(&mut tx, rec.id, rec.user_id, &rec.currency, &fees)
+            .await
+            .map_err(translate_sqlx_error)?;
 
+        outbox::enqueue(&mut *tx, &DomainEvent::OrderPlaced { id: rec.id, market_id: rec.market_id, user_id: rec.user_id })
+            .await
+            .map_err(translate_sqlx_error)?;
+        tx.commit().await.map_err(translate_sqlx_error)?;
         Ok(rec)
     }
 
-    /// Create order and write audit log atomically in a transaction
+    /// Create order and write audit log (and the order.placed event) atomically in a transaction
     pub async fn create_with_audit(&self, req: CreateOrderRequest) -> Result<Order, DataAccessError> {
-        if req.order_id <= 0 || req.user_id <= 0 || req.market_id <= 0 || req.amount <= 0.0 || req.odds <= 0.0 || (req.option != 0 && req.option != 1) {
-            return Err(DataAccessError::InvalidArgument("order fields".into()));
-        }
+        self.create_with_reference(req, &OrderReference::default()).await
+    }
+
+    /// create_with_audit for an order that carries an external reference; a reference already on
+    /// another order is a DuplicateKey
+    pub async fn create_with_reference(&self, req: CreateOrderRequest, reference: &OrderReference) -> Result<Order, DataAccessError> {
+        let req = req.validated()?;
         let mut tx = self.db_pool.begin().await.map_err(translate_sqlx_error)?;
+        let (req, fees) = admit(&mut tx, req).await?;
 
         let order = sqlx::query_as::<_, Order>(
             r#"
-            INSERT INTO orders (order_id, user_id, market_id, amount, odds, option, status)
-            VALUES ($1, $2, $3, $4, $5, $6, 'placed')
-            RETURNING id, order_id, user_id, market_id, amount::TEXT as amount, odds::TEXT as odds,
-                      option, status, version, created_at, updated_at
+            INSERT INTO orders (order_id, user_id, market_id, amount, odds, option, status,
+                                fee_schedule_id, fee_paid, fee_at_placement, fee_due, fee_winnings_rate,
+                                api_key_id, client_order_id, transaction_signature)
+            VALUES ($1, $2, $3, $4, $5, $6, 'placed', $7, $8, $8, $9, $10, $11, $12, $13)
+            RETURNING id, order_id, user_id, market_id, amount, odds,
+                      option, status, currency, fee_paid, version, created_at, updated_at
             "#
         )
         .bind(req.order_id)
@@ -55,9 +206,19 @@ impl OrderRepository {
         .bind(req.amount)
         .bind(req.odds)
         .bind(req.option)
+        .bind(fees.schedule_id)
+        .bind(&fees.paid)
+        .bind(&fees.due)
+        .bind(&fees.winnings_rate)
+        .bind(reference.api_key_id)
+        .bind(&reference.client_order_id)
+        .bind(&reference.transaction_signature)
         .fetch_one(&mut *tx)
         .await
         .map_err(translate_sqlx_error)?;
+        fees::record_placement(&mut tx, order.id, order.user_id, &order.currency, &fees)
+            .await
+            .map_err(translate_sqlx_error)?;
 
         sqlx::query(
             r#"INSERT INTO order_audits (order_id, action, detail) VALUES ($1, 'created', '{}'::jsonb)"#
@@ -66,17 +227,45 @@ impl OrderRepository {
         .execute(&mut *tx)
         .await
         .map_err(translate_sqlx_error)?;
+        outbox::enqueue(&mut *tx, &DomainEvent::OrderPlaced { id: order.id, market_id: order.market_id, user_id: order.user_id })
+            .await
+            .map_err(translate_sqlx_error)?;
 
         tx.commit().await.map_err(translate_sqlx_error)?;
         Ok(order)
     }
 
+    /// A free order_id for an order whose id the server chooses (partner and compat orders)
+    pub async fn next_order_id(&self) -> Result<i64, DataAccessError> {
+        sqlx::query_scalar("SELECT next_order_id()")
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(translate_sqlx_error)
+    }
+
+    /// The order a partner key placed under `client_order_id`
+    pub async fn find_by_client_order_id(&self, api_key_id: i64, client_order_id: &str) -> Result<Option<Order>, DataAccessError> {
+        let rec = sqlx::query_as::<_, Order>(
+            r#"
+            SELECT id, order_id, user_id, market_id, amount, odds,
+                   option, status, currency, fee_paid, version, created_at, updated_at
+            FROM orders WHERE api_key_id = $1 AND client_order_id = $2
+            "#
+        )
+        .bind(api_key_id)
+        .bind(client_order_id)
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(translate_sqlx_error)?;
+        Ok(rec)
+    }
+
     pub async fn find_by_order_id(&self, order_id: i64) -> Result<Option<Order>, DataAccessError> {
         if order_id <= 0 { return Err(DataAccessError::InvalidArgument("order_id".into())); }
         let rec = sqlx::query_as::<_, Order>(
             r#"
-            SELECT id, order_id, user_id, market_id, amount::TEXT as amount, odds::TEXT as odds,
-                   option, status, version, created_at, updated_at
+            SELECT id, order_id, user_id, market_id, amount, odds,
+                   option, status, currency, fee_paid, version, created_at, updated_at
             FROM orders WHERE order_id = $1
             "#
         )
@@ -89,26 +278,35 @@ impl OrderRepository {
 
     pub async fn update_status_with_version(&self, id: i64, expected_version: i32, new_status: OrderStatus) -> Result<Order, DataAccessError> {
         if id <= 0 { return Err(DataAccessError::InvalidArgument("id".into())); }
+        let mut tx = self.db_pool.begin().await.map_err(translate_sqlx_error)?;
         let rec = sqlx::query_as::<_, Order>(
             r#"
             UPDATE orders SET status = $1, version = version + 1
             WHERE id = $2 AND version = $3
-            RETURNING id, order_id, user_id, market_id, amount::TEXT as amount, odds::TEXT as odds,
-                      option, status, version, created_at, updated_at
+            RETURNING id, order_id, user_id, market_id, amount, odds,
+                      option, status, currency, fee_paid, version, created_at, updated_at
             "#
         )
         .bind(new_status)
         .bind(id)
         .bind(expected_version)
-        .fetch_optional(&self.db_pool)
+        .fetch_optional(&mut *tx)
         .await
         .map_err(translate_sqlx_error)?
         .ok_or(DataAccessError::ConcurrencyConflict("orders".into()))?;
+        let (id, market_id, user_id) = (rec.id, rec.market_id, rec.user_id);
+        let event = match new_status {
+            OrderStatus::Placed => DomainEvent::OrderPlaced { id, market_id, user_id },
+            OrderStatus::Cancelled => DomainEvent::OrderCancelled { id, market_id, user_id },
+            OrderStatus::Settled => DomainEvent::OrderSettled { id, market_id, user_id },
+        };
+        outbox::enqueue(&mut *tx, &event).await.map_err(translate_sqlx_error)?;
+        tx.commit().await.map_err(translate_sqlx_error)?;
         Ok(rec)
     }
 
     /// Cancel order with close fields (price, pnl, closed_at) and audit, with optimistic version check
-    pub async fn cancel_with_close_fields(&self, id: i64, expected_version: i32, close_price: Option<f64>, close_pnl: Option<f64>) -> Result<Order, DataAccessError> {
+    pub async fn cancel_with_close_fields(&self, id: i64, expected_version: i32, close_price: Option<BigDecimal>, close_pnl: Option<BigDecimal>) -> Result<Order, DataAccessError> {
         if id <= 0 { return Err(DataAccessError::InvalidArgument("id".into())); }
         let mut tx = self.db_pool.begin().await.map_err(translate_sqlx_error)?;
         let rec = sqlx::query_as::<_, Order>(
@@ -117,8 +315,8 @@ impl OrderRepository {
             SET status = 'cancelled', version = version + 1,
                 closed_at = NOW(), close_price = COALESCE($1, close_price), close_pnl = COALESCE($2, close_pnl)
             WHERE id = $3 AND version = $4
-            RETURNING id, order_id, user_id, market_id, amount::TEXT as amount, odds::TEXT as odds,
-                      option, status, version, created_at, updated_at
+            RETURNING id, order_id, user_id, market_id, amount, odds,
+                      option, status, currency, fee_paid, version, created_at, updated_at
             "#
         )
         .bind(close_price)
@@ -135,6 +333,9 @@ impl OrderRepository {
             .execute(&mut *tx)
             .await
             .map_err(translate_sqlx_error)?;
+        outbox::enqueue(&mut *tx, &DomainEvent::OrderCancelled { id: rec.id, market_id: rec.market_id, user_id: rec.user_id })
+            .await
+            .map_err(translate_sqlx_error)?;
 
         tx.commit().await.map_err(translate_sqlx_error)?;
         Ok(rec)
@@ -145,8 +346,8 @@ impl OrderRepository {
         if address.trim().is_empty() { return Err(DataAccessError::InvalidArgument("address".into())); }
         let rows = sqlx::query_as::<_, Order>(
             r#"
-            SELECT o.id, o.order_id, o.user_id, o.market_id, o.amount::TEXT as amount, o.odds::TEXT as odds,
-                   o.option, o.status, o.version, o.created_at, o.updated_at
+            SELECT o.id, o.order_id, o.user_id, o.market_id, o.amount, o.odds,
+                   o.option, o.status, o.currency, o.fee_paid, o.version, o.created_at, o.updated_at
             FROM orders o
             JOIN users u ON u.id = o.user_id
             WHERE u.address = $1
@@ -160,54 +361,102 @@ impl OrderRepository {
         Ok(rows)
     }
 
-    /// Aggregate user stats by address
+    /// Aggregate user stats by address; amounts per currency
     pub async fn get_user_stats(&self, address: &str) -> Result<UserStats, DataAccessError> {
         if address.trim().is_empty() { return Err(DataAccessError::InvalidArgument("address".into())); }
-        let row = sqlx::query(
+        let rows = sqlx::query(
             r#"
             SELECT
+                o.currency,
                 COUNT(*) FILTER (WHERE o.status = 'placed') AS placed,
                 COUNT(*) FILTER (WHERE o.status = 'cancelled') AS cancelled,
                 COUNT(*) FILTER (WHERE o.status = 'settled') AS settled,
-                COALESCE(SUM(o.amount) FILTER (WHERE o.status = 'placed'), 0)::TEXT AS amount_placed,
-                COALESCE(SUM(o.amount) FILTER (WHERE o.status = 'settled'), 0)::TEXT AS amount_settled
+                COALESCE(SUM(o.amount) FILTER (WHERE o.status = 'placed'), 0) AS amount_placed,
+                COALESCE(SUM(o.amount) FILTER (WHERE o.status = 'settled'), 0) AS amount_settled
             FROM orders o
             JOIN users u ON u.id = o.user_id
             WHERE u.address = $1
+            GROUP BY o.currency
+            ORDER BY o.currency
             "#
         )
         .bind(address)
-        .fetch_one(&self.db_pool)
+        .fetch_all(&self.db_pool)
         .await
         .map_err(translate_sqlx_error)?;
-        Ok(UserStats {
-            placed: row.try_get("placed").unwrap_or(0),
-            cancelled: row.try_get("cancelled").unwrap_or(0),
-            settled: row.try_get("settled").unwrap_or(0),
-            amount_placed: row.try_get("amount_placed").unwrap_or_else(|_| "0".into()),
-            amount_settled: row.try_get("amount_settled").unwrap_or_else(|_| "0".into()),
-        })
+        let mut stats = UserStats { amount_placed: "0".into(), amount_settled: "0".into(), ..UserStats::default() };
+        for row in rows {
+            stats.placed += row.try_get::<i64, _>("placed").unwrap_or(0);
+            stats.cancelled += row.try_get::<i64, _>("cancelled").unwrap_or(0);
+            stats.settled += row.try_get::<i64, _>("settled").unwrap_or(0);
+            let amounts = CurrencyAmounts {
+                currency: row.try_get("currency").unwrap_or_default(),
+                amount_placed: row.try_get("amount_placed").unwrap_or_else(|_| BigDecimal::zero()),
+                amount_settled: row.try_get("amount_settled").unwrap_or_else(|_| BigDecimal::zero()),
+            };
+            if amounts.currency == currency::DEFAULT_CURRENCY {
+                stats.amount_placed = amounts.amount_placed.to_string();
+                stats.amount_settled = amounts.amount_settled.to_string();
+            }
+            stats.by_currency.push(amounts);
+        }
+        Ok(stats)
     }
 
     pub async fn delete_by_id(&self, id: i64) -> Result<(), DataAccessError> {
         if id <= 0 { return Err(DataAccessError::InvalidArgument("id".into())); }
         let mut tx = self.db_pool.begin().await.map_err(translate_sqlx_error)?;
-        // Delete audits then order atomically
-        sqlx::query("DELETE FROM order_audits WHERE order_id = (SELECT order_id FROM orders WHERE id = $1)")
-            .bind(id)
-            .execute(&mut *tx)
-            .await
-            .map_err(translate_sqlx_error)?;
-        let rows_affected = sqlx::query("DELETE FROM orders WHERE id = $1")
+        // order_audits is append-only: the deletion is recorded there with the order as it was
+        let rows_affected = sqlx::query(
+            r#"WITH gone AS (DELETE FROM orders WHERE id = $1 RETURNING *)
+               INSERT INTO order_audits (order_id, action, detail) SELECT order_id, 'deleted', to_jsonb(gone) FROM gone"#
+        )
             .bind(id)
             .execute(&mut *tx)
             .await
             .map_err(translate_sqlx_error)?
             .rows_affected();
-        if rows_affected == 0 { return Err(DataAccessError::Database("order not found".into())); }
+        if rows_affected == 0 { return Err(DataAccessError::NotFound("order".into())); }
+        outbox::enqueue(&mut *tx, &DomainEvent::OrderDeleted { id }).await.map_err(translate_sqlx_error)?;
         tx.commit().await.map_err(translate_sqlx_error)?;
         Ok(())
     }
+
+    /// Claim the payout of a settled order owned by `address` and credit it to the user's balance in
+    /// the order's currency. The order row is locked, so of two concurrent claims the second sees
+    /// AlreadyClaimed.
+    pub async fn claim(&self, id: i64, address: &str) -> Result<Claim, DataAccessError> {
+        let mut tx = self.db_pool.begin().await.map_err(translate_sqlx_error)?;
+        let claim = claim_payout(&mut tx, id, Some(address), None).await?;
+        tx.commit().await.map_err(translate_sqlx_error)?;
+        Ok(claim)
+    }
+}
+
+/// A claimed payout
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Claim {
+    /// orders.id
+    pub id: i64,
+    pub user_id: i64,
+    pub currency: String,
+    pub amount: BigDecimal,
+    pub claimed_at: DateTime<Utc>,
+    /// On-chain payout transaction; None when credited to the balance
+    pub transaction_signature: Option<String>,
+    /// The user's balance in the currency after the credit; None for on-chain payouts
+    pub balance: Option<BigDecimal>,
+}
+
+/// External references stored on an order, each naming at most one order
+#[derive(Debug, Clone, Default)]
+pub struct OrderReference {
+    /// api_keys.id of the partner key that placed the order
+    pub api_key_id: Option<i64>,
+    /// The partner's own reference, unique per key
+    pub client_order_id: Option<String>,
+    /// On-chain transaction that paid for a compat order
+    pub transaction_signature: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -215,16 +464,38 @@ pub struct CreateOrderRequest {
     pub order_id: i64,
     pub user_id: i64,
     pub market_id: i64,
-    pub amount: f64,
-    pub odds: f64,
+    pub amount: BigDecimal,
+    pub odds: BigDecimal,
     pub option: i16,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+impl CreateOrderRequest {
+    /// Ids positive, option 0 or 1, amount and odds exact at their column scales
+    fn validated(self) -> Result<Self, DataAccessError> {
+        if self.order_id <= 0 || self.user_id <= 0 || self.market_id <= 0 || (self.option != 0 && self.option != 1) {
+            return Err(DataAccessError::InvalidArgument("order fields".into()));
+        }
+        let amount = Rounding::DEFAULT.stake(&self.amount).map_err(DataAccessError::InvalidArgument)?;
+        let odds = money::odds(&self.odds).map_err(DataAccessError::InvalidArgument)?;
+        Ok(Self { amount, odds, ..self })
+    }
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct UserStats {
     pub placed: i64,
     pub cancelled: i64,
     pub settled: i64,
+    /// Stakes in the default currency (USDC), as before currencies existed
     pub amount_placed: String,
     pub amount_settled: String,
+    /// Stakes by currency; amounts in different currencies are never added up
+    pub by_currency: Vec<CurrencyAmounts>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct CurrencyAmounts {
+    pub currency: String,
+    pub amount_placed: BigDecimal,
+    pub amount_settled: BigDecimal,
 }
\ No newline at end of file
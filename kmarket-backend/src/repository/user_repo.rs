@@ -73,7 +73,7 @@ impl UserRepository {
             .await
             .map_err(translate_sqlx_error)?
             .rows_affected();
-        if rows_affected == 0 { return Err(DataAccessError::Database("user not found".into())); }
+        if rows_affected == 0 { return Err(DataAccessError::NotFound("user".into())); }
         Ok(())
     }
 }
@@ -0,0 +1,309 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use argon2::{Argon2, PasswordHash, PasswordVerifier, password_hash::{SaltString, PasswordHasher}};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Deserialize;
+use sqlx::{Postgres, Row, Transaction};
+
+use crate::state::AppState;
+use crate::utils::audit;
+use crate::utils::auth::{admin_actor_id, admin_actor_id_allow_pending, require_role, ADMIN_ROLES};
+use crate::utils::errors::AppError;
+use crate::utils::response::ApiResponse;
+
+/// Minimum length for admin passwords
+pub const MIN_PASSWORD_LEN: usize = 8;
+/// Audit actions surfaced by the login-attempts listing
+const LOGIN_ACTIONS: [&str; 4] = ["admin.login_success", "admin.login_failed", "admin.mfa_failed", "admin.login_mfa_challenge"];
+const ADMIN_COLUMNS: &str = "id, email, role, status, totp_enabled, must_reset_password, password_changed_at, last_login_at, invited_by, created_at, updated_at";
+
+fn hash_password(password: &str) -> Result<(String, String)> {
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(password.as_bytes(), &salt)
+        .map_err(|_| AppError::internal("password hashing failed"))?
+        .to_string();
+    Ok((hash, salt.to_string()))
+}
+
+/// Temporary password handed out on invite / forced reset; must be changed on first login
+fn temporary_password() -> String {
+    rand::thread_rng().sample_iter(&Alphanumeric).take(16).map(char::from).collect()
+}
+
+fn admin_json(row: &sqlx::postgres::PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+        "email": row.try_get::<String, _>("email").unwrap_or_default(),
+        "role": row.try_get::<String, _>("role").unwrap_or_default(),
+        "status": row.try_get::<String, _>("status").unwrap_or_default(),
+        "totp_enabled": row.try_get::<bool, _>("totp_enabled").unwrap_or(false),
+        "must_reset_password": row.try_get::<bool, _>("must_reset_password").unwrap_or(false),
+        "password_changed_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("password_changed_at").ok().flatten(),
+        "last_login_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("last_login_at").ok().flatten(),
+        "invited_by": row.try_get::<Option<i64>, _>("invited_by").ok().flatten(),
+        "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+        "updated_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("updated_at").ok(),
+    })
+}
+
+/// Audit inside the caller's transaction so the action and its log commit together
+async fn audit_tx(tx: &mut Transaction<'_, Postgres>, actor_id: i64, action: &str, resource_id: i64, payload: serde_json::Value) -> Result<()> {
+    audit::record(&mut **tx, actor_id, action, "admin_users", Some(resource_id), payload)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+fn not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "admin not found"))
+}
+
+#[derive(Deserialize)]
+pub struct AdminsQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub status: Option<String>,
+    pub role: Option<String>,
+}
+
+pub async fn list_admins(req: HttpRequest, state: web::Data<AppState>, query: web::Query<AdminsQuery>) -> Result<HttpResponse> {
+    let _actor = admin_actor_id(&req, &state.db_pool).await?;
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * limit;
+
+    let filter = "($1::text IS NULL OR status = $1) AND ($2::text IS NULL OR role = $2)";
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM admin_users WHERE {}", filter))
+        .bind(&query.status).bind(&query.role)
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let rows = sqlx::query(&format!("SELECT {} FROM admin_users WHERE {} ORDER BY id ASC LIMIT $3 OFFSET $4", ADMIN_COLUMNS, filter))
+        .bind(&query.status).bind(&query.role).bind(limit).bind(offset)
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let items: Vec<serde_json::Value> = rows.iter().map(admin_json).collect();
+
+    let body = serde_json::json!({ "items": items, "pagination": { "page": page, "limit": limit, "total": total, "totalPages": ((total + limit - 1) / limit) } });
+    Ok(HttpResponse::Ok().json(ApiResponse::success(body)))
+}
+
+pub async fn get_admin(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+    let _actor = admin_actor_id(&req, &state.db_pool).await?;
+    let row = sqlx::query(&format!("SELECT {} FROM admin_users WHERE id = $1", ADMIN_COLUMNS))
+        .bind(path.into_inner())
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    match row {
+        Some(row) => Ok(HttpResponse::Ok().json(ApiResponse::success(admin_json(&row)))),
+        None => Ok(not_found()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct InviteAdminRequest {
+    pub email: String,
+    pub role: Option<String>,
+}
+
+/// Create an admin with a one-time temporary password; the invitee must change it on first login
+pub async fn invite_admin(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<InviteAdminRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let email = payload.email.trim().to_lowercase();
+    let role = payload.role.as_deref().map(str::trim).unwrap_or("operator");
+    if email.is_empty() || !email.contains('@') {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_email", "email invalid")));
+    }
+    if !ADMIN_ROLES.contains(&role) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_role", "role invalid")));
+    }
+
+    let temp = temporary_password();
+    let (hash, salt) = hash_password(&temp)?;
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = sqlx::query(&format!(
+        "INSERT INTO admin_users (email, password_hash, salt, status, role, must_reset_password, invited_by) VALUES ($1, $2, $3, 'active', $4, true, $5) ON CONFLICT (email) DO NOTHING RETURNING {}",
+        ADMIN_COLUMNS
+    ))
+    .bind(&email).bind(hash).bind(salt).bind(role).bind(actor_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    let Some(row) = row else {
+        return Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("email_taken", "an admin with this email already exists")));
+    };
+    let id: i64 = row.try_get("id").unwrap_or_default();
+    audit_tx(&mut tx, actor_id, "admin.admin_invite", id, serde_json::json!({"email": email, "role": role})).await?;
+    tx.commit().await.map_err(AppError::from)?;
+
+    let mut body = admin_json(&row);
+    // Shown once; only the hash is stored
+    body["temporary_password"] = serde_json::json!(temp);
+    Ok(HttpResponse::Created().json(ApiResponse::success(body)))
+}
+
+#[derive(Deserialize)]
+pub struct SetRoleRequest { pub role: String }
+
+pub async fn set_admin_role(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<SetRoleRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let id = path.into_inner();
+    let role = payload.role.trim();
+    if !ADMIN_ROLES.contains(&role) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_role", "role invalid")));
+    }
+    if id == actor_id && role != "admin" {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("self_demotion", "cannot change your own role")));
+    }
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let prev: Option<String> = sqlx::query_scalar("SELECT role FROM admin_users WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    let Some(prev) = prev else { return Ok(not_found()) };
+    sqlx::query("UPDATE admin_users SET role = $1 WHERE id = $2")
+        .bind(role).bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    audit_tx(&mut tx, actor_id, "admin.admin_role", id, serde_json::json!({"from": prev, "to": role})).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id, "role": role}))))
+}
+
+#[derive(Deserialize)]
+pub struct SetStatusRequest { pub status: String }
+
+pub async fn set_admin_status(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<SetStatusRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let id = path.into_inner();
+    let status = payload.status.trim();
+    if !["active", "disabled"].contains(&status) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_status", "status invalid")));
+    }
+    if id == actor_id && status != "active" {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("self_disable", "cannot disable your own account")));
+    }
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let res = sqlx::query("UPDATE admin_users SET status = $1 WHERE id = $2")
+        .bind(status).bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    if res.rows_affected() == 0 { return Ok(not_found()); }
+    audit_tx(&mut tx, actor_id, "admin.admin_status", id, serde_json::json!({"status": status})).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id, "status": status}))))
+}
+
+/// Replace the admin's password with a temporary one and force a change at next login
+pub async fn force_password_reset(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let id = path.into_inner();
+
+    let temp = temporary_password();
+    let (hash, salt) = hash_password(&temp)?;
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let res = sqlx::query("UPDATE admin_users SET password_hash = $1, salt = $2, must_reset_password = true WHERE id = $3")
+        .bind(hash).bind(salt).bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    if res.rows_affected() == 0 { return Ok(not_found()); }
+    audit_tx(&mut tx, actor_id, "admin.admin_password_reset", id, serde_json::json!({})).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id, "temporary_password": temp}))))
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Change your own password; also the way out of a forced reset
+pub async fn change_own_password(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<ChangePasswordRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id_allow_pending(&req, &state.db_pool).await?;
+    let p = payload.into_inner();
+    if p.new_password.chars().count() < MIN_PASSWORD_LEN {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("weak_password", "password must be at least 8 characters")));
+    }
+    if p.new_password == p.current_password {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("password_unchanged", "new password must differ from the current one")));
+    }
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let current_hash: String = sqlx::query_scalar("SELECT password_hash FROM admin_users WHERE id = $1 FOR UPDATE")
+        .bind(actor_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    let parsed = PasswordHash::new(&current_hash)
+        .map_err(|_| AppError::internal("stored password hash is malformed"))?;
+    if Argon2::default().verify_password(p.current_password.as_bytes(), &parsed).is_err() {
+        drop(tx);
+        audit::record(&state.db_pool, actor_id, "admin.password_change_failed", "admin_users", Some(actor_id), serde_json::json!({}))
+            .await
+            .map_err(AppError::from)?;
+        return Ok(HttpResponse::Unauthorized().json(ApiResponse::<()>::error("invalid_credentials", "current password is incorrect")));
+    }
+
+    let (hash, salt) = hash_password(&p.new_password)?;
+    sqlx::query("UPDATE admin_users SET password_hash = $1, salt = $2, must_reset_password = false, password_changed_at = NOW() WHERE id = $3")
+        .bind(hash).bind(salt).bind(actor_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    audit_tx(&mut tx, actor_id, "admin.password_change", actor_id, serde_json::json!({})).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": actor_id, "password_changed": true}))))
+}
+
+#[derive(Deserialize)]
+pub struct LoginAttemptsQuery {
+    pub admin_id: Option<i64>,
+    pub email: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Recent login attempts (success, failure, MFA challenge/failure) from audit_logs, newest first
+pub async fn list_login_attempts(req: HttpRequest, state: web::Data<AppState>, query: web::Query<LoginAttemptsQuery>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin", "analyst"]).await?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let actions: Vec<String> = LOGIN_ACTIONS.iter().map(|s| s.to_string()).collect();
+    let rows = sqlx::query(
+        "SELECT id, actor_id, action, resource_id, payload_json, created_at FROM audit_logs \
+         WHERE action = ANY($1) AND ($2::bigint IS NULL OR resource_id = $2) AND ($3::text IS NULL OR payload_json->>'email' = $3) \
+         ORDER BY created_at DESC, id DESC LIMIT $4"
+    )
+    .bind(actions)
+    .bind(query.admin_id)
+    .bind(query.email.as_deref().map(|e| e.trim().to_string()))
+    .bind(limit)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    let items: Vec<serde_json::Value> = rows.into_iter().map(|row| {
+        let action = row.try_get::<String, _>("action").unwrap_or_default();
+        let payload = row.try_get::<Option<serde_json::Value>, _>("payload_json").ok().flatten().unwrap_or(serde_json::Value::Null);
+        serde_json::json!({
+            "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+            "admin_id": row.try_get::<Option<i64>, _>("resource_id").ok().flatten(),
+            "email": payload.get("email").cloned(),
+            "outcome": action.trim_start_matches("admin."),
+            "detail": payload,
+            "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+        })
+    }).collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items}))))
+}
@@ -0,0 +1,138 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use serde::Deserialize;
+use sqlx::Row;
+
+use crate::state::AppState;
+use crate::utils::api_keys::{generate_key_id, generate_secret, API_KEY_SCOPES};
+use crate::utils::audit;
+use crate::utils::auth::{admin_actor_id, require_role};
+use crate::utils::errors::AppError;
+use crate::utils::response::ApiResponse;
+
+const KEY_COLUMNS: &str = "id, key_id, name, scopes, status, rate_limit_per_min, request_count, last_used_at, created_by, revoked_at, created_at";
+
+fn key_json(row: &sqlx::postgres::PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+        "key_id": row.try_get::<String, _>("key_id").unwrap_or_default(),
+        "name": row.try_get::<String, _>("name").unwrap_or_default(),
+        "scopes": row.try_get::<Vec<String>, _>("scopes").unwrap_or_default(),
+        "status": row.try_get::<String, _>("status").unwrap_or_default(),
+        "rate_limit_per_min": row.try_get::<i32, _>("rate_limit_per_min").unwrap_or_default(),
+        "request_count": row.try_get::<i64, _>("request_count").unwrap_or_default(),
+        "last_used_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("last_used_at").ok().flatten(),
+        "created_by": row.try_get::<Option<i64>, _>("created_by").ok().flatten(),
+        "revoked_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("revoked_at").ok().flatten(),
+        "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+    })
+}
+
+pub async fn list_keys(req: HttpRequest, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin", "analyst"]).await?;
+    let rows = sqlx::query(&format!("SELECT {} FROM api_keys ORDER BY id DESC", KEY_COLUMNS))
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let items: Vec<serde_json::Value> = rows.iter().map(key_json).collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items}))))
+}
+
+#[derive(Deserialize)]
+pub struct CreateKeyRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub rate_limit_per_min: Option<i32>,
+}
+
+/// Create a key; the signing secret is returned only in this response
+pub async fn create_key(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<CreateKeyRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let p = payload.into_inner();
+    let name = p.name.trim();
+    if name.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_name", "name required")));
+    }
+    let mut scopes: Vec<String> = p.scopes.iter().map(|s| s.trim().to_string()).collect();
+    scopes.sort();
+    scopes.dedup();
+    if scopes.is_empty() || scopes.iter().any(|s| !API_KEY_SCOPES.contains(&s.as_str())) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_scopes", &format!("scopes must be a non-empty subset of {:?}", API_KEY_SCOPES))));
+    }
+    let rate_limit = p.rate_limit_per_min.unwrap_or(60);
+    if !(1..=10_000).contains(&rate_limit) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_rate_limit", "rate_limit_per_min must be 1-10000")));
+    }
+
+    let key_id = generate_key_id();
+    let secret = generate_secret();
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = sqlx::query(&format!(
+        "INSERT INTO api_keys (key_id, secret, name, scopes, rate_limit_per_min, created_by) VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+        KEY_COLUMNS
+    ))
+    .bind(&key_id).bind(&secret).bind(name).bind(&scopes).bind(rate_limit).bind(actor_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    let id: i64 = row.try_get("id").unwrap_or_default();
+    audit::record(&mut *tx, actor_id, "admin.api_key_create", "api_keys", Some(id), serde_json::json!({"key_id": key_id, "name": name, "scopes": scopes, "rate_limit_per_min": rate_limit}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+
+    let mut body = key_json(&row);
+    body["secret"] = serde_json::json!(secret);
+    Ok(HttpResponse::Created().json(ApiResponse::success(body)))
+}
+
+pub async fn revoke_key(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let id = path.into_inner();
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let res = sqlx::query("UPDATE api_keys SET status = 'revoked', revoked_at = COALESCE(revoked_at, NOW()) WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    if res.rows_affected() == 0 {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "api key not found")));
+    }
+    audit::record(&mut *tx, actor_id, "admin.api_key_revoke", "api_keys", Some(id), serde_json::json!({}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id, "status": "revoked"}))))
+}
+
+#[derive(Deserialize)]
+pub struct UsageQuery { pub days: Option<i32> }
+
+/// Daily request counts for a key (default last 30 days)
+pub async fn get_key_usage(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, query: web::Query<UsageQuery>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin", "analyst"]).await?;
+    let id = path.into_inner();
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    let key = sqlx::query(&format!("SELECT {} FROM api_keys WHERE id = $1", KEY_COLUMNS))
+        .bind(id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let Some(key) = key else {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "api key not found")));
+    };
+    let rows = sqlx::query("SELECT day, requests FROM api_key_usage_daily WHERE api_key_id = $1 AND day > CURRENT_DATE - $2 ORDER BY day DESC")
+        .bind(id)
+        .bind(days)
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let daily: Vec<serde_json::Value> = rows.into_iter().map(|r| serde_json::json!({
+        "day": r.try_get::<chrono::NaiveDate, _>("day").ok(),
+        "requests": r.try_get::<i64, _>("requests").unwrap_or_default(),
+    })).collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"key": key_json(&key), "daily": daily}))))
+}
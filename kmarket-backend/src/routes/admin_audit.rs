@@ -0,0 +1,124 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use serde::Deserialize;
+use sqlx::{Postgres, QueryBuilder, Row};
+
+use crate::state::AppState;
+use crate::utils::audit::{verify_chain, verify_order_audits_chain};
+use crate::utils::auth::{admin_actor_id, require_role};
+use crate::utils::errors::AppError;
+use crate::utils::response::ApiResponse;
+
+#[derive(Deserialize)]
+pub struct AuditLogsQuery {
+    pub actor_id: Option<i64>,
+    pub action: Option<String>,
+    pub resource: Option<String>,
+    pub resource_id: Option<i64>,
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+    /// Opaque cursor from the previous page's next_cursor
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Audit log search, newest first, keyset-paginated on id
+pub async fn list_audit_logs(req: HttpRequest, state: web::Data<AppState>, query: web::Query<AuditLogsQuery>) -> Result<HttpResponse> {
+    let actor = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor, &["admin", "analyst"]).await?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let cursor = match query.cursor.as_deref().map(str::parse::<i64>) {
+        None => None,
+        Some(Ok(id)) => Some(id),
+        Some(Err(_)) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_cursor", "cursor invalid"))),
+    };
+
+    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+        "SELECT id, chain_seq, actor_id, action, resource, resource_id, payload_json, row_hash, created_at FROM audit_logs WHERE TRUE"
+    );
+    if let Some(v) = query.actor_id { qb.push(" AND actor_id = ").push_bind(v); }
+    if let Some(v) = &query.action {
+        // trailing '*' matches a prefix, e.g. action=admin.market_*
+        match v.strip_suffix('*') {
+            Some(prefix) => { qb.push(" AND action LIKE ").push_bind(format!("{}%", prefix.replace('%', "\\%").replace('_', "\\_"))); }
+            None => { qb.push(" AND action = ").push_bind(v.clone()); }
+        }
+    }
+    if let Some(v) = &query.resource { qb.push(" AND resource = ").push_bind(v.clone()); }
+    if let Some(v) = query.resource_id { qb.push(" AND resource_id = ").push_bind(v); }
+    if let Some(v) = query.from { qb.push(" AND created_at >= ").push_bind(v); }
+    if let Some(v) = query.to { qb.push(" AND created_at < ").push_bind(v); }
+    if let Some(v) = cursor { qb.push(" AND id < ").push_bind(v); }
+    qb.push(" ORDER BY id DESC LIMIT ").push_bind(limit + 1);
+
+    let mut rows = qb.build().fetch_all(&state.db_pool).await.map_err(AppError::from)?;
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    let items: Vec<serde_json::Value> = rows.iter().map(|row| serde_json::json!({
+        "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+        "chain_seq": row.try_get::<Option<i64>, _>("chain_seq").ok().flatten(),
+        "actor_id": row.try_get::<i64, _>("actor_id").unwrap_or_default(),
+        "action": row.try_get::<String, _>("action").unwrap_or_default(),
+        "resource": row.try_get::<String, _>("resource").unwrap_or_default(),
+        "resource_id": row.try_get::<Option<i64>, _>("resource_id").ok().flatten(),
+        "payload": row.try_get::<Option<serde_json::Value>, _>("payload_json").ok().flatten(),
+        "row_hash": row.try_get::<Option<String>, _>("row_hash").ok().flatten(),
+        "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+    })).collect();
+    let next_cursor = if has_more { rows.last().and_then(|r| r.try_get::<i64, _>("id").ok()).map(|id| id.to_string()) } else { None };
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items, "next_cursor": next_cursor}))))
+}
+
+/// Recompute the audit hash chain and report the first broken link, if any
+pub async fn verify_audit_chain(req: HttpRequest, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let actor = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor, &["admin", "analyst"]).await?;
+    let mut conn = state.db_pool.acquire().await.map_err(AppError::from)?;
+    let report = verify_chain(&mut conn).await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(report)))
+}
+
+/// Verify the order_audits hash chain the same way
+pub async fn verify_order_audit_chain(req: HttpRequest, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let actor = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor, &["admin", "analyst"]).await?;
+    let mut conn = state.db_pool.acquire().await.map_err(AppError::from)?;
+    let report = verify_order_audits_chain(&mut conn).await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(report)))
+}
+
+#[derive(Deserialize)]
+pub struct OrderAuditsQuery {
+    pub order_id: Option<i64>,
+    pub action: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Order lifecycle events (order_audits), newest first, keyset-paginated on id
+pub async fn list_order_audits(req: HttpRequest, state: web::Data<AppState>, query: web::Query<OrderAuditsQuery>) -> Result<HttpResponse> {
+    let _actor = admin_actor_id(&req, &state.db_pool).await?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let cursor = match query.cursor.as_deref().map(str::parse::<i64>) {
+        None => None,
+        Some(Ok(id)) => Some(id),
+        Some(Err(_)) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_cursor", "cursor invalid"))),
+    };
+    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new("SELECT id, order_id, action, detail, created_at FROM order_audits WHERE TRUE");
+    if let Some(v) = query.order_id { qb.push(" AND order_id = ").push_bind(v); }
+    if let Some(v) = &query.action { qb.push(" AND action = ").push_bind(v.clone()); }
+    if let Some(v) = cursor { qb.push(" AND id < ").push_bind(v); }
+    qb.push(" ORDER BY id DESC LIMIT ").push_bind(limit + 1);
+
+    let mut rows = qb.build().fetch_all(&state.db_pool).await.map_err(AppError::from)?;
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    let items: Vec<serde_json::Value> = rows.iter().map(|row| serde_json::json!({
+        "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+        "order_id": row.try_get::<i64, _>("order_id").unwrap_or_default(),
+        "action": row.try_get::<String, _>("action").unwrap_or_default(),
+        "detail": row.try_get::<Option<serde_json::Value>, _>("detail").ok().flatten(),
+        "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+    })).collect();
+    let next_cursor = if has_more { rows.last().and_then(|r| r.try_get::<i64, _>("id").ok()).map(|id| id.to_string()) } else { None };
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items, "next_cursor": next_cursor}))))
+}
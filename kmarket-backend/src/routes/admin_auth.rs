@@ -1,11 +1,15 @@
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpResponse, HttpRequest, Result};
 use serde::Deserialize;
-use sqlx::Row;
+use sqlx::{PgExecutor, PgPool, Row};
 use chrono::{Utc, Duration};
-use jsonwebtoken::{encode, EncodingKey, Header};
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
 
 use crate::state::AppState;
+use crate::utils::auth::{issue_admin_token, decode_admin_token, PURPOSE_ACCESS, PURPOSE_MFA};
+use crate::utils::audit;
+use crate::utils::mfa;
+use crate::utils::rate_limit::{client_ip, too_many_requests, Decision};
+use crate::utils::errors::AppError;
 use crate::utils::response::ApiResponse;
 
 #[derive(Deserialize)]
@@ -14,33 +18,60 @@ pub struct LoginRequest {
     pub password: String,
 }
 
-#[derive(serde::Serialize)]
-struct Claims {
-    sub: String,
-    exp: usize,
-    iat: usize,
+async fn audit<'e, E: PgExecutor<'e>>(executor: E, actor_id: i64, action: &str, resource_id: Option<i64>, payload: serde_json::Value) -> Result<()> {
+    audit::record(executor, actor_id, action, "admin_users", resource_id, payload)
+        .await
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+async fn touch_last_login(pool: &PgPool, admin_id: i64) {
+    if let Err(e) = sqlx::query("UPDATE admin_users SET last_login_at = NOW() WHERE id = $1")
+        .bind(admin_id)
+        .execute(pool)
+        .await
+    {
+        tracing::warn!(target: "kmarket_backend", "recording last login of admin {}: {}", admin_id, e);
+    }
+}
+
+fn unix_now() -> u64 { Utc::now().timestamp().max(0) as u64 }
+
+/// Codes are only 6 digits, so every check of one (login, enrollment, changes to MFA) shares
+/// the login budget of the client and the account
+async fn limit_code_checks(req: &HttpRequest, state: &AppState, email: &str) -> Option<HttpResponse> {
+    let limiter = &state.rate_limiter;
+    let ip = client_ip(req);
+    let account = email.to_lowercase();
+    match limiter.check_all(&[(&limiter.policies.login_ip, &ip), (&limiter.policies.login_account, &account)]).await {
+        Decision::Limited { retry_after_secs } => Some(too_many_requests(retry_after_secs)),
+        _ => None,
+    }
 }
 
-pub async fn login(state: web::Data<AppState>, payload: web::Json<LoginRequest>) -> Result<HttpResponse> {
+pub async fn login(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<LoginRequest>) -> Result<HttpResponse> {
     let email = payload.email.trim();
     let password = payload.password.trim();
 
+    // Brute-force protection: throttle per client IP and per target account before touching the DB
+    let limiter = &state.rate_limiter;
+    let ip = client_ip(&req);
+    let account = email.to_lowercase();
+    if let Decision::Limited { retry_after_secs } = limiter.check_all(&[(&limiter.policies.login_ip, &ip), (&limiter.policies.login_account, &account)]).await {
+        audit(&state.db_pool, 0, "admin.login_throttled", None, serde_json::json!({"email": email, "ip": ip})).await?;
+        return Ok(too_many_requests(retry_after_secs));
+    }
+
     // Lookup admin user
-    let row = sqlx::query("SELECT id, email, password_hash, salt, status FROM admin_users WHERE email = $1")
+    let row = sqlx::query("SELECT id, email, password_hash, salt, status, totp_enabled, must_reset_password FROM admin_users WHERE email = $1")
         .bind(email)
         .fetch_optional(&state.db_pool)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
 
     if row.is_none() {
         // Audit: login failed (unknown user)
-        let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, payload_json) VALUES ($1, $2, $3, $4)")
-            .bind(0i64)
-            .bind("admin.login_failed")
-            .bind("admin_users")
-            .bind(serde_json::json!({"email": email}))
-            .execute(&state.db_pool)
-            .await;
+        audit(&state.db_pool, 0, "admin.login_failed", None, serde_json::json!({"email": email})).await?;
         return Ok(HttpResponse::Unauthorized().json(ApiResponse::<()>::error("invalid_credentials", "invalid credentials")));
     }
 
@@ -53,40 +84,258 @@ pub async fn login(state: web::Data<AppState>, payload: web::Json<LoginRequest>)
 
     let hash: String = row.try_get("password_hash").unwrap_or_default();
     let parsed_hash = PasswordHash::new(&hash)
-        .map_err(|_| actix_web::error::ErrorInternalServerError("bad_hash"))?;
+        .map_err(|_| AppError::internal("stored password hash is malformed"))?;
     let verifier = Argon2::default();
     let is_valid = verifier.verify_password(password.as_bytes(), &parsed_hash).is_ok();
 
     if !is_valid {
         // Audit: login failed (wrong password)
-        let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id, payload_json) VALUES ($1, $2, $3, $4, $5)")
-            .bind(admin_id)
-            .bind("admin.login_failed")
-            .bind("admin_users")
-            .bind(admin_id)
-            .bind(serde_json::json!({"email": email}))
-            .execute(&state.db_pool)
-            .await;
+        audit(&state.db_pool, admin_id, "admin.login_failed", Some(admin_id), serde_json::json!({"email": email})).await?;
         return Ok(HttpResponse::Unauthorized().json(ApiResponse::<()>::error("invalid_credentials", "invalid credentials")));
     }
 
+    // Second factor: hand out a short-lived challenge token instead of the session JWT
+    let totp_enabled: bool = row.try_get("totp_enabled").unwrap_or(false);
+    if totp_enabled {
+        let mfa_token = issue_admin_token(email, PURPOSE_MFA, Duration::minutes(5))
+            .map_err(AppError::internal)?;
+        audit(&state.db_pool, admin_id, "admin.login_mfa_challenge", Some(admin_id), serde_json::json!({"email": email})).await?;
+        return Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"mfa_required": true, "mfa_token": mfa_token}))));
+    }
+
     // Issue JWT
-    let now = Utc::now();
-    let exp = now + Duration::minutes(30);
-    let claims = Claims { sub: email.to_string(), iat: now.timestamp() as usize, exp: exp.timestamp() as usize };
-    let secret = std::env::var("ADMIN_JWT_SECRET").unwrap_or_else(|_| "dev_admin_secret".to_string());
-    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes()))
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let token = issue_admin_token(email, PURPOSE_ACCESS, Duration::minutes(30))
+        .map_err(AppError::internal)?;
 
     // Audit: login success
-    let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id, payload_json) VALUES ($1, $2, $3, $4, $5)")
+    audit(&state.db_pool, admin_id, "admin.login_success", Some(admin_id), serde_json::json!({"email": email})).await?;
+    touch_last_login(&state.db_pool, admin_id).await;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+        "token": token,
+        "mfa_required": false,
+        "mfa_enrollment_required": mfa::mfa_required(),
+        "password_reset_required": row.try_get::<bool, _>("must_reset_password").unwrap_or(false),
+    }))))
+}
+
+/// Check a TOTP code (with replay protection) or, failing that, consume a recovery code.
+/// Returns which factor was accepted.
+async fn consume_second_factor(pool: &PgPool, admin_id: i64, secret: &str, code: &str) -> Result<Option<&'static str>, sqlx::Error> {
+    if let Some(step) = mfa::verify_code(secret, code, unix_now()) {
+        // Conditional update makes concurrent reuse of the same code fail
+        let res = sqlx::query("UPDATE admin_users SET totp_last_step = $1 WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1)")
+            .bind(step)
+            .bind(admin_id)
+            .execute(pool)
+            .await?;
+        return Ok(if res.rows_affected() > 0 { Some("totp") } else { None });
+    }
+    let res = sqlx::query("UPDATE admin_recovery_codes SET used_at = NOW() WHERE admin_id = $1 AND code_hash = $2 AND used_at IS NULL")
         .bind(admin_id)
-        .bind("admin.login_success")
-        .bind("admin_users")
+        .bind(mfa::hash_recovery_code(code))
+        .execute(pool)
+        .await?;
+    Ok(if res.rows_affected() > 0 { Some("recovery_code") } else { None })
+}
+
+#[derive(Deserialize)]
+pub struct VerifyMfaRequest {
+    pub mfa_token: String,
+    pub code: String,
+}
+
+pub async fn verify_mfa(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<VerifyMfaRequest>) -> Result<HttpResponse> {
+    let p = payload.into_inner();
+    let email = decode_admin_token(p.mfa_token.trim(), PURPOSE_MFA)?;
+    if let Some(limited) = limit_code_checks(&req, &state, &email).await {
+        return Ok(limited);
+    }
+    let row = sqlx::query("SELECT id, status, totp_secret, totp_enabled FROM admin_users WHERE email = $1")
+        .bind(&email)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let Some(row) = row else {
+        return Ok(HttpResponse::Unauthorized().json(ApiResponse::<()>::error("invalid_credentials", "invalid credentials")));
+    };
+    let admin_id: i64 = row.try_get("id").unwrap_or(0);
+    let status: String = row.try_get("status").unwrap_or_else(|_| "active".into());
+    if status != "active" {
+        return Ok(HttpResponse::Unauthorized().json(ApiResponse::<()>::error("account_disabled", "account disabled")));
+    }
+    let secret: Option<String> = row.try_get("totp_secret").ok().flatten();
+    let enabled: bool = row.try_get("totp_enabled").unwrap_or(false);
+    let (true, Some(secret)) = (enabled, secret) else {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("mfa_not_enabled", "mfa not enabled")));
+    };
+
+    let factor = consume_second_factor(&state.db_pool, admin_id, &secret, &p.code)
+        .await
+        .map_err(AppError::from)?;
+    let Some(factor) = factor else {
+        audit(&state.db_pool, admin_id, "admin.mfa_failed", Some(admin_id), serde_json::json!({"email": email})).await?;
+        return Ok(HttpResponse::Unauthorized().json(ApiResponse::<()>::error("invalid_mfa_code", "invalid mfa code")));
+    };
+
+    let token = issue_admin_token(&email, PURPOSE_ACCESS, Duration::minutes(30))
+        .map_err(AppError::internal)?;
+    audit(&state.db_pool, admin_id, "admin.login_success", Some(admin_id), serde_json::json!({"email": email, "mfa": factor})).await?;
+    touch_last_login(&state.db_pool, admin_id).await;
+    let must_reset: bool = sqlx::query_scalar("SELECT must_reset_password FROM admin_users WHERE id = $1")
         .bind(admin_id)
-        .bind(serde_json::json!({"email": email}))
-        .execute(&state.db_pool)
-        .await;
+        .fetch_one(&state.db_pool)
+        .await
+        .unwrap_or(false);
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"token": token, "password_reset_required": must_reset}))))
+}
+
+/// Start enrollment: generate a pending secret and return the otpauth URI
+pub async fn enroll_mfa(req: HttpRequest, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let actor_id = crate::utils::auth::admin_actor_id_allow_pending(&req, &state.db_pool).await?;
+    let row = sqlx::query("SELECT email, totp_enabled FROM admin_users WHERE id = $1")
+        .bind(actor_id)
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    if row.try_get::<bool, _>("totp_enabled").unwrap_or(false) {
+        return Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("mfa_already_enabled", "mfa already enabled")));
+    }
+    let email: String = row.try_get("email").unwrap_or_default();
+    let secret = mfa::generate_secret();
+    let uri = mfa::otpauth_uri(&secret, &email)
+        .ok_or_else(|| AppError::internal("TOTP setup failed"))?;
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    sqlx::query("UPDATE admin_users SET totp_secret = $1, totp_last_step = NULL WHERE id = $2")
+        .bind(&secret)
+        .bind(actor_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    audit(&mut *tx, actor_id, "admin.mfa_enroll", Some(actor_id), serde_json::json!({})).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"secret": secret, "otpauth_uri": uri}))))
+}
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"token": token}))))
-}
\ No newline at end of file
+#[derive(Deserialize)]
+pub struct MfaCodeRequest { pub code: String }
+
+async fn replace_recovery_codes(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, admin_id: i64) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query("DELETE FROM admin_recovery_codes WHERE admin_id = $1")
+        .bind(admin_id)
+        .execute(&mut **tx)
+        .await?;
+    let codes = mfa::generate_recovery_codes(mfa::RECOVERY_CODE_COUNT);
+    for c in &codes {
+        sqlx::query("INSERT INTO admin_recovery_codes (admin_id, code_hash) VALUES ($1, $2)")
+            .bind(admin_id)
+            .bind(mfa::hash_recovery_code(c))
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(codes)
+}
+
+/// Finish enrollment: verify a code against the pending secret, enable TOTP and return recovery codes once
+pub async fn activate_mfa(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<MfaCodeRequest>) -> Result<HttpResponse> {
+    let actor_id = crate::utils::auth::admin_actor_id_allow_pending(&req, &state.db_pool).await?;
+    let row = sqlx::query("SELECT email, totp_secret, totp_enabled FROM admin_users WHERE id = $1")
+        .bind(actor_id)
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    if row.try_get::<bool, _>("totp_enabled").unwrap_or(false) {
+        return Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("mfa_already_enabled", "mfa already enabled")));
+    }
+    let Some(secret) = row.try_get::<Option<String>, _>("totp_secret").ok().flatten() else {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("mfa_not_enrolled", "call enroll first")));
+    };
+    if let Some(limited) = limit_code_checks(&req, &state, &row.try_get::<String, _>("email").unwrap_or_default()).await {
+        return Ok(limited);
+    }
+    let Some(step) = mfa::verify_code(&secret, &payload.code, unix_now()) else {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_mfa_code", "invalid mfa code")));
+    };
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    sqlx::query("UPDATE admin_users SET totp_enabled = true, totp_enabled_at = NOW(), totp_last_step = $1 WHERE id = $2")
+        .bind(step)
+        .bind(actor_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    let codes = replace_recovery_codes(&mut tx, actor_id).await.map_err(AppError::from)?;
+    audit(&mut *tx, actor_id, "admin.mfa_enabled", Some(actor_id), serde_json::json!({})).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"enabled": true, "recovery_codes": codes}))))
+}
+
+/// Issue a fresh set of recovery codes; requires a valid TOTP code
+pub async fn regenerate_recovery_codes(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<MfaCodeRequest>) -> Result<HttpResponse> {
+    let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
+    let row = sqlx::query("SELECT email, totp_secret, totp_enabled FROM admin_users WHERE id = $1")
+        .bind(actor_id)
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let enabled: bool = row.try_get("totp_enabled").unwrap_or(false);
+    let (true, Some(secret)) = (enabled, row.try_get::<Option<String>, _>("totp_secret").ok().flatten()) else {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("mfa_not_enabled", "mfa not enabled")));
+    };
+    if let Some(limited) = limit_code_checks(&req, &state, &row.try_get::<String, _>("email").unwrap_or_default()).await {
+        return Ok(limited);
+    }
+    let factor = consume_second_factor(&state.db_pool, actor_id, &secret, &payload.code)
+        .await
+        .map_err(AppError::from)?;
+    if factor != Some("totp") {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_mfa_code", "invalid mfa code")));
+    }
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let codes = replace_recovery_codes(&mut tx, actor_id).await.map_err(AppError::from)?;
+    audit(&mut *tx, actor_id, "admin.mfa_recovery_codes", Some(actor_id), serde_json::json!({})).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"recovery_codes": codes}))))
+}
+
+/// Turn TOTP off for the calling admin; refused while ADMIN_MFA_REQUIRED is set
+pub async fn disable_mfa(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<MfaCodeRequest>) -> Result<HttpResponse> {
+    let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
+    if mfa::mfa_required() {
+        return Ok(HttpResponse::Forbidden().json(ApiResponse::<()>::error("mfa_required", "mfa is enforced for admins")));
+    }
+    let row = sqlx::query("SELECT email, totp_secret, totp_enabled FROM admin_users WHERE id = $1")
+        .bind(actor_id)
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let enabled: bool = row.try_get("totp_enabled").unwrap_or(false);
+    let (true, Some(secret)) = (enabled, row.try_get::<Option<String>, _>("totp_secret").ok().flatten()) else {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("mfa_not_enabled", "mfa not enabled")));
+    };
+    if let Some(limited) = limit_code_checks(&req, &state, &row.try_get::<String, _>("email").unwrap_or_default()).await {
+        return Ok(limited);
+    }
+    let factor = consume_second_factor(&state.db_pool, actor_id, &secret, &payload.code)
+        .await
+        .map_err(AppError::from)?;
+    if factor.is_none() {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_mfa_code", "invalid mfa code")));
+    }
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    sqlx::query("UPDATE admin_users SET totp_enabled = false, totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL WHERE id = $1")
+        .bind(actor_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    sqlx::query("DELETE FROM admin_recovery_codes WHERE admin_id = $1")
+        .bind(actor_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    audit(&mut *tx, actor_id, "admin.mfa_disabled", Some(actor_id), serde_json::json!({})).await?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"enabled": false}))))
+}
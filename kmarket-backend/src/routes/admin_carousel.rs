@@ -2,6 +2,8 @@ use actix_web::{web, HttpResponse, Result, HttpRequest};
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use crate::state::AppState;
+use crate::utils::conditional::Representation;
+use crate::utils::errors::AppError;
 use crate::utils::response::ApiResponse;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -38,14 +40,18 @@ pub struct UpdateCarouselItem {
     pub enabled: Option<bool>,
 }
 
+/// Homepage banners change rarely; reuse for a minute, then revalidate with the ETag
+const CAROUSEL_CACHE_CONTROL: &str = "public, max-age=60";
+
 pub async fn list_items(_req: HttpRequest, _state: web::Data<AppState>) -> Result<HttpResponse> {
     let rows = sqlx::query(
-        "SELECT id, title, subtitle, image_url, href, \"order\", enabled, to_char(created_at, 'YYYY-MM-DD\"T\"HH24:MI:SSZ') AS created_at, to_char(updated_at, 'YYYY-MM-DD\"T\"HH24:MI:SSZ') AS updated_at FROM carousel_items ORDER BY \"order\" ASC"
+        "SELECT id, title, subtitle, image_url, href, \"order\", enabled, to_char(created_at, 'YYYY-MM-DD\"T\"HH24:MI:SSZ') AS created_at, to_char(updated_at, 'YYYY-MM-DD\"T\"HH24:MI:SSZ') AS updated_at, updated_at AS modified_at FROM carousel_items ORDER BY \"order\" ASC"
     )
     .fetch_all(&_state.db_pool)
     .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    .map_err(AppError::from)?;
 
+    let modified: Vec<chrono::DateTime<chrono::Utc>> = rows.iter().filter_map(|row| row.try_get("modified_at").ok()).collect();
     let items: Vec<CarouselItem> = rows.into_iter().map(|row| CarouselItem {
         id: row.try_get::<String, _>("id").unwrap_or_default(),
         title: row.try_get::<String, _>("title").unwrap_or_default(),
@@ -58,7 +64,7 @@ pub async fn list_items(_req: HttpRequest, _state: web::Data<AppState>) -> Resul
         updated_at: row.try_get::<Option<String>, _>("updated_at").unwrap_or(None),
     }).collect();
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({ "items": items }))))
+    Ok(Representation::json(&ApiResponse::success(serde_json::json!({ "items": items })), modified)?.respond(&_req, CAROUSEL_CACHE_CONTROL))
 }
 
 pub async fn create_item(_req: HttpRequest, _state: web::Data<AppState>, payload: web::Json<CreateCarouselItem>) -> Result<HttpResponse> {
@@ -78,7 +84,7 @@ pub async fn create_item(_req: HttpRequest, _state: web::Data<AppState>, payload
     .bind(enabled)
     .fetch_one(&_state.db_pool)
     .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    .map_err(AppError::from)?;
 
     let item = CarouselItem {
         id: row.try_get::<String, _>("id").unwrap_or(id),
@@ -115,7 +121,7 @@ pub async fn update_item(_req: HttpRequest, _state: web::Data<AppState>, path: w
             .bind(&id)
             .fetch_optional(&_state.db_pool)
             .await
-            .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+            .map_err(AppError::from)?;
         if let Some(row) = row {
             let item = CarouselItem {
                 id: row.try_get::<String, _>("id").unwrap_or(id),
@@ -134,7 +140,7 @@ pub async fn update_item(_req: HttpRequest, _state: web::Data<AppState>, path: w
         }
     }
 
-    let mut sql = format!("UPDATE carousel_items SET {} , updated_at = NOW() WHERE id = ${} RETURNING id, title, subtitle, image_url, href, \"order\", enabled, to_char(created_at, 'YYYY-MM-DD\"T\"HH24:MI:SSZ') AS created_at, to_char(updated_at, 'YYYY-MM-DD\"T\"HH24:MI:SSZ') AS updated_at", sets.join(", "), sets.len() + 1);
+    let sql = format!("UPDATE carousel_items SET {} , updated_at = NOW() WHERE id = ${} RETURNING id, title, subtitle, image_url, href, \"order\", enabled, to_char(created_at, 'YYYY-MM-DD\"T\"HH24:MI:SSZ') AS created_at, to_char(updated_at, 'YYYY-MM-DD\"T\"HH24:MI:SSZ') AS updated_at", sets.join(", "), sets.len() + 1);
     let mut q = sqlx::query(&sql);
     for v in binds {
         q = match v {
@@ -149,7 +155,7 @@ pub async fn update_item(_req: HttpRequest, _state: web::Data<AppState>, path: w
         };
     }
     q = q.bind(&id);
-    let row = q.fetch_one(&_state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let row = q.fetch_one(&_state.db_pool).await.map_err(AppError::from)?;
     let item = CarouselItem {
         id: row.try_get::<String, _>("id").unwrap_or(id),
         title: row.try_get::<String, _>("title").unwrap_or_default(),
@@ -170,7 +176,7 @@ pub async fn delete_item(_req: HttpRequest, _state: web::Data<AppState>, path: w
         .bind(&id)
         .execute(&_state.db_pool)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     if res.rows_affected() > 0 {
         Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({ "deleted": true, "id": id }))))
     } else {
@@ -0,0 +1,405 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use serde::Deserialize;
+use sqlx::{Postgres, QueryBuilder, Row};
+
+use crate::state::AppState;
+use crate::utils::audit;
+use crate::utils::auth::{admin_actor_id, require_role};
+use crate::utils::errors::AppError;
+use crate::utils::response::ApiResponse;
+
+const READ_ROLES: [&str; 3] = ["admin", "operator", "analyst"];
+const WRITE_ROLES: [&str; 2] = ["admin", "operator"];
+
+const SPORT_COLUMNS: &str = "id, code, name, created_at, updated_at";
+const LEAGUE_COLUMNS: &str = "id, sport_id, code, name, country, logo_url, created_at, updated_at";
+const TEAM_COLUMNS: &str = "id, sport_id, name, code, country, logo_url, created_at, updated_at";
+
+fn sport_json(row: &sqlx::postgres::PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+        "code": row.try_get::<String, _>("code").unwrap_or_default(),
+        "name": row.try_get::<String, _>("name").unwrap_or_default(),
+        "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+        "updated_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("updated_at").ok(),
+    })
+}
+
+fn league_json(row: &sqlx::postgres::PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+        "sport_id": row.try_get::<i64, _>("sport_id").unwrap_or_default(),
+        "code": row.try_get::<String, _>("code").unwrap_or_default(),
+        "name": row.try_get::<String, _>("name").unwrap_or_default(),
+        "country": row.try_get::<Option<String>, _>("country").ok().flatten(),
+        "logo_url": row.try_get::<Option<String>, _>("logo_url").ok().flatten(),
+        "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+        "updated_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("updated_at").ok(),
+    })
+}
+
+fn team_json(row: &sqlx::postgres::PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+        "sport_id": row.try_get::<i64, _>("sport_id").unwrap_or_default(),
+        "name": row.try_get::<String, _>("name").unwrap_or_default(),
+        "code": row.try_get::<Option<String>, _>("code").ok().flatten(),
+        "country": row.try_get::<Option<String>, _>("country").ok().flatten(),
+        "logo_url": row.try_get::<Option<String>, _>("logo_url").ok().flatten(),
+        "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+        "updated_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("updated_at").ok(),
+    })
+}
+
+fn bad_request(msg: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_args", msg))
+}
+
+fn not_found(what: &str) -> HttpResponse {
+    HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", &format!("{} not found", what)))
+}
+
+/// Unique and foreign key violations become 409 / 400 responses; anything else is a 500
+fn db_error(e: sqlx::Error) -> Result<HttpResponse> {
+    match e.as_database_error().and_then(|d| d.code()).as_deref() {
+        Some("23505") => Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("already_exists", "code or name already in use"))),
+        Some("23503") => Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("in_use", "referenced by other catalog entries or markets"))),
+        _ => Err(AppError::from(e).into()),
+    }
+}
+
+/// Trimmed text within max length; empty is rejected
+fn text(v: &str, max: usize) -> Option<&str> {
+    let v = v.trim();
+    (!v.is_empty() && v.len() <= max).then_some(v)
+}
+
+fn valid_logo(url: &str) -> bool {
+    (url.starts_with("https://") || url.starts_with("http://")) && url.len() <= 2048 && !url.contains(char::is_whitespace)
+}
+
+// ---- sports ----
+
+pub async fn list_sports(req: HttpRequest, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &READ_ROLES).await?;
+    let rows = sqlx::query(&format!("SELECT {} FROM sports ORDER BY name", SPORT_COLUMNS))
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let items: Vec<serde_json::Value> = rows.iter().map(sport_json).collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items}))))
+}
+
+#[derive(Deserialize)]
+pub struct SportRequest {
+    pub code: Option<String>,
+    pub name: Option<String>,
+}
+
+pub async fn create_sport(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<SportRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let (Some(code), Some(name)) = (payload.code.as_deref().and_then(|v| text(v, 32)), payload.name.as_deref().and_then(|v| text(v, 64))) else {
+        return Ok(bad_request("code (max 32) and name (max 64) are required"));
+    };
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = match sqlx::query(&format!("INSERT INTO sports (code, name) VALUES ($1, $2) RETURNING {}", SPORT_COLUMNS))
+        .bind(code.to_ascii_lowercase())
+        .bind(name)
+        .fetch_one(&mut *tx)
+        .await
+    {
+        Ok(row) => row,
+        Err(e) => return db_error(e),
+    };
+    let id: i64 = row.try_get("id").unwrap_or_default();
+    audit::record(&mut *tx, actor_id, "admin.sport_create", "sports", Some(id), serde_json::json!({"code": code, "name": name}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(sport_json(&row))))
+}
+
+pub async fn update_sport(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<SportRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let id = path.into_inner();
+    let code = match payload.code.as_deref().map(|v| text(v, 32)) {
+        Some(None) => return Ok(bad_request("code must be 1-32 characters")),
+        v => v.flatten().map(str::to_ascii_lowercase),
+    };
+    let name = match payload.name.as_deref().map(|v| text(v, 64)) {
+        Some(None) => return Ok(bad_request("name must be 1-64 characters")),
+        v => v.flatten(),
+    };
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = match sqlx::query(&format!("UPDATE sports SET code = COALESCE($2, code), name = COALESCE($3, name) WHERE id = $1 RETURNING {}", SPORT_COLUMNS))
+        .bind(id)
+        .bind(&code)
+        .bind(name)
+        .fetch_optional(&mut *tx)
+        .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => return Ok(not_found("sport")),
+        Err(e) => return db_error(e),
+    };
+    audit::record(&mut *tx, actor_id, "admin.sport_update", "sports", Some(id), serde_json::json!({"code": code, "name": name}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(sport_json(&row))))
+}
+
+/// Delete a catalog row; refused with 409 while something still references it
+async fn delete_row(req: HttpRequest, state: web::Data<AppState>, id: i64, table: &str, what: &str) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let res = match sqlx::query(&format!("DELETE FROM {} WHERE id = $1", table)).bind(id).execute(&mut *tx).await {
+        Ok(res) => res,
+        Err(e) => return db_error(e),
+    };
+    if res.rows_affected() == 0 {
+        return Ok(not_found(what));
+    }
+    audit::record(&mut *tx, actor_id, &format!("admin.{}_delete", what), table, Some(id), serde_json::json!({}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id, "deleted": true}))))
+}
+
+pub async fn delete_sport(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+    delete_row(req, state, path.into_inner(), "sports", "sport").await
+}
+
+// ---- leagues ----
+
+#[derive(Deserialize)]
+pub struct LeaguesQuery {
+    pub sport_id: Option<i64>,
+}
+
+pub async fn list_leagues(req: HttpRequest, state: web::Data<AppState>, query: web::Query<LeaguesQuery>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &READ_ROLES).await?;
+    let rows = sqlx::query(&format!("SELECT {} FROM leagues WHERE ($1::BIGINT IS NULL OR sport_id = $1) ORDER BY name", LEAGUE_COLUMNS))
+        .bind(query.sport_id)
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let items: Vec<serde_json::Value> = rows.iter().map(league_json).collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items}))))
+}
+
+#[derive(Deserialize)]
+pub struct LeagueRequest {
+    pub sport_id: Option<i64>,
+    pub code: Option<String>,
+    pub name: Option<String>,
+    pub country: Option<String>,
+    pub logo_url: Option<String>,
+}
+
+/// Shared field checks for league and team payloads: (code, name, country, logo_url)
+fn catalog_fields(code: Option<&str>, code_max: usize, name: Option<&str>, country: Option<&str>, logo_url: Option<&str>) -> Result<[Option<String>; 4], HttpResponse> {
+    let code = match code.map(|v| text(v, code_max)) {
+        Some(None) => return Err(bad_request(&format!("code must be 1-{} characters", code_max))),
+        v => v.flatten().map(str::to_ascii_uppercase),
+    };
+    let name = match name.map(|v| text(v, 128)) {
+        Some(None) => return Err(bad_request("name must be 1-128 characters")),
+        v => v.flatten().map(str::to_string),
+    };
+    let country = match country.map(|v| text(v, 64)) {
+        Some(None) => return Err(bad_request("country must be 1-64 characters")),
+        v => v.flatten().map(str::to_string),
+    };
+    let logo_url = logo_url.map(str::trim);
+    if logo_url.is_some_and(|u| !valid_logo(u)) {
+        return Err(bad_request("logo_url must be an http(s) URL"));
+    }
+    Ok([code, name, country, logo_url.map(str::to_string)])
+}
+
+pub async fn create_league(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<LeagueRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let p = payload.into_inner();
+    let [code, name, country, logo_url] = match catalog_fields(p.code.as_deref(), 32, p.name.as_deref(), p.country.as_deref(), p.logo_url.as_deref()) {
+        Ok(f) => f,
+        Err(resp) => return Ok(resp),
+    };
+    let (Some(sport_id), Some(code), Some(name)) = (p.sport_id, code, name) else {
+        return Ok(bad_request("sport_id, code and name are required"));
+    };
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = match sqlx::query(&format!(
+        "INSERT INTO leagues (sport_id, code, name, country, logo_url) VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+        LEAGUE_COLUMNS
+    ))
+    .bind(sport_id).bind(&code).bind(&name).bind(&country).bind(&logo_url)
+    .fetch_one(&mut *tx)
+    .await
+    {
+        Ok(row) => row,
+        Err(e) if e.as_database_error().and_then(|d| d.code()).as_deref() == Some("23503") => return Ok(bad_request("sport not found")),
+        Err(e) => return db_error(e),
+    };
+    let id: i64 = row.try_get("id").unwrap_or_default();
+    audit::record(&mut *tx, actor_id, "admin.league_create", "leagues", Some(id), serde_json::json!({"sport_id": sport_id, "code": code, "name": name}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(league_json(&row))))
+}
+
+/// Rename or re-describe a league; moving it to another sport is not supported
+pub async fn update_league(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<LeagueRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let id = path.into_inner();
+    let p = payload.into_inner();
+    if p.sport_id.is_some() {
+        return Ok(bad_request("sport_id cannot be changed"));
+    }
+    let [code, name, country, logo_url] = match catalog_fields(p.code.as_deref(), 32, p.name.as_deref(), p.country.as_deref(), p.logo_url.as_deref()) {
+        Ok(f) => f,
+        Err(resp) => return Ok(resp),
+    };
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = match sqlx::query(&format!(
+        "UPDATE leagues SET code = COALESCE($2, code), name = COALESCE($3, name), country = COALESCE($4, country), \
+         logo_url = COALESCE($5, logo_url) WHERE id = $1 RETURNING {}",
+        LEAGUE_COLUMNS
+    ))
+    .bind(id).bind(&code).bind(&name).bind(&country).bind(&logo_url)
+    .fetch_optional(&mut *tx)
+    .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => return Ok(not_found("league")),
+        Err(e) => return db_error(e),
+    };
+    audit::record(&mut *tx, actor_id, "admin.league_update", "leagues", Some(id), serde_json::json!({"code": code, "name": name, "country": country, "logo_url": logo_url}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(league_json(&row))))
+}
+
+pub async fn delete_league(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+    delete_row(req, state, path.into_inner(), "leagues", "league").await
+}
+
+// ---- teams ----
+
+#[derive(Deserialize)]
+pub struct TeamsQuery {
+    pub sport_id: Option<i64>,
+    /// Name or code contains
+    pub q: Option<String>,
+    pub limit: Option<i64>,
+    /// id of the last team on the previous page
+    pub cursor: Option<i64>,
+}
+
+pub async fn list_teams(req: HttpRequest, state: web::Data<AppState>, query: web::Query<TeamsQuery>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &READ_ROLES).await?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(format!("SELECT {} FROM teams WHERE TRUE", TEAM_COLUMNS));
+    if let Some(v) = query.sport_id { qb.push(" AND sport_id = ").push_bind(v); }
+    if let Some(v) = &query.q {
+        let pat = format!("%{}%", v.trim());
+        qb.push(" AND (name ILIKE ").push_bind(pat.clone()).push(" OR code ILIKE ").push_bind(pat).push(")");
+    }
+    if let Some(v) = query.cursor { qb.push(" AND id > ").push_bind(v); }
+    qb.push(" ORDER BY id LIMIT ").push_bind(limit + 1);
+    let mut rows = qb.build().fetch_all(&state.db_pool).await.map_err(AppError::from)?;
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    let items: Vec<serde_json::Value> = rows.iter().map(team_json).collect();
+    let next_cursor = if has_more { items.last().and_then(|i| i["id"].as_i64()) } else { None };
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items, "next_cursor": next_cursor}))))
+}
+
+#[derive(Deserialize)]
+pub struct TeamRequest {
+    pub sport_id: Option<i64>,
+    pub name: Option<String>,
+    pub code: Option<String>,
+    pub country: Option<String>,
+    pub logo_url: Option<String>,
+}
+
+pub async fn create_team(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<TeamRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let p = payload.into_inner();
+    let [code, name, country, logo_url] = match catalog_fields(p.code.as_deref(), 16, p.name.as_deref(), p.country.as_deref(), p.logo_url.as_deref()) {
+        Ok(f) => f,
+        Err(resp) => return Ok(resp),
+    };
+    let (Some(sport_id), Some(name)) = (p.sport_id, name) else {
+        return Ok(bad_request("sport_id and name are required"));
+    };
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = match sqlx::query(&format!(
+        "INSERT INTO teams (sport_id, name, code, country, logo_url) VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+        TEAM_COLUMNS
+    ))
+    .bind(sport_id).bind(&name).bind(&code).bind(&country).bind(&logo_url)
+    .fetch_one(&mut *tx)
+    .await
+    {
+        Ok(row) => row,
+        Err(e) if e.as_database_error().and_then(|d| d.code()).as_deref() == Some("23503") => return Ok(bad_request("sport not found")),
+        Err(e) => return db_error(e),
+    };
+    let id: i64 = row.try_get("id").unwrap_or_default();
+    audit::record(&mut *tx, actor_id, "admin.team_create", "teams", Some(id), serde_json::json!({"sport_id": sport_id, "name": name, "code": code}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(team_json(&row))))
+}
+
+pub async fn update_team(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<TeamRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let id = path.into_inner();
+    let p = payload.into_inner();
+    if p.sport_id.is_some() {
+        return Ok(bad_request("sport_id cannot be changed"));
+    }
+    let [code, name, country, logo_url] = match catalog_fields(p.code.as_deref(), 16, p.name.as_deref(), p.country.as_deref(), p.logo_url.as_deref()) {
+        Ok(f) => f,
+        Err(resp) => return Ok(resp),
+    };
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = match sqlx::query(&format!(
+        "UPDATE teams SET name = COALESCE($2, name), code = COALESCE($3, code), country = COALESCE($4, country), \
+         logo_url = COALESCE($5, logo_url) WHERE id = $1 RETURNING {}",
+        TEAM_COLUMNS
+    ))
+    .bind(id).bind(&name).bind(&code).bind(&country).bind(&logo_url)
+    .fetch_optional(&mut *tx)
+    .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => return Ok(not_found("team")),
+        Err(e) => return db_error(e),
+    };
+    audit::record(&mut *tx, actor_id, "admin.team_update", "teams", Some(id), serde_json::json!({"name": name, "code": code, "country": country, "logo_url": logo_url}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(team_json(&row))))
+}
+
+pub async fn delete_team(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+    delete_row(req, state, path.into_inner(), "teams", "team").await
+}
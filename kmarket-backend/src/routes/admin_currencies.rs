@@ -0,0 +1,343 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use bigdecimal::{BigDecimal, Zero};
+use serde::Deserialize;
+use sqlx::Row;
+
+use crate::state::AppState;
+use crate::utils::audit;
+use crate::utils::auth::{admin_actor_id, require_role};
+use crate::utils::currency::{self, Currency, CURRENCY_COLUMNS, REPORTING_CURRENCY};
+use crate::utils::errors::AppError;
+use crate::utils::money::{self, Rounding};
+use crate::utils::response::ApiResponse;
+
+const READ_ROLES: [&str; 3] = ["admin", "operator", "analyst"];
+/// Currency settings and FX rates change money figures; admins only
+const WRITE_ROLES: [&str; 1] = ["admin"];
+
+fn bad_request(msg: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_args", msg))
+}
+
+fn not_found(what: &str) -> HttpResponse {
+    HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", &format!("{} not found", what)))
+}
+
+/// Trimmed text within max length; empty is rejected
+fn text(v: &str, max: usize) -> Option<&str> {
+    let v = v.trim();
+    (!v.is_empty() && v.len() <= max).then_some(v)
+}
+
+/// A per-user exposure cap: non-negative, at most the currency's decimals
+fn exposure(v: &BigDecimal, decimals: i16) -> std::result::Result<BigDecimal, HttpResponse> {
+    Rounding::new(decimals).amount(v).map_err(|msg| bad_request(&format!("max_user_exposure: {}", msg)))
+}
+
+/// Keeps an explicit null (`Some(Null)`) apart from an absent field (None, via `default`)
+fn present<'de, D: serde::Deserializer<'de>>(d: D) -> std::result::Result<Option<serde_json::Value>, D::Error> {
+    serde_json::Value::deserialize(d).map(Some)
+}
+
+// ---- currencies ----
+
+pub async fn list_currencies(req: HttpRequest, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &READ_ROLES).await?;
+    let items: Vec<Currency> = sqlx::query_as(&format!("SELECT {} FROM currencies ORDER BY code", CURRENCY_COLUMNS))
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items}))))
+}
+
+#[derive(Deserialize)]
+pub struct CreateCurrencyRequest {
+    pub code: String,
+    pub name: String,
+    pub decimals: i16,
+    pub chain: Option<String>,
+    pub mint_address: Option<String>,
+    #[serde(default, deserialize_with = "money::lenient_opt")]
+    pub max_user_exposure: Option<BigDecimal>,
+    pub enabled: Option<bool>,
+}
+
+pub async fn create_currency(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<CreateCurrencyRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let p = payload.into_inner();
+    let (Some(code), Some(name)) = (currency::normalize_code(&p.code), text(&p.name, 64)) else {
+        return Ok(bad_request("code (2-16 letters or digits) and name (max 64) are required"));
+    };
+    if !(0..=money::AMOUNT_SCALE as i16).contains(&p.decimals) {
+        return Ok(bad_request("decimals must be between 0 and 18"));
+    }
+    let max_user_exposure = match p.max_user_exposure.as_ref().map(|v| exposure(v, p.decimals)).transpose() {
+        Ok(v) => v,
+        Err(resp) => return Ok(resp),
+    };
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let created: Option<Currency> = sqlx::query_as(&format!(
+        "INSERT INTO currencies (code, name, decimals, chain, mint_address, max_user_exposure, enabled) \
+         VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, TRUE)) ON CONFLICT DO NOTHING RETURNING {}",
+        CURRENCY_COLUMNS
+    ))
+    .bind(&code)
+    .bind(name)
+    .bind(p.decimals)
+    .bind(p.chain.as_deref().and_then(|v| text(v, 32)))
+    .bind(p.mint_address.as_deref().and_then(|v| text(v, 128)))
+    .bind(&max_user_exposure)
+    .bind(p.enabled)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    let Some(created) = created else {
+        return Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("already_exists", "code or mint already in use")));
+    };
+    audit::record(&mut *tx, actor_id, "admin.currency_create", "currencies", None, serde_json::json!({"code": code, "decimals": p.decimals}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(created)))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateCurrencyRequest {
+    pub name: Option<String>,
+    /// Only while no market uses the currency
+    pub decimals: Option<i16>,
+    pub chain: Option<String>,
+    pub mint_address: Option<String>,
+    /// Absent: unchanged; null: no default cap
+    #[serde(default, deserialize_with = "present")]
+    pub max_user_exposure: Option<serde_json::Value>,
+    pub enabled: Option<bool>,
+}
+
+pub async fn update_currency(req: HttpRequest, state: web::Data<AppState>, path: web::Path<String>, payload: web::Json<UpdateCurrencyRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let code = path.into_inner().to_ascii_uppercase();
+    let p = payload.into_inner();
+    let name = match p.name.as_deref().map(|v| text(v, 64)) {
+        Some(None) => return Ok(bad_request("name must be 1-64 characters")),
+        v => v.flatten(),
+    };
+    if p.decimals.is_some_and(|d| !(0..=money::AMOUNT_SCALE as i16).contains(&d)) {
+        return Ok(bad_request("decimals must be between 0 and 18"));
+    }
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let current: Option<Currency> = sqlx::query_as(&format!("SELECT {} FROM currencies WHERE code = $1 FOR UPDATE", CURRENCY_COLUMNS))
+        .bind(&code)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    let Some(current) = current else { return Ok(not_found("currency")) };
+    if p.decimals.is_some_and(|d| d != current.decimals) {
+        // stakes already placed were validated against the old decimals
+        let in_use: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM markets WHERE currency = $1)")
+            .bind(&code)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        if in_use {
+            return Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("currency_in_use", "decimals cannot change once markets use the currency")));
+        }
+    }
+    let decimals = p.decimals.unwrap_or(current.decimals);
+    let max_user_exposure = match p.max_user_exposure {
+        None => current.max_user_exposure.clone(),
+        Some(serde_json::Value::Null) => None,
+        Some(v) => match money::lenient(v) {
+            Ok(v) => match exposure(&v, decimals) {
+                Ok(v) => Some(v),
+                Err(resp) => return Ok(resp),
+            },
+            Err(e) => return Ok(bad_request(&format!("max_user_exposure: {}", e))),
+        },
+    };
+    let updated: Currency = sqlx::query_as(&format!(
+        "UPDATE currencies SET name = COALESCE($2, name), decimals = $3, chain = COALESCE($4, chain), \
+             mint_address = COALESCE($5, mint_address), max_user_exposure = $6, enabled = COALESCE($7, enabled) \
+         WHERE code = $1 RETURNING {}",
+        CURRENCY_COLUMNS
+    ))
+    .bind(&code)
+    .bind(name)
+    .bind(decimals)
+    .bind(p.chain.as_deref().and_then(|v| text(v, 32)))
+    .bind(p.mint_address.as_deref().and_then(|v| text(v, 128)))
+    .bind(&max_user_exposure)
+    .bind(p.enabled)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    audit::record(&mut *tx, actor_id, "admin.currency_update", "currencies", None, serde_json::json!({
+        "code": code,
+        "decimals": updated.decimals,
+        "max_user_exposure": updated.max_user_exposure,
+        "enabled": updated.enabled,
+    }))
+    .await
+    .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(updated)))
+}
+
+// ---- FX rates ----
+
+fn fx_json(row: &sqlx::postgres::PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "currency": row.try_get::<String, _>("currency").unwrap_or_default(),
+        "quote": REPORTING_CURRENCY,
+        "rate": row.try_get::<BigDecimal, _>("rate").ok(),
+        "updated_by": row.try_get::<Option<i64>, _>("updated_by").ok().flatten(),
+        "updated_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("updated_at").ok(),
+    })
+}
+
+pub async fn list_fx_rates(req: HttpRequest, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &READ_ROLES).await?;
+    let rows = sqlx::query("SELECT currency, rate, updated_by, updated_at FROM fx_rates ORDER BY currency")
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let items: Vec<serde_json::Value> = rows.iter().map(fx_json).collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items}))))
+}
+
+#[derive(Deserialize)]
+pub struct FxRateRequest {
+    /// Value of one unit in the reporting currency (USD)
+    #[serde(deserialize_with = "money::lenient")]
+    pub rate: BigDecimal,
+}
+
+pub async fn set_fx_rate(req: HttpRequest, state: web::Data<AppState>, path: web::Path<String>, payload: web::Json<FxRateRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let code = path.into_inner().to_ascii_uppercase();
+    let Ok(rate) = Rounding::DEFAULT.stake(&payload.rate) else {
+        return Ok(bad_request("rate must be positive with at most 18 decimal places"));
+    };
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    if currency::find(&mut tx, &code).await.map_err(AppError::from)?.is_none() {
+        return Ok(not_found("currency"));
+    }
+    let row = sqlx::query(
+        "INSERT INTO fx_rates (currency, rate, updated_by, updated_at) VALUES ($1, $2, $3, NOW()) \
+         ON CONFLICT (currency) DO UPDATE SET rate = EXCLUDED.rate, updated_by = EXCLUDED.updated_by, updated_at = NOW() \
+         RETURNING currency, rate, updated_by, updated_at"
+    )
+    .bind(&code)
+    .bind(&rate)
+    .bind(actor_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    audit::record(&mut *tx, actor_id, "admin.fx_rate_set", "fx_rates", None, serde_json::json!({"currency": code, "rate": rate}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(fx_json(&row))))
+}
+
+pub async fn delete_fx_rate(req: HttpRequest, state: web::Data<AppState>, path: web::Path<String>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let code = path.into_inner().to_ascii_uppercase();
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let res = sqlx::query("DELETE FROM fx_rates WHERE currency = $1")
+        .bind(&code)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    if res.rows_affected() == 0 {
+        return Ok(not_found("fx rate"));
+    }
+    audit::record(&mut *tx, actor_id, "admin.fx_rate_delete", "fx_rates", None, serde_json::json!({"currency": code}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"currency": code, "deleted": true}))))
+}
+
+// ---- reporting ----
+
+#[derive(Deserialize)]
+pub struct CurrencyReportQuery {
+    /// Orders created at or after
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    /// Orders created before
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Money columns of the report, in output order
+const REPORT_AMOUNTS: [&str; 5] = ["volume", "open_stake", "payouts", "house_pnl", "fees"];
+
+/// Order figures per currency; with FX rates set, also consolidated in the reporting currency.
+/// Currencies with orders but no rate are listed as unpriced and left out of the consolidation.
+pub async fn currency_report(req: HttpRequest, state: web::Data<AppState>, query: web::Query<CurrencyReportQuery>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &READ_ROLES).await?;
+    let rows = sqlx::query(
+        r#"SELECT c.code, c.decimals, f.rate,
+                  (SELECT COUNT(*) FROM markets m WHERE m.currency = c.code) AS markets,
+                  COUNT(o.id) AS orders,
+                  COUNT(DISTINCT o.user_id) AS users,
+                  COALESCE(SUM(o.amount) FILTER (WHERE o.status <> 'cancelled'), 0) AS volume,
+                  COALESCE(SUM(o.amount) FILTER (WHERE o.status = 'placed'), 0) AS open_stake,
+                  COALESCE(SUM(o.close_price) FILTER (WHERE o.status = 'settled'), 0) AS payouts,
+                  COALESCE(-SUM(o.close_pnl) FILTER (WHERE o.status = 'settled'), 0) AS house_pnl,
+                  COALESCE(SUM(o.fee_paid), 0) AS fees
+           FROM currencies c
+           LEFT JOIN fx_rates f ON f.currency = c.code
+           LEFT JOIN orders o ON o.currency = c.code
+                AND ($1::timestamptz IS NULL OR o.created_at >= $1)
+                AND ($2::timestamptz IS NULL OR o.created_at < $2)
+           GROUP BY c.code, c.decimals, f.rate
+           ORDER BY c.code"#
+    )
+    .bind(query.from)
+    .bind(query.to)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    let mut totals = REPORT_AMOUNTS.map(|_| BigDecimal::zero());
+    let mut unpriced = Vec::new();
+    let mut items = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let code: String = row.try_get("code").map_err(AppError::from)?;
+        let rate: Option<BigDecimal> = row.try_get("rate").map_err(AppError::from)?;
+        let orders: i64 = row.try_get("orders").unwrap_or(0);
+        let mut item = serde_json::json!({
+            "currency": code,
+            "decimals": row.try_get::<i16, _>("decimals").unwrap_or_default(),
+            "markets": row.try_get::<i64, _>("markets").unwrap_or(0),
+            "orders": orders,
+            "users": row.try_get::<i64, _>("users").unwrap_or(0),
+            "fx_rate": rate,
+        });
+        for (i, column) in REPORT_AMOUNTS.iter().enumerate() {
+            let amount: BigDecimal = row.try_get(*column).map_err(AppError::from)?;
+            if let Some(rate) = &rate {
+                totals[i] += &amount * rate;
+            }
+            item[*column] = serde_json::json!(amount);
+        }
+        if rate.is_none() && orders > 0 {
+            unpriced.push(code);
+        }
+        items.push(item);
+    }
+    let mut consolidated = serde_json::json!({"currency": REPORTING_CURRENCY, "unpriced": unpriced});
+    for (column, total) in REPORT_AMOUNTS.iter().zip(&totals) {
+        consolidated[*column] = serde_json::json!(Rounding::DEFAULT.round_down(total));
+    }
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"currencies": items, "consolidated": consolidated}))))
+}
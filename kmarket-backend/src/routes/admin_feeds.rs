@@ -0,0 +1,223 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use serde::Deserialize;
+use sqlx::Row;
+
+use crate::state::AppState;
+use crate::utils::audit;
+use crate::utils::auth::{admin_actor_id, require_role};
+use crate::utils::feeds::{self, FeedIngestor, FEED_KINDS};
+use crate::utils::errors::AppError;
+use crate::utils::response::ApiResponse;
+
+const PROVIDER_COLUMNS: &str = "id, name, kind, source, enabled, interval_secs, last_sync_at, last_success_at, last_status, last_error, created_by, created_at, updated_at";
+
+fn provider_json(row: &sqlx::postgres::PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+        "name": row.try_get::<String, _>("name").unwrap_or_default(),
+        "kind": row.try_get::<String, _>("kind").unwrap_or_default(),
+        "source": row.try_get::<String, _>("source").unwrap_or_default(),
+        "enabled": row.try_get::<bool, _>("enabled").unwrap_or_default(),
+        "interval_secs": row.try_get::<i32, _>("interval_secs").unwrap_or_default(),
+        "last_sync_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("last_sync_at").ok().flatten(),
+        "last_success_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("last_success_at").ok().flatten(),
+        "last_status": row.try_get::<String, _>("last_status").unwrap_or_default(),
+        "last_error": row.try_get::<Option<String>, _>("last_error").ok().flatten(),
+        "created_by": row.try_get::<Option<i64>, _>("created_by").ok().flatten(),
+        "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+        "updated_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("updated_at").ok(),
+    })
+}
+
+fn invalid_source(kind: &str) -> HttpResponse {
+    let msg = if kind == "file" { "file source must be a file name inside FEED_DIR" } else { "http source must be an http(s) base URL" };
+    HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_source", msg))
+}
+
+fn valid_interval(secs: i32) -> bool {
+    (5..=86_400).contains(&secs)
+}
+
+/// Providers with their last sync status
+pub async fn list_providers(req: HttpRequest, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin", "analyst"]).await?;
+    let rows = sqlx::query(&format!("SELECT {} FROM feed_providers ORDER BY id", PROVIDER_COLUMNS))
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let items: Vec<serde_json::Value> = rows.iter().map(provider_json).collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items}))))
+}
+
+#[derive(Deserialize)]
+pub struct CreateProviderRequest {
+    /// Unique; stored on feed-created markets and as match_state source
+    pub name: String,
+    /// file | http
+    pub kind: String,
+    /// File name under FEED_DIR (.json or .csv), or base URL serving /fixtures, /odds and /results
+    pub source: String,
+    pub enabled: Option<bool>,
+    pub interval_secs: Option<i32>,
+}
+
+pub async fn create_provider(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<CreateProviderRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let p = payload.into_inner();
+    let name = p.name.trim();
+    let source = p.source.trim();
+    if name.is_empty() || name.len() > 64 {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_name", "name must be 1-64 characters")));
+    }
+    if !FEED_KINDS.contains(&p.kind.as_str()) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_kind", "kind must be file or http")));
+    }
+    if !feeds::valid_source(&p.kind, source) {
+        return Ok(invalid_source(&p.kind));
+    }
+    let interval_secs = p.interval_secs.unwrap_or(60);
+    if !valid_interval(interval_secs) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_interval", "interval_secs must be 5..=86400")));
+    }
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = sqlx::query(&format!(
+        "INSERT INTO feed_providers (name, kind, source, enabled, interval_secs, created_by) VALUES ($1, $2, $3, $4, $5, $6) \
+         ON CONFLICT (name) DO NOTHING RETURNING {}",
+        PROVIDER_COLUMNS
+    ))
+    .bind(name).bind(&p.kind).bind(source).bind(p.enabled.unwrap_or(true)).bind(interval_secs).bind(actor_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    let Some(row) = row else {
+        return Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("name_taken", "a provider with this name already exists")));
+    };
+    let id: i64 = row.try_get("id").unwrap_or_default();
+    audit::record(&mut *tx, actor_id, "admin.feed_create", "feed_providers", Some(id), serde_json::json!({"name": name, "kind": p.kind, "source": source}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(provider_json(&row))))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateProviderRequest {
+    pub source: Option<String>,
+    pub enabled: Option<bool>,
+    pub interval_secs: Option<i32>,
+}
+
+pub async fn update_provider(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<UpdateProviderRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let id = path.into_inner();
+    let p = payload.into_inner();
+    if p.interval_secs.is_some_and(|s| !valid_interval(s)) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_interval", "interval_secs must be 5..=86400")));
+    }
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let kind: Option<String> = sqlx::query_scalar("SELECT kind FROM feed_providers WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    let Some(kind) = kind else {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "feed provider not found")));
+    };
+    let source = p.source.as_deref().map(str::trim);
+    if source.is_some_and(|s| !feeds::valid_source(&kind, s)) {
+        return Ok(invalid_source(&kind));
+    }
+    let row = sqlx::query(&format!(
+        "UPDATE feed_providers SET source = COALESCE($2, source), enabled = COALESCE($3, enabled), \
+         interval_secs = COALESCE($4, interval_secs) WHERE id = $1 RETURNING {}",
+        PROVIDER_COLUMNS
+    ))
+    .bind(id).bind(source).bind(p.enabled).bind(p.interval_secs)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    audit::record(&mut *tx, actor_id, "admin.feed_update", "feed_providers", Some(id), serde_json::json!({"source": source, "enabled": p.enabled, "interval_secs": p.interval_secs}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(provider_json(&row))))
+}
+
+/// Sync a provider now (enabled or not) and return the run report
+pub async fn sync_provider(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin", "operator"]).await?;
+    let id = path.into_inner();
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = sqlx::query("UPDATE feed_providers SET last_sync_at = NOW() WHERE id = $1 RETURNING name, kind, source")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    let Some(row) = row else {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "feed provider not found")));
+    };
+    let name: String = row.try_get("name").unwrap_or_default();
+    let provider = match feeds::provider_for(&name, &row.try_get::<String, _>("kind").unwrap_or_default(), &row.try_get::<String, _>("source").unwrap_or_default()) {
+        Ok(p) => p,
+        Err(e) => return Ok(HttpResponse::UnprocessableEntity().json(ApiResponse::<()>::error("invalid_source", &e))),
+    };
+    audit::record(&mut *tx, actor_id, "admin.feed_sync", "feed_providers", Some(id), serde_json::json!({"name": name}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    let report = FeedIngestor::new(state.db_pool.clone())
+        .sync(id, provider.as_ref())
+        .await
+        .map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(report)))
+}
+
+#[derive(Deserialize)]
+pub struct RunsQuery {
+    pub limit: Option<i64>,
+    /// id of the last run on the previous page
+    pub cursor: Option<String>,
+}
+
+/// Sync log of one provider, newest first
+pub async fn list_runs(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, query: web::Query<RunsQuery>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin", "analyst"]).await?;
+    let provider_id = path.into_inner();
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let cursor = match query.cursor.as_deref().map(str::parse::<i64>) {
+        None => i64::MAX,
+        Some(Ok(id)) => id,
+        Some(Err(_)) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_cursor", "cursor invalid"))),
+    };
+    let mut rows = sqlx::query(
+        "SELECT id, status, fixtures, odds, results, errors, started_at, finished_at FROM feed_sync_runs \
+         WHERE provider_id = $1 AND id < $2 ORDER BY id DESC LIMIT $3"
+    )
+    .bind(provider_id)
+    .bind(cursor)
+    .bind(limit + 1)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(AppError::from)?;
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    let items: Vec<serde_json::Value> = rows.iter().map(|row| serde_json::json!({
+        "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+        "status": row.try_get::<String, _>("status").unwrap_or_default(),
+        "fixtures": row.try_get::<i32, _>("fixtures").unwrap_or_default(),
+        "odds": row.try_get::<i32, _>("odds").unwrap_or_default(),
+        "results": row.try_get::<i32, _>("results").unwrap_or_default(),
+        "errors": row.try_get::<serde_json::Value, _>("errors").unwrap_or_default(),
+        "started_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("started_at").ok(),
+        "finished_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("finished_at").ok().flatten(),
+    })).collect();
+    let next_cursor = if has_more { items.last().and_then(|i| i["id"].as_i64()).map(|id| id.to_string()) } else { None };
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items, "next_cursor": next_cursor}))))
+}
@@ -0,0 +1,320 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use bigdecimal::{BigDecimal, Zero};
+use serde::Deserialize;
+use sqlx::Row;
+
+use crate::state::AppState;
+use crate::utils::audit;
+use crate::utils::auth::{admin_actor_id, require_role};
+use crate::utils::currency;
+use crate::utils::errors::AppError;
+use crate::utils::fees::{self, FeeSchedule, FEE_SCHEDULE_COLUMNS};
+use crate::utils::list_query::{ListQuery, Sort, SortField};
+use crate::utils::money;
+use crate::utils::pagination::{self, PageMode};
+use crate::utils::response::ApiResponse;
+
+const READ_ROLES: [&str; 3] = ["admin", "operator", "analyst"];
+/// Fee schedules change what users are charged; admins only
+const WRITE_ROLES: [&str; 1] = ["admin"];
+
+fn bad_request(msg: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_args", msg))
+}
+
+fn not_found(what: &str) -> HttpResponse {
+    HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", &format!("{} not found", what)))
+}
+
+/// A rate field, defaulting to zero when absent
+fn rate(name: &str, v: Option<&BigDecimal>) -> std::result::Result<Option<BigDecimal>, HttpResponse> {
+    v.map(fees::rate).transpose().map_err(|msg| bad_request(&format!("{}: {}", name, msg)))
+}
+
+/// A flat fee in `currency`: non-negative, within its decimals; only currency schedules have one
+async fn flat_fee(conn: &mut sqlx::PgConnection, v: &BigDecimal, code: Option<&str>) -> Result<std::result::Result<BigDecimal, HttpResponse>> {
+    if v.is_zero() {
+        return Ok(Ok(BigDecimal::zero()));
+    }
+    let Some(code) = code else {
+        return Ok(Err(bad_request("flat_fee needs a currency")));
+    };
+    let Some(found) = currency::find(conn, code).await.map_err(AppError::from)? else {
+        return Ok(Err(bad_request("unknown currency")));
+    };
+    Ok(found.rounding().amount(v).map_err(|msg| bad_request(&format!("flat_fee: {}", msg))))
+}
+
+fn charge_at(v: Option<&str>) -> std::result::Result<Option<&'static str>, HttpResponse> {
+    match v.map(str::trim) {
+        None => Ok(None),
+        Some(fees::CHARGE_AT_PLACEMENT) => Ok(Some(fees::CHARGE_AT_PLACEMENT)),
+        Some(fees::CHARGE_AT_SETTLEMENT) => Ok(Some(fees::CHARGE_AT_SETTLEMENT)),
+        Some(_) => Err(bad_request("charge_at must be placement or settlement")),
+    }
+}
+
+// ---- fee schedules ----
+
+#[derive(Deserialize)]
+pub struct FeeSchedulesQuery {
+    pub scope: Option<String>,
+    pub market_id: Option<i64>,
+    pub tier: Option<String>,
+}
+
+pub async fn list_fee_schedules(req: HttpRequest, state: web::Data<AppState>, query: web::Query<FeeSchedulesQuery>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &READ_ROLES).await?;
+    let items: Vec<FeeSchedule> = sqlx::query_as(&format!(
+        "SELECT {} FROM fee_schedules \
+         WHERE ($1::TEXT IS NULL OR scope = $1) AND ($2::BIGINT IS NULL OR market_id = $2) AND ($3::TEXT IS NULL OR tier = $3) \
+         ORDER BY CASE scope WHEN 'global' THEN 0 WHEN 'tier' THEN 1 ELSE 2 END, id",
+        FEE_SCHEDULE_COLUMNS
+    ))
+    .bind(query.scope.as_deref())
+    .bind(query.market_id)
+    .bind(query.tier.as_deref())
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items}))))
+}
+
+#[derive(Deserialize)]
+pub struct CreateFeeScheduleRequest {
+    /// global | tier | market
+    pub scope: String,
+    /// Required for scope market
+    pub market_id: Option<i64>,
+    /// Required for scope tier (users.fee_tier)
+    pub tier: Option<String>,
+    /// Only orders in this currency; a market schedule takes the market's
+    pub currency: Option<String>,
+    #[serde(default, deserialize_with = "money::lenient_opt")]
+    pub stake_rate: Option<BigDecimal>,
+    #[serde(default, deserialize_with = "money::lenient_opt")]
+    pub winnings_rate: Option<BigDecimal>,
+    #[serde(default, deserialize_with = "money::lenient_opt")]
+    pub flat_fee: Option<BigDecimal>,
+    /// placement (default) | settlement
+    pub charge_at: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+pub async fn create_fee_schedule(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<CreateFeeScheduleRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let p = payload.into_inner();
+    let scope = p.scope.trim();
+    let tier = p.tier.as_deref().map(str::trim);
+    match scope {
+        fees::SCOPE_GLOBAL if p.market_id.is_none() && tier.is_none() => {}
+        fees::SCOPE_TIER if p.market_id.is_none() && tier.is_some_and(|t| !t.is_empty() && t.len() <= 32) => {}
+        fees::SCOPE_MARKET if p.market_id.is_some() && tier.is_none() => {}
+        _ => return Ok(bad_request("scope must be global, tier (with tier, max 32) or market (with market_id)")),
+    }
+    let (stake_rate, winnings_rate) = match (rate("stake_rate", p.stake_rate.as_ref()), rate("winnings_rate", p.winnings_rate.as_ref())) {
+        (Ok(s), Ok(w)) => (s.unwrap_or_else(BigDecimal::zero), w.unwrap_or_else(BigDecimal::zero)),
+        (Err(resp), _) | (_, Err(resp)) => return Ok(resp),
+    };
+    let charge_at = match charge_at(p.charge_at.as_deref()) {
+        Ok(v) => v.unwrap_or(fees::CHARGE_AT_PLACEMENT),
+        Err(resp) => return Ok(resp),
+    };
+    let mut code = match p.currency.as_deref().map(currency::normalize_code) {
+        Some(None) => return Ok(bad_request("unknown currency")),
+        v => v.flatten(),
+    };
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    if let Some(market_id) = p.market_id {
+        let market_currency: Option<String> = sqlx::query_scalar("SELECT currency FROM markets WHERE id = $1")
+            .bind(market_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        let Some(market_currency) = market_currency else { return Ok(not_found("market")) };
+        if code.as_ref().is_some_and(|c| *c != market_currency) {
+            return Ok(bad_request("currency must be the market's"));
+        }
+        code = Some(market_currency);
+    }
+    let flat_fee = match flat_fee(&mut tx, &p.flat_fee.unwrap_or_else(BigDecimal::zero), code.as_deref()).await? {
+        Ok(v) => v,
+        Err(resp) => return Ok(resp),
+    };
+    if let Some(code) = code.as_deref() {
+        if currency::find(&mut tx, code).await.map_err(AppError::from)?.is_none() {
+            return Ok(bad_request("unknown currency"));
+        }
+    }
+    let created: FeeSchedule = sqlx::query_as(&format!(
+        "INSERT INTO fee_schedules (scope, market_id, tier, currency, stake_rate, winnings_rate, flat_fee, charge_at, enabled) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, COALESCE($9, TRUE)) RETURNING {}",
+        FEE_SCHEDULE_COLUMNS
+    ))
+    .bind(scope)
+    .bind(p.market_id)
+    .bind(tier)
+    .bind(&code)
+    .bind(&stake_rate)
+    .bind(&winnings_rate)
+    .bind(&flat_fee)
+    .bind(charge_at)
+    .bind(p.enabled)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    audit::record(&mut *tx, actor_id, "admin.fee_schedule_create", "fee_schedules", Some(created.id), serde_json::to_value(&created).unwrap_or_default())
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(created)))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateFeeScheduleRequest {
+    #[serde(default, deserialize_with = "money::lenient_opt")]
+    pub stake_rate: Option<BigDecimal>,
+    #[serde(default, deserialize_with = "money::lenient_opt")]
+    pub winnings_rate: Option<BigDecimal>,
+    #[serde(default, deserialize_with = "money::lenient_opt")]
+    pub flat_fee: Option<BigDecimal>,
+    pub charge_at: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Changes apply to orders placed from now on; placed orders keep their terms
+pub async fn update_fee_schedule(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<UpdateFeeScheduleRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let id = path.into_inner();
+    let p = payload.into_inner();
+    let (stake_rate, winnings_rate) = match (rate("stake_rate", p.stake_rate.as_ref()), rate("winnings_rate", p.winnings_rate.as_ref())) {
+        (Ok(s), Ok(w)) => (s, w),
+        (Err(resp), _) | (_, Err(resp)) => return Ok(resp),
+    };
+    let charge_at = match charge_at(p.charge_at.as_deref()) {
+        Ok(v) => v,
+        Err(resp) => return Ok(resp),
+    };
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let current: Option<FeeSchedule> = sqlx::query_as(&format!("SELECT {} FROM fee_schedules WHERE id = $1 FOR UPDATE", FEE_SCHEDULE_COLUMNS))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    let Some(current) = current else { return Ok(not_found("fee schedule")) };
+    let flat_fee = match &p.flat_fee {
+        Some(v) => match flat_fee(&mut tx, v, current.currency.as_deref()).await? {
+            Ok(v) => Some(v),
+            Err(resp) => return Ok(resp),
+        },
+        None => None,
+    };
+    let updated: FeeSchedule = sqlx::query_as(&format!(
+        "UPDATE fee_schedules SET stake_rate = COALESCE($2, stake_rate), winnings_rate = COALESCE($3, winnings_rate), \
+             flat_fee = COALESCE($4, flat_fee), charge_at = COALESCE($5, charge_at), enabled = COALESCE($6, enabled) \
+         WHERE id = $1 RETURNING {}",
+        FEE_SCHEDULE_COLUMNS
+    ))
+    .bind(id)
+    .bind(&stake_rate)
+    .bind(&winnings_rate)
+    .bind(&flat_fee)
+    .bind(charge_at)
+    .bind(p.enabled)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    audit::record(&mut *tx, actor_id, "admin.fee_schedule_update", "fee_schedules", Some(id), serde_json::to_value(&updated).unwrap_or_default())
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(updated)))
+}
+
+pub async fn delete_fee_schedule(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &WRITE_ROLES).await?;
+    let id = path.into_inner();
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let res = sqlx::query("DELETE FROM fee_schedules WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    if res.rows_affected() == 0 {
+        return Ok(not_found("fee schedule"));
+    }
+    audit::record(&mut *tx, actor_id, "admin.fee_schedule_delete", "fee_schedules", Some(id), serde_json::json!({}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id, "deleted": true}))))
+}
+
+// ---- ledger ----
+
+#[derive(Deserialize)]
+pub struct LedgerQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub user_id: Option<i64>,
+    /// orders.id
+    pub order_id: Option<i64>,
+    pub kind: Option<String>,
+    pub currency: Option<String>,
+    /// Keyset paging on created_at and id; empty for the first page, then the previous next_cursor
+    pub cursor: Option<String>,
+    /// Count matching rows (default: true with page, false with cursor)
+    pub with_total: Option<bool>,
+    /// asc | desc (default desc)
+    pub order: Option<String>,
+}
+
+/// Sortable fields of /admin/ledger (first = default)
+const LEDGER_SORTS: &[SortField] = &[
+    SortField { name: "created_at", column: "created_at", keyset: true },
+];
+
+pub async fn list_ledger(req: HttpRequest, state: web::Data<AppState>, query: web::Query<LedgerQuery>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &READ_ROLES).await?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 100);
+    let mode = match PageMode::from_query(query.page, limit, query.cursor.as_deref()) {
+        Ok(mode) => mode,
+        Err(resp) => return Ok(resp),
+    };
+    let sort = match Sort::parse(LEDGER_SORTS, None, query.order.as_deref(), &mode) {
+        Ok(sort) => sort,
+        Err(resp) => return Ok(resp),
+    };
+
+    let mut list = ListQuery::new("id, user_id, order_id, currency, kind, amount, detail, created_at", "ledger_entries", "id");
+    list.filter_opt("user_id = {}", query.user_id)
+        .filter_opt("order_id = {}", query.order_id)
+        .filter_opt("kind = {}", query.kind.as_ref())
+        .filter_opt("currency = {}", query.currency.as_ref().map(|c| c.to_ascii_uppercase()));
+    let page = list.fetch(&state.db_pool, sort, limit, &mode, mode.wants_total(query.with_total)).await
+        .map_err(AppError::from)?;
+    let (rows, total, next_cursor) = (page.rows, page.total, page.next_cursor);
+
+    let items: Vec<serde_json::Value> = rows.into_iter().map(|row| {
+        serde_json::json!({
+            "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+            "user_id": row.try_get::<i64, _>("user_id").unwrap_or_default(),
+            "order_id": row.try_get::<Option<i64>, _>("order_id").ok().flatten(),
+            "currency": row.try_get::<String, _>("currency").unwrap_or_default(),
+            "kind": row.try_get::<String, _>("kind").unwrap_or_default(),
+            "amount": row.try_get::<BigDecimal, _>("amount").ok(),
+            "detail": row.try_get::<serde_json::Value, _>("detail").unwrap_or_default(),
+            "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+        })
+    }).collect();
+
+    let body = serde_json::json!({ "items": items, "pagination": pagination::pagination_json(&mode, limit, total, next_cursor) });
+    Ok(HttpResponse::Ok().json(ApiResponse::success(body)))
+}
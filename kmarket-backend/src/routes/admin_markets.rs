@@ -1,9 +1,15 @@
 use actix_web::{web, HttpResponse, Result, HttpRequest};
 use serde::Deserialize;
 use sqlx::Row;
-use bigdecimal::BigDecimal;
 
 use crate::state::AppState;
+use crate::models::event::DomainEvent;
+use crate::utils::{audit, catalog, currency, match_state, outbox};
+use crate::utils::catalog::MarketRefs;
+use crate::utils::list_query::{ListQuery, Sort, SortField};
+use crate::utils::pagination::{self, PageMode};
+use crate::utils::match_state::MatchStateUpdate;
+use crate::utils::errors::AppError;
 use crate::utils::response::ApiResponse;
 
 #[derive(Deserialize)]
@@ -12,55 +18,56 @@ pub struct AdminMarketsQuery {
     pub limit: Option<i64>,
     pub status: Option<String>,
     pub q: Option<String>,
+    /// true: only markets the scheduler flagged for attention (attention_reason set)
+    pub attention: Option<bool>,
+    /// Currency code, e.g. SOL
+    pub currency: Option<String>,
+    /// Keyset paging on the sort field and id; empty for the first page, then the previous next_cursor
+    pub cursor: Option<String>,
+    /// Count matching rows (default: true with page, false with cursor)
+    pub with_total: Option<bool>,
+    /// One of MARKET_SORTS (default start_time)
+    pub sort: Option<String>,
+    /// asc | desc (default desc)
+    pub order: Option<String>,
 }
 
+/// Sortable fields of /admin/markets (first = default)
+const MARKET_SORTS: &[SortField] = &[
+    SortField { name: "start_time", column: "start_time", keyset: true },
+    SortField { name: "end_time", column: "end_time", keyset: true },
+    SortField { name: "created_at", column: "created_at", keyset: true },
+    SortField { name: "title", column: "title", keyset: false },
+    SortField { name: "total_volume", column: "total_volume", keyset: false },
+];
+
 pub async fn list_markets(req: HttpRequest, state: web::Data<AppState>, query: web::Query<AdminMarketsQuery>) -> Result<HttpResponse> {
     // auth guard
     let _actor = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
-    let page = query.page.unwrap_or(1).max(1);
     let limit = query.limit.unwrap_or(20).clamp(1, 100);
-    let offset = (page - 1) * limit;
-
-    let mut count_sql = String::from("SELECT COUNT(*) AS total FROM markets");
-    let mut idx = 1;
-    let mut has_where = false;
-    if let Some(status) = &query.status {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("status = ${}", idx));
-        idx += 1;
-    }
-    if let Some(_q) = &query.q {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("(title ILIKE ${} OR option_a ILIKE ${} OR option_b ILIKE ${})", idx, idx + 1, idx + 2));
-        idx += 3;
-    }
-    let mut qc = sqlx::query(&count_sql);
-    if let Some(status) = &query.status { qc = qc.bind(status); }
-    if let Some(q) = &query.q { let pat = format!("%{}%", q); qc = qc.bind(pat.clone()).bind(pat.clone()).bind(pat); }
-    let row = qc.fetch_one(&state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
-    let total: i64 = row.try_get("total").unwrap_or(0);
+    let mode = match PageMode::from_query(query.page, limit, query.cursor.as_deref()) {
+        Ok(mode) => mode,
+        Err(resp) => return Ok(resp),
+    };
+    let sort = match Sort::parse(MARKET_SORTS, query.sort.as_deref(), query.order.as_deref(), &mode) {
+        Ok(sort) => sort,
+        Err(resp) => return Ok(resp),
+    };
 
-    let mut data_sql = String::from(
-        "SELECT id, market_id, title, description, option_a, option_b, start_time, end_time, status, winning_option, odds_home_bps, odds_away_bps, total_bets, total_volume FROM markets"
+    let mut list = ListQuery::new(
+        "id, market_id, title, description, option_a, option_b, start_time, end_time, open_time, close_time, status, state, winning_option, odds_home_bps, odds_away_bps, total_bets, total_volume, currency, attention_reason, attention_at, league_id, home_team_id, away_team_id",
+        "markets",
+        "id",
     );
-    let mut idx2 = 1;
-    let mut has_where2 = false;
-    if let Some(status) = &query.status {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("status = ${}", idx2));
-        idx2 += 1;
+    list.filter_opt("status = {}", query.status.as_ref())
+        .filter_opt("title ILIKE {} OR option_a ILIKE {} OR option_b ILIKE {}", query.q.as_ref().map(|q| format!("%{}%", q)))
+        .filter_opt("currency = {}", query.currency.as_ref().map(|c| c.to_ascii_uppercase()));
+    if query.attention == Some(true) {
+        list.condition("attention_at IS NOT NULL AND status IN ('pending', 'active')");
     }
-    if let Some(_q) = &query.q {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("(title ILIKE ${} OR option_a ILIKE ${} OR option_b ILIKE ${})", idx2, idx2 + 1, idx2 + 2));
-        idx2 += 3;
-    }
-    data_sql.push_str(&format!(" ORDER BY start_time DESC LIMIT ${} OFFSET ${}", idx2, idx2 + 1));
-    let mut qd = sqlx::query(&data_sql);
-    if let Some(status) = &query.status { qd = qd.bind(status); }
-    if let Some(q) = &query.q { let pat = format!("%{}%", q); qd = qd.bind(pat.clone()).bind(pat.clone()).bind(pat); }
-    qd = qd.bind(limit).bind(offset);
-    let rows = qd.fetch_all(&state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let page = list.fetch(&state.db_pool, sort, limit, &mode, mode.wants_total(query.with_total)).await
+        .map_err(AppError::from)?;
+    let (rows, total, next_cursor) = (page.rows, page.total, page.next_cursor);
 
     let items: Vec<serde_json::Value> = rows.into_iter().map(|row| {
         serde_json::json!({
@@ -72,19 +79,25 @@ pub async fn list_markets(req: HttpRequest, state: web::Data<AppState>, query: w
             "option_b": row.try_get::<String, _>("option_b").unwrap_or_default(),
             "start_time": row.try_get::<chrono::DateTime<chrono::Utc>, _>("start_time").ok(),
             "end_time": row.try_get::<chrono::DateTime<chrono::Utc>, _>("end_time").ok(),
+            "open_time": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("open_time").ok().flatten(),
+            "close_time": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("close_time").ok().flatten(),
             "status": row.try_get::<String, _>("status").unwrap_or_default(),
+            "state": row.try_get::<Option<i32>, _>("state").ok().flatten(),
             "winning_option": row.try_get::<Option<i16>, _>("winning_option").ok().flatten(),
             "odds_home_bps": row.try_get::<Option<i32>, _>("odds_home_bps").ok().flatten(),
             "odds_away_bps": row.try_get::<Option<i32>, _>("odds_away_bps").ok().flatten(),
             "total_bets": row.try_get::<Option<i32>, _>("total_bets").ok().flatten(),
             "total_volume": row.try_get::<Option<bigdecimal::BigDecimal>, _>("total_volume").ok().flatten(),
+            "currency": row.try_get::<String, _>("currency").unwrap_or_default(),
+            "attention_reason": row.try_get::<Option<String>, _>("attention_reason").ok().flatten(),
+            "attention_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("attention_at").ok().flatten(),
+            "league_id": row.try_get::<Option<i64>, _>("league_id").ok().flatten(),
+            "home_team_id": row.try_get::<Option<i64>, _>("home_team_id").ok().flatten(),
+            "away_team_id": row.try_get::<Option<i64>, _>("away_team_id").ok().flatten(),
         })
     }).collect();
 
-    let body = serde_json::json!({
-        "items": items,
-        "pagination": { "page": page, "limit": limit, "total": total, "totalPages": ((total + limit - 1) / limit) }
-    });
+    let body = serde_json::json!({ "items": items, "pagination": pagination::pagination_json(&mode, limit, total, next_cursor) });
     Ok(HttpResponse::Ok().json(ApiResponse::success(body)))
 }
 
@@ -97,11 +110,21 @@ pub struct CreateAdminMarket {
     pub option_b: String,
     pub start_time: chrono::DateTime<chrono::Utc>,
     pub end_time: chrono::DateTime<chrono::Utc>,
+    /// pending markets are opened by the scheduler at this time
+    pub open_time: Option<chrono::DateTime<chrono::Utc>>,
+    /// betting closes at this time; defaults to end_time
+    pub close_time: Option<chrono::DateTime<chrono::Utc>>,
     pub status: String,
     pub odds_home_bps: Option<i32>,
     pub odds_away_bps: Option<i32>,
     pub home_name: Option<String>,
     pub away_name: Option<String>,
+    pub league_id: Option<i64>,
+    /// Catalog teams; home_name / away_name default to their names
+    pub home_team_id: Option<i64>,
+    pub away_team_id: Option<i64>,
+    /// Currency code (default USDC); must be enabled
+    pub currency: Option<String>,
 }
 
 pub async fn create_market(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<CreateAdminMarket>) -> Result<HttpResponse> {
@@ -112,9 +135,20 @@ pub async fn create_market(req: HttpRequest, state: web::Data<AppState>, payload
         return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_status", "status must be one of pending/active/settled/cancelled")));
     }
 
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let refs = MarketRefs { league_id: p.league_id, home_team_id: p.home_team_id, away_team_id: p.away_team_id };
+    if let Err(msg) = catalog::check_market_refs(&mut tx, refs).await.map_err(AppError::from)? {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_catalog_ref", &msg)));
+    }
+    let currency = match currency::check_enabled(&mut tx, p.currency.as_deref().unwrap_or(currency::DEFAULT_CURRENCY)).await.map_err(AppError::from)? {
+        Ok(code) => code,
+        Err(msg) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_currency", &msg))),
+    };
     let rec = sqlx::query(
-        r#"INSERT INTO markets (market_id, title, description, option_a, option_b, start_time, end_time, status, odds_home_bps, odds_away_bps, home_name, away_name, market_address)
-           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        r#"INSERT INTO markets (market_id, title, description, option_a, option_b, start_time, end_time, status, odds_home_bps, odds_away_bps, home_name, away_name, market_address, open_time, close_time,
+                               league_id, home_team_id, away_team_id, currency)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8::market_status, $9, $10,
+                   COALESCE($11, (SELECT name FROM teams WHERE id = $17)), COALESCE($12, (SELECT name FROM teams WHERE id = $18)), $13, $14, $15, $16, $17, $18, $19)
            RETURNING id"#
     )
     .bind(p.market_id)
@@ -130,20 +164,23 @@ pub async fn create_market(req: HttpRequest, state: web::Data<AppState>, payload
     .bind(p.home_name)
     .bind(p.away_name)
     .bind(format!("market_{}", p.market_id))
-    .fetch_one(&state.db_pool)
+    .bind(p.open_time)
+    .bind(p.close_time.unwrap_or(p.end_time))
+    .bind(p.league_id)
+    .bind(p.home_team_id)
+    .bind(p.away_team_id)
+    .bind(&currency)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    .map_err(AppError::from)?;
     let id: i64 = rec.try_get("id").unwrap_or_default();
 
     // Audit
-    let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id, payload_json) VALUES ($1, $2, $3, $4, $5)")
-        .bind(actor_id)
-        .bind("admin.market_create")
-        .bind("markets")
-        .bind(id)
-        .bind(serde_json::json!({"market_id": p.market_id, "title": p.title}))
-        .execute(&state.db_pool)
-        .await;
+    audit::record(&mut *tx, actor_id, "admin.market_create", "markets", Some(id), serde_json::json!({"market_id": p.market_id, "title": p.title, "currency": currency}))
+        .await
+        .map_err(AppError::from)?;
+    outbox::enqueue(&mut *tx, &DomainEvent::MarketCreated { id }).await.map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
 
     Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id}))))
 }
@@ -156,11 +193,18 @@ pub struct UpdateAdminMarket {
     pub option_b: Option<String>,
     pub start_time: Option<chrono::DateTime<chrono::Utc>>,
     pub end_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub open_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub close_time: Option<chrono::DateTime<chrono::Utc>>,
     pub status: Option<String>,
     pub odds_home_bps: Option<i32>,
     pub odds_away_bps: Option<i32>,
     pub home_name: Option<String>,
     pub away_name: Option<String>,
+    pub league_id: Option<i64>,
+    pub home_team_id: Option<i64>,
+    pub away_team_id: Option<i64>,
+    /// Only while the market has no orders
+    pub currency: Option<String>,
 }
 
 pub async fn update_market(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<UpdateAdminMarket>) -> Result<HttpResponse> {
@@ -178,14 +222,23 @@ pub async fn update_market(req: HttpRequest, state: web::Data<AppState>, path: w
     if let Some(v) = p.option_b { push_set!("option_b", v); }
     if let Some(v) = p.start_time { push_set!("start_time", v); }
     if let Some(v) = p.end_time { push_set!("end_time", v); }
+    // bound as text; cast so the schedule columns accept it
+    if let Some(v) = p.open_time { sets.push(format!("open_time = ${}::timestamptz", sets.len() + 1)); binds.push(serde_json::json!(v)); }
+    if let Some(v) = p.close_time { sets.push(format!("close_time = ${}::timestamptz", sets.len() + 1)); binds.push(serde_json::json!(v)); }
     if let Some(v) = p.status { push_set!("status", v); }
     if let Some(v) = p.odds_home_bps { push_set!("odds_home_bps", v); }
     if let Some(v) = p.odds_away_bps { push_set!("odds_away_bps", v); }
     if let Some(v) = p.home_name { push_set!("home_name", v); }
     if let Some(v) = p.away_name { push_set!("away_name", v); }
+    if let Some(v) = p.league_id { push_set!("league_id", v); }
+    if let Some(v) = p.home_team_id { push_set!("home_team_id", v); }
+    if let Some(v) = p.away_team_id { push_set!("away_team_id", v); }
+    if let Some(v) = &p.currency { push_set!("currency", currency::normalize_code(v).unwrap_or_default()); }
+    let changes_refs = p.league_id.is_some() || p.home_team_id.is_some() || p.away_team_id.is_some();
 
     if sets.is_empty() { return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("no_fields", "no fields to update"))); }
-    let mut sql = format!("UPDATE markets SET {} WHERE id = ${} RETURNING id", sets.join(", "), sets.len() + 1);
+    let fields: Vec<String> = sets.iter().filter_map(|s| s.split(" = ").next().map(str::to_string)).collect();
+    let sql = format!("UPDATE markets SET {} WHERE id = ${} RETURNING id", sets.join(", "), sets.len() + 1);
     let mut q = sqlx::query(&sql);
     for v in binds {
         q = match v {
@@ -201,16 +254,39 @@ pub async fn update_market(req: HttpRequest, state: web::Data<AppState>, path: w
         };
     }
     q = q.bind(id);
-    let rec = q.fetch_one(&state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    if let Some(code) = &p.currency {
+        if let Err(msg) = currency::check_enabled(&mut tx, code).await.map_err(AppError::from)? {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_currency", &msg)));
+        }
+    }
+    if changes_refs {
+        // check the combination the market ends up with, not just the fields sent
+        let current: Option<(Option<i64>, Option<i64>, Option<i64>)> = sqlx::query_as("SELECT league_id, home_team_id, away_team_id FROM markets WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        let Some((league_id, home_team_id, away_team_id)) = current else {
+            return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "market not found")));
+        };
+        let refs = MarketRefs {
+            league_id: p.league_id.or(league_id),
+            home_team_id: p.home_team_id.or(home_team_id),
+            away_team_id: p.away_team_id.or(away_team_id),
+        };
+        if let Err(msg) = catalog::check_market_refs(&mut tx, refs).await.map_err(AppError::from)? {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_catalog_ref", &msg)));
+        }
+    }
+    let rec = q.fetch_one(&mut *tx).await.map_err(AppError::from)?;
     let rid: i64 = rec.try_get("id").unwrap_or(id);
 
-    let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id) VALUES ($1, $2, $3, $4)")
-        .bind(actor_id)
-        .bind("admin.market_update")
-        .bind("markets")
-        .bind(rid)
-        .execute(&state.db_pool)
-        .await;
+    audit::record(&mut *tx, actor_id, "admin.market_update", "markets", Some(rid), serde_json::json!({"fields": fields}))
+        .await
+        .map_err(AppError::from)?;
+    outbox::enqueue(&mut *tx, &DomainEvent::MarketUpdated { id: rid, fields }).await.map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
 
     Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": rid}))))
 }
@@ -218,19 +294,20 @@ pub async fn update_market(req: HttpRequest, state: web::Data<AppState>, path: w
 pub async fn deactivate_market(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
     let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
     let id = path.into_inner();
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
     let rec = sqlx::query("UPDATE markets SET status = 'cancelled' WHERE id = $1 RETURNING id")
         .bind(id)
-        .fetch_one(&state.db_pool)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     let rid: i64 = rec.try_get("id").unwrap_or(id);
-    let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id) VALUES ($1, $2, $3, $4)")
-        .bind(actor_id)
-        .bind("admin.market_deactivate")
-        .bind("markets")
-        .bind(rid)
-        .execute(&state.db_pool)
-        .await;
+    audit::record(&mut *tx, actor_id, "admin.market_deactivate", "markets", Some(rid), serde_json::json!({}))
+        .await
+        .map_err(AppError::from)?;
+    outbox::enqueue(&mut *tx, &DomainEvent::MarketStatusChanged { id: rid, status: "cancelled".into() })
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": rid, "status": "cancelled"}))))
 }
 
@@ -241,21 +318,57 @@ pub async fn settle_market(req: HttpRequest, state: web::Data<AppState>, path: w
     let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
     let id = path.into_inner();
     let p = payload.into_inner();
-    let resolved_at = p.resolved_at.unwrap_or_else(|| chrono::Utc::now());
+    let resolved_at = p.resolved_at.unwrap_or_else(chrono::Utc::now);
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
     let rec = sqlx::query("UPDATE markets SET status = 'settled', winning_option = $1, resolved_at = $2 WHERE id = $3 RETURNING id")
         .bind(p.winning_option)
         .bind(resolved_at)
         .bind(id)
-        .fetch_one(&state.db_pool)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     let rid: i64 = rec.try_get("id").unwrap_or(id);
-    let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id) VALUES ($1, $2, $3, $4)")
-        .bind(actor_id)
-        .bind("admin.market_settle")
-        .bind("markets")
-        .bind(rid)
-        .execute(&state.db_pool)
-        .await;
+    audit::record(&mut *tx, actor_id, "admin.market_settle", "markets", Some(rid), serde_json::json!({"winning_option": p.winning_option}))
+        .await
+        .map_err(AppError::from)?;
+    outbox::enqueue(&mut *tx, &DomainEvent::MarketSettled { id: rid, winning_option: p.winning_option })
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": rid, "status": "settled"}))))
-}
\ No newline at end of file
+}
+#[derive(Deserialize)]
+pub struct UpdateMatchStateRequest {
+    #[serde(flatten)]
+    pub state: MatchStateUpdate,
+    /// Who reported it, e.g. a feed name; defaults to "admin"
+    pub source: Option<String>,
+}
+
+/// Record a fixture's live state (phase, clock, score, cards). Fields left out are unchanged.
+pub async fn update_match_state(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<UpdateMatchStateRequest>) -> Result<HttpResponse> {
+    let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
+    crate::utils::auth::require_role(&state.db_pool, actor_id, &["admin", "operator"]).await?;
+    let id = path.into_inner();
+    let p = payload.into_inner();
+    if let Err(msg) = p.state.validate() {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_ARGS", &msg)));
+    }
+    let source = p.source.as_deref().map(str::trim).filter(|s| !s.is_empty() && s.len() <= 64).unwrap_or("admin");
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let Some(stored) = match_state::upsert(&mut tx, id, &p.state, source).await.map_err(AppError::from)? else {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("MARKET_NOT_FOUND", "market not found")));
+    };
+    audit::record(&mut *tx, actor_id, "admin.match_state_update", "markets", Some(id), serde_json::json!({
+        "phase": p.state.phase, "period": p.state.period, "clock_seconds": p.state.clock_seconds,
+        "home_score": p.state.home_score, "away_score": p.state.away_score,
+        "home_red_cards": p.state.home_red_cards, "away_red_cards": p.state.away_red_cards, "source": source,
+    }))
+    .await
+    .map_err(AppError::from)?;
+    outbox::enqueue(&mut *tx, &DomainEvent::MatchStateChanged { id, phase: stored.phase.clone() })
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(stored)))
+}
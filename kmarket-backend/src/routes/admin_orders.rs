@@ -2,8 +2,14 @@ use actix_web::{web, HttpResponse, Result, HttpRequest};
 use serde::Deserialize;
 use sqlx::Row;
 use bigdecimal::BigDecimal;
-use bigdecimal::ToPrimitive;
 use crate::state::AppState;
+use crate::models::event::DomainEvent;
+use crate::repository::order_repo;
+use crate::utils::{audit, currency, fees, outbox};
+use crate::utils::list_query::{ListQuery, Sort, SortField};
+use crate::utils::pagination::{self, PageMode};
+use crate::utils::errors::{AppError, DataAccessError};
+use crate::utils::money::{self, Rounding};
 use crate::utils::response::ApiResponse;
 
 #[derive(Deserialize)]
@@ -13,60 +19,49 @@ pub struct AdminOrdersQuery {
     pub status: Option<String>,
     pub user: Option<String>,
     pub market_id: Option<i64>,
+    /// Currency code, e.g. USDC
+    pub currency: Option<String>,
+    /// Keyset paging on the sort field and id; empty for the first page, then the previous next_cursor
+    pub cursor: Option<String>,
+    /// Count matching rows (default: true with page, false with cursor)
+    pub with_total: Option<bool>,
+    /// One of ORDER_SORTS (default created_at)
+    pub sort: Option<String>,
+    /// asc | desc (default desc)
+    pub order: Option<String>,
 }
 
+/// Sortable fields of /admin/orders (first = default)
+const ORDER_SORTS: &[SortField] = &[
+    SortField { name: "created_at", column: "o.created_at", keyset: true },
+    SortField { name: "updated_at", column: "o.updated_at", keyset: true },
+    SortField { name: "amount", column: "o.amount", keyset: false },
+];
+
 pub async fn list_orders(req: HttpRequest, state: web::Data<AppState>, query: web::Query<AdminOrdersQuery>) -> Result<HttpResponse> {
     let _actor = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
-    let page = query.page.unwrap_or(1).max(1);
     let limit = query.limit.unwrap_or(20).clamp(1, 100);
-    let offset = (page - 1) * limit;
-
-    let mut count_sql = String::from("SELECT COUNT(*) AS total FROM orders o JOIN users u ON u.id = o.user_id JOIN markets m ON m.id = o.market_id");
-    let mut idx = 1;
-    let mut has_where = false;
-    if let Some(status) = &query.status {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("o.status = ${}", idx)); idx += 1;
-    }
-    if let Some(user) = &query.user {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("u.address = ${}", idx)); idx += 1;
-    }
-    if let Some(market_id) = &query.market_id {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("m.market_id = ${}", idx)); idx += 1;
-    }
-    let mut qc = sqlx::query(&count_sql);
-    if let Some(status) = &query.status { qc = qc.bind(status); }
-    if let Some(user) = &query.user { qc = qc.bind(user); }
-    if let Some(market_id) = &query.market_id { qc = qc.bind(market_id); }
-    let row = qc.fetch_one(&state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
-    let total: i64 = row.try_get("total").unwrap_or(0);
-
-    let mut data_sql = String::from(
-        "SELECT o.id, o.order_id, o.user_id, u.address AS wallet_address, o.market_id, m.market_id AS fixture_id, o.amount, o.odds, o.option, o.status, o.created_at, o.updated_at, o.closed_at, o.close_price, o.close_pnl FROM orders o JOIN users u ON u.id = o.user_id JOIN markets m ON m.id = o.market_id"
+    let mode = match PageMode::from_query(query.page, limit, query.cursor.as_deref()) {
+        Ok(mode) => mode,
+        Err(resp) => return Ok(resp),
+    };
+    let sort = match Sort::parse(ORDER_SORTS, query.sort.as_deref(), query.order.as_deref(), &mode) {
+        Ok(sort) => sort,
+        Err(resp) => return Ok(resp),
+    };
+
+    let mut list = ListQuery::new(
+        "o.id, o.order_id, o.user_id, u.address AS wallet_address, o.market_id, m.market_id AS fixture_id, o.amount, o.currency, o.odds, o.option, o.status, o.created_at, o.updated_at, o.closed_at, o.close_price, o.close_pnl, o.fee_paid, o.claimed_at, o.claim_tx_signature",
+        "orders o JOIN users u ON u.id = o.user_id JOIN markets m ON m.id = o.market_id",
+        "o.id",
     );
-    let mut idx2 = 1;
-    let mut has_where2 = false;
-    if let Some(status) = &query.status {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("o.status = ${}", idx2)); idx2 += 1;
-    }
-    if let Some(user) = &query.user {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("u.address = ${}", idx2)); idx2 += 1;
-    }
-    if let Some(market_id) = &query.market_id {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("m.market_id = ${}", idx2)); idx2 += 1;
-    }
-    data_sql.push_str(&format!(" ORDER BY o.created_at DESC LIMIT ${} OFFSET ${}", idx2, idx2 + 1));
-    let mut qd = sqlx::query(&data_sql);
-    if let Some(status) = &query.status { qd = qd.bind(status); }
-    if let Some(user) = &query.user { qd = qd.bind(user); }
-    if let Some(market_id) = &query.market_id { qd = qd.bind(market_id); }
-    qd = qd.bind(limit).bind(offset);
-    let rows = qd.fetch_all(&state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    list.filter_opt("o.status = {}", query.status.as_ref())
+        .filter_opt("u.address = {}", query.user.as_ref())
+        .filter_opt("m.market_id = {}", query.market_id)
+        .filter_opt("o.currency = {}", query.currency.as_ref().map(|c| c.to_ascii_uppercase()));
+    let page = list.fetch(&state.db_pool, sort, limit, &mode, mode.wants_total(query.with_total)).await
+        .map_err(AppError::from)?;
+    let (rows, total, next_cursor) = (page.rows, page.total, page.next_cursor);
 
     let items: Vec<serde_json::Value> = rows.into_iter().map(|row| {
         serde_json::json!({
@@ -77,6 +72,7 @@ pub async fn list_orders(req: HttpRequest, state: web::Data<AppState>, query: we
             "market_id": row.try_get::<i64, _>("market_id").unwrap_or_default(),
             "fixture_id": row.try_get::<i64, _>("fixture_id").unwrap_or_default(),
             "amount": row.try_get::<BigDecimal, _>("amount").unwrap_or_else(|_| BigDecimal::from(0)).to_string(),
+            "currency": row.try_get::<String, _>("currency").unwrap_or_default(),
             "odds": row.try_get::<BigDecimal, _>("odds").unwrap_or_else(|_| BigDecimal::from(0)).to_string(),
             "option": row.try_get::<i16, _>("option").unwrap_or_default(),
             "status": row.try_get::<String, _>("status").unwrap_or_default(),
@@ -85,10 +81,13 @@ pub async fn list_orders(req: HttpRequest, state: web::Data<AppState>, query: we
             "closed_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("closed_at").ok().flatten(),
             "close_price": row.try_get::<Option<BigDecimal>, _>("close_price").ok().flatten().map(|d| d.to_string()),
             "close_pnl": row.try_get::<Option<BigDecimal>, _>("close_pnl").ok().flatten().map(|d| d.to_string()),
+            "fee_paid": row.try_get::<BigDecimal, _>("fee_paid").ok().map(|d| d.to_string()),
+            "claimed_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("claimed_at").ok().flatten(),
+            "claim_tx_signature": row.try_get::<Option<String>, _>("claim_tx_signature").ok().flatten(),
         })
     }).collect();
 
-    let body = serde_json::json!({ "items": items, "pagination": { "page": page, "limit": limit, "total": total, "totalPages": ((total + limit - 1) / limit) } });
+    let body = serde_json::json!({ "items": items, "pagination": pagination::pagination_json(&mode, limit, total, next_cursor) });
     Ok(HttpResponse::Ok().json(ApiResponse::success(body)))
 }
 
@@ -96,12 +95,12 @@ pub async fn get_order_detail(req: HttpRequest, state: web::Data<AppState>, path
     let _actor = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
     let id = path.into_inner();
     let row = sqlx::query(
-        "SELECT o.id, o.order_id, o.user_id, u.address AS wallet_address, o.market_id, m.market_id AS fixture_id, o.amount, o.odds, o.option, o.status, o.created_at, o.updated_at, o.closed_at, o.close_price, o.close_pnl FROM orders o JOIN users u ON u.id = o.user_id JOIN markets m ON m.id = o.market_id WHERE o.id = $1"
+        "SELECT o.id, o.order_id, o.user_id, u.address AS wallet_address, o.market_id, m.market_id AS fixture_id, o.amount, o.currency, o.odds, o.option, o.status, o.created_at, o.updated_at, o.closed_at, o.close_price, o.close_pnl, o.fee_paid, o.claimed_at, o.claim_tx_signature FROM orders o JOIN users u ON u.id = o.user_id JOIN markets m ON m.id = o.market_id WHERE o.id = $1"
     )
     .bind(id)
     .fetch_optional(&state.db_pool)
     .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    .map_err(AppError::from)?;
 
     if let Some(row) = row {
         let item = serde_json::json!({
@@ -112,6 +111,7 @@ pub async fn get_order_detail(req: HttpRequest, state: web::Data<AppState>, path
             "market_id": row.try_get::<i64, _>("market_id").unwrap_or_default(),
             "fixture_id": row.try_get::<i64, _>("fixture_id").unwrap_or_default(),
             "amount": row.try_get::<BigDecimal, _>("amount").map(|v| v.to_string()).unwrap_or_else(|_| BigDecimal::from(0).to_string()),
+            "currency": row.try_get::<String, _>("currency").unwrap_or_default(),
             "odds": row.try_get::<BigDecimal, _>("odds").map(|v| v.to_string()).unwrap_or_else(|_| BigDecimal::from(0).to_string()),
             "option": row.try_get::<i16, _>("option").unwrap_or_default(),
             "status": row.try_get::<String, _>("status").unwrap_or_default(),
@@ -120,6 +120,9 @@ pub async fn get_order_detail(req: HttpRequest, state: web::Data<AppState>, path
             "closed_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("closed_at").ok().flatten(),
             "close_price": row.try_get::<Option<BigDecimal>, _>("close_price").ok().flatten().map(|d| d.to_string()),
             "close_pnl": row.try_get::<Option<BigDecimal>, _>("close_pnl").ok().flatten().map(|d| d.to_string()),
+            "fee_paid": row.try_get::<BigDecimal, _>("fee_paid").ok().map(|d| d.to_string()),
+            "claimed_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("claimed_at").ok().flatten(),
+            "claim_tx_signature": row.try_get::<Option<String>, _>("claim_tx_signature").ok().flatten(),
         });
         return Ok(HttpResponse::Ok().json(ApiResponse::success(item)));
     }
@@ -133,75 +136,224 @@ pub async fn cancel_order(req: HttpRequest, state: web::Data<AppState>, path: we
     let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
     let id = path.into_inner();
     let reason = payload.reason.clone();
-    let mut tx = state.db_pool.begin().await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
     let exists = sqlx::query("SELECT status FROM orders WHERE id = $1")
         .bind(id)
         .fetch_optional(&mut *tx)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     if let Some(r) = exists { let st: String = r.try_get("status").unwrap_or_default(); if st == "cancelled" { tx.rollback().await.ok(); return Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id, "status": st})))); } }
-    let rec = sqlx::query("UPDATE orders SET status = 'cancelled' WHERE id = $1 RETURNING id")
+    let rec = sqlx::query("UPDATE orders SET status = 'cancelled' WHERE id = $1 RETURNING id, market_id, user_id")
         .bind(id)
         .fetch_one(&mut *tx)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     let rid: i64 = rec.try_get("id").unwrap_or(id);
     // audit
-    let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id, payload_json) VALUES ($1, $2, $3, $4, $5)")
-        .bind(actor_id).bind("admin.order_cancel").bind("orders").bind(rid)
-        .bind(serde_json::json!({"reason": reason}))
-        .execute(&mut *tx)
-        .await;
-    tx.commit().await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    audit::record(&mut *tx, actor_id, "admin.order_cancel", "orders", Some(rid), serde_json::json!({"reason": reason}))
+        .await
+        .map_err(AppError::from)?;
+    let event = DomainEvent::OrderCancelled { id: rid, market_id: rec.try_get("market_id").unwrap_or_default(), user_id: rec.try_get("user_id").unwrap_or_default() };
+    outbox::enqueue(&mut *tx, &event).await.map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": rid, "status": "cancelled"}))))
 }
 
 #[derive(Deserialize)]
-pub struct SettleOrderRequest { pub close_price: f64, pub closed_at: Option<chrono::DateTime<chrono::Utc>> }
+pub struct SettleOrderRequest {
+    /// Amount returned to the user (decimal string or number) before settlement fees, which are
+    /// withheld from it; close_pnl = close_price - amount
+    #[serde(deserialize_with = "money::lenient")]
+    pub close_price: BigDecimal,
+    pub closed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
 
 pub async fn settle_order(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<SettleOrderRequest>) -> Result<HttpResponse> {
     let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
     let id = path.into_inner();
     let p = payload.into_inner();
-    let closed_at = p.closed_at.unwrap_or_else(|| chrono::Utc::now());
-    let mut tx = state.db_pool.begin().await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let closed_at = p.closed_at.unwrap_or_else(chrono::Utc::now);
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
 
     // read order
-    let row = sqlx::query("SELECT user_id, amount FROM orders WHERE id = $1")
+    let row = sqlx::query("SELECT o.user_id, o.market_id, o.amount, o.status::TEXT AS status, o.claimed_at, c.decimals FROM orders o JOIN currencies c ON c.code = o.currency WHERE o.id = $1 FOR UPDATE OF o")
         .bind(id)
-        .fetch_one(&mut *tx)
+        .fetch_optional(&mut *tx)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::NotFound("order".into()))?;
+    if row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("claimed_at").map_err(AppError::from)?.is_some() {
+        // the payout is gone; settling again would change a paid liability
+        return Err(AppError::from(DataAccessError::AlreadyClaimed(id)).into());
+    }
+    let rounding = Rounding::new(row.try_get("decimals").map_err(AppError::from)?);
+    let close_price = rounding.amount(&p.close_price).map_err(|m| AppError::bad_request("invalid_argument", m))?;
+    // settling again would charge fees and add the pnl a second time
+    let status: String = row.try_get("status").map_err(AppError::from)?;
+    if status != "placed" {
+        return Err(AppError::conflict("order_not_open", format!("order is {}, only placed orders can be settled", status)).into());
+    }
     let user_id: i64 = row.try_get("user_id").unwrap_or_default();
-    let amount_dec: BigDecimal = row.try_get("amount").unwrap_or_else(|_| BigDecimal::from(0));
-    let amount = amount_dec.to_f64().unwrap_or(0.0);
-    let close_pnl = p.close_price - amount;
+    let market_id: i64 = row.try_get("market_id").unwrap_or_default();
+    let amount: BigDecimal = row.try_get("amount").unwrap_or_else(|_| BigDecimal::from(0));
+    let close_pnl = &close_price - &amount;
 
-    // update order
-    let _ = sqlx::query("UPDATE orders SET status = 'settled', closed_at = $1, close_price = $2, close_pnl = $3 WHERE id = $4")
+    // update order, then withhold settlement fees from the payout
+    let settled = sqlx::query("UPDATE orders SET status = 'settled', closed_at = $1, close_price = $2, close_pnl = $3 WHERE id = $4")
         .bind(closed_at)
-        .bind(p.close_price)
-        .bind(close_pnl)
+        .bind(&close_price)
+        .bind(&close_pnl)
         .bind(id)
         .execute(&mut *tx)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?
+        .rows_affected();
+    if settled != 1 {
+        return Err(AppError::internal(format!("settling order {} updated {} rows", id, settled)).into());
+    }
+    fees::charge_at_settlement(&mut tx, &[id]).await.map_err(AppError::from)?;
+    let row = sqlx::query("SELECT close_price, close_pnl, fee_paid FROM orders WHERE id = $1")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    let close_price: BigDecimal = row.try_get("close_price").map_err(AppError::from)?;
+    let close_pnl: BigDecimal = row.try_get("close_pnl").map_err(AppError::from)?;
+    let fee_paid: BigDecimal = row.try_get("fee_paid").map_err(AppError::from)?;
 
-    // update user total_pnl
-    let _ = sqlx::query("UPDATE users SET total_pnl = COALESCE(total_pnl, 0) + $1 WHERE id = $2")
-        .bind(close_pnl)
+    // update user total_pnl, overall and per currency
+    let credited = sqlx::query("UPDATE users SET total_pnl = COALESCE(total_pnl, 0) + $1 WHERE id = $2")
+        .bind(&close_pnl)
         .bind(user_id)
         .execute(&mut *tx)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?
+        .rows_affected();
+    if credited != 1 {
+        return Err(AppError::internal(format!("order {} belongs to missing user {}", id, user_id)).into());
+    }
+    currency::add_settled_pnl(&mut tx, &[id]).await.map_err(AppError::from)?;
 
     // audit
-    let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id, payload_json) VALUES ($1, $2, $3, $4, $5)")
-        .bind(actor_id).bind("admin.order_settle").bind("orders").bind(id)
-        .bind(serde_json::json!({"close_price": p.close_price, "close_pnl": close_pnl}))
-        .execute(&mut *tx)
-        .await;
+    audit::record(&mut *tx, actor_id, "admin.order_settle", "orders", Some(id), serde_json::json!({"close_price": close_price, "close_pnl": close_pnl, "fee_paid": fee_paid}))
+        .await
+        .map_err(AppError::from)?;
+    outbox::enqueue(&mut *tx, &DomainEvent::OrderSettled { id, market_id, user_id })
+        .await
+        .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id, "status": "settled", "close_pnl": close_pnl, "fee_paid": fee_paid}))))
+}
+#[derive(Deserialize)]
+pub struct ClaimOrderRequest {
+    /// The on-chain transaction that paid the order out
+    pub transaction_signature: String,
+}
+
+/// Record that a settled winning order was paid out on-chain; the user's balance is not credited
+pub async fn claim_order(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<ClaimOrderRequest>) -> Result<HttpResponse> {
+    let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
+    crate::utils::auth::require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let id = path.into_inner();
+    let signature = payload.transaction_signature.trim();
+    if signature.is_empty() || signature.len() > 128 {
+        return Err(AppError::bad_request("invalid_argument", "transaction_signature must be 1-128 characters").into());
+    }
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let claim = order_repo::record_onchain_payout(&mut tx, id, signature).await.map_err(AppError::from)?;
+    audit::record(&mut *tx, actor_id, "admin.order_claim", "orders", Some(id), serde_json::json!({"transaction_signature": signature, "amount": claim.amount}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(claim)))
+}
 
-    tx.commit().await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
-    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id, "status": "settled", "close_pnl": close_pnl}))))
-}
\ No newline at end of file
+#[derive(Deserialize)]
+pub struct UnclaimedQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub user: Option<String>,
+    /// Currency code, e.g. USDC
+    pub currency: Option<String>,
+    /// Keyset paging on closed_at and id; empty for the first page, then the previous next_cursor
+    pub cursor: Option<String>,
+    /// Count matching rows (default: true with page, false with cursor)
+    pub with_total: Option<bool>,
+    /// asc (default, oldest first) | desc
+    pub order: Option<String>,
+}
+
+/// Sortable fields of /admin/claims/unclaimed (first = default)
+const UNCLAIMED_SORTS: &[SortField] = &[
+    SortField { name: "closed_at", column: "o.closed_at", keyset: true },
+];
+
+/// Unclaimed payouts of settled orders, the house's outstanding liabilities: totals per currency
+/// and the positions behind them, oldest first
+pub async fn list_unclaimed(req: HttpRequest, state: web::Data<AppState>, query: web::Query<UnclaimedQuery>) -> Result<HttpResponse> {
+    let _actor = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let mode = match PageMode::from_query(query.page, limit, query.cursor.as_deref()) {
+        Ok(mode) => mode,
+        Err(resp) => return Ok(resp),
+    };
+    let sort = match Sort::parse(UNCLAIMED_SORTS, None, Some(query.order.as_deref().unwrap_or("asc")), &mode) {
+        Ok(sort) => sort,
+        Err(resp) => return Ok(resp),
+    };
+    let currency = query.currency.as_ref().map(|c| c.to_ascii_uppercase());
+
+    let totals = sqlx::query(
+        "SELECT o.currency, COUNT(*) AS positions, COUNT(DISTINCT o.user_id) AS users, SUM(o.close_price) AS amount, MIN(o.closed_at) AS oldest \
+         FROM orders o JOIN users u ON u.id = o.user_id \
+         WHERE o.status = 'settled' AND o.claimed_at IS NULL AND o.close_price > 0 \
+           AND ($1::TEXT IS NULL OR o.currency = $1) AND ($2::TEXT IS NULL OR u.address = $2) \
+         GROUP BY o.currency ORDER BY o.currency"
+    )
+    .bind(&currency)
+    .bind(&query.user)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(AppError::from)?;
+    let totals: Vec<serde_json::Value> = totals.into_iter().map(|row| {
+        serde_json::json!({
+            "currency": row.try_get::<String, _>("currency").unwrap_or_default(),
+            "positions": row.try_get::<i64, _>("positions").unwrap_or(0),
+            "users": row.try_get::<i64, _>("users").unwrap_or(0),
+            "amount": row.try_get::<BigDecimal, _>("amount").ok(),
+            "oldest_closed_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("oldest").ok().flatten(),
+        })
+    }).collect();
+
+    let mut list = ListQuery::new(
+        "o.id, o.order_id, o.user_id, u.address AS wallet_address, o.market_id, m.market_id AS fixture_id, o.currency, o.amount, o.close_price, o.fee_paid, o.closed_at",
+        "orders o JOIN users u ON u.id = o.user_id JOIN markets m ON m.id = o.market_id",
+        "o.id",
+    );
+    list.condition("o.status = 'settled' AND o.claimed_at IS NULL AND o.close_price > 0")
+        .filter_opt("u.address = {}", query.user.as_ref())
+        .filter_opt("o.currency = {}", currency);
+    let page = list.fetch(&state.db_pool, sort, limit, &mode, mode.wants_total(query.with_total)).await
+        .map_err(AppError::from)?;
+    let (rows, total, next_cursor) = (page.rows, page.total, page.next_cursor);
+
+    let items: Vec<serde_json::Value> = rows.into_iter().map(|row| {
+        serde_json::json!({
+            "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+            "order_id": row.try_get::<i64, _>("order_id").unwrap_or_default(),
+            "user_id": row.try_get::<i64, _>("user_id").unwrap_or_default(),
+            "wallet_address": row.try_get::<String, _>("wallet_address").unwrap_or_default(),
+            "market_id": row.try_get::<i64, _>("market_id").unwrap_or_default(),
+            "fixture_id": row.try_get::<i64, _>("fixture_id").unwrap_or_default(),
+            "currency": row.try_get::<String, _>("currency").unwrap_or_default(),
+            "amount": row.try_get::<BigDecimal, _>("amount").ok().map(|d| d.to_string()),
+            "payout": row.try_get::<BigDecimal, _>("close_price").ok().map(|d| d.to_string()),
+            "fee_paid": row.try_get::<BigDecimal, _>("fee_paid").ok().map(|d| d.to_string()),
+            "closed_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("closed_at").ok().flatten(),
+        })
+    }).collect();
+
+    let body = serde_json::json!({ "totals": totals, "items": items, "pagination": pagination::pagination_json(&mode, limit, total, next_cursor) });
+    Ok(HttpResponse::Ok().json(ApiResponse::success(body)))
+}
@@ -0,0 +1,179 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use serde::Deserialize;
+
+use crate::models::event::DomainEvent;
+use crate::state::AppState;
+use crate::utils::{audit, catalog, outbox};
+use crate::utils::auth::{admin_actor_id, require_role};
+use crate::utils::match_state::PHASE_LIVE;
+use crate::utils::response::ApiResponse;
+use crate::utils::simulator::{self, MatchSimulation, Sport, DEFAULT_ODDS_BPS, DEFAULT_TICK_SECS, MAX_DURATION_SECS, SIMULATION_COLUMNS, SIMULATION_RUNNING, SIMULATION_STOPPED};
+use crate::utils::errors::AppError;
+
+#[derive(Deserialize)]
+pub struct StartSimulationRequest {
+    /// football | basketball | american_football; defaults to the sport of the market's league,
+    /// then to the title (NBA, NFL) for markets without one, else football
+    pub sport: Option<String>,
+    /// Wall-clock length of the match, at most 30 minutes
+    pub duration_secs: Option<i32>,
+    pub tick_secs: Option<i32>,
+    /// Replays the same match when reused
+    pub seed: Option<i64>,
+}
+
+fn sport_from_title(title: &str) -> Sport {
+    let title = title.to_ascii_uppercase();
+    if title.contains("NBA") {
+        Sport::Basketball
+    } else if title.contains("NFL") {
+        Sport::AmericanFootball
+    } else {
+        Sport::Football
+    }
+}
+
+/// Start a simulated match: the market goes live (pending -> active) and the simulator advances
+/// clock, score and odds every tick_secs, settling the market from the final score.
+pub async fn start_simulation(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<StartSimulationRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let id = path.into_inner();
+    let p = payload.into_inner();
+    let duration_secs = p.duration_secs.unwrap_or(MAX_DURATION_SECS);
+    let tick_secs = p.tick_secs.unwrap_or(DEFAULT_TICK_SECS);
+    if !(1..=MAX_DURATION_SECS).contains(&duration_secs) || !(1..=60).contains(&tick_secs) || tick_secs > duration_secs {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_schedule", &format!("duration_secs must be 1..={} and tick_secs 1..=60, not longer than the match", MAX_DURATION_SECS))));
+    }
+    let sport = match p.sport.as_deref().map(Sport::parse) {
+        Some(Some(s)) => Some(s),
+        Some(None) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_sport", "sport must be football, basketball or american_football"))),
+        None => None,
+    };
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let market: Option<(String, String, Option<i32>, Option<i32>)> = sqlx::query_as("SELECT title, status::TEXT, odds_home_bps, odds_away_bps FROM markets WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    let Some((title, status, odds_home, odds_away)) = market else {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "market not found")));
+    };
+    if status != "pending" && status != "active" {
+        return Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("invalid_status", "only pending or active markets can be simulated")));
+    }
+    let running: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM match_simulations WHERE market_id = $1 AND status = $2)")
+        .bind(id)
+        .bind(SIMULATION_RUNNING)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    if running {
+        return Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("simulation_running", "a simulation is already running for this market")));
+    }
+
+    let sport = match sport {
+        Some(s) => s,
+        None => catalog::market_sport_code(&mut tx, id).await.map_err(AppError::from)?
+            .and_then(|code| Sport::parse(&code))
+            .unwrap_or_else(|| sport_from_title(&title)),
+    };
+    let odds_home = odds_home.filter(|b| *b > 10000).unwrap_or(DEFAULT_ODDS_BPS);
+    let odds_away = odds_away.filter(|b| *b > 10000).unwrap_or(DEFAULT_ODDS_BPS);
+    let (period, minute, second, phase) = simulator::match_clock(sport, 0.0);
+    let seed = p.seed.unwrap_or_else(rand::random);
+    let sim = sqlx::query_as::<_, MatchSimulation>(&format!(
+        "INSERT INTO match_simulations (market_id, sport, seed, tick_secs, duration_secs, period, minute, second, phase, \
+             pre_odds_home_bps, pre_odds_away_bps, odds_home_bps, odds_away_bps, started_by, next_tick_at) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $10, $11, $12, NOW() + $4 * INTERVAL '1 second') RETURNING {}",
+        SIMULATION_COLUMNS
+    ))
+    .bind(id).bind(sport.as_str()).bind(seed).bind(tick_secs).bind(duration_secs).bind(period).bind(minute).bind(second).bind(&phase)
+    .bind(odds_home).bind(odds_away).bind(actor_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    if status == "pending" {
+        sqlx::query("UPDATE markets SET status = 'active' WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        outbox::enqueue(&mut *tx, &DomainEvent::MarketStatusChanged { id, status: "active".into() })
+            .await
+            .map_err(AppError::from)?;
+    }
+    simulator::record_match_state(&mut tx, &sim, PHASE_LIVE).await.map_err(AppError::from)?;
+    audit::record(&mut *tx, actor_id, "admin.simulation_start", "markets", Some(id), serde_json::json!({
+        "simulation_id": sim.id, "sport": sim.sport, "duration_secs": duration_secs, "tick_secs": tick_secs, "seed": seed,
+    }))
+    .await
+    .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(sim)))
+}
+
+/// Latest simulation of a market (running or finished)
+pub async fn get_simulation(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin", "analyst"]).await?;
+    let sim = sqlx::query_as::<_, MatchSimulation>(&format!(
+        "SELECT {} FROM match_simulations WHERE market_id = $1 ORDER BY id DESC LIMIT 1",
+        SIMULATION_COLUMNS
+    ))
+    .bind(path.into_inner())
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(AppError::from)?;
+    match sim {
+        Some(sim) => Ok(HttpResponse::Ok().json(ApiResponse::success(sim))),
+        None => Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "no simulation for this market"))),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct StopSimulationRequest {
+    /// Settle the market from the current score (default); false leaves the market live
+    pub settle: Option<bool>,
+}
+
+/// End a running simulation early
+pub async fn stop_simulation(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<StopSimulationRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let id = path.into_inner();
+    let settle = payload.settle.unwrap_or(true);
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    // waits for a tick in progress, so the stop applies to the latest score
+    let sim = sqlx::query_as::<_, MatchSimulation>(&format!(
+        "SELECT {} FROM match_simulations WHERE market_id = $1 AND status = $2 FOR UPDATE",
+        SIMULATION_COLUMNS
+    ))
+    .bind(id)
+    .bind(SIMULATION_RUNNING)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    let Some(mut sim) = sim else {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "no running simulation for this market")));
+    };
+    audit::record(&mut *tx, actor_id, "admin.simulation_stop", "markets", Some(id), serde_json::json!({"simulation_id": sim.id, "settle": settle}))
+        .await
+        .map_err(AppError::from)?;
+    if settle {
+        simulator::finish(&mut tx, &mut sim, actor_id).await.map_err(AppError::from)?;
+    } else {
+        sim = sqlx::query_as::<_, MatchSimulation>(&format!(
+            "UPDATE match_simulations SET status = $2, finished_at = NOW() WHERE id = $1 RETURNING {}",
+            SIMULATION_COLUMNS
+        ))
+        .bind(sim.id)
+        .bind(SIMULATION_STOPPED)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    }
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(sim)))
+}
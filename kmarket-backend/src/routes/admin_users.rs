@@ -1,8 +1,14 @@
 use actix_web::{web, HttpResponse, Result, HttpRequest};
+use bigdecimal::BigDecimal;
 use serde::Deserialize;
 use sqlx::Row;
 
 use crate::state::AppState;
+use crate::models::event::DomainEvent;
+use crate::utils::{audit, currency, money, outbox};
+use crate::utils::list_query::{ListQuery, Sort, SortField};
+use crate::utils::pagination::{self, PageMode};
+use crate::utils::errors::AppError;
 use crate::utils::response::ApiResponse;
 
 #[derive(Deserialize)]
@@ -13,70 +19,49 @@ pub struct AdminUsersQuery {
     pub status: Option<String>,
     pub blacklisted: Option<bool>,
     pub whitelisted: Option<bool>,
+    /// Keyset paging on the sort field and id; empty for the first page, then the previous next_cursor
+    pub cursor: Option<String>,
+    /// Count matching rows (default: true with page, false with cursor)
+    pub with_total: Option<bool>,
+    /// One of USER_SORTS (default created_at)
+    pub sort: Option<String>,
+    /// asc | desc (default desc)
+    pub order: Option<String>,
 }
 
+/// Sortable fields of /admin/users (first = default)
+const USER_SORTS: &[SortField] = &[
+    SortField { name: "created_at", column: "u.created_at", keyset: true },
+    SortField { name: "updated_at", column: "u.updated_at", keyset: true },
+    SortField { name: "total_pnl", column: "u.total_pnl", keyset: false },
+    SortField { name: "balance", column: "u.balance", keyset: false },
+    SortField { name: "address", column: "u.address", keyset: false },
+];
+
 pub async fn list_users(req: HttpRequest, state: web::Data<AppState>, query: web::Query<AdminUsersQuery>) -> Result<HttpResponse> {
     let _actor = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
-    let page = query.page.unwrap_or(1).max(1);
     let limit = query.limit.unwrap_or(20).clamp(1, 100);
-    let offset = (page - 1) * limit;
-
-    let mut count_sql = String::from("SELECT COUNT(*) AS total FROM users u");
-    let mut idx = 1;
-    let mut has_where = false;
-    if let Some(status) = &query.status {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("u.status = ${}", idx)); idx += 1;
-    }
-    if let Some(blacklisted) = query.blacklisted {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("u.blacklisted = ${}", idx)); idx += 1;
-    }
-    if let Some(whitelisted) = query.whitelisted {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("u.whitelisted = ${}", idx)); idx += 1;
-    }
-    if let Some(_q) = &query.q {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("(u.address ILIKE ${} OR u.username ILIKE ${} OR u.email ILIKE ${})", idx, idx + 1, idx + 2)); idx += 3;
-    }
-    let mut qc = sqlx::query(&count_sql);
-    if let Some(status) = &query.status { qc = qc.bind(status); }
-    if let Some(blacklisted) = query.blacklisted { qc = qc.bind(blacklisted); }
-    if let Some(whitelisted) = query.whitelisted { qc = qc.bind(whitelisted); }
-    if let Some(q) = &query.q { let pat = format!("%{}%", q); qc = qc.bind(pat.clone()).bind(pat.clone()).bind(pat); }
-    let row = qc.fetch_one(&state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
-    let total: i64 = row.try_get("total").unwrap_or(0);
-
-    let mut data_sql = String::from(
-        "SELECT u.id, u.address, u.username, u.email, u.status, u.total_pnl, u.balance, u.blacklisted, u.whitelisted, u.created_at, u.updated_at FROM users u"
+    let mode = match PageMode::from_query(query.page, limit, query.cursor.as_deref()) {
+        Ok(mode) => mode,
+        Err(resp) => return Ok(resp),
+    };
+    let sort = match Sort::parse(USER_SORTS, query.sort.as_deref(), query.order.as_deref(), &mode) {
+        Ok(sort) => sort,
+        Err(resp) => return Ok(resp),
+    };
+
+    let mut list = ListQuery::new(
+        "u.id, u.address, u.username, u.email, u.status, u.total_pnl, u.balance, u.blacklisted, u.whitelisted, u.created_at, u.updated_at",
+        "users u",
+        "u.id",
     );
-    let mut idx2 = 1;
-    let mut has_where2 = false;
-    if let Some(status) = &query.status {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("u.status = ${}", idx2)); idx2 += 1;
-    }
-    if let Some(blacklisted) = query.blacklisted {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("u.blacklisted = ${}", idx2)); idx2 += 1;
-    }
-    if let Some(whitelisted) = query.whitelisted {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("u.whitelisted = ${}", idx2)); idx2 += 1;
-    }
-    if let Some(_q) = &query.q {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("(u.address ILIKE ${} OR u.username ILIKE ${} OR u.email ILIKE ${})", idx2, idx2 + 1, idx2 + 2)); idx2 += 3;
-    }
-    data_sql.push_str(&format!(" ORDER BY u.created_at DESC LIMIT ${} OFFSET ${}", idx2, idx2 + 1));
-    let mut qd = sqlx::query(&data_sql);
-    if let Some(status) = &query.status { qd = qd.bind(status); }
-    if let Some(blacklisted) = query.blacklisted { qd = qd.bind(blacklisted); }
-    if let Some(whitelisted) = query.whitelisted { qd = qd.bind(whitelisted); }
-    if let Some(q) = &query.q { let pat = format!("%{}%", q); qd = qd.bind(pat.clone()).bind(pat.clone()).bind(pat); }
-    qd = qd.bind(limit).bind(offset);
-    let rows = qd.fetch_all(&state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    list.filter_opt("u.status = {}", query.status.as_ref())
+        .filter_opt("u.blacklisted = {}", query.blacklisted)
+        .filter_opt("u.whitelisted = {}", query.whitelisted)
+        .filter_opt("u.address ILIKE {} OR u.username ILIKE {} OR u.email ILIKE {}", query.q.as_ref().map(|q| format!("%{}%", q)));
+    let page = list.fetch(&state.db_pool, sort, limit, &mode, mode.wants_total(query.with_total)).await
+        .map_err(AppError::from)?;
+    let (rows, total, next_cursor) = (page.rows, page.total, page.next_cursor);
 
     let items: Vec<serde_json::Value> = rows.into_iter().map(|row| {
         serde_json::json!({
@@ -94,18 +79,18 @@ pub async fn list_users(req: HttpRequest, state: web::Data<AppState>, query: web
         })
     }).collect();
 
-    let body = serde_json::json!({ "items": items, "pagination": { "page": page, "limit": limit, "total": total, "totalPages": ((total + limit - 1) / limit) } });
+    let body = serde_json::json!({ "items": items, "pagination": pagination::pagination_json(&mode, limit, total, next_cursor) });
     Ok(HttpResponse::Ok().json(ApiResponse::success(body)))
 }
 
 pub async fn get_user_detail(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
     let _actor = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
     let id = path.into_inner();
-    let row = sqlx::query("SELECT id, address, username, email, status, total_pnl, balance, blacklisted, whitelisted, created_at, updated_at FROM users WHERE id = $1")
+    let row = sqlx::query("SELECT id, address, username, email, status, total_pnl, balance, blacklisted, whitelisted, fee_tier, created_at, updated_at FROM users WHERE id = $1")
         .bind(id)
         .fetch_optional(&state.db_pool)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
 
     if let Some(row) = row {
         let item = serde_json::json!({
@@ -118,6 +103,7 @@ pub async fn get_user_detail(req: HttpRequest, state: web::Data<AppState>, path:
             "balance": row.try_get::<Option<bigdecimal::BigDecimal>, _>("balance").ok().flatten().map(|d| d.to_string()),
             "blacklisted": row.try_get::<bool, _>("blacklisted").unwrap_or(false),
             "whitelisted": row.try_get::<bool, _>("whitelisted").unwrap_or(false),
+            "fee_tier": row.try_get::<Option<String>, _>("fee_tier").ok().flatten(),
             "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
             "updated_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("updated_at").ok(),
         });
@@ -135,17 +121,21 @@ pub async fn update_user_status(req: HttpRequest, state: web::Data<AppState>, pa
     let status = payload.status.trim();
     let allowed = ["active", "disabled", "suspended"];
     if !allowed.contains(&status) { return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_status", "status invalid"))); }
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
     let rec = sqlx::query("UPDATE users SET status = $1 WHERE id = $2 RETURNING id")
         .bind(status)
         .bind(id)
-        .fetch_one(&state.db_pool)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     let rid: i64 = rec.try_get("id").unwrap_or(id);
-    let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id, payload_json) VALUES ($1, $2, $3, $4, $5)")
-        .bind(actor_id).bind("admin.user_status").bind("users").bind(rid)
-        .bind(serde_json::json!({"status": status}))
-        .execute(&state.db_pool).await;
+    audit::record(&mut *tx, actor_id, "admin.user_status", "users", Some(rid), serde_json::json!({"status": status}))
+        .await
+        .map_err(AppError::from)?;
+    outbox::enqueue(&mut *tx, &DomainEvent::UserStatusChanged { id: rid, status: status.to_string() })
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": rid, "status": status}))))
 }
 
@@ -156,17 +146,21 @@ pub async fn set_blacklist(req: HttpRequest, state: web::Data<AppState>, path: w
     let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
     let id = path.into_inner();
     let val = payload.value.unwrap_or(true);
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
     let rec = sqlx::query("UPDATE users SET blacklisted = $1 WHERE id = $2 RETURNING id")
         .bind(val)
         .bind(id)
-        .fetch_one(&state.db_pool)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     let rid: i64 = rec.try_get("id").unwrap_or(id);
-    let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id, payload_json) VALUES ($1, $2, $3, $4, $5)")
-        .bind(actor_id).bind("admin.user_blacklist").bind("users").bind(rid)
-        .bind(serde_json::json!({"blacklisted": val}))
-        .execute(&state.db_pool).await;
+    audit::record(&mut *tx, actor_id, "admin.user_blacklist", "users", Some(rid), serde_json::json!({"blacklisted": val}))
+        .await
+        .map_err(AppError::from)?;
+    outbox::enqueue(&mut *tx, &DomainEvent::UserBlacklisted { id: rid, blacklisted: val })
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": rid, "blacklisted": val}))))
 }
 
@@ -174,20 +168,52 @@ pub async fn set_whitelist(req: HttpRequest, state: web::Data<AppState>, path: w
     let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
     let id = path.into_inner();
     let val = payload.value.unwrap_or(true);
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
     let rec = sqlx::query("UPDATE users SET whitelisted = $1 WHERE id = $2 RETURNING id")
         .bind(val)
         .bind(id)
-        .fetch_one(&state.db_pool)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     let rid: i64 = rec.try_get("id").unwrap_or(id);
-    let _ = sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id, payload_json) VALUES ($1, $2, $3, $4, $5)")
-        .bind(actor_id).bind("admin.user_whitelist").bind("users").bind(rid)
-        .bind(serde_json::json!({"whitelisted": val}))
-        .execute(&state.db_pool).await;
+    audit::record(&mut *tx, actor_id, "admin.user_whitelist", "users", Some(rid), serde_json::json!({"whitelisted": val}))
+        .await
+        .map_err(AppError::from)?;
+    outbox::enqueue(&mut *tx, &DomainEvent::UserWhitelisted { id: rid, whitelisted: val })
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": rid, "whitelisted": val}))))
 }
 
+#[derive(Deserialize)]
+pub struct FeeTierRequest {
+    /// Tier whose fee schedules apply to the user's new orders; null for none
+    pub fee_tier: Option<String>,
+}
+
+pub async fn set_fee_tier(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<FeeTierRequest>) -> Result<HttpResponse> {
+    let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
+    let id = path.into_inner();
+    let tier = payload.fee_tier.as_deref().map(str::trim);
+    if tier.is_some_and(|t| t.is_empty() || t.len() > 32) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_argument", "fee_tier must be 1-32 characters")));
+    }
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let rec = sqlx::query("UPDATE users SET fee_tier = $1 WHERE id = $2 RETURNING id")
+        .bind(tier)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    let rid: i64 = rec.try_get("id").unwrap_or(id);
+    audit::record(&mut *tx, actor_id, "admin.user_fee_tier", "users", Some(rid), serde_json::json!({"fee_tier": tier}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": rid, "fee_tier": tier}))))
+}
+
 pub async fn get_user_stats(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
     let _actor = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
     let id = path.into_inner();
@@ -197,7 +223,7 @@ pub async fn get_user_stats(req: HttpRequest, state: web::Data<AppState>, path:
     .bind(id)
     .fetch_optional(&state.db_pool)
     .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    .map_err(AppError::from)?;
     if let Some(row) = row {
         let body = serde_json::json!({
             "total_pnl": row.try_get::<Option<bigdecimal::BigDecimal>, _>("total_pnl").ok().flatten().map(|d| d.to_string()),
@@ -207,4 +233,83 @@ pub async fn get_user_stats(req: HttpRequest, state: web::Data<AppState>, path:
         return Ok(HttpResponse::Ok().json(ApiResponse::success(body)));
     }
     Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "user not found")))
-}
\ No newline at end of file
+}
+/// Per-currency balance, PnL, open stakes and exposure limit of a user
+pub async fn get_user_balances(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+    let _actor = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
+    let id = path.into_inner();
+    let exists: Option<i64> = sqlx::query_scalar("SELECT id FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    if exists.is_none() {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "user not found")));
+    }
+    let rows = sqlx::query(
+        "SELECT c.code, COALESCE(b.balance, 0) AS balance, COALESCE(b.total_pnl, 0) AS total_pnl, b.exposure_limit, \
+                COALESCE(b.exposure_limit, c.max_user_exposure) AS effective_limit, \
+                (SELECT COALESCE(SUM(o.amount), 0) FROM orders o WHERE o.user_id = $1 AND o.currency = c.code AND o.status = 'placed') AS open_exposure \
+         FROM currencies c LEFT JOIN user_balances b ON b.currency = c.code AND b.user_id = $1 \
+         WHERE b.user_id IS NOT NULL OR EXISTS (SELECT 1 FROM orders o WHERE o.user_id = $1 AND o.currency = c.code) \
+         ORDER BY c.code"
+    )
+    .bind(id)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(AppError::from)?;
+    let items: Vec<serde_json::Value> = rows.into_iter().map(|row| {
+        serde_json::json!({
+            "currency": row.try_get::<String, _>("code").unwrap_or_default(),
+            "balance": row.try_get::<BigDecimal, _>("balance").ok(),
+            "total_pnl": row.try_get::<BigDecimal, _>("total_pnl").ok(),
+            "open_exposure": row.try_get::<BigDecimal, _>("open_exposure").ok(),
+            "exposure_limit": row.try_get::<Option<BigDecimal>, _>("exposure_limit").ok().flatten(),
+            "effective_limit": row.try_get::<Option<BigDecimal>, _>("effective_limit").ok().flatten(),
+        })
+    }).collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items}))))
+}
+
+#[derive(Deserialize)]
+pub struct ExposureLimitRequest {
+    /// Cap on the user's open stakes in the currency; null falls back to the currency default
+    #[serde(default, deserialize_with = "money::lenient_opt")]
+    pub exposure_limit: Option<BigDecimal>,
+}
+
+pub async fn set_exposure_limit(req: HttpRequest, state: web::Data<AppState>, path: web::Path<(i64, String)>, payload: web::Json<ExposureLimitRequest>) -> Result<HttpResponse> {
+    let actor_id = crate::utils::auth::admin_actor_id(&req, &state.db_pool).await?;
+    let (id, code) = path.into_inner();
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let Some(found) = currency::find(&mut tx, &code.to_ascii_uppercase()).await.map_err(AppError::from)? else {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "currency not found")));
+    };
+    let limit = match payload.exposure_limit.as_ref().map(|v| found.rounding().amount(v)).transpose() {
+        Ok(limit) => limit,
+        Err(msg) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_argument", &msg))),
+    };
+    let exists: Option<i64> = sqlx::query_scalar("SELECT id FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    if exists.is_none() {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "user not found")));
+    }
+    sqlx::query(
+        "INSERT INTO user_balances (user_id, currency, exposure_limit) VALUES ($1, $2, $3) \
+         ON CONFLICT (user_id, currency) DO UPDATE SET exposure_limit = EXCLUDED.exposure_limit"
+    )
+    .bind(id)
+    .bind(&found.code)
+    .bind(&limit)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    audit::record(&mut *tx, actor_id, "admin.user_exposure_limit", "users", Some(id), serde_json::json!({"currency": found.code, "exposure_limit": limit}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id, "currency": found.code, "exposure_limit": limit}))))
+}
@@ -0,0 +1,261 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use serde::Deserialize;
+use sqlx::{Postgres, QueryBuilder, Row};
+
+use crate::state::AppState;
+use crate::utils::api_keys::generate_secret;
+use crate::utils::audit;
+use crate::utils::auth::{admin_actor_id, require_role};
+use crate::utils::response::ApiResponse;
+use crate::utils::webhooks::WEBHOOK_EVENT_TYPES;
+use crate::utils::errors::AppError;
+
+const SUBSCRIPTION_COLUMNS: &str = "id, url, event_types, api_key_id, description, active, created_by, created_at, updated_at";
+
+fn subscription_json(row: &sqlx::postgres::PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+        "url": row.try_get::<String, _>("url").unwrap_or_default(),
+        "event_types": row.try_get::<Vec<String>, _>("event_types").unwrap_or_default(),
+        "api_key_id": row.try_get::<Option<i64>, _>("api_key_id").ok().flatten(),
+        "description": row.try_get::<Option<String>, _>("description").ok().flatten(),
+        "active": row.try_get::<bool, _>("active").unwrap_or_default(),
+        "created_by": row.try_get::<Option<i64>, _>("created_by").ok().flatten(),
+        "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+        "updated_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("updated_at").ok(),
+    })
+}
+
+fn valid_url(url: &str) -> bool {
+    (url.starts_with("https://") || url.starts_with("http://")) && url.len() <= 2048 && !url.contains(char::is_whitespace)
+}
+
+/// Trimmed, sorted, deduplicated event types; None if empty or unknown
+fn normalize_event_types(types: &[String]) -> Option<Vec<String>> {
+    let mut types: Vec<String> = types.iter().map(|s| s.trim().to_string()).collect();
+    types.sort();
+    types.dedup();
+    (!types.is_empty() && types.iter().all(|t| WEBHOOK_EVENT_TYPES.contains(&t.as_str()))).then_some(types)
+}
+
+fn invalid_event_types() -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_event_types", &format!("event_types must be a non-empty subset of {:?}", WEBHOOK_EVENT_TYPES)))
+}
+
+pub async fn list_subscriptions(req: HttpRequest, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin", "analyst"]).await?;
+    let rows = sqlx::query(&format!(
+        "SELECT {}, \
+                (SELECT COUNT(*) FROM webhook_deliveries d WHERE d.subscription_id = s.id AND d.status = 'pending') AS pending, \
+                (SELECT COUNT(*) FROM webhook_deliveries d WHERE d.subscription_id = s.id AND d.status = 'dead') AS dead \
+         FROM webhook_subscriptions s ORDER BY id DESC",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(AppError::from)?;
+    let items: Vec<serde_json::Value> = rows.iter().map(|r| {
+        let mut item = subscription_json(r);
+        item["pending_deliveries"] = serde_json::json!(r.try_get::<i64, _>("pending").unwrap_or(0));
+        item["dead_deliveries"] = serde_json::json!(r.try_get::<i64, _>("dead").unwrap_or(0));
+        item
+    }).collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items}))))
+}
+
+#[derive(Deserialize)]
+pub struct CreateSubscriptionRequest {
+    pub url: String,
+    pub event_types: Vec<String>,
+    /// Partner key whose referred users' orders this subscription receives
+    pub api_key_id: Option<i64>,
+    pub description: Option<String>,
+}
+
+/// Create a subscription; the signing secret is returned only in this response
+pub async fn create_subscription(req: HttpRequest, state: web::Data<AppState>, payload: web::Json<CreateSubscriptionRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let p = payload.into_inner();
+    let url = p.url.trim();
+    if !valid_url(url) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_url", "url must be an http(s) URL")));
+    }
+    let Some(event_types) = normalize_event_types(&p.event_types) else { return Ok(invalid_event_types()); };
+    if let Some(key_id) = p.api_key_id {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM api_keys WHERE id = $1)")
+            .bind(key_id)
+            .fetch_one(&state.db_pool)
+            .await
+            .map_err(AppError::from)?;
+        if !exists {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_api_key", "api key not found")));
+        }
+    }
+
+    let secret = generate_secret();
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = sqlx::query(&format!(
+        "INSERT INTO webhook_subscriptions (url, event_types, secret, api_key_id, description, created_by) VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .bind(url).bind(&event_types).bind(&secret).bind(p.api_key_id).bind(p.description.as_deref().map(str::trim)).bind(actor_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    let id: i64 = row.try_get("id").unwrap_or_default();
+    audit::record(&mut *tx, actor_id, "admin.webhook_create", "webhook_subscriptions", Some(id), serde_json::json!({"url": url, "event_types": event_types, "api_key_id": p.api_key_id}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+
+    let mut body = subscription_json(&row);
+    body["secret"] = serde_json::json!(secret);
+    Ok(HttpResponse::Created().json(ApiResponse::success(body)))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateSubscriptionRequest {
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub description: Option<String>,
+    pub active: Option<bool>,
+}
+
+pub async fn update_subscription(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, payload: web::Json<UpdateSubscriptionRequest>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let id = path.into_inner();
+    let p = payload.into_inner();
+    let url = p.url.as_deref().map(str::trim);
+    if url.is_some_and(|u| !valid_url(u)) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_url", "url must be an http(s) URL")));
+    }
+    let event_types = match &p.event_types {
+        Some(types) => match normalize_event_types(types) {
+            Some(t) => Some(t),
+            None => return Ok(invalid_event_types()),
+        },
+        None => None,
+    };
+
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let row = sqlx::query(&format!(
+        "UPDATE webhook_subscriptions SET url = COALESCE($2, url), event_types = COALESCE($3, event_types), \
+         description = COALESCE($4, description), active = COALESCE($5, active) WHERE id = $1 RETURNING {}",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .bind(id).bind(url).bind(&event_types).bind(p.description.as_deref().map(str::trim)).bind(p.active)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    let Some(row) = row else {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "webhook subscription not found")));
+    };
+    audit::record(&mut *tx, actor_id, "admin.webhook_update", "webhook_subscriptions", Some(id), serde_json::json!({"url": url, "event_types": event_types, "active": p.active}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(subscription_json(&row))))
+}
+
+pub async fn delete_subscription(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let id = path.into_inner();
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let res = sqlx::query("DELETE FROM webhook_subscriptions WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    if res.rows_affected() == 0 {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "webhook subscription not found")));
+    }
+    audit::record(&mut *tx, actor_id, "admin.webhook_delete", "webhook_subscriptions", Some(id), serde_json::json!({}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": id, "deleted": true}))))
+}
+
+#[derive(Deserialize)]
+pub struct DeliveriesQuery {
+    pub status: Option<String>,
+    pub event_type: Option<String>,
+    pub limit: Option<i64>,
+    /// id of the last delivery on the previous page
+    pub cursor: Option<String>,
+}
+
+/// Delivery log of one subscription, newest first
+pub async fn list_deliveries(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>, query: web::Query<DeliveriesQuery>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin", "analyst"]).await?;
+    let subscription_id = path.into_inner();
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let cursor = match query.cursor.as_deref().map(str::parse::<i64>) {
+        None => None,
+        Some(Ok(id)) => Some(id),
+        Some(Err(_)) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_cursor", "cursor invalid"))),
+    };
+    if query.status.as_deref().is_some_and(|s| !["pending", "delivered", "dead"].contains(&s)) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_status", "status must be pending, delivered or dead")));
+    }
+
+    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+        "SELECT id, outbox_event_id, event_type, payload, status, attempts, next_attempt_at, last_status_code, last_error, last_attempt_at, delivered_at, created_at \
+         FROM webhook_deliveries WHERE subscription_id = "
+    );
+    qb.push_bind(subscription_id);
+    if let Some(v) = &query.status { qb.push(" AND status = ").push_bind(v.clone()); }
+    if let Some(v) = &query.event_type { qb.push(" AND event_type = ").push_bind(v.clone()); }
+    if let Some(v) = cursor { qb.push(" AND id < ").push_bind(v); }
+    qb.push(" ORDER BY id DESC LIMIT ").push_bind(limit + 1);
+
+    let mut rows = qb.build().fetch_all(&state.db_pool).await.map_err(AppError::from)?;
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    let items: Vec<serde_json::Value> = rows.iter().map(|row| serde_json::json!({
+        "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+        "outbox_event_id": row.try_get::<i64, _>("outbox_event_id").unwrap_or_default(),
+        "event_type": row.try_get::<String, _>("event_type").unwrap_or_default(),
+        "payload": row.try_get::<serde_json::Value, _>("payload").unwrap_or_default(),
+        "status": row.try_get::<String, _>("status").unwrap_or_default(),
+        "attempts": row.try_get::<i32, _>("attempts").unwrap_or_default(),
+        "next_attempt_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("next_attempt_at").ok(),
+        "last_status_code": row.try_get::<Option<i32>, _>("last_status_code").ok().flatten(),
+        "last_error": row.try_get::<Option<String>, _>("last_error").ok().flatten(),
+        "last_attempt_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("last_attempt_at").ok().flatten(),
+        "delivered_at": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("delivered_at").ok().flatten(),
+        "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+    })).collect();
+    let next_cursor = if has_more { items.last().and_then(|i| i["id"].as_i64()).map(|id| id.to_string()) } else { None };
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"items": items, "next_cursor": next_cursor}))))
+}
+
+/// Requeue a dead-lettered (or still pending) delivery for an immediate attempt with a fresh retry budget
+pub async fn retry_delivery(req: HttpRequest, state: web::Data<AppState>, path: web::Path<(i64, i64)>) -> Result<HttpResponse> {
+    let actor_id = admin_actor_id(&req, &state.db_pool).await?;
+    require_role(&state.db_pool, actor_id, &["admin"]).await?;
+    let (subscription_id, delivery_id) = path.into_inner();
+    let mut tx = state.db_pool.begin().await.map_err(AppError::from)?;
+    let res = sqlx::query(
+        "UPDATE webhook_deliveries SET status = 'pending', attempts = 0, next_attempt_at = NOW() \
+         WHERE id = $1 AND subscription_id = $2 AND status IN ('pending', 'dead')"
+    )
+    .bind(delivery_id)
+    .bind(subscription_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+    if res.rows_affected() == 0 {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("not_found", "no pending or dead delivery with that id")));
+    }
+    audit::record(&mut *tx, actor_id, "admin.webhook_retry", "webhook_deliveries", Some(delivery_id), serde_json::json!({"subscription_id": subscription_id}))
+        .await
+        .map_err(AppError::from)?;
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"id": delivery_id, "status": "pending"}))))
+}
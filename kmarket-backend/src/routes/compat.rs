@@ -1,20 +1,31 @@
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use bigdecimal::{BigDecimal, Zero};
 use serde::Deserialize;
 use sqlx::Row;
 
 use crate::state::AppState;
-use crate::utils::{response::ApiResponse};
-use crate::repository::{order_repo::OrderRepository, user_repo::UserRepository};
-use crate::models::order::OrderStatus;
+use crate::utils::list_query::{ListQuery, Sort, SortField};
+use crate::utils::pagination::{self, PageMode};
+use crate::utils::{cache::{self, NS_MARKETS}, conditional::Representation, rate_limit::{too_many_requests, Decision}, response::ApiResponse};
+use crate::repository::{order_repo::{OrderReference, OrderRepository}, user_repo::UserRepository};
 use crate::models::dto::{FrontendMarket, FrontendPosition};
+use crate::utils::errors::{AppError, DataAccessError};
+use crate::utils::money::{self, Rounding};
 
 #[derive(Deserialize)]
 pub struct GetMarketsQuery { pub page: Option<i64>, pub page_size: Option<i64> }
 
-pub async fn get_frontend_markets(state: web::Data<AppState>, query: web::Query<GetMarketsQuery>) -> Result<HttpResponse> {
+/// Market lists change with odds and exposure; short reuse, then revalidate with the ETag
+const MARKETS_CACHE_CONTROL: &str = "public, max-age=10";
+
+pub async fn get_frontend_markets(req: HttpRequest, state: web::Data<AppState>, query: web::Query<GetMarketsQuery>) -> Result<HttpResponse> {
     let page = query.page.unwrap_or(1);
     let page_size = query.page_size.unwrap_or(20).min(100);
     let offset = (page - 1) * page_size;
+    let cache_key = cache::key(NS_MARKETS, &serde_json::json!({"route": "compat", "page": page, "page_size": page_size}));
+    if let Some(rep) = state.cache.get(&cache_key).await {
+        return Ok(state.cache.respond(&req, rep, true, MARKETS_CACHE_CONTROL));
+    }
     // Query markets with frontend-aligned columns
     let rows = sqlx::query(
         r#"
@@ -25,14 +36,15 @@ pub async fn get_frontend_markets(state: web::Data<AppState>, query: web::Query<
             home_code, away_code, home_name, away_name,
             start_time, close_time, state, result,
             odds_home_bps, odds_away_bps,
-            COALESCE(max_exposure, 0)::DOUBLE PRECISION AS max_exposure,
-            COALESCE(current_exposure, 0)::DOUBLE PRECISION AS current_exposure,
-            COALESCE(total_volume, 0)::DOUBLE PRECISION AS total_volume,
+            COALESCE(max_exposure, 0) AS max_exposure,
+            COALESCE(current_exposure, 0) AS current_exposure,
+            COALESCE(total_volume, 0) AS total_volume,
             COALESCE(total_bets, 0) AS total_bets,
+            currency,
             created_at, updated_at, resolved_at
         FROM markets
         WHERE (state = 1 OR state IS NULL) AND (close_time IS NULL OR close_time > NOW())
-        ORDER BY created_at DESC
+        ORDER BY created_at DESC, id DESC
         LIMIT $1 OFFSET $2
         "#
     )
@@ -40,7 +52,7 @@ pub async fn get_frontend_markets(state: web::Data<AppState>, query: web::Query<
     .bind(offset)
     .fetch_all(&state.db_pool)
     .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    .map_err(AppError::from)?;
 
     let markets: Vec<FrontendMarket> = rows.into_iter().map(|row| FrontendMarket {
         id: row.try_get("id").unwrap_or(0),
@@ -56,16 +68,19 @@ pub async fn get_frontend_markets(state: web::Data<AppState>, query: web::Query<
         result: row.try_get("result").unwrap_or(0),
         odds_home_bps: row.try_get::<Option<i32>, _>("odds_home_bps").unwrap_or(None),
         odds_away_bps: row.try_get::<Option<i32>, _>("odds_away_bps").unwrap_or(None),
-        max_exposure: row.try_get("max_exposure").unwrap_or(0.0),
-        current_exposure: row.try_get("current_exposure").unwrap_or(0.0),
-        total_volume: row.try_get("total_volume").unwrap_or(0.0),
+        max_exposure: row.try_get("max_exposure").unwrap_or_else(|_| BigDecimal::zero()),
+        current_exposure: row.try_get("current_exposure").unwrap_or_else(|_| BigDecimal::zero()),
+        total_volume: row.try_get("total_volume").unwrap_or_else(|_| BigDecimal::zero()),
         total_bets: row.try_get("total_bets").unwrap_or(0),
+        currency: row.try_get("currency").unwrap_or_default(),
         created_at: row.try_get("created_at").unwrap(),
         updated_at: row.try_get("updated_at").unwrap(),
         resolved_at: row.try_get::<Option<_>, _>("resolved_at").unwrap_or(None),
     }).collect();
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success(markets)))
+    let rep = Representation::json(&ApiResponse::success(&markets), markets.iter().map(|m| m.updated_at))?;
+    state.cache.set(&cache_key, rep.clone()).await;
+    Ok(state.cache.respond(&req, rep, false, MARKETS_CACHE_CONTROL))
 }
 
 #[derive(Deserialize)]
@@ -77,77 +92,62 @@ pub struct PositionsQuery {
     pub fixture_id: Option<String>,  // market_id (numeric) 或 market_address
     pub page: Option<i64>,
     pub limit: Option<i64>,
+    /// Keyset paging on the sort field and id; empty for the first page, then the previous next_cursor
+    pub cursor: Option<String>,
+    /// Count matching rows (default: true with page, false with cursor)
+    pub with_total: Option<bool>,
+    /// One of POSITION_SORTS (default created_at)
+    pub sort: Option<String>,
+    /// asc | desc (default desc)
+    pub order: Option<String>,
 }
 
+/// positions_v.status of an open position (placed order); settled and cancelled are history
+const POSITION_OPEN: i32 = 1;
+
+/// Sortable fields of positions (first = default)
+const POSITION_SORTS: &[SortField] = &[
+    SortField { name: "created_at", column: "created_at", keyset: true },
+    SortField { name: "updated_at", column: "updated_at", keyset: true },
+    SortField { name: "amount", column: "amount", keyset: false },
+];
+
 pub async fn get_frontend_positions(
     state: web::Data<AppState>,
     path: web::Path<AddressPath>,
     query: web::Query<PositionsQuery>,
 ) -> Result<HttpResponse> {
-    let page = query.page.unwrap_or(1).max(1);
     let limit = query.limit.unwrap_or(50).clamp(1, 100);
-    let offset = (page - 1) * limit;
-
-    // COUNT SQL
-    let mut count_sql = String::from("SELECT COUNT(*) AS total FROM positions_v WHERE wallet_address = $1");
-    let mut idx = 2;
-    if let Some(status) = &query.status {
-        match status.as_str() {
-            "current" | "open" => { count_sql.push_str(&format!(" AND status = ${}", idx)); idx += 1; }
-            "history" | "closed" => { count_sql.push_str(&format!(" AND status <> ${}", idx)); idx += 1; }
-            _ => {}
-        }
-    }
-    if let Some(fid) = &query.fixture_id {
-        // 数字 -> 过滤 market_id；否则过滤 market_address
-        if fid.chars().all(|c| c.is_ascii_digit()) {
-            count_sql.push_str(&format!(" AND market_id = ${}", idx)); idx += 1;
-        } else {
-            count_sql.push_str(&format!(" AND market_address = ${}", idx)); idx += 1;
-        }
-    }
-    tracing::info!(target: "kmarket_backend", "SQL COUNT (positions): {}", count_sql);
-    let mut qc = sqlx::query(&count_sql).bind(&path.address);
-    if let Some(status) = &query.status {
-        match status.as_str() { "current" | "open" => { qc = qc.bind(1i32); }, "history" | "closed" => { qc = qc.bind(1i32); }, _ => {} }
-    }
-    if let Some(fid) = &query.fixture_id {
-        if fid.chars().all(|c| c.is_ascii_digit()) { qc = qc.bind(fid.parse::<i64>().unwrap_or(0)); } else { qc = qc.bind(fid.to_string()); }
-    }
-    let row = qc.fetch_one(&state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
-    let total: i64 = row.try_get("total").unwrap_or(0);
+    let mode = match PageMode::from_query(query.page, limit, query.cursor.as_deref()) {
+        Ok(mode) => mode,
+        Err(resp) => return Ok(resp),
+    };
+    let sort = match Sort::parse(POSITION_SORTS, query.sort.as_deref(), query.order.as_deref(), &mode) {
+        Ok(sort) => sort,
+        Err(resp) => return Ok(resp),
+    };
 
-    // DATA SQL
-    let mut data_sql = String::from(
-        "SELECT id, user_id, market_id, wallet_address, market_address, nonce, selected_team, amount::DOUBLE PRECISION as amount, multiplier_bps, status, timestamp, created_at, updated_at FROM positions_v WHERE wallet_address = $1"
+    let mut list = ListQuery::new(
+        "id, user_id, market_id, wallet_address, market_address, nonce, selected_team, amount, currency, multiplier_bps, status, is_claimed, fee_paid, timestamp, created_at, updated_at",
+        "positions_v",
+        "id",
     );
-    let mut idx2 = 2;
-    if let Some(status) = &query.status {
-        match status.as_str() {
-            "current" | "open" => { data_sql.push_str(&format!(" AND status = ${}", idx2)); idx2 += 1; }
-            "history" | "closed" => { data_sql.push_str(&format!(" AND status <> ${}", idx2)); idx2 += 1; }
-            _ => {}
-        }
-    }
-    if let Some(fid) = &query.fixture_id {
-        if fid.chars().all(|c| c.is_ascii_digit()) {
-            data_sql.push_str(&format!(" AND market_id = ${}", idx2)); idx2 += 1;
-        } else {
-            data_sql.push_str(&format!(" AND market_address = ${}", idx2)); idx2 += 1;
-        }
-    }
-    data_sql.push_str(&format!(" ORDER BY created_at DESC LIMIT ${} OFFSET ${}", idx2, idx2 + 1));
-    tracing::info!(target: "kmarket_backend", "SQL DATA (positions): {}", data_sql);
-
-    let mut qd = sqlx::query(&data_sql).bind(&path.address);
-    if let Some(status) = &query.status {
-        match status.as_str() { "current" | "open" => { qd = qd.bind(1i32); }, "history" | "closed" => { qd = qd.bind(1i32); }, _ => {} }
+    list.filter("wallet_address = {}", &path.address);
+    match query.status.as_deref() {
+        Some("current" | "open") => { list.filter("status = {}", POSITION_OPEN); }
+        Some("history" | "closed") => { list.filter("status <> {}", POSITION_OPEN); }
+        _ => {}
     }
     if let Some(fid) = &query.fixture_id {
-        if fid.chars().all(|c| c.is_ascii_digit()) { qd = qd.bind(fid.parse::<i64>().unwrap_or(0)); } else { qd = qd.bind(fid.to_string()); }
+        // 数字 -> 过滤 market_id；否则过滤 market_address
+        match fid.parse::<i64>() {
+            Ok(market_id) if fid.chars().all(|c| c.is_ascii_digit()) => list.filter("market_id = {}", market_id),
+            _ => list.filter("market_address = {}", fid),
+        };
     }
-    qd = qd.bind(limit).bind(offset);
-    let rows = qd.fetch_all(&state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let page = list.fetch(&state.db_pool, sort, limit, &mode, mode.wants_total(query.with_total)).await
+        .map_err(AppError::from)?;
+    let (rows, total, next_cursor) = (page.rows, page.total, page.next_cursor);
 
     let positions: Vec<FrontendPosition> = rows.iter().map(|row| {
         crate::utils::mappers::map_position_row_to_frontend(
@@ -158,19 +158,23 @@ pub async fn get_frontend_positions(
             row.try_get::<Option<String>, _>("market_address").unwrap_or(None),
             row.try_get("nonce").unwrap_or(0),
             row.try_get("selected_team").unwrap_or(1),
-            row.try_get("amount").unwrap_or(0.0),
+            row.try_get("amount").unwrap_or_else(|_| BigDecimal::zero()),
+            row.try_get::<String, _>("currency").unwrap_or_default(),
             row.try_get("multiplier_bps").unwrap_or(0),
             row.try_get("status").unwrap_or(1),
+            row.try_get("is_claimed").unwrap_or(false),
+            row.try_get("fee_paid").unwrap_or_else(|_| BigDecimal::zero()),
             row.try_get("timestamp").unwrap(),
             row.try_get("created_at").unwrap(),
             row.try_get("updated_at").unwrap(),
         )
     }).collect();
 
-    let body = serde_json::json!({
-        "positions": positions,
-        "pagination": { "page": page, "limit": limit, "total": total, "total_pages": if limit > 0 { (total + limit - 1) / limit } else { 0 } }
-    });
+    let pagination = match mode {
+        PageMode::Offset { page, .. } => serde_json::json!({ "page": page, "limit": limit, "total": total, "total_pages": total.map(|t| (t + limit - 1) / limit) }),
+        PageMode::After(_) => pagination::pagination_json(&mode, limit, total, next_cursor),
+    };
+    let body = serde_json::json!({ "positions": positions, "pagination": pagination });
     Ok(HttpResponse::Ok().json(ApiResponse::success(body)))
 }
 
@@ -180,7 +184,9 @@ pub struct CreateFrontendPositionRequest {
     pub fixture_id: Option<i64>,
     pub market_address: Option<String>,
     pub selected_team: i32,
-    pub amount: f64,
+    /// Decimal string (or number); exact
+    #[serde(deserialize_with = "money::lenient")]
+    pub amount: BigDecimal,
     pub multiplier_bps: i32,
     pub odds_home_bps: Option<i32>,
     pub odds_away_bps: Option<i32>,
@@ -190,15 +196,23 @@ pub struct CreateFrontendPositionRequest {
 pub async fn create_frontend_position(state: web::Data<AppState>, body: web::Json<CreateFrontendPositionRequest>) -> Result<HttpResponse> {
     let req = body.into_inner();
     tracing::info!(target: "kmarket_backend", "create_frontend_position: wallet={}, market_addr={:?}, team={}, amount={}, multiplier_bps={}, odds_h_bps={:?}, odds_a_bps={:?}", req.wallet_address, req.market_address, req.selected_team, req.amount, req.multiplier_bps, req.odds_home_bps, req.odds_away_bps);
-    if req.wallet_address.trim().is_empty() || req.amount <= 0.0 || (req.selected_team != 1 && req.selected_team != 2) {
+    if req.wallet_address.trim().is_empty() || req.amount <= BigDecimal::zero() || (req.selected_team != 1 && req.selected_team != 2) {
         return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_ARGS", "Missing or invalid fields")));
     }
+    let transaction_signature = req.transaction_signature.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+    if transaction_signature.as_ref().is_some_and(|s| s.chars().count() > 128) {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_ARGS", "transaction_signature must be at most 128 characters")));
+    }
+    let limiter = &state.rate_limiter;
+    if let Decision::Limited { retry_after_secs } = limiter.check(&limiter.policies.order_wallet, req.wallet_address.trim()).await {
+        return Ok(too_many_requests(retry_after_secs));
+    }
     let user_repo = UserRepository::new(state.db_pool.clone());
     let user = match user_repo.find_by_address(&req.wallet_address).await {
         Ok(Some(u)) => u,
         _ => match user_repo.create(crate::repository::user_repo::CreateUserRequest { address: req.wallet_address.clone(), username: None, email: None, password_hash: None, salt: None, status: None }).await {
             Ok(u) => u,
-            Err(e) => return Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("USER_CREATE_FAILED", &format!("{}", e))))
+            Err(e) => return Err(AppError::from(e).into())
         }
     };
     // 统一：优先使用业务ID fixture_id (market_id)，其次使用 market_address，再次解析 'market_<id>'
@@ -207,7 +221,7 @@ pub async fn create_frontend_position(state: web::Data<AppState>, body: web::Jso
             .bind(fid)
             .fetch_optional(&state.db_pool)
             .await
-            .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+            .map_err(AppError::from)?;
         match row { Some(r) => r.try_get("id").unwrap_or(0), None => 0 }
     } else if let Some(addr) = req.market_address.as_ref() {
         // 1) 地址查找
@@ -215,7 +229,7 @@ pub async fn create_frontend_position(state: web::Data<AppState>, body: web::Jso
             .bind(addr)
             .fetch_optional(&state.db_pool)
             .await
-            .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+            .map_err(AppError::from)?;
         let mut mid = match row { Some(r) => r.try_get("id").unwrap_or(0), None => 0 };
         // 2) 兼容 'market_<id>'
         if mid == 0 {
@@ -225,7 +239,7 @@ pub async fn create_frontend_position(state: web::Data<AppState>, body: web::Jso
                         .bind(n)
                         .fetch_optional(&state.db_pool)
                         .await
-                        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+                        .map_err(AppError::from)?;
                     mid = match row2 { Some(r) => r.try_get("id").unwrap_or(0), None => 0 };
                 }
             }
@@ -234,39 +248,77 @@ pub async fn create_frontend_position(state: web::Data<AppState>, body: web::Jso
     } else { 0 };
     tracing::info!(target: "kmarket_backend", "create_frontend_position: resolved market_id={}", market_id);
     if market_id == 0 { return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("MARKET_NOT_FOUND", "market_address not found"))); }
-    let mut odds = (req.multiplier_bps as f64) / 10000.0;
-    if req.selected_team == 1 { if let Some(bps) = req.odds_home_bps { odds = (bps as f64) / 10000.0; }}
-    if req.selected_team == 2 { if let Some(bps) = req.odds_away_bps { odds = (bps as f64) / 10000.0; }}
+    let side_bps = if req.selected_team == 1 { req.odds_home_bps } else { req.odds_away_bps };
+    let odds = money::odds_from_bps(side_bps.unwrap_or(req.multiplier_bps));
     let option: i16 = if req.selected_team == 1 { 0 } else { 1 };
-    let order_id: i64 = if let Some(sig) = req.transaction_signature { (xxhash_rust::xxh3::xxh3_64(sig.as_bytes()) as i64).abs() } else { chrono::Utc::now().timestamp_millis() };
     let repo = OrderRepository::new(state.db_pool.clone());
-    match repo.create_with_audit(crate::repository::order_repo::CreateOrderRequest { order_id, user_id: user.id, market_id, amount: req.amount, odds, option }).await {
+    // the id is allocated by the server; a signature is stored on the order, so a replayed transaction is a duplicate
+    let order_id = repo.next_order_id().await.map_err(AppError::from)?;
+    let reference = OrderReference { transaction_signature, ..OrderReference::default() };
+    match repo.create_with_reference(crate::repository::order_repo::CreateOrderRequest { order_id, user_id: user.id, market_id, amount: req.amount, odds, option }, &reference).await {
         Ok(order) => Ok(HttpResponse::Ok().json(ApiResponse::success(order))),
-        Err(e) => Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("ORDER_CREATE_FAILED", &format!("{}", e))))
+        Err(crate::utils::errors::DataAccessError::MarketClosed(_)) => Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("MARKET_CLOSED", "market is closed for betting"))),
+        Err(crate::utils::errors::DataAccessError::DuplicateKey(_)) => Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("DUPLICATE_ORDER", "transaction_signature already placed an order"))),
+        Err(e) => Err(AppError::from(e).into())
     }
 }
 
 #[derive(Deserialize)]
-pub struct CloseFrontendPositionRequest { pub position_id: i64, pub wallet_address: Option<String>, pub close_price: Option<f64> }
+pub struct CloseFrontendPositionRequest {
+    pub position_id: i64,
+    pub wallet_address: Option<String>,
+    #[serde(default, deserialize_with = "money::lenient_opt")]
+    pub close_price: Option<BigDecimal>,
+}
 
 pub async fn close_frontend_position(state: web::Data<AppState>, body: web::Json<CloseFrontendPositionRequest>) -> Result<HttpResponse> {
     let req = body.into_inner();
     if req.position_id <= 0 { return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_ARGS", "position_id required"))); }
-    let order = sqlx::query("SELECT id, version, amount::DOUBLE PRECISION as amount FROM orders WHERE id = $1")
+    let order = sqlx::query("SELECT o.id, o.version, o.amount, c.decimals FROM orders o JOIN currencies c ON c.code = o.currency WHERE o.id = $1")
         .bind(req.position_id)
         .fetch_optional(&state.db_pool)
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     if order.is_none() { return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("POSITION_NOT_FOUND", "position not found"))); }
     let row = order.unwrap();
     let version: i32 = row.try_get("version").unwrap_or(0);
-    let amount: f64 = row.try_get("amount").unwrap_or(0.0);
+    let amount: BigDecimal = row.try_get("amount").unwrap_or_else(|_| BigDecimal::zero());
+    let rounding = Rounding::new(row.try_get("decimals").map_err(AppError::from)?);
     // 简化 PnL：若提供 close_price 则 pnl = close_price - amount；否则 pnl = 0（可扩展为市场价）
-    let close_price = req.close_price.unwrap_or(amount);
-    let close_pnl = close_price - amount;
+    let close_price = match req.close_price.as_ref().map(|p| rounding.amount(p)).transpose() {
+        Ok(p) => p.unwrap_or_else(|| amount.clone()),
+        Err(msg) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_ARGS", &msg))),
+    };
+    let close_pnl = &close_price - &amount;
     let repo = OrderRepository::new(state.db_pool.clone());
     match repo.cancel_with_close_fields(req.position_id, version, Some(close_price), Some(close_pnl)).await {
         Ok(updated) => Ok(HttpResponse::Ok().json(ApiResponse::success(updated))),
-        Err(e) => Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("POSITION_CLOSE_FAILED", &format!("{}", e))))
+        Err(e) => Err(AppError::from(e).into())
+    }
+}
+#[derive(Deserialize)]
+pub struct ClaimFrontendPositionRequest {
+    pub wallet_address: String,
+    /// Not accepted here: on-chain payouts are recorded by an admin (POST /admin/orders/{id}/claim)
+    pub transaction_signature: Option<String>,
+}
+
+pub async fn claim_frontend_position(state: web::Data<AppState>, path: web::Path<i64>, body: web::Json<ClaimFrontendPositionRequest>) -> Result<HttpResponse> {
+    let id = path.into_inner();
+    let req = body.into_inner();
+    if req.wallet_address.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_ARGS", "wallet_address required")));
+    }
+    // anyone can call this, so it only credits the owner's balance; an on-chain payout is reported by an admin
+    if req.transaction_signature.is_some() {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_ARGS", "transaction_signature is not accepted; on-chain payouts are recorded by an admin")));
     }
-}
\ No newline at end of file
+    let repo = OrderRepository::new(state.db_pool.clone());
+    match repo.claim(id, req.wallet_address.trim()).await {
+        Ok(claim) => Ok(HttpResponse::Ok().json(ApiResponse::success(claim))),
+        Err(DataAccessError::NotFound(_)) => Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("POSITION_NOT_FOUND", "position not found"))),
+        Err(DataAccessError::AlreadyClaimed(_)) => Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("ALREADY_CLAIMED", "position was already claimed"))),
+        Err(DataAccessError::NotClaimable(_)) => Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("NOT_CLAIMABLE", "only settled winning positions can be claimed"))),
+        Err(e) => Err(AppError::from(e).into())
+    }
+}
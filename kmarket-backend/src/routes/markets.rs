@@ -1,37 +1,50 @@
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpRequest, HttpResponse, Result};
 use serde::Deserialize;
 use crate::state::AppState;
 use crate::repository::market_repo::{MarketRepository, CreateMarketRequest};
+use crate::utils::cache::{self, NS_MARKETS};
+use crate::utils::conditional::Representation;
+use crate::utils::errors::AppError;
 use crate::utils::response::ApiResponse;
 
 #[derive(Deserialize)]
 pub struct GetMarketsQuery { pub page: Option<i64>, pub page_size: Option<i64> }
 
-pub async fn get_markets(state: web::Data<AppState>, query: web::Query<GetMarketsQuery>) -> Result<HttpResponse> {
+/// Public market reads: short reuse, then revalidate with the ETag
+const MARKETS_CACHE_CONTROL: &str = "public, max-age=10";
+
+pub async fn get_markets(req: HttpRequest, state: web::Data<AppState>, query: web::Query<GetMarketsQuery>) -> Result<HttpResponse> {
     let page = query.page.unwrap_or(1);
     let page_size = query.page_size.unwrap_or(20).min(100);
     let offset = (page - 1) * page_size;
+    let cache_key = cache::key(NS_MARKETS, &serde_json::json!({"route": "markets", "page": page, "page_size": page_size}));
+    if let Some(rep) = state.cache.get(&cache_key).await {
+        return Ok(state.cache.respond(&req, rep, true, MARKETS_CACHE_CONTROL));
+    }
 
     let repo = MarketRepository::new(state.db_pool.clone());
     let markets = repo.get_active_markets(page_size, offset).await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
-    Ok(HttpResponse::Ok().json(ApiResponse::success(markets)))
+        .map_err(AppError::from)?;
+    let rep = Representation::json(&ApiResponse::success(&markets), markets.iter().map(|m| m.updated_at))?;
+    state.cache.set(&cache_key, rep.clone()).await;
+    Ok(state.cache.respond(&req, rep, false, MARKETS_CACHE_CONTROL))
 }
 
-pub async fn get_market_detail(state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
+pub async fn get_market_detail(req: HttpRequest, state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
     let market_id = path.into_inner();
     let repo = MarketRepository::new(state.db_pool.clone());
     let market = repo.find_by_market_id(market_id).await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?
-        .ok_or_else(|| actix_web::error::ErrorNotFound("Market not found"))?;
-    Ok(HttpResponse::Ok().json(ApiResponse::success(market)))
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::NotFound("market".into()))?;
+    let modified = market.updated_at;
+    Ok(Representation::json(&ApiResponse::success(market), [modified])?.respond(&req, MARKETS_CACHE_CONTROL))
 }
 
 pub async fn get_market_stats(state: web::Data<AppState>, path: web::Path<i64>) -> Result<HttpResponse> {
     let market_id = path.into_inner();
     let repo = MarketRepository::new(state.db_pool.clone());
     let stats = repo.get_market_stats(market_id).await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(stats)))
 }
 
@@ -56,7 +69,7 @@ pub async fn create_market(state: web::Data<AppState>, body: web::Json<CreateMar
         option_b: body.option_b.clone(),
         start_time: body.start_time,
         end_time: body.end_time,
-    }).await.map_err(|e| actix_web::error::ErrorBadRequest(e))?;
+    }).await.map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(created)))
 }
 
@@ -75,7 +88,7 @@ pub async fn update_market_status(state: web::Data<AppState>, path: web::Path<i6
     let repo = MarketRepository::new(state.db_pool.clone());
     let updated = repo.update_status_with_version(id, body.expected_version, new_status)
         .await
-        .map_err(|e| actix_web::error::ErrorConflict(e))?;
+        .map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(updated)))
 }
 
@@ -83,6 +96,6 @@ pub async fn delete_market(state: web::Data<AppState>, path: web::Path<i64>) ->
     let id = path.into_inner();
     let repo = MarketRepository::new(state.db_pool.clone());
     repo.delete_by_id(id).await
-        .map_err(|e| actix_web::error::ErrorNotFound(e))?;
+        .map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"deleted": true, "id": id}))))
 }
\ No newline at end of file
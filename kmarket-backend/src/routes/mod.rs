@@ -5,7 +5,18 @@ pub mod compat;
 pub mod users;
 pub mod sports;
 pub mod admin_auth;
+pub mod admin_admins;
 pub mod admin_markets;
 pub mod admin_orders;
 pub mod admin_users;
-pub mod admin_carousel;
\ No newline at end of file
+pub mod admin_carousel;
+pub mod admin_api_keys;
+pub mod admin_audit;
+pub mod admin_webhooks;
+pub mod admin_simulations;
+pub mod admin_feeds;
+pub mod admin_catalog;
+pub mod admin_currencies;
+pub mod admin_fees;
+pub mod partner;
+pub mod ws;
\ No newline at end of file
@@ -1,7 +1,11 @@
 use actix_web::{web, HttpResponse, Result};
+use bigdecimal::BigDecimal;
 use serde::Deserialize;
 use crate::state::AppState;
 use crate::repository::order_repo::{OrderRepository, CreateOrderRequest};
+use crate::utils::rate_limit::{too_many_requests, Decision};
+use crate::utils::errors::AppError;
+use crate::utils::money;
 use crate::utils::response::ApiResponse;
 
 #[derive(Deserialize)]
@@ -9,21 +13,28 @@ pub struct CreateOrderBody {
     pub order_id: i64,
     pub user_id: i64,
     pub market_id: i64,
-    pub amount: f64,
-    pub odds: f64,
+    /// Decimal string (or number); exact
+    #[serde(deserialize_with = "money::lenient")]
+    pub amount: BigDecimal,
+    #[serde(deserialize_with = "money::lenient")]
+    pub odds: BigDecimal,
     pub option: i16,
 }
 
 pub async fn create_order(state: web::Data<AppState>, body: web::Json<CreateOrderBody>) -> Result<HttpResponse> {
+    let limiter = &state.rate_limiter;
+    if let Decision::Limited { retry_after_secs } = limiter.check(&limiter.policies.order_wallet, &format!("user:{}", body.user_id)).await {
+        return Ok(too_many_requests(retry_after_secs));
+    }
     let repo = OrderRepository::new(state.db_pool.clone());
     let order = repo.create_with_audit(CreateOrderRequest {
         order_id: body.order_id,
         user_id: body.user_id,
         market_id: body.market_id,
-        amount: body.amount,
-        odds: body.odds,
+        amount: body.amount.clone(),
+        odds: body.odds.clone(),
         option: body.option,
-    }).await.map_err(|e| actix_web::error::ErrorBadRequest(e))?;
+    }).await.map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(order)))
 }
 
@@ -33,14 +44,14 @@ pub struct AddressPath { pub address: String }
 pub async fn get_user_orders(state: web::Data<AppState>, path: web::Path<AddressPath>) -> Result<HttpResponse> {
     let repo = OrderRepository::new(state.db_pool.clone());
     let orders = repo.get_user_orders_by_address(&path.address).await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(orders)))
 }
 
 pub async fn get_user_stats(state: web::Data<AppState>, path: web::Path<AddressPath>) -> Result<HttpResponse> {
     let repo = OrderRepository::new(state.db_pool.clone());
     let stats = repo.get_user_stats(&path.address).await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(stats)))
 }
 
@@ -48,17 +59,16 @@ pub async fn get_user_stats(state: web::Data<AppState>, path: web::Path<AddressP
 pub struct OrderPath { pub id: i64 }
 
 pub async fn get_order(state: web::Data<AppState>, path: web::Path<OrderPath>) -> Result<HttpResponse> {
-    let repo = OrderRepository::new(state.db_pool.clone());
     let id = path.id;
     // translate id->order_id if necessary by lookup; here assume id as order_id for simplicity if not found by id
     let found = sqlx::query_as::<_, crate::models::order::Order>(
-        r#"SELECT id, order_id, user_id, market_id, amount::TEXT as amount, odds::TEXT as odds,
-            option, status, version, created_at, updated_at FROM orders WHERE id = $1"#
+        r#"SELECT id, order_id, user_id, market_id, amount, odds,
+            option, status, currency, fee_paid, version, created_at, updated_at FROM orders WHERE id = $1"#
     )
     .bind(id)
     .fetch_optional(&state.db_pool)
     .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    .map_err(AppError::from)?;
     match found {
         Some(order) => Ok(HttpResponse::Ok().json(ApiResponse::success(order))),
         None => Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("NOT_FOUND", "order not found"))),
@@ -79,7 +89,7 @@ pub async fn update_order_status(state: web::Data<AppState>, path: web::Path<Ord
     let repo = OrderRepository::new(state.db_pool.clone());
     let updated = repo.update_status_with_version(id, body.expected_version, new_status)
         .await
-        .map_err(|e| actix_web::error::ErrorConflict(e))?;
+        .map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(updated)))
 }
 
@@ -87,6 +97,6 @@ pub async fn delete_order(state: web::Data<AppState>, path: web::Path<OrderPath>
     let id = path.id;
     let repo = OrderRepository::new(state.db_pool.clone());
     repo.delete_by_id(id).await
-        .map_err(|e| actix_web::error::ErrorNotFound(e))?;
+        .map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"deleted": true, "id": id}))))
 }
\ No newline at end of file
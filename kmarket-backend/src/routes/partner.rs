@@ -0,0 +1,153 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use bigdecimal::BigDecimal;
+use serde::Deserialize;
+use sqlx::Row;
+
+use crate::repository::market_repo::MarketRepository;
+use crate::models::order::Order;
+use crate::repository::order_repo::{CreateOrderRequest, OrderReference, OrderRepository};
+use crate::repository::user_repo::{CreateUserRequest, UserRepository};
+use crate::state::AppState;
+use crate::utils::api_keys::{authenticate_api_key, SCOPE_MARKETS_READ, SCOPE_ORDERS_WRITE, SCOPE_REPORTS_READ};
+use crate::utils::errors::{AppError, DataAccessError};
+use crate::utils::money;
+use crate::utils::response::ApiResponse;
+
+#[derive(Deserialize)]
+pub struct PartnerMarketsQuery { pub page: Option<i64>, pub page_size: Option<i64> }
+
+pub async fn list_markets(req: HttpRequest, state: web::Data<AppState>, query: web::Query<PartnerMarketsQuery>) -> Result<HttpResponse> {
+    authenticate_api_key(&req, &[], &state, SCOPE_MARKETS_READ).await?;
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query.page_size.unwrap_or(20).clamp(1, 100);
+    let markets = MarketRepository::new(state.db_pool.clone())
+        .get_active_markets(page_size, (page - 1) * page_size)
+        .await
+        .map_err(AppError::from)?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(markets)))
+}
+
+#[derive(Deserialize)]
+pub struct PartnerOrderRequest {
+    /// Wallet of the user the order is placed for; created on first use
+    pub wallet_address: String,
+    /// Business market id (markets.market_id)
+    pub market_id: i64,
+    /// Decimal string (or number); exact
+    #[serde(deserialize_with = "money::lenient")]
+    pub amount: BigDecimal,
+    #[serde(deserialize_with = "money::lenient")]
+    pub odds: BigDecimal,
+    pub option: i16,
+    /// Partner-side reference; the same reference from the same key maps to the same order
+    pub client_order_id: String,
+}
+
+/// Place an order on behalf of a user. The body is signed, so it is read raw and parsed here.
+pub async fn place_order(req: HttpRequest, state: web::Data<AppState>, body: web::Bytes) -> Result<HttpResponse> {
+    let key = authenticate_api_key(&req, &body, &state, SCOPE_ORDERS_WRITE).await?;
+    let Ok(p) = serde_json::from_slice::<PartnerOrderRequest>(&body) else {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_ARGS", "invalid order body")));
+    };
+    let client_order_id = p.client_order_id.trim().to_string();
+    if p.wallet_address.trim().is_empty() || client_order_id.is_empty() || client_order_id.len() > 64 {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_ARGS", "wallet_address and client_order_id (at most 64 characters) required")));
+    }
+
+    let market_row = sqlx::query("SELECT id FROM markets WHERE market_id = $1")
+        .bind(p.market_id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let Some(market_row) = market_row else {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("MARKET_NOT_FOUND", "market not found")));
+    };
+    let market_id: i64 = market_row.try_get("id").unwrap_or(0);
+
+    let user_repo = UserRepository::new(state.db_pool.clone());
+    let wallet = p.wallet_address.trim().to_string();
+    let user = match user_repo.find_by_address(&wallet).await.map_err(AppError::from)? {
+        Some(u) => u,
+        None => {
+            let user = user_repo.create(CreateUserRequest { address: wallet, username: None, email: None, password_hash: None, salt: None, status: None })
+                .await
+                .map_err(AppError::from)?;
+            // The partner that brings a user in is its referrer (scopes order webhooks)
+            sqlx::query("UPDATE users SET referrer_api_key_id = $1 WHERE id = $2 AND referrer_api_key_id IS NULL")
+                .bind(key.id)
+                .bind(user.id)
+                .execute(&state.db_pool)
+                .await
+                .map_err(AppError::from)?;
+            user
+        }
+    };
+
+    // a retried reference gets its order back
+    let repo = OrderRepository::new(state.db_pool.clone());
+    let req = CreateOrderRequest { order_id: 0, user_id: user.id, market_id, amount: p.amount, odds: p.odds, option: p.option };
+    if let Some(order) = repo.find_by_client_order_id(key.id, &client_order_id).await.map_err(AppError::from)? {
+        return existing_order(order, &req);
+    }
+    let order_id = repo.next_order_id().await.map_err(AppError::from)?;
+    let reference = OrderReference { api_key_id: Some(key.id), client_order_id: Some(client_order_id.clone()), ..OrderReference::default() };
+    match repo.create_with_reference(CreateOrderRequest { order_id, ..req.clone() }, &reference).await {
+        Ok(order) => {
+            tracing::info!(target: "kmarket_backend", "partner order: key={} order_id={} user={}", key.key_id, order.order_id, user.id);
+            Ok(HttpResponse::Ok().json(ApiResponse::success(order)))
+        }
+        // a concurrent request with the same reference got there first
+        Err(DataAccessError::DuplicateKey(_)) => match repo.find_by_client_order_id(key.id, &client_order_id).await.map_err(AppError::from)? {
+            Some(order) => existing_order(order, &req),
+            None => Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("DUPLICATE_ORDER", "order could not be placed; retry"))),
+        },
+        Err(DataAccessError::InvalidArgument(m)) => Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_ARGS", &m))),
+        Err(DataAccessError::MarketClosed(_)) => Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("MARKET_CLOSED", "market is closed for betting"))),
+        Err(e) => Err(AppError::from(e).into()),
+    }
+}
+
+/// Answer a request whose client_order_id already names an order: the order itself when the
+/// request is a retry of it, else a conflict
+fn existing_order(order: Order, req: &CreateOrderRequest) -> Result<HttpResponse> {
+    let same = order.user_id == req.user_id && order.market_id == req.market_id
+        && order.amount == req.amount && order.odds == req.odds && order.option == req.option;
+    if !same {
+        return Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("DUPLICATE_ORDER", "client_order_id already used for a different order")));
+    }
+    Ok(HttpResponse::Ok().json(ApiResponse::success(order)))
+}
+
+/// Aggregate market and order figures for reporting partners; volume per currency
+pub async fn report_summary(req: HttpRequest, state: web::Data<AppState>) -> Result<HttpResponse> {
+    authenticate_api_key(&req, &[], &state, SCOPE_REPORTS_READ).await?;
+    let markets = sqlx::query("SELECT status::TEXT AS status, COUNT(*) AS n FROM markets GROUP BY status")
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let orders = sqlx::query("SELECT COUNT(*) AS n, COUNT(DISTINCT user_id) AS users FROM orders")
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let by_currency = sqlx::query("SELECT currency, COUNT(*) AS n, COALESCE(SUM(amount), 0)::TEXT AS volume, COUNT(DISTINCT user_id) AS users FROM orders GROUP BY currency ORDER BY currency")
+        .fetch_all(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let by_status: serde_json::Map<String, serde_json::Value> = markets.into_iter()
+        .map(|r| (r.try_get::<String, _>("status").unwrap_or_default(), serde_json::json!(r.try_get::<i64, _>("n").unwrap_or(0))))
+        .collect();
+    let by_currency: Vec<serde_json::Value> = by_currency.into_iter().map(|r| serde_json::json!({
+        "currency": r.try_get::<String, _>("currency").unwrap_or_default(),
+        "count": r.try_get::<i64, _>("n").unwrap_or(0),
+        "volume": r.try_get::<String, _>("volume").unwrap_or_default(),
+        "users": r.try_get::<i64, _>("users").unwrap_or(0),
+    })).collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+        "markets_by_status": by_status,
+        "orders": {
+            "count": orders.try_get::<i64, _>("n").unwrap_or(0),
+            "users": orders.try_get::<i64, _>("users").unwrap_or(0),
+            "by_currency": by_currency,
+        },
+    }))))
+}
@@ -1,141 +1,333 @@
-use actix_web::{web, HttpResponse, Result};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix_web::{http::header, web, web::Bytes, HttpRequest, HttpResponse, Result};
 use serde::Deserialize;
-use sqlx::Row;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
+use tokio::sync::broadcast;
 
 use crate::state::AppState;
+use crate::utils::cache::{self, NS_FIXTURES};
+use crate::utils::conditional::Representation;
+use crate::utils::list_query::{push_template, ListQuery, Sort, SortField};
+use crate::utils::pagination::{self, PageMode};
+use crate::utils::realtime::{Push, TOPIC_FIXTURES_LIVE};
+use crate::utils::errors::AppError;
 use crate::utils::response::ApiResponse;
 
 #[derive(Deserialize)]
 pub struct FixturesQuery {
     pub status: Option<String>,
+    /// Sport code or name, or (as before the catalog) a league code or name such as NBA
     pub sport: Option<String>,
+    /// League code or name
     pub league: Option<String>,
+    pub sport_id: Option<i64>,
+    pub league_id: Option<i64>,
+    /// Fixtures where this team plays home or away
+    pub team_id: Option<i64>,
     pub q: Option<String>,
     pub page: Option<i64>,
     pub limit: Option<i64>,
+    /// Keyset paging on the sort field and id; empty for the first page, then the previous next_cursor
+    pub cursor: Option<String>,
+    /// Count matching fixtures (default: true with page, false with cursor)
+    pub with_total: Option<bool>,
+    /// One of FIXTURE_SORTS (default kickoff_time)
+    pub sort: Option<String>,
+    /// asc | desc (default desc)
+    pub order: Option<String>,
+}
+
+/// Map a sports_fixtures_v row to the MockFixture-compatible shape the frontend expects
+pub fn fixture_json(row: &PgRow) -> serde_json::Value {
+    let id: String = row.try_get("id").unwrap_or_default();
+    let title: String = row.try_get("title").unwrap_or_default();
+    let sport: String = row.try_get("sport").unwrap_or_else(|_| "Sports".into());
+    let league: Option<String> = row.try_get("league").ok();
+    let home_team: String = row.try_get("home_team").unwrap_or_default();
+    let away_team: String = row.try_get("away_team").unwrap_or_default();
+    let kickoff_time: chrono::DateTime<chrono::Utc> = row.try_get("kickoff_time").unwrap_or_else(|_| chrono::Utc::now());
+    let status: String = row.try_get("status").unwrap_or_else(|_| "pre".into());
+    let pre_odds: Option<serde_json::Value> = row.try_get("pre_odds").ok();
+    let live_odds: Option<serde_json::Value> = row.try_get("live_odds").ok();
+    let live_stats: Option<serde_json::Value> = row.try_get("live_stats").ok().flatten();
+    let id_of = |col: &str| row.try_get::<Option<i64>, _>(col).ok().flatten();
+    let logo_of = |col: &str| row.try_get::<Option<String>, _>(col).ok().flatten();
+
+    serde_json::json!({
+        "id": id,
+        "title": title,
+        "sport": sport,
+        "league": league,
+        "sportId": id_of("sport_id"),
+        "leagueId": id_of("league_id"),
+        "homeTeam": home_team,
+        "awayTeam": away_team,
+        "homeTeamId": id_of("home_team_id"),
+        "awayTeamId": id_of("away_team_id"),
+        "homeTeamLogo": logo_of("home_team_logo"),
+        "awayTeamLogo": logo_of("away_team_logo"),
+        "kickoffTime": kickoff_time,
+        "status": status,
+        "preOdds": pre_odds,
+        "liveOdds": live_odds,
+        "liveStats": live_stats,
+    })
 }
 
-pub async fn get_fixtures(state: web::Data<AppState>, query: web::Query<FixturesQuery>) -> Result<HttpResponse> {
-    let page = query.page.unwrap_or(1).max(1);
+/// `sport` matches the sport's code or name, and also a league code or name: before the catalog
+/// the view reported leagues such as NBA or Premier League as the sport, and clients still send those
+const SPORT_FILTER: &str = "(LOWER(sport_code) = LOWER({}) OR LOWER(sport) = LOWER({}) OR LOWER(league_code) = LOWER({}) OR LOWER(league) = LOWER({}))";
+const LEAGUE_FILTER: &str = "(LOWER(league_code) = LOWER({}) OR LOWER(league) = LOWER({}))";
+
+/// Live odds and scores: clients may reuse a response briefly, then revalidate with the ETag
+const FIXTURES_CACHE_CONTROL: &str = "public, max-age=5";
+
+/// Sortable fields of fixtures (first = default)
+const FIXTURE_SORTS: &[SortField] = &[
+    SortField { name: "kickoff_time", column: "kickoff_time", keyset: true },
+    SortField { name: "updated_at", column: "updated_at", keyset: true },
+    SortField { name: "title", column: "title", keyset: false },
+];
+
+pub async fn get_fixtures(req: HttpRequest, state: web::Data<AppState>, query: web::Query<FixturesQuery>) -> Result<HttpResponse> {
     let limit = query.limit.unwrap_or(20).clamp(1, 100);
-    let offset = (page - 1) * limit;
-
-    // Build COUNT SQL with positional binds ($1, $2, ...)
-    let mut count_sql = String::from("SELECT COUNT(*) as total FROM sports_fixtures_v");
-    let mut idx = 1;
-    let mut has_where = false;
-    if let Some(status) = &query.status {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("status = ${}", idx));
-        idx += 1;
-    }
-    if let Some(sport) = &query.sport {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("sport = ${}", idx));
-        idx += 1;
-    }
-    if let Some(league) = &query.league {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        count_sql.push_str(&format!("league = ${}", idx));
-        idx += 1;
-    }
-    if let Some(_q) = &query.q {
-        if !has_where { count_sql.push_str(" WHERE "); has_where = true; } else { count_sql.push_str(" AND "); }
-        // three binds: title/home_team/away_team
-        count_sql.push_str(&format!("(title ILIKE ${} OR home_team ILIKE ${} OR away_team ILIKE ${})", idx, idx + 1, idx + 2));
-        idx += 3;
-    }
-    // Debug log
-    tracing::info!(target: "kmarket_backend", "SQL COUNT: {}", count_sql);
-
-    // Prepare COUNT query and bind values in same order
-    let mut qc = sqlx::query(&count_sql);
-    if let Some(status) = &query.status { qc = qc.bind(status); }
-    if let Some(sport) = &query.sport { qc = qc.bind(sport); }
-    if let Some(league) = &query.league { qc = qc.bind(league); }
-    if let Some(q) = &query.q {
-        let pat = format!("%{}%", q);
-        qc = qc.bind(pat.clone()).bind(pat.clone()).bind(pat);
+    let mode = match PageMode::from_query(query.page, limit, query.cursor.as_deref()) {
+        Ok(mode) => mode,
+        Err(resp) => return Ok(resp),
+    };
+    let sort = match Sort::parse(FIXTURE_SORTS, query.sort.as_deref(), query.order.as_deref(), &mode) {
+        Ok(sort) => sort,
+        Err(resp) => return Ok(resp),
+    };
+    let with_total = mode.wants_total(query.with_total);
+
+    // sport / league match case-insensitively, so they share an entry across casings
+    let page_key = match mode {
+        PageMode::Offset { page, .. } => serde_json::json!(page),
+        PageMode::After(cursor) => serde_json::json!(cursor.map(|c| c.encode()).unwrap_or_default()),
+    };
+    let cache_key = cache::key(NS_FIXTURES, &serde_json::json!({
+        "page": page_key, "limit": limit, "with_total": with_total, "sort": [sort.column, sort.desc], "status": query.status,
+        "sport": query.sport.as_deref().map(str::to_lowercase), "league": query.league.as_deref().map(str::to_lowercase),
+        "sport_id": query.sport_id, "league_id": query.league_id, "team_id": query.team_id, "q": query.q,
+    }));
+    if let Some(rep) = state.cache.get(&cache_key).await {
+        return Ok(state.cache.respond(&req, rep, true, FIXTURES_CACHE_CONTROL));
     }
-    let row = qc.fetch_one(&state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
-    let total: i64 = row.try_get("total").unwrap_or(0);
-
-    // Build DATA SQL similarly
-    // Return business id (market_id) as id to the frontend for consistency
-    // Cast to TEXT to avoid type mismatch when mapping to String in JSON response
-    let mut data_sql = String::from("SELECT market_id::TEXT AS id, title, sport, league, home_team, away_team, kickoff_time, status, pre_odds, live_odds FROM sports_fixtures_v");
-    let mut idx2 = 1;
-    let mut has_where2 = false;
-    if let Some(status) = &query.status {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("status = ${}", idx2));
-        idx2 += 1;
+
+    let mut list = ListQuery::new(
+        "market_id::TEXT AS id, title, sport, league, sport_id, league_id, home_team, away_team, home_team_id, away_team_id, home_team_logo, away_team_logo, kickoff_time, status, pre_odds, live_odds, live_stats, updated_at",
+        "sports_fixtures_v",
+        "market_id",
+    );
+    list.filter_opt("status = {}", query.status.as_ref())
+        .filter_opt(SPORT_FILTER, query.sport.as_ref())
+        .filter_opt(LEAGUE_FILTER, query.league.as_ref())
+        .filter_opt("sport_id = {}", query.sport_id)
+        .filter_opt("league_id = {}", query.league_id)
+        .filter_opt("home_team_id = {} OR away_team_id = {}", query.team_id)
+        .filter_opt("title ILIKE {} OR home_team ILIKE {} OR away_team ILIKE {}", query.q.as_ref().map(|q| format!("%{}%", q)));
+    let page = list.fetch(&state.db_pool, sort, limit, &mode, with_total).await
+        .map_err(AppError::from)?;
+    let (rows, total, next_cursor) = (page.rows, page.total, page.next_cursor);
+
+    let fixtures: Vec<serde_json::Value> = rows.iter().map(fixture_json).collect();
+    let modified = rows.iter().filter_map(|r| r.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("updated_at").ok().flatten());
+
+    let body = serde_json::json!({
+        "fixtures": fixtures,
+        "pagination": pagination::pagination_json(&mode, limit, total, next_cursor),
+    });
+    let rep = Representation::json(&ApiResponse::success(body), modified)?;
+    state.cache.set(&cache_key, rep.clone()).await;
+    Ok(state.cache.respond(&req, rep, false, FIXTURES_CACHE_CONTROL))
+}
+/// Fixture stream tuning: poll interval (other instances / direct DB edits), keep-alive comment
+/// interval, and how far behind the cursor each poll re-reads so rows whose updated_at
+/// (transaction start) precedes a later-committed row are not skipped.
+const STREAM_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const STREAM_KEEPALIVE: Duration = Duration::from_secs(15);
+const STREAM_LOOKBACK_MICROS: i64 = 5_000_000;
+const STREAM_BATCH: i64 = 500;
+const STREAM_SNAPSHOT_LIMIT: i64 = 200;
+
+const FIXTURE_COLUMNS: &str = "f.id, f.title, f.sport, f.league, f.sport_id, f.league_id, f.home_team, f.away_team, f.home_team_id, f.away_team_id, \
+    f.home_team_logo, f.away_team_logo, f.kickoff_time, f.status, f.pre_odds, f.live_odds, f.live_stats";
+
+#[derive(Deserialize, Clone)]
+pub struct FixtureStreamQuery {
+    pub status: Option<String>,
+    pub sport: Option<String>,
+    pub league: Option<String>,
+    pub sport_id: Option<i64>,
+    pub league_id: Option<i64>,
+    pub team_id: Option<i64>,
+    /// For clients that cannot set the Last-Event-ID header (EventSource polyfills)
+    #[serde(rename = "lastEventId")]
+    pub last_event_id: Option<String>,
+}
+
+impl FixtureStreamQuery {
+    fn push_filters(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        if let Some(v) = &self.status { qb.push(" AND f.status = ").push_bind(v.clone()); }
+        if let Some(v) = &self.sport { qb.push(" AND "); push_template(qb, SPORT_FILTER, &[v.into()]); }
+        if let Some(v) = &self.league { qb.push(" AND "); push_template(qb, LEAGUE_FILTER, &[v.into()]); }
+        if let Some(v) = self.sport_id { qb.push(" AND f.sport_id = ").push_bind(v); }
+        if let Some(v) = self.league_id { qb.push(" AND f.league_id = ").push_bind(v); }
+        if let Some(v) = self.team_id { qb.push(" AND (f.home_team_id = ").push_bind(v).push(" OR f.away_team_id = ").push_bind(v).push(")"); }
     }
-    if let Some(sport) = &query.sport {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("sport = ${}", idx2));
-        idx2 += 1;
+}
+
+fn sse_event(id: i64, event: &str, data: &serde_json::Value) -> String {
+    format!("id: {}\nevent: {}\ndata: {}\n\n", id, event, data)
+}
+
+struct FixtureStream {
+    pool: PgPool,
+    rx: broadcast::Receiver<Arc<Push>>,
+    filters: FixtureStreamQuery,
+    /// updated_at (epoch micros) of the newest change sent; doubles as the SSE event id
+    cursor: i64,
+    /// Last revision sent per market, so lookback re-reads are not sent twice
+    sent: HashMap<i64, i64>,
+    /// Markets the client currently holds; on resume this is unknown, so removals are always sent
+    visible: HashSet<i64>,
+    resumed: bool,
+    catching_up: bool,
+    pending: Option<Bytes>,
+    poll: tokio::time::Interval,
+    last_write: Instant,
+}
+
+impl FixtureStream {
+    async fn snapshot(&mut self) -> Result<String, sqlx::Error> {
+        let cursor: i64 = sqlx::query_scalar("SELECT COALESCE((EXTRACT(EPOCH FROM MAX(updated_at)) * 1000000)::BIGINT, 0) FROM markets")
+            .fetch_one(&self.pool)
+            .await?;
+        let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(format!(
+            "SELECT {}, m.market_id AS mid, (EXTRACT(EPOCH FROM m.updated_at) * 1000000)::BIGINT AS rev \
+             FROM sports_fixtures_v f JOIN markets m ON m.market_id = f.market_id WHERE TRUE",
+            FIXTURE_COLUMNS
+        ));
+        self.filters.push_filters(&mut qb);
+        qb.push(" ORDER BY f.kickoff_time DESC LIMIT ").push_bind(STREAM_SNAPSHOT_LIMIT);
+        let rows = qb.build().fetch_all(&self.pool).await?;
+        for row in &rows {
+            let mid: i64 = row.try_get("mid").unwrap_or_default();
+            self.sent.insert(mid, row.try_get("rev").unwrap_or_default());
+            self.visible.insert(mid);
+        }
+        let fixtures: Vec<serde_json::Value> = rows.iter().map(fixture_json).collect();
+        self.cursor = cursor;
+        Ok(sse_event(cursor, "snapshot", &serde_json::json!({"fixtures": fixtures})))
     }
-    if let Some(league) = &query.league {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("league = ${}", idx2));
-        idx2 += 1;
+
+    /// Markets changed since the cursor: `fixture` when it matches the filters, `fixture_removed` when it no longer does
+    async fn changes(&mut self) -> Result<String, sqlx::Error> {
+        let since = if self.catching_up { self.cursor } else { self.cursor - STREAM_LOOKBACK_MICROS };
+        let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(format!(
+            "SELECT {}, m.market_id AS mid, (EXTRACT(EPOCH FROM m.updated_at) * 1000000)::BIGINT AS rev \
+             FROM markets m LEFT JOIN sports_fixtures_v f ON f.market_id = m.market_id",
+            FIXTURE_COLUMNS
+        ));
+        self.filters.push_filters(&mut qb);
+        qb.push(" WHERE m.updated_at > TIMESTAMPTZ 'epoch' + ").push_bind(since).push(" * INTERVAL '1 microsecond'");
+        qb.push(" ORDER BY m.updated_at, m.id LIMIT ").push_bind(STREAM_BATCH);
+        let rows = qb.build().fetch_all(&self.pool).await?;
+        self.catching_up = rows.len() as i64 == STREAM_BATCH;
+
+        let mut out = String::new();
+        for row in &rows {
+            let mid: i64 = row.try_get("mid").unwrap_or_default();
+            let rev: i64 = row.try_get("rev").unwrap_or_default();
+            self.cursor = self.cursor.max(rev);
+            if self.sent.get(&mid).is_some_and(|&r| r >= rev) { continue; }
+            self.sent.insert(mid, rev);
+            let matched = row.try_get::<Option<String>, _>("id").ok().flatten().is_some();
+            if matched {
+                self.visible.insert(mid);
+                out.push_str(&sse_event(rev, "fixture", &fixture_json(row)));
+            } else if self.visible.remove(&mid) || self.resumed {
+                out.push_str(&sse_event(rev, "fixture_removed", &serde_json::json!({"id": mid.to_string()})));
+            }
+        }
+        Ok(out)
     }
-    if let Some(_q) = &query.q {
-        if !has_where2 { data_sql.push_str(" WHERE "); has_where2 = true; } else { data_sql.push_str(" AND "); }
-        data_sql.push_str(&format!("(title ILIKE ${} OR home_team ILIKE ${} OR away_team ILIKE ${})", idx2, idx2 + 1, idx2 + 2));
-        idx2 += 3;
+
+    async fn next_chunk(&mut self) -> Bytes {
+        if let Some(b) = self.pending.take() { return b; }
+        loop {
+            if !self.catching_up {
+                tokio::select! {
+                    push = self.rx.recv() => {
+                        // Lagged or closed: fall through to a poll, which catches up from the cursor anyway
+                        if matches!(&push, Ok(p) if p.topic != TOPIC_FIXTURES_LIVE) { continue; }
+                    }
+                    _ = self.poll.tick() => {}
+                }
+            }
+            match self.changes().await {
+                Ok(out) if !out.is_empty() => { self.last_write = Instant::now(); return Bytes::from(out); }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(target: "kmarket_backend", "fixture stream poll failed: {}", e);
+                    self.catching_up = false;
+                }
+            }
+            if self.last_write.elapsed() >= STREAM_KEEPALIVE {
+                self.last_write = Instant::now();
+                return Bytes::from_static(b": keepalive\n\n");
+            }
+        }
     }
-    data_sql.push_str(&format!(" ORDER BY kickoff_time DESC LIMIT ${} OFFSET ${}", idx2, idx2 + 1));
-    tracing::info!(target: "kmarket_backend", "SQL DATA: {}", data_sql);
-
-    // Prepare DATA query and bind values, then limit/offset
-    let mut qd = sqlx::query(&data_sql);
-    if let Some(status) = &query.status { qd = qd.bind(status); }
-    if let Some(sport) = &query.sport { qd = qd.bind(sport); }
-    if let Some(league) = &query.league { qd = qd.bind(league); }
-    if let Some(q) = &query.q {
-        let pat = format!("%{}%", q);
-        qd = qd.bind(pat.clone()).bind(pat.clone()).bind(pat);
+}
+
+/// SSE fallback for clients that cannot use /ws: GET /sports/fixtures/stream?status=&sport=&league=
+/// Emits `snapshot` (same fixture objects as get_fixtures) then `fixture` / `fixture_removed` updates.
+/// Event ids are market update times; reconnecting with Last-Event-ID replays changes since then.
+pub async fn stream_fixtures(req: HttpRequest, state: web::Data<AppState>, query: web::Query<FixtureStreamQuery>) -> Result<HttpResponse> {
+    let filters = query.into_inner();
+    let last_event_id = req.headers().get("Last-Event-ID").and_then(|v| v.to_str().ok()).map(str::to_string)
+        .or_else(|| filters.last_event_id.clone());
+    let resume_from = match last_event_id.as_deref().map(|v| v.trim().parse::<i64>()) {
+        None => None,
+        Some(Ok(v)) if v >= 0 => Some(v),
+        Some(_) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_last_event_id", "Last-Event-ID must be an event id from this stream"))),
+    };
+
+    let mut stream = FixtureStream {
+        pool: state.db_pool.clone(),
+        rx: state.realtime.subscribe(),
+        filters,
+        cursor: resume_from.unwrap_or(0),
+        sent: HashMap::new(),
+        visible: HashSet::new(),
+        resumed: resume_from.is_some(),
+        catching_up: resume_from.is_some(),
+        pending: None,
+        poll: tokio::time::interval(STREAM_POLL_INTERVAL),
+        last_write: Instant::now(),
+    };
+    let mut first = String::from("retry: 3000\n\n");
+    if resume_from.is_none() {
+        first.push_str(&stream.snapshot().await.map_err(AppError::from)?);
     }
-    qd = qd.bind(limit).bind(offset);
-    let rows = qd.fetch_all(&state.db_pool).await.map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
-
-    // Map to MockFixture-compatible shape
-    let fixtures: Vec<serde_json::Value> = rows.into_iter().map(|row| {
-        let id: String = row.try_get("id").unwrap_or_default();
-        let title: String = row.try_get("title").unwrap_or_default();
-        let sport: String = row.try_get("sport").unwrap_or_else(|_| "Sports".into());
-        let league: Option<String> = row.try_get("league").ok();
-        let home_team: String = row.try_get("home_team").unwrap_or_default();
-        let away_team: String = row.try_get("away_team").unwrap_or_default();
-        let kickoff_time: chrono::DateTime<chrono::Utc> = row.try_get("kickoff_time").unwrap_or_else(|_| chrono::Utc::now());
-        let status: String = row.try_get("status").unwrap_or_else(|_| "pre".into());
-        let pre_odds: Option<serde_json::Value> = row.try_get("pre_odds").ok();
-        let live_odds: Option<serde_json::Value> = row.try_get("live_odds").ok();
-
-        serde_json::json!({
-            "id": id,
-            "title": title,
-            "sport": sport,
-            "league": league,
-            "homeTeam": home_team,
-            "awayTeam": away_team,
-            "kickoffTime": kickoff_time,
-            "status": status,
-            "preOdds": pre_odds,
-            "liveOdds": live_odds,
-        })
-    }).collect();
+    stream.pending = Some(Bytes::from(first));
+    stream.poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
-    let body = serde_json::json!({
-        "fixtures": fixtures,
-        "pagination": {
-            "page": page,
-            "limit": limit,
-            "total": total,
-            "totalPages": ((total + limit - 1) / limit),
-        }
+    let body = futures_util::stream::unfold(stream, |mut s| async move {
+        let chunk = s.next_chunk().await;
+        Some((Ok::<_, actix_web::Error>(chunk), s))
     });
-    Ok(HttpResponse::Ok().json(ApiResponse::success(body)))
-}
\ No newline at end of file
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        // keep Compress from buffering events
+        .insert_header(header::ContentEncoding::Identity)
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(body))
+}
@@ -2,6 +2,7 @@ use actix_web::{web, HttpResponse, Result};
 use serde::Deserialize;
 use crate::state::AppState;
 use crate::repository::user_repo::{UserRepository, CreateUserRequest};
+use crate::utils::errors::AppError;
 use crate::utils::response::ApiResponse;
 
 #[derive(Deserialize)]
@@ -15,7 +16,7 @@ pub async fn create_user(state: web::Data<AppState>, body: web::Json<CreateUserB
     let repo = UserRepository::new(state.db_pool.clone());
     let user = repo.create(CreateUserRequest { address: body.address.clone(), username: body.username.clone(), email: body.email.clone(), password_hash: None, salt: None, status: Some("active".into()) })
         .await
-        .map_err(|e| actix_web::error::ErrorBadRequest(e))?;
+        .map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(user)))
 }
 
@@ -30,7 +31,7 @@ pub async fn get_user(state: web::Data<AppState>, path: web::Path<UserPath>) ->
     .bind(id)
     .fetch_optional(&state.db_pool)
     .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    .map_err(AppError::from)?;
     match rec { Some(u) => Ok(HttpResponse::Ok().json(ApiResponse::success(u))), None => Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("NOT_FOUND", "user not found"))) }
 }
 
@@ -41,13 +42,13 @@ pub async fn update_user_email(state: web::Data<AppState>, path: web::Path<UserP
     let repo = UserRepository::new(state.db_pool.clone());
     let updated = repo.update_email_with_version(path.id, body.expected_version, &body.email)
         .await
-        .map_err(|e| actix_web::error::ErrorConflict(e))?;
+        .map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(updated)))
 }
 
 pub async fn delete_user(state: web::Data<AppState>, path: web::Path<UserPath>) -> Result<HttpResponse> {
     let repo = UserRepository::new(state.db_pool.clone());
     repo.delete_by_id(path.id).await
-        .map_err(|e| actix_web::error::ErrorNotFound(e))?;
+        .map_err(AppError::from)?;
     Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"deleted": true, "id": path.id}))))
 }
\ No newline at end of file
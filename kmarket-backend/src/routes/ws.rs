@@ -0,0 +1,123 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use actix_ws::{Message, Session};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::state::AppState;
+use crate::utils::realtime::{is_valid_topic, TOPIC_RESYNC};
+
+/// Server ping interval; clients that send nothing (not even a pong) for CLIENT_TIMEOUT are dropped
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+const MAX_TOPICS: usize = 50;
+
+#[derive(Deserialize)]
+pub struct WsQuery {
+    /// Comma-separated topics to subscribe on connect, so a reconnecting client resumes in one step
+    pub topics: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum ClientMessage {
+    Subscribe { topics: Vec<String> },
+    Unsubscribe { topics: Vec<String> },
+    Ping,
+}
+
+async fn send(session: &mut Session, value: serde_json::Value) -> Result<(), actix_ws::Closed> {
+    session.text(value.to_string()).await
+}
+
+/// Adds valid topics up to MAX_TOPICS; returns the ones rejected
+fn subscribe_all(subscribed: &mut HashSet<String>, topics: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut rejected = Vec::new();
+    for t in topics {
+        let t = t.trim().to_string();
+        if t.is_empty() { continue; }
+        if !is_valid_topic(&t) || (subscribed.len() >= MAX_TOPICS && !subscribed.contains(&t)) {
+            rejected.push(t);
+        } else {
+            subscribed.insert(t);
+        }
+    }
+    rejected
+}
+
+fn subscribed_json(subscribed: &HashSet<String>, rejected: Vec<String>) -> serde_json::Value {
+    let mut topics: Vec<&String> = subscribed.iter().collect();
+    topics.sort();
+    serde_json::json!({"type": "subscribed", "topics": topics, "rejected": rejected})
+}
+
+/// WebSocket push channel.
+/// Client -> server: {"op":"subscribe","topics":[...]}, {"op":"unsubscribe","topics":[...]}, {"op":"ping"}
+/// Server -> client: {"type":"event","topic","event","data","ts"}, {"type":"subscribed",...},
+/// {"type":"pong"}, {"type":"lagged","missed":n|null} (refetch over REST), {"type":"error",...}
+pub async fn connect(req: HttpRequest, body: web::Payload, state: web::Data<AppState>, query: web::Query<WsQuery>) -> Result<HttpResponse> {
+    let (response, mut session, mut msg_stream) = actix_ws::handle(&req, body)?;
+    let mut rx = state.realtime.subscribe();
+    let mut subscribed = HashSet::new();
+    let initial = query.into_inner().topics.unwrap_or_default();
+    let rejected = subscribe_all(&mut subscribed, initial.split(',').map(str::to_string));
+
+    actix_web::rt::spawn(async move {
+        if send(&mut session, subscribed_json(&subscribed, rejected)).await.is_err() { return; }
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        heartbeat.tick().await;
+        let mut last_seen = Instant::now();
+
+        let close_reason = loop {
+            tokio::select! {
+                msg = msg_stream.recv() => {
+                    let sent = match msg {
+                        Some(Ok(Message::Text(text))) => {
+                            last_seen = Instant::now();
+                            let reply = match serde_json::from_str::<ClientMessage>(&text) {
+                                Ok(ClientMessage::Subscribe { topics }) => {
+                                    let rejected = subscribe_all(&mut subscribed, topics);
+                                    subscribed_json(&subscribed, rejected)
+                                }
+                                Ok(ClientMessage::Unsubscribe { topics }) => {
+                                    for t in topics { subscribed.remove(t.trim()); }
+                                    subscribed_json(&subscribed, Vec::new())
+                                }
+                                Ok(ClientMessage::Ping) => serde_json::json!({"type": "pong"}),
+                                Err(_) => serde_json::json!({"type": "error", "code": "invalid_message", "message": "expected {\"op\": \"subscribe\" | \"unsubscribe\" | \"ping\"}"}),
+                            };
+                            send(&mut session, reply).await
+                        }
+                        Some(Ok(Message::Ping(bytes))) => { last_seen = Instant::now(); session.pong(&bytes).await }
+                        Some(Ok(Message::Pong(_))) => { last_seen = Instant::now(); Ok(()) }
+                        Some(Ok(Message::Close(reason))) => break reason,
+                        Some(Ok(_)) => Ok(()),
+                        Some(Err(_)) | None => break None,
+                    };
+                    if sent.is_err() { return; }
+                }
+                push = rx.recv() => {
+                    let sent = match push {
+                        Ok(p) if subscribed.contains(&p.topic) => {
+                            send(&mut session, serde_json::json!({"type": "event", "topic": p.topic, "event": p.event, "data": p.data, "ts": p.ts})).await
+                        }
+                        Ok(p) if p.topic == TOPIC_RESYNC => send(&mut session, serde_json::json!({"type": "lagged", "missed": null})).await,
+                        Ok(_) => Ok(()),
+                        Err(RecvError::Lagged(missed)) => send(&mut session, serde_json::json!({"type": "lagged", "missed": missed})).await,
+                        Err(RecvError::Closed) => break None,
+                    };
+                    if sent.is_err() { return; }
+                }
+                _ = heartbeat.tick() => {
+                    if last_seen.elapsed() > CLIENT_TIMEOUT { break None; }
+                    if session.ping(b"").await.is_err() { return; }
+                }
+            }
+        };
+        let _ = session.close(close_reason).await;
+    });
+
+    Ok(response)
+}
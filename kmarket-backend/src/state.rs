@@ -1,13 +1,51 @@
+use std::borrow::Cow;
+use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use sqlx::migrate::{Migration, Migrator};
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 
+use crate::utils::cache::ResponseCache;
+use crate::utils::rate_limit::{RateLimiter, RatePolicies};
+use crate::utils::realtime::RealtimeHub;
+
+/// Seeded on first boot when admin_users is empty
+pub const DEFAULT_ADMIN_EMAIL: &str = "admin@kmarket.local";
+pub const DEFAULT_ADMIN_PASSWORD: &str = "admin123";
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Released migrations that PostgreSQL rejects as written (0002: a nested `$$` inside its DO
+/// block; 0008: positions_v changes column types without a DROP VIEW). Their files stay as
+/// released; these bodies from migrations/repairs run in their place.
+const REPAIRED_MIGRATIONS: &[(i64, &str)] = &[
+    (2, include_str!("../migrations/repairs/0002_init_schema.sql")),
+    (8, include_str!("../migrations/repairs/0008_close_fields.sql")),
+];
+
+/// The embedded migrations with REPAIRED_MIGRATIONS applied; used at startup and by tests
+pub fn migrator() -> Migrator {
+    let migrations: Vec<Migration> = MIGRATOR
+        .iter()
+        .map(|m| match REPAIRED_MIGRATIONS.iter().find(|(version, _)| *version == m.version) {
+            Some((_, sql)) => Migration::new(m.version, m.description.clone(), m.migration_type, Cow::Borrowed(*sql)),
+            None => m.clone(),
+        })
+        .collect();
+    Migrator { migrations: Cow::Owned(migrations), ignore_missing: MIGRATOR.ignore_missing, locking: MIGRATOR.locking }
+}
+
 /// Global application state with database connection
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: PgPool,
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Fan-out to WebSocket/SSE subscribers, across instances via LISTEN/NOTIFY (see REALTIME_FANOUT)
+    pub realtime: Arc<RealtimeHub>,
+    /// Cached public list responses (see CACHE_BACKEND), invalidated by market events
+    pub cache: Arc<ResponseCache>,
 }
 
 impl AppState {
@@ -20,8 +58,7 @@ impl AppState {
         let pool = connect_with_retry(&url, 5).await?;
 
         // Run migrations to ensure views/columns exist
-        static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
-        let _ = MIGRATOR.run(&pool).await;
+        run_migrations(&pool).await?;
 
     // Set per-session statement timeout for 60s according to spec
     sqlx::query("SET statement_timeout = 60000")
@@ -34,11 +71,25 @@ impl AppState {
     // Ensure positions view exists (defensive against migration cache)
     ensure_market_columns(&pool).await?;
     ensure_positions_view(&pool).await?;
-    // 按用户要求：视图交由数据库手动维护，启动不再自动重建
-    // ensure_sports_fixtures_view(&pool).await?;
+    // sports_fixtures_v is maintained by migrations (see 0024_sports_catalog.sql)
     ensure_default_admin(&pool).await?;
+    refuse_default_admin_password_in_production(&pool).await?;
 
-        Ok(Self { db_pool: pool })
+        let rate_limiter = RateLimiter::from_env().await;
+        let realtime = RealtimeHub::from_env(&pool);
+        let cache = ResponseCache::from_env().await;
+        Ok(Self { db_pool: pool, rate_limiter: Arc::new(rate_limiter), realtime: Arc::new(realtime), cache: Arc::new(cache) })
+    }
+
+    /// State around an already-migrated pool with default in-memory services (tests, tooling)
+    pub fn from_pool(db_pool: PgPool) -> Self {
+        Self {
+            db_pool,
+            rate_limiter: Arc::new(RateLimiter::memory(RatePolicies::default())),
+            realtime: Arc::new(RealtimeHub::new()),
+            // handlers read their own writes in tests; cache tests opt in
+            cache: Arc::new(ResponseCache::disabled()),
+        }
     }
 }
 
@@ -95,6 +146,11 @@ pub async fn heartbeat(pool: &PgPool) -> bool {
         .is_ok()
 }
 
+async fn run_migrations(pool: &PgPool) -> Result<()> {
+    migrator().run(pool).await.context("running database migrations")?;
+    Ok(())
+}
+
 async fn align_sequences(pool: &PgPool) -> Result<()> {
     sqlx::query("SELECT setval(pg_get_serial_sequence('users','id'), COALESCE((SELECT MAX(id) FROM users), 0) + 1, false)")
         .execute(pool).await?;
@@ -154,51 +210,6 @@ async fn ensure_positions_view(pool: &PgPool) -> Result<()> {
     Ok(())
 }
 
-async fn ensure_sports_fixtures_view(pool: &PgPool) -> Result<()> {
-    // Always recreate to ensure column alignment with frontend expectations
-    let _ = sqlx::query(
-        r#"
-        CREATE OR REPLACE VIEW sports_fixtures_v AS
-        SELECT
-            m.market_id,
-            COALESCE(m.title, 'Fixture') AS title,
-            -- Rough sport classification from title; fallback 'Sports'
-            CASE
-                WHEN m.title ILIKE '%NBA%' THEN 'NBA'
-                WHEN m.title ILIKE '%NFL%' THEN 'NFL'
-                WHEN m.title ILIKE '%Premier%' OR m.title ILIKE '%EPL%' THEN 'Premier League'
-                WHEN m.title ILIKE '%MLB%' THEN 'MLB'
-                WHEN m.title ILIKE '%UCL%' OR m.title ILIKE '%UEFA%' THEN 'UCL'
-                WHEN m.title ILIKE '%Tennis%' THEN 'Tennis'
-                ELSE 'Sports'
-            END AS sport,
-            -- Simple league inference from title keywords (optional)
-            CASE
-                WHEN m.title ILIKE '%EPL%' OR m.title ILIKE '%Premier%' THEN 'EPL'
-                WHEN m.title ILIKE '%UEFA%' OR m.title ILIKE '%UCL%' THEN 'UEFA Champions League'
-                ELSE NULL
-            END AS league,
-            COALESCE(m.home_name, 'Home') AS home_team,
-            COALESCE(m.away_name, 'Away') AS away_team,
-            COALESCE(m.start_time, NOW()) AS kickoff_time,
-            CASE
-                WHEN m.status = 'active' AND NOW() BETWEEN COALESCE(m.start_time, NOW() - INTERVAL '1 hour') AND COALESCE(m.end_time, NOW() + INTERVAL '1 hour') THEN 'live'
-                WHEN m.status = 'active' AND (m.start_time IS NULL OR NOW() < m.start_time) THEN 'pre'
-                WHEN m.status = 'pending' THEN 'pre'
-                WHEN m.status = 'closed' OR m.resolved_at IS NOT NULL THEN 'final'
-                ELSE 'pre'
-            END AS status,
-            jsonb_build_object('home', COALESCE(m.odds_home_bps, 0) / 10000.0, 'away', COALESCE(m.odds_away_bps, 0) / 10000.0) AS pre_odds,
-            jsonb_build_object('home', COALESCE(m.odds_home_bps, 0) / 10000.0, 'away', COALESCE(m.odds_away_bps, 0) / 10000.0) AS live_odds
-        FROM markets m
-        WHERE m.status IN ('active','pending','closed');
-        "#
-    )
-    .execute(pool)
-    .await;
-    Ok(())
-}
-
 async fn ensure_market_columns(pool: &PgPool) -> Result<()> {
     // Defensive: add missing columns required by compat outputs
     let stmts = [
@@ -237,7 +248,7 @@ async fn ensure_default_admin(pool: &PgPool) -> Result<()> {
         use rand::rngs::OsRng;
         let salt = SaltString::generate(&mut OsRng);
         let argon2 = Argon2::default();
-        let password = "admin123";
+        let password = DEFAULT_ADMIN_PASSWORD;
         let password_hash = argon2.hash_password(password.as_bytes(), &salt)
             .map(|ph| ph.to_string())
             .unwrap_or_else(|_| "".to_string());
@@ -245,12 +256,38 @@ async fn ensure_default_admin(pool: &PgPool) -> Result<()> {
         let _ = sqlx::query(
             "INSERT INTO admin_users (email, password_hash, salt, status) VALUES ($1, $2, $3, 'active')"
         )
-        .bind("admin@kmarket.local")
+        .bind(DEFAULT_ADMIN_EMAIL)
         .bind(password_hash)
         .bind(salt.to_string())
         .execute(pool)
         .await;
-        tracing::info!(target: "kmarket_backend", "Default admin user created: admin@kmarket.local / admin123");
+        tracing::info!(target: "kmarket_backend", "Default admin user created: {} / {}", DEFAULT_ADMIN_EMAIL, DEFAULT_ADMIN_PASSWORD);
+    }
+    Ok(())
+}
+
+/// True when APP_ENV=production (or prod)
+pub fn is_production() -> bool {
+    std::env::var("APP_ENV")
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "production" | "prod"))
+        .unwrap_or(false)
+}
+
+/// Refuse to boot in production while the seeded admin still accepts the default password
+async fn refuse_default_admin_password_in_production(pool: &PgPool) -> Result<()> {
+    if !is_production() { return Ok(()); }
+    let hash: Option<String> = sqlx::query_scalar("SELECT password_hash FROM admin_users WHERE email = $1 AND status = 'active'")
+        .bind(DEFAULT_ADMIN_EMAIL)
+        .fetch_optional(pool)
+        .await?;
+    if let Some(hash) = hash {
+        use argon2::{Argon2, PasswordHash, PasswordVerifier};
+        let still_default = PasswordHash::new(&hash)
+            .map(|h| Argon2::default().verify_password(DEFAULT_ADMIN_PASSWORD.as_bytes(), &h).is_ok())
+            .unwrap_or(false);
+        if still_default {
+            anyhow::bail!("refusing to start in production: default admin {} still uses the default password", DEFAULT_ADMIN_EMAIL);
+        }
     }
     Ok(())
 }
\ No newline at end of file
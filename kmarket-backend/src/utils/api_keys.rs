@@ -0,0 +1,161 @@
+use actix_web::{error::InternalError, HttpRequest, HttpResponse};
+use hmac::{Hmac, Mac};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+
+use crate::state::AppState;
+use crate::utils::rate_limit::{too_many_requests, Decision, RatePolicy};
+use crate::utils::errors::AppError;
+use crate::utils::response::ApiResponse;
+
+/// Read markets and fixtures
+pub const SCOPE_MARKETS_READ: &str = "markets:read";
+/// Place orders on behalf of users
+pub const SCOPE_ORDERS_WRITE: &str = "orders:write";
+/// Read aggregate reports
+pub const SCOPE_REPORTS_READ: &str = "reports:read";
+pub const API_KEY_SCOPES: [&str; 3] = [SCOPE_MARKETS_READ, SCOPE_ORDERS_WRITE, SCOPE_REPORTS_READ];
+
+/// Signed requests must carry a timestamp within this many seconds of server time
+pub const SIGNATURE_WINDOW_SECS: i64 = 300;
+
+pub const HEADER_KEY: &str = "X-Api-Key";
+pub const HEADER_TIMESTAMP: &str = "X-Api-Timestamp";
+pub const HEADER_NONCE: &str = "X-Api-Nonce";
+pub const HEADER_SIGNATURE: &str = "X-Api-Signature";
+
+/// Public key identifier, e.g. "pk_3f9a..."
+pub fn generate_key_id() -> String {
+    let raw: String = rand::thread_rng().sample_iter(&Alphanumeric).take(24).map(char::from).collect();
+    format!("pk_{}", raw.to_lowercase())
+}
+
+/// 256-bit signing secret, hex encoded
+pub fn generate_secret() -> String {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    hex::encode(bytes)
+}
+
+/// Canonical request: METHOD \n path?query \n timestamp \n nonce \n sha256_hex(body)
+pub fn string_to_sign(method: &str, path_and_query: &str, timestamp: i64, nonce: &str, body: &[u8]) -> String {
+    format!("{}\n{}\n{}\n{}\n{}", method.to_uppercase(), path_and_query, timestamp, nonce, hex::encode(Sha256::digest(body)))
+}
+
+/// Hex HMAC-SHA256 signature a client sends in X-Api-Signature
+pub fn sign_request(secret: &str, method: &str, path_and_query: &str, timestamp: i64, nonce: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(string_to_sign(method, path_and_query, timestamp, nonce, body).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn signature_matches(secret: &str, canonical: &str, signature_hex: &str) -> bool {
+    let Ok(sig) = hex::decode(signature_hex.trim()) else { return false };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(canonical.as_bytes());
+    // constant-time comparison
+    mac.verify_slice(&sig).is_ok()
+}
+
+/// An authenticated partner key
+#[derive(Debug, Clone)]
+pub struct ApiKeyPrincipal {
+    pub id: i64,
+    pub key_id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+}
+
+fn reject(resp: HttpResponse) -> actix_web::Error {
+    InternalError::from_response("api_key_rejected", resp).into()
+}
+
+fn unauthorized(code: &str, message: &str) -> actix_web::Error {
+    reject(HttpResponse::Unauthorized().json(ApiResponse::<()>::error(code, message)))
+}
+
+fn header<'a>(req: &'a HttpRequest, name: &str) -> Option<&'a str> {
+    req.headers().get(name).and_then(|v| v.to_str().ok()).map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Authenticate a signed partner request and require `scope`.
+/// Checks, in order: headers, timestamp window, key status, signature, scope, per-key rate limit,
+/// nonce reuse. Successful calls are counted in the key's usage counters.
+pub async fn authenticate_api_key(req: &HttpRequest, body: &[u8], state: &AppState, scope: &str) -> Result<ApiKeyPrincipal, actix_web::Error> {
+    let (Some(key_id), Some(ts), Some(nonce), Some(signature)) = (
+        header(req, HEADER_KEY), header(req, HEADER_TIMESTAMP), header(req, HEADER_NONCE), header(req, HEADER_SIGNATURE),
+    ) else {
+        return Err(unauthorized("missing_signature", "X-Api-Key, X-Api-Timestamp, X-Api-Nonce and X-Api-Signature are required"));
+    };
+    let ts: i64 = ts.parse().map_err(|_| unauthorized("invalid_timestamp", "timestamp must be unix seconds"))?;
+    if (chrono::Utc::now().timestamp() - ts).abs() > SIGNATURE_WINDOW_SECS {
+        return Err(unauthorized("stale_timestamp", "timestamp outside the allowed window"));
+    }
+    if nonce.len() < 8 || nonce.len() > 64 {
+        return Err(unauthorized("invalid_nonce", "nonce must be 8-64 characters"));
+    }
+
+    let row = sqlx::query("SELECT id, key_id, secret, name, scopes, status, rate_limit_per_min FROM api_keys WHERE key_id = $1")
+        .bind(key_id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    let Some(row) = row else { return Err(unauthorized("invalid_api_key", "unknown or revoked api key")) };
+    if row.try_get::<String, _>("status").unwrap_or_default() != "active" {
+        return Err(unauthorized("invalid_api_key", "unknown or revoked api key"));
+    }
+    let secret: String = row.try_get("secret").unwrap_or_default();
+    let path = req.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    if !signature_matches(&secret, &string_to_sign(req.method().as_str(), path, ts, nonce, body), signature) {
+        return Err(unauthorized("invalid_signature", "signature mismatch"));
+    }
+
+    let principal = ApiKeyPrincipal {
+        id: row.try_get("id").unwrap_or_default(),
+        key_id: row.try_get("key_id").unwrap_or_default(),
+        name: row.try_get("name").unwrap_or_default(),
+        scopes: row.try_get("scopes").unwrap_or_default(),
+    };
+    if !principal.scopes.iter().any(|s| s == scope) {
+        return Err(reject(HttpResponse::Forbidden().json(ApiResponse::<()>::error("insufficient_scope", &format!("api key lacks scope {}", scope)))));
+    }
+
+    let per_min = row.try_get::<i32, _>("rate_limit_per_min").unwrap_or(60).max(1) as u32;
+    if let Decision::Limited { retry_after_secs } = state.rate_limiter.check(&RatePolicy::new("partner:key", per_min, 60), &principal.key_id).await {
+        return Err(reject(too_many_requests(retry_after_secs)));
+    }
+
+    let fresh = sqlx::query("INSERT INTO api_key_nonces (api_key_id, nonce) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(principal.id)
+        .bind(nonce)
+        .execute(&state.db_pool)
+        .await
+        .map_err(AppError::from)?;
+    if fresh.rows_affected() == 0 {
+        return Err(unauthorized("replayed_request", "nonce already used"));
+    }
+    // Nonces older than the window can no longer be replayed; prune occasionally
+    if rand::thread_rng().gen_ratio(1, 100) {
+        let _ = sqlx::query("DELETE FROM api_key_nonces WHERE created_at < NOW() - make_interval(secs => $1)")
+            .bind((SIGNATURE_WINDOW_SECS * 2) as f64)
+            .execute(&state.db_pool)
+            .await;
+    }
+
+    record_usage(state, principal.id).await;
+    Ok(principal)
+}
+
+async fn record_usage(state: &AppState, api_key_id: i64) {
+    let res = sqlx::query(
+        "WITH k AS (UPDATE api_keys SET request_count = request_count + 1, last_used_at = NOW() WHERE id = $1 RETURNING id) \
+         INSERT INTO api_key_usage_daily (api_key_id, day, requests) SELECT id, CURRENT_DATE, 1 FROM k \
+         ON CONFLICT (api_key_id, day) DO UPDATE SET requests = api_key_usage_daily.requests + 1"
+    )
+    .bind(api_key_id)
+    .execute(&state.db_pool)
+    .await;
+    if let Err(e) = res {
+        tracing::warn!(target: "kmarket_backend", "api key usage update failed for {}: {}", api_key_id, e);
+    }
+}
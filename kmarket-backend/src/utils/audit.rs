@@ -0,0 +1,141 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{postgres::PgRow, PgConnection, PgExecutor, Row};
+
+/// actor_id of changes made by the server itself (e.g. the market scheduler), not an admin
+pub const SYSTEM_ACTOR_ID: i64 = 0;
+
+/// Append an audit_logs row. Pass the enclosing transaction so the action and its audit
+/// commit (or fail) together; errors must be propagated, never dropped.
+/// chain_seq / prev_hash / row_hash are filled in by the audit_logs_chain trigger.
+pub async fn record<'e, E: PgExecutor<'e>>(
+    executor: E,
+    actor_id: i64,
+    action: &str,
+    resource: &str,
+    resource_id: Option<i64>,
+    payload: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO audit_logs (actor_id, action, resource, resource_id, payload_json) VALUES ($1, $2, $3, $4, $5)")
+        .bind(actor_id)
+        .bind(action)
+        .bind(resource)
+        .bind(resource_id)
+        .bind(payload)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Hash of one chain link; must match the audit_logs_row_hash SQL function (migration 0016)
+#[allow(clippy::too_many_arguments)]
+pub fn row_hash(prev_hash: &str, chain_seq: i64, actor_id: i64, action: &str, resource: &str, resource_id: Option<i64>, payload_text: Option<&str>, created_micros: i64) -> String {
+    let canonical = format!(
+        "{}|{}|{}|{}|{}|{}|{}|{}",
+        prev_hash,
+        chain_seq,
+        actor_id,
+        action,
+        resource,
+        resource_id.map(|v| v.to_string()).unwrap_or_default(),
+        payload_text.unwrap_or(""),
+        created_micros
+    );
+    hex::encode(Sha256::digest(canonical.as_bytes()))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChainReport {
+    pub valid: bool,
+    pub checked: i64,
+    /// First chain_seq that failed verification
+    pub broken_at: Option<i64>,
+    pub reason: Option<String>,
+}
+
+const VERIFY_BATCH: i64 = 1000;
+
+/// Hash of one order_audits chain link; must match the order_audits_row_hash SQL function (migration 0033)
+pub fn order_audit_row_hash(prev_hash: &str, chain_seq: i64, order_id: i64, action: &str, detail_text: Option<&str>, created_micros: i64) -> String {
+    let canonical = format!("{}|{}|{}|{}|{}|{}", prev_hash, chain_seq, order_id, action, detail_text.unwrap_or(""), created_micros);
+    hex::encode(Sha256::digest(canonical.as_bytes()))
+}
+
+/// Walk the whole audit_logs chain in chain_seq order, recomputing every hash and link.
+/// Detects edited rows (hash mismatch), removed rows (sequence gap) and re-linked rows.
+pub async fn verify_chain(conn: &mut PgConnection) -> Result<ChainReport, sqlx::Error> {
+    walk_chain(
+        conn,
+        "SELECT chain_seq, prev_hash, row_hash, actor_id, action, resource, resource_id, payload_json::TEXT AS payload, \
+         (EXTRACT(EPOCH FROM created_at) * 1000000)::BIGINT AS created_micros \
+         FROM audit_logs WHERE chain_seq >= $1 ORDER BY chain_seq ASC LIMIT $2",
+        |prev, seq, r| {
+            let payload: Option<String> = r.try_get("payload")?;
+            Ok(row_hash(
+                prev,
+                seq,
+                r.try_get("actor_id")?,
+                &r.try_get::<String, _>("action")?,
+                &r.try_get::<String, _>("resource")?,
+                r.try_get("resource_id")?,
+                payload.as_deref(),
+                r.try_get("created_micros")?,
+            ))
+        },
+    )
+    .await
+}
+
+/// Same walk over the order_audits chain
+pub async fn verify_order_audits_chain(conn: &mut PgConnection) -> Result<ChainReport, sqlx::Error> {
+    walk_chain(
+        conn,
+        "SELECT chain_seq, prev_hash, row_hash, order_id, action, detail::TEXT AS detail, \
+         (EXTRACT(EPOCH FROM created_at) * 1000000)::BIGINT AS created_micros \
+         FROM order_audits WHERE chain_seq >= $1 ORDER BY chain_seq ASC LIMIT $2",
+        |prev, seq, r| {
+            let detail: Option<String> = r.try_get("detail")?;
+            Ok(order_audit_row_hash(prev, seq, r.try_get("order_id")?, &r.try_get::<String, _>("action")?, detail.as_deref(), r.try_get("created_micros")?))
+        },
+    )
+    .await
+}
+
+/// `select` reads a batch starting at chain_seq $1 (at most $2 rows); `link` recomputes a
+/// row's hash from the previous one
+async fn walk_chain<F>(conn: &mut PgConnection, select: &str, link: F) -> Result<ChainReport, sqlx::Error>
+where
+    F: Fn(&str, i64, &PgRow) -> Result<String, sqlx::Error>,
+{
+    let mut expected_seq = 1i64;
+    let mut prev = String::new();
+    let mut checked = 0i64;
+    loop {
+        let rows = sqlx::query(select)
+            .bind(expected_seq)
+            .bind(VERIFY_BATCH)
+            .fetch_all(&mut *conn)
+            .await?;
+        if rows.is_empty() { break; }
+        for r in &rows {
+            let seq: i64 = r.try_get("chain_seq")?;
+            let broken = |reason: &str| ChainReport { valid: false, checked, broken_at: Some(seq), reason: Some(reason.to_string()) };
+            if seq != expected_seq {
+                return Ok(ChainReport { broken_at: Some(expected_seq), ..broken("missing row") });
+            }
+            let stored_prev: Option<String> = r.try_get("prev_hash")?;
+            if stored_prev.as_deref().unwrap_or("") != prev {
+                return Ok(broken("prev_hash does not match previous row"));
+            }
+            let computed = link(&prev, seq, r)?;
+            let stored: Option<String> = r.try_get("row_hash")?;
+            if stored.as_deref() != Some(computed.as_str()) {
+                return Ok(broken("row_hash mismatch"));
+            }
+            prev = computed;
+            expected_seq += 1;
+            checked += 1;
+        }
+    }
+    Ok(ChainReport { valid: true, checked, broken_at: None, reason: None })
+}
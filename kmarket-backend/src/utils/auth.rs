@@ -1,26 +1,106 @@
-use actix_web::{HttpRequest, error::ErrorUnauthorized};
-use jsonwebtoken::{DecodingKey, Validation, decode, Algorithm};
-use serde::Deserialize;
+use actix_web::HttpRequest;
+use chrono::{Duration, Utc};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode, Algorithm};
+use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Row};
 
-#[derive(Deserialize)]
-struct Claims { sub: String, exp: usize, iat: usize }
+use crate::utils::errors::AppError;
 
-/// Validate Authorization: Bearer <JWT>, return admin_users.id
-pub async fn admin_actor_id(req: &HttpRequest, pool: &PgPool) -> Result<i64, actix_web::Error> {
+/// Full admin session token
+pub const PURPOSE_ACCESS: &str = "access";
+/// Short-lived token proving the password step passed; only accepted by the MFA verify endpoint
+pub const PURPOSE_MFA: &str = "mfa";
+
+#[derive(Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: usize,
+    pub iat: usize,
+    // Tokens issued before MFA existed carry no purpose and are access tokens
+    #[serde(default = "default_purpose")]
+    pub purpose: String,
+}
+
+fn default_purpose() -> String { PURPOSE_ACCESS.to_string() }
+
+fn jwt_secret() -> String {
+    std::env::var("ADMIN_JWT_SECRET").unwrap_or_else(|_| "dev_admin_secret".to_string())
+}
+
+/// Sign an admin JWT for `email` with the given purpose and lifetime
+pub fn issue_admin_token(email: &str, purpose: &str, ttl: Duration) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let claims = Claims {
+        sub: email.to_string(),
+        iat: now.timestamp() as usize,
+        exp: (now + ttl).timestamp() as usize,
+        purpose: purpose.to_string(),
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret().as_bytes()))
+}
+
+/// Decode and validate an admin JWT, requiring the expected purpose; returns the email (sub)
+pub fn decode_admin_token(token: &str, purpose: &str) -> Result<String, AppError> {
+    let data = decode::<Claims>(token, &DecodingKey::from_secret(jwt_secret().as_bytes()), &Validation::new(Algorithm::HS256))
+        .map_err(|_| AppError::Unauthorized("invalid_token"))?;
+    if data.claims.purpose != purpose { return Err(AppError::Unauthorized("invalid_token")); }
+    Ok(data.claims.sub)
+}
+
+fn bearer_token(req: &HttpRequest) -> Result<String, AppError> {
     let auth = req.headers().get("authorization").and_then(|v| v.to_str().ok()).unwrap_or("");
     if !auth.to_lowercase().starts_with("bearer ") {
-        return Err(ErrorUnauthorized("missing_bearer"));
+        return Err(AppError::Unauthorized("missing_bearer"));
     }
-    let token = auth.trim()[7..].trim();
-    let secret = std::env::var("ADMIN_JWT_SECRET").unwrap_or_else(|_| "dev_admin_secret".to_string());
-    let data = decode::<Claims>(token, &DecodingKey::from_secret(secret.as_bytes()), &Validation::new(Algorithm::HS256))
-        .map_err(|_| ErrorUnauthorized("invalid_token"))?;
-    let email = data.claims.sub;
-    let row = sqlx::query("SELECT id FROM admin_users WHERE email = $1")
+    Ok(auth.trim()[7..].trim().to_string())
+}
+
+/// Roles an admin account can hold (see docs/admin.md RBAC)
+pub const ADMIN_ROLES: [&str; 4] = ["admin", "operator", "analyst", "support"];
+
+async fn resolve_admin(req: &HttpRequest, pool: &PgPool, enforce_setup: bool) -> Result<i64, AppError> {
+    let token = bearer_token(req)?;
+    let email = decode_admin_token(&token, PURPOSE_ACCESS)?;
+    let row = sqlx::query("SELECT id, status, totp_enabled, must_reset_password FROM admin_users WHERE email = $1")
         .bind(email)
         .fetch_optional(pool)
         .await
-        .map_err(|_| ErrorUnauthorized("auth_db_error"))?;
-    if let Some(r) = row { Ok(r.try_get("id").unwrap_or(0)) } else { Err(ErrorUnauthorized("admin_not_found")) }
-}
\ No newline at end of file
+        .map_err(AppError::from)?;
+    let Some(r) = row else { return Err(AppError::Unauthorized("admin_not_found")) };
+    if r.try_get::<String, _>("status").unwrap_or_default() != "active" {
+        return Err(AppError::Unauthorized("account_disabled"));
+    }
+    if enforce_setup {
+        if r.try_get::<bool, _>("must_reset_password").unwrap_or(false) {
+            return Err(AppError::Forbidden("password_reset_required"));
+        }
+        if crate::utils::mfa::mfa_required() && !r.try_get::<bool, _>("totp_enabled").unwrap_or(false) {
+            return Err(AppError::Forbidden("mfa_enrollment_required"));
+        }
+    }
+    Ok(r.try_get("id").unwrap_or(0))
+}
+
+/// Validate Authorization: Bearer <JWT>, return admin_users.id
+pub async fn admin_actor_id(req: &HttpRequest, pool: &PgPool) -> Result<i64, AppError> {
+    resolve_admin(req, pool, true).await
+}
+
+/// Same as `admin_actor_id` but lets through admins with pending account setup
+/// (forced password reset, or missing TOTP while MFA is enforced) so they can complete it
+pub async fn admin_actor_id_allow_pending(req: &HttpRequest, pool: &PgPool) -> Result<i64, AppError> {
+    resolve_admin(req, pool, false).await
+}
+
+/// Reject the request unless the admin holds one of `roles`
+pub async fn require_role(pool: &PgPool, actor_id: i64, roles: &[&str]) -> Result<(), AppError> {
+    let role: Option<String> = sqlx::query_scalar("SELECT role FROM admin_users WHERE id = $1")
+        .bind(actor_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::from)?;
+    match role {
+        Some(r) if roles.contains(&r.as_str()) => Ok(()),
+        _ => Err(AppError::Forbidden("insufficient_role")),
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{HttpRequest, HttpResponse};
+use async_trait::async_trait;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::models::event::{DomainEvent, OutboxEvent};
+use crate::utils::conditional::Representation;
+use crate::utils::outbox::EventSubscriber;
+
+/// Public fixture lists (sports::get_fixtures)
+pub const NS_FIXTURES: &str = "fixtures";
+/// Public market lists (markets::get_markets, compat::get_frontend_markets)
+pub const NS_MARKETS: &str = "markets";
+
+const DEFAULT_TTL_SECS: u64 = 10;
+const DEFAULT_MAX_ENTRIES: usize = 1_000;
+
+/// Cache key for a list query: the namespace plus a digest of the normalized parameters
+/// (defaults resolved and filters trimmed, so `?page=1` and no page share an entry)
+pub fn key(namespace: &str, params: &impl Serialize) -> String {
+    let json = serde_json::to_vec(params).unwrap_or_default();
+    format!("{}:{}", namespace, hex::encode(Sha256::digest(&json)))
+}
+
+fn namespace_of(key: &str) -> &str {
+    key.split_once(':').map(|(ns, _)| ns).unwrap_or(key)
+}
+
+struct Entry {
+    value: Representation,
+    expires: Instant,
+    last_used: u64,
+}
+
+/// In-process TTL cache; evicts expired entries, then the least recently used, when full
+pub struct MemoryCache {
+    entries: Mutex<(HashMap<String, Entry>, u64)>,
+    max_entries: usize,
+}
+
+impl MemoryCache {
+    pub fn new(max_entries: usize) -> Self {
+        Self { entries: Mutex::new((HashMap::new(), 0)), max_entries: max_entries.max(1) }
+    }
+
+    fn get(&self, key: &str) -> Option<Representation> {
+        let mut guard = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let (entries, clock) = &mut *guard;
+        *clock += 1;
+        match entries.get_mut(key) {
+            Some(e) if e.expires > Instant::now() => {
+                e.last_used = *clock;
+                Some(e.value.clone())
+            }
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn set(&self, key: &str, value: Representation, ttl: Duration) {
+        let mut guard = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let (entries, clock) = &mut *guard;
+        *clock += 1;
+        if entries.len() >= self.max_entries && !entries.contains_key(key) {
+            let now = Instant::now();
+            entries.retain(|_, e| e.expires > now);
+            if entries.len() >= self.max_entries {
+                if let Some(lru) = entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone()) {
+                    entries.remove(&lru);
+                }
+            }
+        }
+        entries.insert(key.to_string(), Entry { value, expires: Instant::now() + ttl, last_used: *clock });
+    }
+
+    fn invalidate(&self, namespace: &str) {
+        let mut guard = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        guard.0.retain(|k, _| namespace_of(k) != namespace);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Shared by all instances. Invalidation bumps a per-namespace generation that is part of every
+/// key ("cache:{ns}:{gen}:{digest}"), so stale entries are never read and simply expire.
+pub struct RedisCache {
+    conn: redis::aio::ConnectionManager,
+}
+
+impl RedisCache {
+    pub async fn connect(url: &str) -> redis::RedisResult<Self> {
+        let client = redis::Client::open(url)?;
+        let conn = client.get_connection_manager().await?;
+        Ok(Self { conn })
+    }
+
+    async fn versioned(&self, key: &str) -> redis::RedisResult<String> {
+        let mut conn = self.conn.clone();
+        let ns = namespace_of(key);
+        let gen: Option<u64> = redis::cmd("GET").arg(format!("cache:gen:{}", ns)).query_async(&mut conn).await?;
+        Ok(format!("cache:{}:{}:{}", ns, gen.unwrap_or(0), &key[ns.len()..].trim_start_matches(':')))
+    }
+
+    async fn get(&self, key: &str) -> redis::RedisResult<Option<Representation>> {
+        let key = self.versioned(key).await?;
+        let value: Option<String> = redis::cmd("GET").arg(key).query_async(&mut self.conn.clone()).await?;
+        // entries written by an older layout read as misses
+        Ok(value.and_then(|v| serde_json::from_str(&v).ok()))
+    }
+
+    async fn set(&self, key: &str, value: &Representation, ttl: Duration) -> redis::RedisResult<()> {
+        let key = self.versioned(key).await?;
+        let value = serde_json::to_string(value).unwrap_or_default();
+        redis::cmd("SET").arg(key).arg(value).arg("EX").arg(ttl.as_secs().max(1)).query_async(&mut self.conn.clone()).await
+    }
+
+    async fn invalidate(&self, namespace: &str) -> redis::RedisResult<()> {
+        redis::cmd("INCR").arg(format!("cache:gen:{}", namespace)).query_async::<_, i64>(&mut self.conn.clone()).await.map(|_| ())
+    }
+}
+
+pub enum CacheBackend {
+    Memory(MemoryCache),
+    Redis(RedisCache),
+    Disabled,
+}
+
+/// Cache for serialized list responses. Redis errors are logged and treated as misses, so an
+/// outage costs database load, never availability.
+pub struct ResponseCache {
+    backend: CacheBackend,
+    pub ttl: Duration,
+}
+
+impl ResponseCache {
+    pub fn new(backend: CacheBackend, ttl: Duration) -> Self {
+        Self { backend, ttl }
+    }
+
+    pub fn memory(max_entries: usize, ttl: Duration) -> Self {
+        Self::new(CacheBackend::Memory(MemoryCache::new(max_entries)), ttl)
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(CacheBackend::Disabled, Duration::ZERO)
+    }
+
+    /// Build from CACHE_BACKEND (memory | redis | off), CACHE_TTL_SECS, CACHE_MAX_ENTRIES and
+    /// REDIS_URL. Falls back to the in-memory cache when Redis is unreachable.
+    pub async fn from_env() -> Self {
+        let ttl = Duration::from_secs(std::env::var("CACHE_TTL_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_TTL_SECS));
+        let max_entries = std::env::var("CACHE_MAX_ENTRIES").ok().and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_MAX_ENTRIES);
+        let kind = std::env::var("CACHE_BACKEND").unwrap_or_else(|_| "memory".to_string());
+        if ttl.is_zero() {
+            return Self::disabled();
+        }
+        match kind.trim().to_lowercase().as_str() {
+            "off" | "disabled" | "none" => Self::disabled(),
+            "redis" => {
+                let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379/0".to_string());
+                match RedisCache::connect(&url).await {
+                    Ok(c) => Self::new(CacheBackend::Redis(c), ttl),
+                    Err(e) => {
+                        tracing::warn!(target: "kmarket_backend", "cache redis unavailable ({}), using in-memory cache", e);
+                        Self::memory(max_entries, ttl)
+                    }
+                }
+            }
+            _ => Self::memory(max_entries, ttl),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self.backend, CacheBackend::Disabled)
+    }
+
+    /// Conditional response for a representation (see Representation::respond), with
+    /// X-Cache HIT/MISS when caching is on
+    pub fn respond(&self, req: &HttpRequest, rep: Representation, hit: bool, cache_control: &str) -> HttpResponse {
+        let mut resp = rep.respond(req, cache_control);
+        if self.is_enabled() {
+            resp.headers_mut().insert(HeaderName::from_static("x-cache"), HeaderValue::from_static(if hit { "HIT" } else { "MISS" }));
+        }
+        resp
+    }
+
+    pub async fn get(&self, key: &str) -> Option<Representation> {
+        match &self.backend {
+            CacheBackend::Memory(m) => m.get(key),
+            CacheBackend::Redis(r) => r.get(key).await.unwrap_or_else(|e| {
+                tracing::warn!(target: "kmarket_backend", "cache redis get failed: {}", e);
+                None
+            }),
+            CacheBackend::Disabled => None,
+        }
+    }
+
+    pub async fn set(&self, key: &str, value: Representation) {
+        match &self.backend {
+            CacheBackend::Memory(m) => m.set(key, value, self.ttl),
+            CacheBackend::Redis(r) => {
+                if let Err(e) = r.set(key, &value, self.ttl).await {
+                    tracing::warn!(target: "kmarket_backend", "cache redis set failed: {}", e);
+                }
+            }
+            CacheBackend::Disabled => {}
+        }
+    }
+
+    /// Drop every entry of a namespace
+    pub async fn invalidate(&self, namespace: &str) {
+        match &self.backend {
+            CacheBackend::Memory(m) => m.invalidate(namespace),
+            CacheBackend::Redis(r) => {
+                if let Err(e) = r.invalidate(namespace).await {
+                    tracing::warn!(target: "kmarket_backend", "cache redis invalidate failed: {}", e);
+                }
+            }
+            CacheBackend::Disabled => {}
+        }
+    }
+}
+
+/// Invalidates cached lists when markets, odds or match state change. Events are handled by
+/// one instance; with the memory backend other instances serve their entries until the TTL.
+pub struct CacheSubscriber {
+    cache: std::sync::Arc<ResponseCache>,
+}
+
+impl CacheSubscriber {
+    pub fn new(cache: std::sync::Arc<ResponseCache>) -> Self { Self { cache } }
+}
+
+#[async_trait]
+impl EventSubscriber for CacheSubscriber {
+    fn name(&self) -> &'static str { "cache" }
+
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), String> {
+        match &event.event {
+            DomainEvent::MarketCreated { .. }
+            | DomainEvent::MarketUpdated { .. }
+            | DomainEvent::MarketStatusChanged { .. }
+            | DomainEvent::MarketSettled { .. }
+            | DomainEvent::MarketClosed { .. }
+            | DomainEvent::MarketDeleted { .. } => {
+                self.cache.invalidate(NS_FIXTURES).await;
+                self.cache.invalidate(NS_MARKETS).await;
+            }
+            DomainEvent::MatchStateChanged { .. } => self.cache.invalidate(NS_FIXTURES).await,
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use sqlx::PgConnection;
+
+/// A market's catalog references, as stored on markets (league_id, home_team_id, away_team_id)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarketRefs {
+    pub league_id: Option<i64>,
+    pub home_team_id: Option<i64>,
+    pub away_team_id: Option<i64>,
+}
+
+/// Check that the referenced league and teams exist, the teams differ and all belong to one sport.
+/// The outer error is a database failure; the inner one a message for the caller.
+pub async fn check_market_refs(conn: &mut PgConnection, refs: MarketRefs) -> Result<Result<(), String>, sqlx::Error> {
+    if refs.home_team_id.is_some() && refs.home_team_id == refs.away_team_id {
+        return Ok(Err("home and away team must differ".into()));
+    }
+    let mut sports = Vec::new();
+    if let Some(id) = refs.league_id {
+        match sqlx::query_scalar::<_, i64>("SELECT sport_id FROM leagues WHERE id = $1").bind(id).fetch_optional(&mut *conn).await? {
+            Some(sport) => sports.push(sport),
+            None => return Ok(Err(format!("league {} not found", id))),
+        }
+    }
+    for id in [refs.home_team_id, refs.away_team_id].into_iter().flatten() {
+        match sqlx::query_scalar::<_, i64>("SELECT sport_id FROM teams WHERE id = $1").bind(id).fetch_optional(&mut *conn).await? {
+            Some(sport) => sports.push(sport),
+            None => return Ok(Err(format!("team {} not found", id))),
+        }
+    }
+    if sports.windows(2).any(|w| w[0] != w[1]) {
+        return Ok(Err("league and teams must belong to the same sport".into()));
+    }
+    Ok(Ok(()))
+}
+
+/// League id for a code or name (case-insensitive), e.g. a feed's league label
+pub async fn league_by_label(conn: &mut PgConnection, label: &str) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT id FROM leagues WHERE LOWER(code) = LOWER($1) OR LOWER(name) = LOWER($1) ORDER BY (LOWER(code) = LOWER($1)) DESC, id LIMIT 1")
+        .bind(label.trim())
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+/// Sport code (simulator sport name) of a market's league
+pub async fn market_sport_code(conn: &mut PgConnection, market_pk: i64) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT s.code FROM markets m JOIN leagues l ON l.id = m.league_id JOIN sports s ON s.id = l.sport_id WHERE m.id = $1")
+        .bind(market_pk)
+        .fetch_optional(&mut *conn)
+        .await
+}
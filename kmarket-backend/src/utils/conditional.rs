@@ -0,0 +1,65 @@
+use std::time::SystemTime;
+
+use actix_web::http::header::{self, ContentType, EntityTag, HttpDate, IfModifiedSince, IfNoneMatch};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::utils::errors::AppError;
+
+/// A serialized JSON body with its validators. The ETag is a digest of the exact bytes, so it is
+/// strong and changes whenever a row version / updated_at change (or a derived field such as a
+/// fixture's status) shows up in the output. Last-Modified is the newest updated_at of the rows.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Representation {
+    pub body: String,
+    pub etag: String,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+impl Representation {
+    pub fn new(body: String, last_modified: Option<DateTime<Utc>>) -> Self {
+        let etag = hex::encode(&Sha256::digest(body.as_bytes())[..16]);
+        Self { body, etag, last_modified }
+    }
+
+    /// Serialize a response body; `last_modified` is taken over the rows it was built from
+    pub fn json(value: &impl Serialize, last_modified: impl IntoIterator<Item = DateTime<Utc>>) -> actix_web::Result<Self> {
+        let body = serde_json::to_string(value).map_err(AppError::internal)?;
+        Ok(Self::new(body, last_modified.into_iter().max()))
+    }
+
+    /// Whether the client's copy is current. If-None-Match (weak comparison, as RFC 9110 requires
+    /// for GET) takes precedence; If-Modified-Since is only consulted without it.
+    pub fn not_modified(&self, req: &HttpRequest) -> bool {
+        if req.headers().contains_key(header::IF_NONE_MATCH) {
+            let ours = EntityTag::new_strong(self.etag.clone());
+            return match req.get_header::<IfNoneMatch>() {
+                Some(IfNoneMatch::Any) => true,
+                Some(IfNoneMatch::Items(tags)) => tags.iter().any(|t| t.weak_eq(&ours)),
+                None => false,
+            };
+        }
+        match (req.get_header::<IfModifiedSince>(), self.last_modified) {
+            // HTTP dates have second precision
+            (Some(IfModifiedSince(since)), Some(modified)) => modified.timestamp() <= DateTime::<Utc>::from(SystemTime::from(since)).timestamp(),
+            _ => false,
+        }
+    }
+
+    /// 200 with the body, or 304 when the request's validators match; both carry ETag,
+    /// Last-Modified and `cache_control`
+    pub fn respond(self, req: &HttpRequest, cache_control: &str) -> HttpResponse {
+        let not_modified = self.not_modified(req);
+        let mut resp = if not_modified { HttpResponse::NotModified() } else { HttpResponse::Ok() };
+        resp.insert_header(header::ETag(EntityTag::new_strong(self.etag.clone())))
+            .insert_header((header::CACHE_CONTROL, cache_control.to_string()));
+        if let Some(modified) = self.last_modified {
+            resp.insert_header(header::LastModified(HttpDate::from(SystemTime::from(modified))));
+        }
+        if not_modified {
+            return resp.finish();
+        }
+        resp.content_type(ContentType::json()).body(self.body)
+    }
+}
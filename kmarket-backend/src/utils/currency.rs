@@ -0,0 +1,76 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgConnection;
+
+use crate::utils::money::Rounding;
+
+/// Currency fx_rates are quoted in, and consolidated report totals with them
+pub const REPORTING_CURRENCY: &str = "USD";
+
+/// markets.currency default; pre-currency amounts were USDC
+pub const DEFAULT_CURRENCY: &str = "USDC";
+
+pub const CURRENCY_COLUMNS: &str = "code, name, decimals, chain, mint_address, max_user_exposure, enabled, created_at, updated_at";
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Currency {
+    pub code: String,
+    pub name: String,
+    pub decimals: i16,
+    pub chain: Option<String>,
+    /// SPL mint or token contract; None for off-chain units such as points
+    pub mint_address: Option<String>,
+    /// Default cap on a user's open stakes in this currency; None = unlimited
+    pub max_user_exposure: Option<BigDecimal>,
+    /// Disabled currencies take no new markets; existing ones keep trading
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Currency {
+    pub fn rounding(&self) -> Rounding {
+        Rounding::new(self.decimals)
+    }
+}
+
+/// Canonical code: 2-16 ASCII letters or digits, upper-cased
+pub fn normalize_code(code: &str) -> Option<String> {
+    let code = code.trim();
+    ((2..=16).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphanumeric())).then(|| code.to_ascii_uppercase())
+}
+
+pub async fn find(conn: &mut PgConnection, code: &str) -> Result<Option<Currency>, sqlx::Error> {
+    sqlx::query_as(&format!("SELECT {} FROM currencies WHERE code = $1", CURRENCY_COLUMNS))
+        .bind(code)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+/// Canonical code of an enabled currency, for a new or changed market.
+/// The outer error is a database failure; the inner one a message for the caller.
+pub async fn check_enabled(conn: &mut PgConnection, code: &str) -> Result<Result<String, String>, sqlx::Error> {
+    let found = match normalize_code(code) {
+        Some(code) => find(conn, &code).await?,
+        None => None,
+    };
+    Ok(match found {
+        Some(c) if c.enabled => Ok(c.code),
+        Some(c) => Err(format!("currency {} is disabled", c.code)),
+        None => Err(format!("unknown currency {}", code.trim())),
+    })
+}
+
+/// Add the settled orders' close_pnl to their users' per-currency totals
+pub async fn add_settled_pnl(conn: &mut PgConnection, order_ids: &[i64]) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO user_balances (user_id, currency, total_pnl) \
+         SELECT user_id, currency, SUM(close_pnl) FROM orders WHERE id = ANY($1) AND close_pnl IS NOT NULL GROUP BY user_id, currency \
+         ON CONFLICT (user_id, currency) DO UPDATE SET total_pnl = user_balances.total_pnl + EXCLUDED.total_pnl"
+    )
+    .bind(order_ids)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
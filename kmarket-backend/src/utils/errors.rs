@@ -1,9 +1,15 @@
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
 use thiserror::Error;
 
+use crate::utils::request_id;
+use crate::utils::response::ApiResponse;
+
 #[derive(Debug, Error)]
 pub enum DataAccessError {
     #[error("invalid argument: {0}")]
     InvalidArgument(String),
+    #[error("{0} not found")]
+    NotFound(String),
     #[error("duplicate key: {0}")]
     DuplicateKey(String),
     #[error("referential integrity violation: {0}")]
@@ -14,6 +20,15 @@ pub enum DataAccessError {
     NotNullViolation(String),
     #[error("concurrency conflict on {0}")]
     ConcurrencyConflict(String),
+    #[error("market {0} is closed for betting")]
+    MarketClosed(i64),
+    #[error("exposure limit reached for {0}")]
+    ExposureLimitExceeded(String),
+    /// Only settled orders with a payout can be claimed
+    #[error("position {0} has no payout to claim")]
+    NotClaimable(i64),
+    #[error("position {0} was already claimed")]
+    AlreadyClaimed(i64),
     #[error("database error: {0}")]
     Database(String),
 }
@@ -33,4 +48,140 @@ pub fn translate_sqlx_error(e: sqlx::Error) -> DataAccessError {
         }
         other => DataAccessError::Database(other.to_string()),
     }
-}
\ No newline at end of file
+}
+
+/// Error returned by handlers. Always rendered as the `ApiResponse` envelope with a stable
+/// machine `code` and the request id; details of server-side failures are logged, never sent.
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("{code}: {message}")]
+    BadRequest { code: &'static str, message: String },
+    /// Code names the reason, e.g. `missing_bearer`, `invalid_token`
+    #[error("unauthorized: {0}")]
+    Unauthorized(&'static str),
+    #[error("forbidden: {0}")]
+    Forbidden(&'static str),
+    /// What was not found, e.g. "market"
+    #[error("{0} not found")]
+    NotFound(String),
+    #[error("{code}: {message}")]
+    Conflict { code: &'static str, message: String },
+    #[error(transparent)]
+    Data(#[from] DataAccessError),
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+impl AppError {
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::BadRequest { code, message: message.into() }
+    }
+
+    pub fn conflict(code: &'static str, message: impl Into<String>) -> Self {
+        Self::Conflict { code, message: message.into() }
+    }
+
+    /// Unexpected failure; the detail only reaches the log
+    pub fn internal(e: impl std::fmt::Display) -> Self {
+        Self::Internal(e.to_string())
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::BadRequest { code, .. } | Self::Conflict { code, .. } => code,
+            Self::Unauthorized(code) | Self::Forbidden(code) => code,
+            Self::NotFound(_) => "not_found",
+            Self::Data(e) => match e {
+                DataAccessError::InvalidArgument(_) => "invalid_argument",
+                DataAccessError::NotFound(_) => "not_found",
+                DataAccessError::DuplicateKey(_) => "duplicate_key",
+                DataAccessError::ReferentialIntegrity(_) => "referential_integrity",
+                DataAccessError::ConstraintViolation(_) => "constraint_violation",
+                DataAccessError::NotNullViolation(_) => "missing_field",
+                DataAccessError::ConcurrencyConflict(_) => "version_conflict",
+                DataAccessError::MarketClosed(_) => "market_closed",
+                DataAccessError::ExposureLimitExceeded(_) => "exposure_limit_exceeded",
+                DataAccessError::NotClaimable(_) => "not_claimable",
+                DataAccessError::AlreadyClaimed(_) => "already_claimed",
+                DataAccessError::Database(_) => "internal_error",
+            },
+            Self::Internal(_) => "internal_error",
+        }
+    }
+
+    /// Client-facing message. Database messages name constraints and columns, so only
+    /// caller-supplied text is passed through.
+    fn public_message(&self) -> String {
+        match self {
+            Self::BadRequest { message, .. } | Self::Conflict { message, .. } => message.clone(),
+            Self::Unauthorized(_) => "authentication required".into(),
+            Self::Forbidden(_) => "not allowed".into(),
+            Self::NotFound(what) => format!("{} not found", what),
+            Self::Data(e) => match e {
+                DataAccessError::InvalidArgument(field) => format!("invalid argument: {}", field),
+                DataAccessError::NotFound(_)
+                | DataAccessError::MarketClosed(_)
+                | DataAccessError::ExposureLimitExceeded(_)
+                | DataAccessError::NotClaimable(_)
+                | DataAccessError::AlreadyClaimed(_) => e.to_string(),
+                DataAccessError::DuplicateKey(_) => "resource already exists".into(),
+                DataAccessError::ReferentialIntegrity(_) => "referenced resource is missing or still in use".into(),
+                DataAccessError::ConstraintViolation(_) => "value violates a constraint".into(),
+                DataAccessError::NotNullViolation(_) => "a required field is missing".into(),
+                DataAccessError::ConcurrencyConflict(_) => "resource was modified concurrently; reload and retry".into(),
+                DataAccessError::Database(_) => "internal server error".into(),
+            },
+            Self::Internal(_) => "internal server error".into(),
+        }
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Data(translate_sqlx_error(e))
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::BadRequest { .. } => StatusCode::BAD_REQUEST,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict { .. } => StatusCode::CONFLICT,
+            Self::Data(e) => match e {
+                DataAccessError::InvalidArgument(_)
+                | DataAccessError::ConstraintViolation(_)
+                | DataAccessError::NotNullViolation(_) => StatusCode::BAD_REQUEST,
+                DataAccessError::NotFound(_) => StatusCode::NOT_FOUND,
+                DataAccessError::DuplicateKey(_)
+                | DataAccessError::ReferentialIntegrity(_)
+                | DataAccessError::ConcurrencyConflict(_)
+                | DataAccessError::MarketClosed(_)
+                | DataAccessError::ExposureLimitExceeded(_)
+                | DataAccessError::NotClaimable(_)
+                | DataAccessError::AlreadyClaimed(_) => StatusCode::CONFLICT,
+                DataAccessError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let request_id = request_id::current();
+        if status.is_server_error() {
+            tracing::error!(request_id = %request_id, error = %self, "request failed");
+        } else {
+            tracing::debug!(request_id = %request_id, error = %self, "request rejected");
+        }
+        let mut body = ApiResponse::<()>::error(self.code(), &self.public_message());
+        if let Some(error) = body.error.as_mut() {
+            error.request_id = Some(request_id.clone());
+        }
+        HttpResponse::build(status)
+            .insert_header((request_id::HEADER, request_id))
+            .json(body)
+    }
+}
@@ -0,0 +1,535 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool, Row};
+
+use crate::models::event::DomainEvent;
+use crate::utils::catalog::{self, MarketRefs};
+use crate::utils::match_state::{self, MatchStateUpdate};
+use crate::utils::outbox;
+
+/// attention_reason set when a provider reports a result, so an admin settles the market
+pub const ATTENTION_FEED_RESULT: &str = "feed_result";
+pub const FEED_KINDS: [&str; 2] = ["file", "http"];
+
+/// Providers synced per pass of the background loop
+const BATCH_SIZE: i64 = 10;
+/// Error entries kept per sync run
+const MAX_RUN_ERRORS: usize = 100;
+/// Match length assumed when a fixture has no end time
+const DEFAULT_MATCH_HOURS: i64 = 2;
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedTeam {
+    /// Provider team id
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedFixture {
+    /// Provider event id; markets are matched on (provider, external_id)
+    pub external_id: String,
+    pub title: Option<String>,
+    pub league: Option<String>,
+    pub home: FeedTeam,
+    pub away: FeedTeam,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+/// Decimal odds for a fixture's home / away outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedOdds {
+    pub fixture_id: String,
+    pub home: f64,
+    pub away: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedResult {
+    pub fixture_id: String,
+    pub home_score: i32,
+    pub away_score: i32,
+    /// final | cancelled
+    pub status: String,
+}
+
+/// Source of fixtures, odds and results. Implementations only fetch and parse; mapping to
+/// markets, history and the sync log is done by FeedIngestor.
+#[async_trait]
+pub trait FeedProvider: Send + Sync {
+    fn name(&self) -> &str;
+    async fn fetch_fixtures(&self) -> Result<Vec<FeedFixture>, String>;
+    async fn fetch_odds(&self) -> Result<Vec<FeedOdds>, String>;
+    async fn fetch_results(&self) -> Result<Vec<FeedResult>, String>;
+}
+
+/// Whole-feed document served by file providers
+#[derive(Debug, Default, Deserialize)]
+pub struct FeedDocument {
+    #[serde(default)]
+    pub fixtures: Vec<FeedFixture>,
+    #[serde(default)]
+    pub odds: Vec<FeedOdds>,
+    #[serde(default)]
+    pub results: Vec<FeedResult>,
+}
+
+/// Split one CSV line, honouring double-quoted fields ("" escapes a quote)
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => { field.push('"'); chars.next(); }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+/// Fixture sheet: one row per fixture with columns external_id, title, league, home_id, home_name,
+/// away_id, away_name, start_time, end_time, odds_home, odds_away, home_score, away_score, result.
+/// Odds and result columns may be empty; a row with scores and a result also yields a FeedResult.
+pub fn parse_csv(text: &str) -> Result<FeedDocument, String> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header = csv_fields(lines.next().ok_or("empty file")?);
+    let col = |name: &str| header.iter().position(|h| h == name);
+    for required in ["external_id", "home_id", "home_name", "away_id", "away_name", "start_time"] {
+        if col(required).is_none() { return Err(format!("missing column {}", required)); }
+    }
+    let mut doc = FeedDocument::default();
+    for (n, line) in lines.enumerate() {
+        let fields = csv_fields(line);
+        let get = |name: &str| col(name).and_then(|i| fields.get(i)).map(String::as_str).filter(|v| !v.is_empty());
+        let row = n + 2;
+        let external_id = get("external_id").ok_or(format!("row {}: external_id is empty", row))?.to_string();
+        let time = |name: &str| get(name).map(|v| DateTime::parse_from_rfc3339(v).map(|t| t.with_timezone(&Utc)).map_err(|e| format!("row {}: {}: {}", row, name, e))).transpose();
+        doc.fixtures.push(FeedFixture {
+            external_id: external_id.clone(),
+            title: get("title").map(str::to_string),
+            league: get("league").map(str::to_string),
+            home: FeedTeam { id: get("home_id").unwrap_or_default().to_string(), name: get("home_name").unwrap_or_default().to_string() },
+            away: FeedTeam { id: get("away_id").unwrap_or_default().to_string(), name: get("away_name").unwrap_or_default().to_string() },
+            start_time: time("start_time")?.ok_or(format!("row {}: start_time is empty", row))?,
+            end_time: time("end_time")?,
+        });
+        let number = |name: &str| get(name).map(|v| v.parse::<f64>().map_err(|_| format!("row {}: {} is not a number", row, name))).transpose();
+        if let (Some(home), Some(away)) = (number("odds_home")?, number("odds_away")?) {
+            doc.odds.push(FeedOdds { fixture_id: external_id.clone(), home, away });
+        }
+        if let (Some(home), Some(away), Some(status)) = (number("home_score")?, number("away_score")?, get("result")) {
+            doc.results.push(FeedResult { fixture_id: external_id, home_score: home as i32, away_score: away as i32, status: status.to_string() });
+        }
+    }
+    Ok(doc)
+}
+
+/// Reads a JSON document ({fixtures, odds, results}) or a CSV fixture sheet on every fetch
+pub struct FileProvider {
+    name: String,
+    path: PathBuf,
+}
+
+impl FileProvider {
+    pub fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self { name: name.into(), path: path.into() }
+    }
+
+    async fn load(&self) -> Result<FeedDocument, String> {
+        let text = tokio::fs::read_to_string(&self.path).await.map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        if self.path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")) {
+            parse_csv(&text)
+        } else {
+            serde_json::from_str(&text).map_err(|e| format!("invalid feed document: {}", e))
+        }
+    }
+}
+
+#[async_trait]
+impl FeedProvider for FileProvider {
+    fn name(&self) -> &str { &self.name }
+    async fn fetch_fixtures(&self) -> Result<Vec<FeedFixture>, String> { Ok(self.load().await?.fixtures) }
+    async fn fetch_odds(&self) -> Result<Vec<FeedOdds>, String> { Ok(self.load().await?.odds) }
+    async fn fetch_results(&self) -> Result<Vec<FeedResult>, String> { Ok(self.load().await?.results) }
+}
+
+/// JSON arrays from GET {base_url}/fixtures, /odds and /results
+pub struct HttpProvider {
+    name: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl HttpProvider {
+    pub fn new(name: impl Into<String>, base_url: &str) -> Self {
+        let client = reqwest::Client::builder().timeout(HTTP_TIMEOUT).build().unwrap_or_default();
+        Self { name: name.into(), base_url: base_url.trim_end_matches('/').to_string(), client }
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        let url = format!("{}/{}", self.base_url, path);
+        let resp = self.client.get(&url).send().await.map_err(|e| format!("GET {}: {}", url, e))?;
+        if !resp.status().is_success() {
+            return Err(format!("GET {}: HTTP {}", url, resp.status().as_u16()));
+        }
+        resp.json().await.map_err(|e| format!("GET {}: {}", url, e))
+    }
+}
+
+#[async_trait]
+impl FeedProvider for HttpProvider {
+    fn name(&self) -> &str { &self.name }
+    async fn fetch_fixtures(&self) -> Result<Vec<FeedFixture>, String> { self.get("fixtures").await }
+    async fn fetch_odds(&self) -> Result<Vec<FeedOdds>, String> { self.get("odds").await }
+    async fn fetch_results(&self) -> Result<Vec<FeedResult>, String> { self.get("results").await }
+}
+
+/// Directory file providers read from (FEED_DIR, default ./feeds)
+pub fn feed_dir() -> PathBuf {
+    PathBuf::from(std::env::var("FEED_DIR").unwrap_or_else(|_| "./feeds".to_string()))
+}
+
+/// File sources are plain names inside FEED_DIR; HTTP sources are http(s) base URLs
+pub fn valid_source(kind: &str, source: &str) -> bool {
+    match kind {
+        "file" => {
+            let path = Path::new(source);
+            !source.is_empty() && path.components().all(|c| matches!(c, std::path::Component::Normal(_)))
+        }
+        "http" => (source.starts_with("http://") || source.starts_with("https://")) && !source.contains(char::is_whitespace),
+        _ => false,
+    }
+}
+
+/// Provider for a feed_providers row
+pub fn provider_for(name: &str, kind: &str, source: &str) -> Result<Box<dyn FeedProvider>, String> {
+    if !valid_source(kind, source) {
+        return Err(format!("invalid {} source {:?}", kind, source));
+    }
+    Ok(match kind {
+        "file" => Box::new(FileProvider::new(name, feed_dir().join(source))),
+        _ => Box::new(HttpProvider::new(name, source)),
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncError {
+    /// fixtures | odds | results
+    pub stage: &'static str,
+    pub external_id: Option<String>,
+    pub error: String,
+}
+
+/// Outcome of one sync run, as stored in feed_sync_runs
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncReport {
+    pub run_id: i64,
+    /// ok | partial (some items or stages failed) | error (nothing could be fetched)
+    pub status: &'static str,
+    pub fixtures: usize,
+    pub odds: usize,
+    pub results: usize,
+    pub errors: Vec<SyncError>,
+}
+
+fn odds_to_bps(decimal: f64) -> Option<i32> {
+    (decimal.is_finite() && decimal > 1.0 && decimal <= 1000.0).then(|| (decimal * 10000.0).round() as i32)
+}
+
+/// Maps provider data onto markets: fixtures upsert markets by (provider, external_id), odds
+/// changes update the market and odds_history, results go to feed_results and match_state and
+/// flag the market for settlement. Every item is written in its own transaction with its domain
+/// events, so one bad item only adds an entry to the run's error log.
+#[derive(Clone)]
+pub struct FeedIngestor {
+    pool: PgPool,
+}
+
+impl FeedIngestor {
+    pub fn new(pool: PgPool) -> Self { Self { pool } }
+
+    /// Sync one provider and record the run; `provider_id` is feed_providers.id
+    pub async fn sync(&self, provider_id: i64, provider: &dyn FeedProvider) -> Result<SyncReport, sqlx::Error> {
+        let run_id: i64 = sqlx::query_scalar("INSERT INTO feed_sync_runs (provider_id) VALUES ($1) RETURNING id")
+            .bind(provider_id)
+            .fetch_one(&self.pool)
+            .await?;
+        let name = provider.name();
+        let mut errors = Vec::new();
+        let mut failed_stages = 0;
+        let (mut fixtures, mut odds, mut results) = (0, 0, 0);
+
+        match provider.fetch_fixtures().await {
+            Ok(items) => for f in &items {
+                match self.apply_fixture(name, f).await {
+                    Ok(true) => fixtures += 1,
+                    Ok(false) => {}
+                    Err(e) => errors.push(SyncError { stage: "fixtures", external_id: Some(f.external_id.clone()), error: e }),
+                }
+            },
+            Err(e) => { failed_stages += 1; errors.push(SyncError { stage: "fixtures", external_id: None, error: e }); }
+        }
+        match provider.fetch_odds().await {
+            Ok(items) => for o in &items {
+                match self.apply_odds(name, o).await {
+                    Ok(true) => odds += 1,
+                    Ok(false) => {}
+                    Err(e) => errors.push(SyncError { stage: "odds", external_id: Some(o.fixture_id.clone()), error: e }),
+                }
+            },
+            Err(e) => { failed_stages += 1; errors.push(SyncError { stage: "odds", external_id: None, error: e }); }
+        }
+        match provider.fetch_results().await {
+            Ok(items) => for r in &items {
+                match self.apply_result(name, r).await {
+                    Ok(true) => results += 1,
+                    Ok(false) => {}
+                    Err(e) => errors.push(SyncError { stage: "results", external_id: Some(r.fixture_id.clone()), error: e }),
+                }
+            },
+            Err(e) => { failed_stages += 1; errors.push(SyncError { stage: "results", external_id: None, error: e }); }
+        }
+
+        let status = match (failed_stages, errors.is_empty()) {
+            (3, _) => "error",
+            (_, true) => "ok",
+            _ => "partial",
+        };
+        errors.truncate(MAX_RUN_ERRORS);
+        let errors_json = serde_json::to_value(&errors).unwrap_or_default();
+        sqlx::query("UPDATE feed_sync_runs SET status = $2, fixtures = $3, odds = $4, results = $5, errors = $6, finished_at = NOW() WHERE id = $1")
+            .bind(run_id).bind(status).bind(fixtures as i32).bind(odds as i32).bind(results as i32).bind(&errors_json)
+            .execute(&self.pool)
+            .await?;
+        sqlx::query(
+            "UPDATE feed_providers SET last_status = $2, last_error = $3, \
+             last_success_at = CASE WHEN $2 = 'error' THEN last_success_at ELSE NOW() END WHERE id = $1"
+        )
+        .bind(provider_id)
+        .bind(status)
+        .bind(errors.first().map(|e| format!("{}: {}", e.stage, e.error)))
+        .execute(&self.pool)
+        .await?;
+        if status != "ok" {
+            tracing::warn!(target: "kmarket_backend", "feed {}: sync {} with {} error(s)", name, status, errors.len());
+        }
+        Ok(SyncReport { run_id, status, fixtures, odds, results, errors })
+    }
+
+    /// Our name, code and catalog team for a provider team, recording the feed's name on first
+    /// sight. A mapping pointed at a catalog team takes that team's name.
+    async fn team(conn: &mut PgConnection, provider: &str, team: &FeedTeam) -> Result<(String, Option<i32>, Option<i64>), sqlx::Error> {
+        sqlx::query_as(
+            "WITH ins AS (INSERT INTO feed_team_mappings (provider, external_id, name) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING RETURNING name, code, team_id) \
+             SELECT COALESCE(t.name, x.name), x.code, x.team_id \
+             FROM (SELECT name, code, team_id FROM ins UNION ALL SELECT name, code, team_id FROM feed_team_mappings WHERE provider = $1 AND external_id = $2 LIMIT 1) x \
+             LEFT JOIN teams t ON t.id = x.team_id"
+        )
+        .bind(provider)
+        .bind(&team.id)
+        .bind(&team.name)
+        .fetch_one(&mut *conn)
+        .await
+    }
+
+    /// Create or update the fixture's market. Settled/cancelled markets are left alone.
+    /// Returns whether anything changed.
+    async fn apply_fixture(&self, provider: &str, f: &FeedFixture) -> Result<bool, String> {
+        if f.external_id.is_empty() || f.home.id.is_empty() || f.away.id.is_empty() || f.home.name.is_empty() || f.away.name.is_empty() {
+            return Err("external_id and both teams' id and name are required".into());
+        }
+        let end_time = f.end_time.unwrap_or(f.start_time + chrono::Duration::hours(DEFAULT_MATCH_HOURS));
+        if end_time <= f.start_time {
+            return Err("end_time must be after start_time".into());
+        }
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        let (home, home_code, home_team_id) = Self::team(&mut tx, provider, &f.home).await.map_err(|e| e.to_string())?;
+        let (away, away_code, away_team_id) = Self::team(&mut tx, provider, &f.away).await.map_err(|e| e.to_string())?;
+        let league_id = match &f.league {
+            Some(label) => catalog::league_by_label(&mut tx, label).await.map_err(|e| e.to_string())?,
+            None => None,
+        };
+        catalog::check_market_refs(&mut tx, MarketRefs { league_id, home_team_id, away_team_id }).await.map_err(|e| e.to_string())??;
+        let title = f.title.clone().unwrap_or_else(|| match &f.league {
+            Some(league) => format!("{}: {} vs {}", league, home, away),
+            None => format!("{} vs {}", home, away),
+        });
+        let row = sqlx::query(
+            "WITH n AS (SELECT nextval('feed_market_id_seq') AS mid) \
+             INSERT INTO markets (market_id, title, option_a, option_b, home_name, away_name, home_code, away_code, start_time, end_time, close_time, \
+                                  status, feed_provider, external_id, market_address, league_id, home_team_id, away_team_id) \
+             SELECT n.mid, $1, $2, $3, $2, $3, $4, $5, $6, $7, $7, 'pending', $8, $9, 'market_' || n.mid, $10, $11, $12 FROM n \
+             ON CONFLICT (feed_provider, external_id) WHERE external_id IS NOT NULL DO UPDATE SET \
+                 title = EXCLUDED.title, option_a = EXCLUDED.option_a, option_b = EXCLUDED.option_b, home_name = EXCLUDED.home_name, \
+                 away_name = EXCLUDED.away_name, home_code = EXCLUDED.home_code, away_code = EXCLUDED.away_code, \
+                 start_time = EXCLUDED.start_time, end_time = EXCLUDED.end_time, close_time = EXCLUDED.close_time, \
+                 league_id = EXCLUDED.league_id, home_team_id = EXCLUDED.home_team_id, away_team_id = EXCLUDED.away_team_id \
+             WHERE markets.status IN ('pending', 'active') \
+               AND (markets.title, markets.option_a, markets.option_b, markets.home_code, markets.away_code, markets.start_time, markets.end_time, \
+                    markets.league_id, markets.home_team_id, markets.away_team_id) \
+                   IS DISTINCT FROM (EXCLUDED.title, EXCLUDED.option_a, EXCLUDED.option_b, EXCLUDED.home_code, EXCLUDED.away_code, EXCLUDED.start_time, EXCLUDED.end_time, \
+                    EXCLUDED.league_id, EXCLUDED.home_team_id, EXCLUDED.away_team_id) \
+             RETURNING id, (xmax = 0) AS inserted"
+        )
+        .bind(&title).bind(&home).bind(&away).bind(home_code).bind(away_code).bind(f.start_time).bind(end_time).bind(provider).bind(&f.external_id)
+        .bind(league_id).bind(home_team_id).bind(away_team_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        let Some(row) = row else {
+            tx.commit().await.map_err(|e| e.to_string())?;
+            return Ok(false);
+        };
+        let id: i64 = row.try_get("id").map_err(|e| e.to_string())?;
+        let event = if row.try_get::<bool, _>("inserted").unwrap_or(false) {
+            DomainEvent::MarketCreated { id }
+        } else {
+            let fields = ["title", "option_a", "option_b", "home_name", "away_name", "start_time", "end_time", "close_time", "league_id", "home_team_id", "away_team_id"];
+            DomainEvent::MarketUpdated { id, fields: fields.iter().map(|f| f.to_string()).collect() }
+        };
+        outbox::enqueue(&mut *tx, &event).await.map_err(|e| e.to_string())?;
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+
+    /// Market (id, status) a provider fixture maps to, locked for update
+    async fn market_for(conn: &mut PgConnection, provider: &str, external_id: &str) -> Result<(i64, String), String> {
+        let row: Option<(i64, String)> = sqlx::query_as("SELECT id, status::TEXT FROM markets WHERE feed_provider = $1 AND external_id = $2 FOR UPDATE")
+            .bind(provider)
+            .bind(external_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        row.ok_or_else(|| "unknown fixture".to_string())
+    }
+
+    async fn apply_odds(&self, provider: &str, o: &FeedOdds) -> Result<bool, String> {
+        let (Some(home), Some(away)) = (odds_to_bps(o.home), odds_to_bps(o.away)) else {
+            return Err("odds must be decimal odds above 1.0".into());
+        };
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        let (id, status) = Self::market_for(&mut tx, provider, &o.fixture_id).await?;
+        if status != "pending" && status != "active" {
+            return Ok(false);
+        }
+        let res = sqlx::query(
+            "UPDATE markets SET odds_home_bps = $2, odds_away_bps = $3 \
+             WHERE id = $1 AND (odds_home_bps, odds_away_bps) IS DISTINCT FROM ($2, $3)"
+        )
+        .bind(id).bind(home).bind(away)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        if res.rows_affected() == 0 {
+            return Ok(false);
+        }
+        sqlx::query("INSERT INTO odds_history (market_id, source, odds_home_bps, odds_away_bps) VALUES ($1, $2, $3, $4)")
+            .bind(id).bind(provider).bind(home).bind(away)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        outbox::enqueue(&mut *tx, &DomainEvent::MarketUpdated { id, fields: vec!["odds_home_bps".into(), "odds_away_bps".into()] })
+            .await
+            .map_err(|e| e.to_string())?;
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+
+    async fn apply_result(&self, provider: &str, r: &FeedResult) -> Result<bool, String> {
+        if r.status != "final" && r.status != "cancelled" {
+            return Err("status must be final or cancelled".into());
+        }
+        if r.home_score < 0 || r.away_score < 0 {
+            return Err("scores must not be negative".into());
+        }
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        let (id, _) = Self::market_for(&mut tx, provider, &r.fixture_id).await?;
+        let changed = sqlx::query(
+            "INSERT INTO feed_results (provider, market_id, home_score, away_score, status) VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (provider, market_id) DO UPDATE SET home_score = EXCLUDED.home_score, away_score = EXCLUDED.away_score, \
+                 status = EXCLUDED.status, received_at = NOW() \
+             WHERE (feed_results.home_score, feed_results.away_score, feed_results.status) IS DISTINCT FROM (EXCLUDED.home_score, EXCLUDED.away_score, EXCLUDED.status)"
+        )
+        .bind(provider).bind(id).bind(r.home_score).bind(r.away_score).bind(&r.status)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        if changed.rows_affected() == 0 {
+            return Ok(false);
+        }
+        let update = MatchStateUpdate {
+            phase: Some(match_state::PHASE_FINISHED.to_string()),
+            home_score: Some(r.home_score),
+            away_score: Some(r.away_score),
+            ..Default::default()
+        };
+        match_state::upsert(&mut tx, id, &update, provider).await.map_err(|e| e.to_string())?;
+        sqlx::query("UPDATE markets SET attention_reason = $2, attention_at = NOW() WHERE id = $1 AND status IN ('pending', 'active')")
+            .bind(id)
+            .bind(ATTENTION_FEED_RESULT)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        outbox::enqueue(&mut *tx, &DomainEvent::MatchStateChanged { id, phase: match_state::PHASE_FINISHED.into() })
+            .await
+            .map_err(|e| e.to_string())?;
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+
+    /// Sync every enabled provider whose interval has elapsed. Providers are claimed by
+    /// stamping last_sync_at (SKIP LOCKED), so instances never sync the same provider at once.
+    pub async fn run_due(&self) -> Result<Vec<SyncReport>, sqlx::Error> {
+        let due = sqlx::query(
+            "UPDATE feed_providers SET last_sync_at = NOW() \
+             WHERE id IN (SELECT id FROM feed_providers WHERE enabled \
+                              AND (last_sync_at IS NULL OR last_sync_at + interval_secs * INTERVAL '1 second' <= NOW()) \
+                          ORDER BY last_sync_at NULLS FIRST LIMIT $1 FOR UPDATE SKIP LOCKED) \
+             RETURNING id, name, kind, source"
+        )
+        .bind(BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut reports = Vec::new();
+        for row in &due {
+            let id: i64 = row.try_get("id")?;
+            let name: String = row.try_get("name")?;
+            match provider_for(&name, &row.try_get::<String, _>("kind")?, &row.try_get::<String, _>("source")?) {
+                Ok(provider) => reports.push(self.sync(id, provider.as_ref()).await?),
+                Err(e) => {
+                    sqlx::query("UPDATE feed_providers SET last_status = 'error', last_error = $2 WHERE id = $1")
+                        .bind(id)
+                        .bind(&e)
+                        .execute(&self.pool)
+                        .await?;
+                }
+            }
+        }
+        Ok(reports)
+    }
+
+    /// Background loop: check for due providers every `interval`
+    pub fn spawn(self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_due().await {
+                    tracing::warn!(target: "kmarket_backend", "feed ingestion: {}", e);
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,139 @@
+use bigdecimal::{BigDecimal, Signed, Zero};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgConnection;
+
+use crate::utils::ledger;
+use crate::utils::money::{self, Rounding};
+
+pub const SCOPE_GLOBAL: &str = "global";
+pub const SCOPE_TIER: &str = "tier";
+pub const SCOPE_MARKET: &str = "market";
+
+pub const CHARGE_AT_PLACEMENT: &str = "placement";
+pub const CHARGE_AT_SETTLEMENT: &str = "settlement";
+
+/// Scale of the NUMERIC(10,8) fee rate columns
+pub const RATE_SCALE: i64 = 8;
+
+pub const FEE_SCHEDULE_COLUMNS: &str = "id, scope, market_id, tier, currency, stake_rate, winnings_rate, flat_fee, charge_at, enabled, created_at, updated_at";
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct FeeSchedule {
+    pub id: i64,
+    /// global | tier | market
+    pub scope: String,
+    pub market_id: Option<i64>,
+    /// users.fee_tier the schedule applies to
+    pub tier: Option<String>,
+    /// Only orders in this currency; None = any. A market schedule takes the market's currency.
+    pub currency: Option<String>,
+    /// Fraction of the stake, e.g. 0.02
+    pub stake_rate: BigDecimal,
+    /// Fraction of the winnings (payout - stake) of a winning order; always charged at settlement
+    pub winnings_rate: BigDecimal,
+    /// Amount of `currency` per order
+    pub flat_fee: BigDecimal,
+    /// When the stake and flat fees are charged: placement | settlement
+    pub charge_at: String,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Fee terms fixed on an order when it is placed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrderFees {
+    pub schedule_id: Option<i64>,
+    /// Charged at placement, out of the stake: only the rest earns the odds
+    pub paid: BigDecimal,
+    /// Stake and flat fee deferred to settlement
+    pub due: BigDecimal,
+    pub winnings_rate: BigDecimal,
+}
+
+impl FeeSchedule {
+    /// Terms for a stake of `amount`; fees are rounded down to the currency's decimals
+    pub fn terms(&self, amount: &BigDecimal, rounding: Rounding) -> OrderFees {
+        let stake_fee = rounding.round_down(&(amount * &self.stake_rate)) + rounding.round_down(&self.flat_fee);
+        let (paid, due) = if self.charge_at == CHARGE_AT_SETTLEMENT {
+            (BigDecimal::zero(), stake_fee)
+        } else {
+            (stake_fee, BigDecimal::zero())
+        };
+        OrderFees { schedule_id: Some(self.id), paid, due, winnings_rate: self.winnings_rate.clone() }
+    }
+}
+
+/// A fee rate: a fraction between 0 and 1 with at most RATE_SCALE places
+pub fn rate(v: &BigDecimal) -> Result<BigDecimal, String> {
+    if v.is_negative() || *v > BigDecimal::from(1) {
+        return Err("fee rate must be between 0 and 1".into());
+    }
+    if money::decimal_places(v) > RATE_SCALE {
+        return Err(format!("fee rate has more than {} decimal places", RATE_SCALE));
+    }
+    Ok(v.with_scale(RATE_SCALE))
+}
+
+/// The enabled schedule for an order: the market's, else the user's tier's, else the global one;
+/// within a scope a schedule for the order's currency beats one for any currency
+pub async fn resolve(conn: &mut PgConnection, market_id: i64, user_id: i64, currency: &str) -> Result<Option<FeeSchedule>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM fee_schedules f \
+         WHERE f.enabled AND (f.currency IS NULL OR f.currency = $3) AND ( \
+             (f.scope = 'market' AND f.market_id = $1) \
+             OR (f.scope = 'tier' AND f.tier = (SELECT fee_tier FROM users WHERE id = $2)) \
+             OR f.scope = 'global') \
+         ORDER BY CASE f.scope WHEN 'market' THEN 0 WHEN 'tier' THEN 1 ELSE 2 END, f.currency IS NULL \
+         LIMIT 1",
+        FEE_SCHEDULE_COLUMNS
+    ))
+    .bind(market_id)
+    .bind(user_id)
+    .bind(currency)
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+/// Fee terms of a new order; no schedule means no fees
+pub async fn for_order(conn: &mut PgConnection, market_id: i64, user_id: i64, currency: &str, amount: &BigDecimal, rounding: Rounding) -> Result<OrderFees, sqlx::Error> {
+    Ok(resolve(conn, market_id, user_id, currency).await?.map(|s| s.terms(amount, rounding)).unwrap_or_default())
+}
+
+/// Ledger entry for the fee charged when an order was placed (none when it is zero)
+pub async fn record_placement(conn: &mut PgConnection, order_id: i64, user_id: i64, currency: &str, fees: &OrderFees) -> Result<(), sqlx::Error> {
+    if fees.paid.is_zero() {
+        return Ok(());
+    }
+    let detail = serde_json::json!({"charged_at": CHARGE_AT_PLACEMENT, "fee_schedule_id": fees.schedule_id});
+    ledger::record(conn, user_id, Some(order_id), currency, ledger::KIND_FEE, &-&fees.paid, detail).await
+}
+
+/// Charge the settlement fees of just-settled orders: the deferred stake/flat fee plus the
+/// winnings rate on (close_price - amount), withheld from the payout and capped at it (a losing
+/// order owes nothing more). close_price and close_pnl become net of the fee, fee_paid grows and
+/// each charge is a ledger entry. Call before crediting the orders' PnL.
+pub async fn charge_at_settlement(conn: &mut PgConnection, order_ids: &[i64]) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "WITH f AS ( \
+             SELECT o.id, LEAST(o.close_price, o.fee_due + TRUNC(GREATEST(o.close_price - o.amount, 0) * o.fee_winnings_rate, c.decimals)) AS fee \
+             FROM orders o JOIN currencies c ON c.code = o.currency \
+             WHERE o.id = ANY($1) AND o.close_price IS NOT NULL \
+         ), charged AS ( \
+             UPDATE orders o SET close_price = o.close_price - f.fee, close_pnl = o.close_pnl - f.fee, \
+                 fee_paid = o.fee_paid + f.fee, fee_due = 0 \
+             FROM f WHERE o.id = f.id \
+             RETURNING o.id, o.user_id, o.currency, o.fee_schedule_id, f.fee \
+         ) \
+         INSERT INTO ledger_entries (user_id, order_id, currency, kind, amount, detail) \
+         SELECT user_id, id, currency, $2, -fee, jsonb_build_object('charged_at', $3::TEXT, 'fee_schedule_id', fee_schedule_id) \
+         FROM charged WHERE fee > 0"
+    )
+    .bind(order_ids)
+    .bind(ledger::KIND_FEE)
+    .bind(CHARGE_AT_SETTLEMENT)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::{header::CONTENT_TYPE, Method, StatusCode},
+    middleware::Next,
+    web, HttpResponse, ResponseError,
+};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Row};
+
+use crate::state::AppState;
+use crate::utils::{auth, rate_limit};
+use crate::utils::errors::AppError;
+
+pub const HEADER: &str = "idempotency-key";
+/// Set on a response that was replayed from an earlier request with the same key
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+pub const MAX_KEY_LEN: usize = 255;
+/// Width of idempotency_keys.scope
+const MAX_SCOPE_LEN: usize = 255;
+/// How long a completed key keeps answering retries
+pub const RETENTION_HOURS: i32 = 24;
+/// A key whose first request has not completed after this long (e.g. the instance died) is
+/// handed to the next request that uses it
+pub const IN_FLIGHT_TIMEOUT_SECS: f64 = 60.0;
+
+/// Writes whose responses carry credentials or secrets (tokens, temporary passwords, signing
+/// secrets) are never stored; a key sent to them is ignored. Partner requests are signed and
+/// carry their own nonce.
+const UNSTORED_PATHS: &[&str] = &["/admin/auth/", "/admin/admins", "/partner/"];
+const UNSTORED_CREATES: &[&str] = &["/admin/api-keys", "/admin/webhooks"];
+
+fn is_covered(req: &ServiceRequest) -> bool {
+    let path = req.path();
+    match *req.method() {
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE => {}
+        _ => return false,
+    }
+    if UNSTORED_PATHS.iter().any(|p| path.contains(p)) {
+        return false;
+    }
+    !(*req.method() == Method::POST && UNSTORED_CREATES.iter().any(|p| path.ends_with(p)))
+}
+
+/// Keys are opaque client-chosen strings, e.g. a UUID per logical operation
+fn accept(raw: &str) -> bool {
+    !raw.is_empty() && raw.len() <= MAX_KEY_LEN && raw.chars().all(|c| c.is_ascii_graphic())
+}
+
+/// Public callers have no account: their keys belong to the client IP, narrowed to the wallet
+/// the body names (order placement, claims). The wallet is unverified, so it never widens a
+/// scope beyond one IP: another client naming the same wallet cannot replay or block its keys.
+fn public_scope(req: &ServiceRequest, body: &[u8]) -> String {
+    let ip = rate_limit::client_ip(req.request());
+    let wallet = serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v.get("wallet_address")?.as_str().map(|w| w.trim().to_string()))
+        .filter(|w| !w.is_empty());
+    let scope = match wallet {
+        Some(w) => format!("ip:{}:wallet:{}", ip, w),
+        None => format!("ip:{}", ip),
+    };
+    scope.chars().take(MAX_SCOPE_LEN).collect()
+}
+
+/// Identifies the request a key was first used for
+fn fingerprint(method: &Method, path: &str, query: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(query.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+/// Responses that ask the client to try again release the key instead of being stored
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+enum Begin {
+    /// This request runs the operation; complete or release the row when it is done
+    Started(i64),
+    Replay { status: i16, content_type: Option<String>, body: Vec<u8> },
+    InProgress,
+    Mismatch,
+}
+
+/// Claim `key` for this request, or report what an earlier request with it left behind.
+/// Expired keys and abandoned in-flight ones are taken over.
+async fn begin(pool: &PgPool, scope: &str, key: &str, method: &Method, path: &str, request_hash: &str) -> Result<Begin, sqlx::Error> {
+    let started: Option<i64> = sqlx::query_scalar(
+        "INSERT INTO idempotency_keys (scope, idempotency_key, method, path, request_hash, expires_at) \
+         VALUES ($1, $2, $3, $4, $5, NOW() + make_interval(hours => $6)) \
+         ON CONFLICT (scope, idempotency_key) DO UPDATE SET method = EXCLUDED.method, path = EXCLUDED.path, \
+             request_hash = EXCLUDED.request_hash, response_status = NULL, response_content_type = NULL, response_body = NULL, \
+             created_at = NOW(), completed_at = NULL, expires_at = EXCLUDED.expires_at \
+         WHERE idempotency_keys.expires_at < NOW() \
+             OR (idempotency_keys.completed_at IS NULL AND idempotency_keys.created_at < NOW() - make_interval(secs => $7)) \
+         RETURNING id"
+    )
+    .bind(scope)
+    .bind(key)
+    .bind(method.as_str())
+    .bind(path)
+    .bind(request_hash)
+    .bind(RETENTION_HOURS)
+    .bind(IN_FLIGHT_TIMEOUT_SECS)
+    .fetch_optional(pool)
+    .await?;
+    if let Some(id) = started {
+        return Ok(Begin::Started(id));
+    }
+    let row = sqlx::query("SELECT request_hash, response_status, response_content_type, response_body FROM idempotency_keys WHERE scope = $1 AND idempotency_key = $2")
+        .bind(scope)
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+    // released between the two statements: let the client retry
+    let Some(r) = row else { return Ok(Begin::InProgress) };
+    if r.try_get::<String, _>("request_hash")? != request_hash {
+        return Ok(Begin::Mismatch);
+    }
+    Ok(match r.try_get::<Option<i16>, _>("response_status")? {
+        Some(status) => Begin::Replay {
+            status,
+            content_type: r.try_get("response_content_type")?,
+            body: r.try_get::<Option<Vec<u8>>, _>("response_body")?.unwrap_or_default(),
+        },
+        None => Begin::InProgress,
+    })
+}
+
+async fn complete(pool: &PgPool, id: i64, status: StatusCode, content_type: Option<&str>, body: &[u8]) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE idempotency_keys SET response_status = $2, response_content_type = $3, response_body = $4, completed_at = NOW() WHERE id = $1 AND completed_at IS NULL")
+        .bind(id)
+        .bind(status.as_u16() as i16)
+        .bind(content_type)
+        .bind(body)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+async fn release(pool: &PgPool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM idempotency_keys WHERE id = $1 AND completed_at IS NULL")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+fn replay(status: i16, content_type: Option<String>, body: Vec<u8>) -> HttpResponse {
+    let mut builder = HttpResponse::build(StatusCode::from_u16(status as u16).unwrap_or(StatusCode::OK));
+    if let Some(ct) = content_type {
+        builder.insert_header((CONTENT_TYPE, ct));
+    }
+    builder.insert_header((REPLAYED_HEADER, "true")).body(body)
+}
+
+/// Middleware for API writes: a request carrying an Idempotency-Key runs once per key and
+/// caller (each admin; on the public API each client IP, and wallet within it). A retry with the same key
+/// and the same method, path, query and body gets the stored response with
+/// `Idempotent-Replayed: true`; the same key on a different request is rejected, as is a retry
+/// while the first is still running.
+/// Requests without the header are untouched.
+pub async fn handle(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let Some(raw) = req.headers().get(HEADER).cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    };
+    let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    };
+    if !is_covered(&req) {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    }
+    let key = match raw.to_str().map(str::trim) {
+        Ok(k) if accept(k) => k.to_string(),
+        _ => {
+            let e = AppError::bad_request("invalid_idempotency_key", format!("Idempotency-Key must be 1-{} printable ASCII characters", MAX_KEY_LEN));
+            return Ok(req.into_response(e.error_response()));
+        }
+    };
+    let pool = &state.db_pool;
+    let body = req.extract::<web::Bytes>().await?;
+    let (method, path) = (req.method().clone(), req.path().to_string());
+    let request_hash = fingerprint(&method, &path, req.query_string(), &body);
+    req.set_payload(Payload::from(body.clone()));
+    // an admin's keys are their own; unauthenticated admin requests fall through to the handler's 401
+    let scope = if req.path().contains("/admin/") {
+        match auth::admin_actor_id(req.request(), pool).await {
+            Ok(actor_id) => format!("admin:{}", actor_id),
+            Err(_) => return next.call(req).await.map(ServiceResponse::map_into_boxed_body),
+        }
+    } else {
+        public_scope(&req, &body)
+    };
+
+    let id = match begin(pool, &scope, &key, &method, &path, &request_hash).await.map_err(AppError::from)? {
+        Begin::Started(id) => id,
+        Begin::Replay { status, content_type, body } => return Ok(req.into_response(replay(status, content_type, body))),
+        Begin::InProgress => {
+            let e = AppError::conflict("idempotency_key_in_progress", "a request with this Idempotency-Key is still being processed; retry later");
+            return Ok(req.into_response(e.error_response()));
+        }
+        Begin::Mismatch => {
+            let e = AppError::conflict("idempotency_key_reused", "Idempotency-Key was already used for a different request");
+            return Ok(req.into_response(e.error_response()));
+        }
+    };
+    // Expired keys no longer answer retries; prune occasionally
+    if rand::thread_rng().gen_ratio(1, 100) {
+        let _ = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at < NOW()").execute(pool).await;
+    }
+
+    let resp = match next.call(req).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            let _ = release(pool, id).await;
+            return Err(e);
+        }
+    };
+    if is_retryable(resp.status()) {
+        if let Err(e) = release(pool, id).await {
+            tracing::warn!(target: "kmarket_backend", "idempotency: releasing key {}: {}", id, e);
+        }
+        return Ok(resp.map_into_boxed_body());
+    }
+    let (http_req, res) = resp.into_parts();
+    let (res, res_body) = res.into_parts();
+    let bytes = match body::to_bytes(res_body).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let _ = release(pool, id).await;
+            let e: Box<dyn std::error::Error> = e.into();
+            return Err(AppError::internal(e).into());
+        }
+    };
+    let content_type = res.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(str::to_string);
+    if let Err(e) = complete(pool, id, res.status(), content_type.as_deref(), &bytes).await {
+        tracing::warn!(target: "kmarket_backend", "idempotency: storing response for key {}: {}", id, e);
+    }
+    Ok(ServiceResponse::new(http_req, res.set_body(bytes)).map_into_boxed_body())
+}
@@ -0,0 +1,30 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgConnection;
+
+/// A fee charged on an order (negative)
+pub const KIND_FEE: &str = "fee";
+/// A claimed payout (positive); credited to the balance unless paid out on-chain
+pub const KIND_PAYOUT: &str = "payout";
+
+/// Append a ledger entry. `amount` is signed from the user's side: debits negative, credits
+/// positive. Pass the transaction that moves the money so the two commit together.
+pub async fn record(
+    conn: &mut PgConnection,
+    user_id: i64,
+    order_id: Option<i64>,
+    currency: &str,
+    kind: &str,
+    amount: &BigDecimal,
+    detail: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO ledger_entries (user_id, order_id, currency, kind, amount, detail) VALUES ($1, $2, $3, $4, $5, $6)")
+        .bind(user_id)
+        .bind(order_id)
+        .bind(currency)
+        .bind(kind)
+        .bind(amount)
+        .bind(detail)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
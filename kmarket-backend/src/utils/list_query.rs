@@ -0,0 +1,183 @@
+use actix_web::HttpResponse;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
+
+use crate::utils::pagination::{self, Cursor, PageMode};
+use crate::utils::response::ApiResponse;
+
+/// Bind value of a list filter
+#[derive(Debug, Clone)]
+pub enum Arg {
+    Text(String),
+    Int(i64),
+    Int4(i32),
+    Bool(bool),
+    Time(DateTime<Utc>),
+}
+
+impl From<String> for Arg { fn from(v: String) -> Self { Arg::Text(v) } }
+impl From<&str> for Arg { fn from(v: &str) -> Self { Arg::Text(v.to_string()) } }
+impl From<&String> for Arg { fn from(v: &String) -> Self { Arg::Text(v.clone()) } }
+impl From<i64> for Arg { fn from(v: i64) -> Self { Arg::Int(v) } }
+impl From<i32> for Arg { fn from(v: i32) -> Self { Arg::Int4(v) } }
+impl From<bool> for Arg { fn from(v: bool) -> Self { Arg::Bool(v) } }
+impl From<DateTime<Utc>> for Arg { fn from(v: DateTime<Utc>) -> Self { Arg::Time(v) } }
+
+fn push_arg(qb: &mut QueryBuilder<'_, Postgres>, arg: &Arg) {
+    match arg {
+        Arg::Text(v) => qb.push_bind(v.clone()),
+        Arg::Int(v) => qb.push_bind(*v),
+        Arg::Int4(v) => qb.push_bind(*v),
+        Arg::Bool(v) => qb.push_bind(*v),
+        Arg::Time(v) => qb.push_bind(*v),
+    };
+}
+
+/// Push `template`, binding `args` at its `{}` placeholders in order. A single argument is bound
+/// at every placeholder, e.g. `(title ILIKE {} OR home_team ILIKE {})`.
+pub fn push_template(qb: &mut QueryBuilder<'_, Postgres>, template: &str, args: &[Arg]) {
+    for (i, part) in template.split("{}").enumerate() {
+        if i > 0 {
+            if let Some(arg) = args.get(i - 1).or_else(|| args.first().filter(|_| args.len() == 1)) {
+                push_arg(qb, arg);
+            }
+        }
+        qb.push(part);
+    }
+}
+
+/// Column clients may sort by with `sort=<name>`. Cursor paging needs a non-null timestamp
+/// column (`keyset`); other fields page by offset only.
+pub struct SortField {
+    pub name: &'static str,
+    pub column: &'static str,
+    pub keyset: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sort {
+    pub column: &'static str,
+    pub desc: bool,
+}
+
+impl Sort {
+    /// Resolve `sort` / `order` (asc | desc, default desc) against a whitelist whose first
+    /// entry is the default field
+    pub fn parse(fields: &'static [SortField], sort: Option<&str>, order: Option<&str>, mode: &PageMode) -> Result<Self, HttpResponse> {
+        let invalid = |msg: String| HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_sort", &msg));
+        let field = match sort.map(str::trim).filter(|s| !s.is_empty()) {
+            None => &fields[0],
+            Some(name) => match fields.iter().find(|f| f.name == name) {
+                Some(f) => f,
+                None => {
+                    let names: Vec<&str> = fields.iter().map(|f| f.name).collect();
+                    return Err(invalid(format!("sort must be one of: {}", names.join(", "))));
+                }
+            },
+        };
+        let desc = match order.map(|o| o.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("desc") => true,
+            Some("asc") => false,
+            Some(_) => return Err(invalid("order must be asc or desc".into())),
+        };
+        if matches!(mode, PageMode::After(_)) && !field.keyset {
+            return Err(invalid(format!("cursor paging does not support sort={}", field.name)));
+        }
+        Ok(Self { column: field.column, desc })
+    }
+}
+
+/// One page of a list query
+pub struct ListPage {
+    pub rows: Vec<PgRow>,
+    /// None unless a total was requested
+    pub total: Option<i64>,
+    pub next_cursor: Option<String>,
+}
+
+/// Filtered, sorted, paginated SELECT over a table or join. Filters are declared once and
+/// pushed into both the COUNT and the page query with their binds, so the two cannot disagree.
+pub struct ListQuery {
+    columns: String,
+    from: String,
+    /// Unique column breaking sort ties (and the id half of keyset cursors)
+    id_column: &'static str,
+    filters: Vec<(String, Vec<Arg>)>,
+}
+
+impl ListQuery {
+    pub fn new(columns: impl Into<String>, from: impl Into<String>, id_column: &'static str) -> Self {
+        Self { columns: columns.into(), from: from.into(), id_column, filters: Vec::new() }
+    }
+
+    /// AND a condition; see push_template for the placeholders
+    pub fn filter(&mut self, template: impl Into<String>, arg: impl Into<Arg>) -> &mut Self {
+        self.filters.push((template.into(), vec![arg.into()]));
+        self
+    }
+
+    /// `filter` when the value is present
+    pub fn filter_opt<T: Into<Arg>>(&mut self, template: &str, arg: Option<T>) -> &mut Self {
+        if let Some(arg) = arg { self.filter(template, arg); }
+        self
+    }
+
+    /// AND a condition without binds
+    pub fn condition(&mut self, sql: impl Into<String>) -> &mut Self {
+        self.filters.push((sql.into(), Vec::new()));
+        self
+    }
+
+    fn push_where(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        qb.push(" WHERE TRUE");
+        for (template, args) in &self.filters {
+            qb.push(" AND (");
+            push_template(qb, template, args);
+            qb.push(")");
+        }
+    }
+
+    pub fn count_query(&self) -> QueryBuilder<'_, Postgres> {
+        let mut qb = QueryBuilder::new(format!("SELECT COUNT(*) FROM {}", self.from));
+        self.push_where(&mut qb);
+        qb
+    }
+
+    /// Keyset pages also select the sort key and id as list_sort_at / list_sort_id for the cursor
+    pub fn page_query(&self, sort: Sort, limit: i64, mode: &PageMode) -> QueryBuilder<'_, Postgres> {
+        let mut qb = QueryBuilder::new(format!("SELECT {}", self.columns));
+        if let PageMode::After(_) = mode {
+            qb.push(format!(", {} AS list_sort_at, {} AS list_sort_id", sort.column, self.id_column));
+        }
+        qb.push(format!(" FROM {}", self.from));
+        self.push_where(&mut qb);
+        if let PageMode::After(Some(c)) = mode {
+            qb.push(format!(" AND ({}, {}) {} (", sort.column, self.id_column, if sort.desc { "<" } else { ">" }));
+            qb.push_bind(c.at).push(", ").push_bind(c.id).push(")");
+        }
+        let dir = if sort.desc { "DESC" } else { "ASC" };
+        qb.push(format!(" ORDER BY {} {} NULLS LAST, {} {}", sort.column, dir, self.id_column, dir));
+        match mode {
+            PageMode::Offset { offset, .. } => { qb.push(" LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(*offset); }
+            PageMode::After(_) => { qb.push(" LIMIT ").push_bind(limit + 1); }
+        }
+        qb
+    }
+
+    pub async fn fetch(&self, pool: &PgPool, sort: Sort, limit: i64, mode: &PageMode, with_total: bool) -> Result<ListPage, sqlx::Error> {
+        let total = if with_total {
+            Some(self.count_query().build_query_scalar::<i64>().fetch_one(pool).await?)
+        } else {
+            None
+        };
+        let mut rows = self.page_query(sort, limit, mode).build().fetch_all(pool).await?;
+        let next_cursor = match mode {
+            PageMode::After(_) => pagination::next_cursor(&mut rows, limit, |r| {
+                Some(Cursor { at: r.try_get("list_sort_at").ok()?, id: r.try_get("list_sort_id").ok()? })
+            }),
+            PageMode::Offset { .. } => None,
+        };
+        Ok(ListPage { rows, total, next_cursor })
+    }
+}
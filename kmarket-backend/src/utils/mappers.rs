@@ -1,5 +1,6 @@
 use crate::models::dto::{FrontendMarket, FrontendPosition};
 use crate::models::market::Market;
+use bigdecimal::{BigDecimal, Zero};
 use chrono::{DateTime, Utc};
 use sqlx::types::chrono;
 
@@ -18,16 +19,18 @@ pub fn map_market_to_frontend(m: &Market) -> FrontendMarket {
         result: match m.winning_option { Some(0) => 1, Some(1) => 2, _ => 0 },
         odds_home_bps: None,
         odds_away_bps: None,
-        max_exposure: 0.0,
-        current_exposure: 0.0,
-        total_volume: 0.0,
+        max_exposure: BigDecimal::zero(),
+        current_exposure: BigDecimal::zero(),
+        total_volume: BigDecimal::zero(),
         total_bets: 0,
+        currency: m.currency.clone(),
         created_at: m.created_at,
         updated_at: m.updated_at,
         resolved_at: None,
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn map_position_row_to_frontend(
     id: i64,
     user_id: i64,
@@ -36,9 +39,12 @@ pub fn map_position_row_to_frontend(
     market_address: Option<String>,
     nonce: i64,
     selected_team: i32,
-    amount: f64,
+    amount: BigDecimal,
+    currency: String,
     multiplier_bps: i32,
     status: i32,
+    is_claimed: bool,
+    fee_paid: BigDecimal,
     timestamp: DateTime<Utc>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
@@ -54,14 +60,15 @@ pub fn map_position_row_to_frontend(
         position_type: "OPEN".into(),
         selected_team,
         amount,
+        currency,
         multiplier_bps,
         odds_home_bps: None,
         odds_away_bps: None,
         payout_expected: None,
         status,
-        is_claimed: false,
-        pnl: 0.0,
-        fee_paid: 0.0,
+        is_claimed,
+        pnl: BigDecimal::zero(),
+        fee_paid,
         close_price: None,
         close_pnl: None,
         timestamp,
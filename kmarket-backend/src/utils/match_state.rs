@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+
+/// match_state.phase values; live and half_time both show the fixture as live
+pub const MATCH_PHASES: [&str; 4] = ["pre", "live", "half_time", "finished"];
+pub const PHASE_LIVE: &str = "live";
+pub const PHASE_FINISHED: &str = "finished";
+
+/// A match_state row, serialized as the fixtures API `liveStats` object
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchState {
+    #[serde(skip)]
+    pub market_id: i64,
+    pub phase: String,
+    pub period: i32,
+    pub clock_seconds: i32,
+    pub home_score: i32,
+    pub away_score: i32,
+    pub home_red_cards: i32,
+    pub away_red_cards: i32,
+    pub source: String,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Partial update: fields left out keep their stored value (or the column default on first write)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MatchStateUpdate {
+    pub phase: Option<String>,
+    pub period: Option<i32>,
+    /// Match clock as displayed for the sport (football counts up across the match)
+    pub clock_seconds: Option<i32>,
+    pub home_score: Option<i32>,
+    pub away_score: Option<i32>,
+    pub home_red_cards: Option<i32>,
+    pub away_red_cards: Option<i32>,
+}
+
+impl MatchStateUpdate {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.phase.as_deref().is_some_and(|p| !MATCH_PHASES.contains(&p)) {
+            return Err(format!("phase must be one of {:?}", MATCH_PHASES));
+        }
+        let counts = [self.period, self.clock_seconds, self.home_score, self.away_score, self.home_red_cards, self.away_red_cards];
+        if counts.iter().flatten().any(|v| *v < 0) {
+            return Err("period, clock, scores and cards must not be negative".into());
+        }
+        Ok(())
+    }
+}
+
+/// Write a market's match state and touch markets.updated_at so the fixture stream sends the
+/// change. Returns None if the market does not exist. Call validate() first.
+pub async fn upsert(conn: &mut PgConnection, market_pk: i64, update: &MatchStateUpdate, source: &str) -> Result<Option<MatchState>, sqlx::Error> {
+    let state = sqlx::query_as::<_, MatchState>(
+        "INSERT INTO match_state (market_id, phase, period, clock_seconds, home_score, away_score, home_red_cards, away_red_cards, source) \
+         SELECT id, COALESCE($2, 'pre'), COALESCE($3, 0), COALESCE($4, 0), COALESCE($5, 0), COALESCE($6, 0), COALESCE($7, 0), COALESCE($8, 0), $9 \
+         FROM markets WHERE id = $1 \
+         ON CONFLICT (market_id) DO UPDATE SET phase = COALESCE($2, match_state.phase), period = COALESCE($3, match_state.period), \
+             clock_seconds = COALESCE($4, match_state.clock_seconds), home_score = COALESCE($5, match_state.home_score), \
+             away_score = COALESCE($6, match_state.away_score), home_red_cards = COALESCE($7, match_state.home_red_cards), \
+             away_red_cards = COALESCE($8, match_state.away_red_cards), source = $9 \
+         RETURNING market_id, phase, period, clock_seconds, home_score, away_score, home_red_cards, away_red_cards, source, updated_at"
+    )
+    .bind(market_pk)
+    .bind(update.phase.as_deref())
+    .bind(update.period)
+    .bind(update.clock_seconds)
+    .bind(update.home_score)
+    .bind(update.away_score)
+    .bind(update.home_red_cards)
+    .bind(update.away_red_cards)
+    .bind(source)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if state.is_some() {
+        sqlx::query("UPDATE markets SET updated_at = NOW() WHERE id = $1")
+            .bind(market_pk)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(state)
+}
@@ -0,0 +1,69 @@
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+/// Issuer shown in authenticator apps
+pub const TOTP_ISSUER: &str = "KMarket";
+/// TOTP time step in seconds (RFC 6238 default)
+pub const TOTP_STEP: u64 = 30;
+/// Number of recovery codes issued on activation
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Whether every admin must have TOTP enabled before using admin endpoints (ADMIN_MFA_REQUIRED=true)
+pub fn mfa_required() -> bool {
+    std::env::var("ADMIN_MFA_REQUIRED")
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Generate a new random TOTP secret, base32 encoded (160 bits)
+pub fn generate_secret() -> String {
+    match Secret::generate_secret().to_encoded() {
+        Secret::Encoded(s) => s,
+        Secret::Raw(_) => unreachable!("to_encoded returns Encoded"),
+    }
+}
+
+fn build_totp(secret_b32: &str, account: &str) -> Option<TOTP> {
+    let bytes = Secret::Encoded(secret_b32.to_string()).to_bytes().ok()?;
+    // skew is handled by verify_code so the matched step can be reported
+    TOTP::new(Algorithm::SHA1, 6, 0, TOTP_STEP, bytes, Some(TOTP_ISSUER.to_string()), account.to_string()).ok()
+}
+
+/// otpauth:// URI for enrollment (QR code content)
+pub fn otpauth_uri(secret_b32: &str, account: &str) -> Option<String> {
+    build_totp(secret_b32, account).map(|t| t.get_url())
+}
+
+/// Generate the code for a unix timestamp (used by tests and tooling)
+pub fn generate_code(secret_b32: &str, unix_time: u64) -> Option<String> {
+    build_totp(secret_b32, "").map(|t| t.generate(unix_time))
+}
+
+/// Verify a 6-digit code allowing one step of clock skew.
+/// Returns the matched time step so callers can reject replays (step <= last used step).
+pub fn verify_code(secret_b32: &str, code: &str, unix_time: u64) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) { return None; }
+    let totp = build_totp(secret_b32, "")?;
+    let current = unix_time / TOTP_STEP;
+    for step in [current.saturating_sub(1), current, current + 1] {
+        if totp.check(code, step * TOTP_STEP) { return Some(step as i64); }
+    }
+    None
+}
+
+/// Generate human-friendly one-time recovery codes (xxxxx-xxxxx)
+pub fn generate_recovery_codes(n: usize) -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..n).map(|_| {
+        let raw: String = (&mut rng).sample_iter(&Alphanumeric).take(10).map(char::from).collect::<String>().to_lowercase();
+        format!("{}-{}", &raw[..5], &raw[5..])
+    }).collect()
+}
+
+/// Recovery codes are high entropy, so a plain SHA-256 is sufficient for storage
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized = code.trim().to_lowercase();
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
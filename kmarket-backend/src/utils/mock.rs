@@ -47,9 +47,6 @@ pub async fn insert_mock_order(pool: &PgPool, user_id: i64, market_id: i64) -> R
 }
 
 pub async fn cleanup_all(pool: &PgPool) -> Result<(), sqlx::Error> {
-    sqlx::query("DELETE FROM order_audits;")
-        .execute(pool)
-        .await?;
     sqlx::query("DELETE FROM orders;")
         .execute(pool)
         .await?;
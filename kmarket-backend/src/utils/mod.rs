@@ -3,4 +3,26 @@ pub mod health;
 pub mod mock;
 pub mod response;
 pub mod mappers;
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod mfa;
+pub mod rate_limit;
+pub mod api_keys;
+pub mod audit;
+pub mod realtime;
+pub mod outbox;
+pub mod webhooks;
+pub mod scheduler;
+pub mod simulator;
+pub mod match_state;
+pub mod feeds;
+pub mod catalog;
+pub mod cache;
+pub mod conditional;
+pub mod pagination;
+pub mod list_query;
+pub mod request_id;
+pub mod money;
+pub mod currency;
+pub mod ledger;
+pub mod fees;
+pub mod idempotency;
@@ -0,0 +1,157 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bigdecimal::{BigDecimal, Signed, Zero};
+use serde::{de, Deserializer};
+
+/// Scale of the NUMERIC(38,18) amount columns (stakes, payouts, PnL, balances)
+pub const AMOUNT_SCALE: i64 = 18;
+/// Scale of the NUMERIC(18,8) odds column
+pub const ODDS_SCALE: i64 = 8;
+
+/// Rounding rule for amounts of one currency. Client-supplied amounts must already fit the
+/// currency's decimals (they are rejected, never rounded); computed payouts are rounded toward
+/// zero, so the ledger never credits a fraction the currency cannot represent.
+/// Currencies' decimals live in the `currencies` table (see `currency::Currency::rounding`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rounding {
+    pub decimals: i64,
+}
+
+impl Rounding {
+    /// Unit-less amounts: the full column scale
+    pub const DEFAULT: Rounding = Rounding { decimals: AMOUNT_SCALE };
+
+    pub fn new(decimals: i16) -> Self {
+        Self { decimals: i64::from(decimals) }
+    }
+
+    /// A stake: positive and within the currency's decimals
+    pub fn stake(&self, v: &BigDecimal) -> Result<BigDecimal, String> {
+        if !v.is_positive() {
+            return Err("amount must be positive".into());
+        }
+        self.exact(v)
+    }
+
+    /// A non-negative amount (e.g. an admin-entered close price) within the currency's decimals
+    pub fn amount(&self, v: &BigDecimal) -> Result<BigDecimal, String> {
+        if v.is_negative() {
+            return Err("amount must not be negative".into());
+        }
+        self.exact(v)
+    }
+
+    fn exact(&self, v: &BigDecimal) -> Result<BigDecimal, String> {
+        if decimal_places(v) > self.decimals {
+            return Err(format!("amount has more than {} decimal places", self.decimals));
+        }
+        Ok(v.with_scale(self.decimals))
+    }
+
+    /// Round a computed amount toward zero to the currency's decimals. Same as SQL `TRUNC(v, decimals)`.
+    pub fn round_down(&self, v: &BigDecimal) -> BigDecimal {
+        v.with_scale(self.decimals)
+    }
+}
+
+/// Significant decimal places of `v` (0 for integers)
+pub fn decimal_places(v: &BigDecimal) -> i64 {
+    if v.is_zero() {
+        return 0;
+    }
+    v.normalized().as_bigint_and_exponent().1.max(0)
+}
+
+/// Decimal odds: positive with at most ODDS_SCALE places
+pub fn odds(v: &BigDecimal) -> Result<BigDecimal, String> {
+    if !v.is_positive() {
+        return Err("odds must be positive".into());
+    }
+    if decimal_places(v) > ODDS_SCALE {
+        return Err(format!("odds have more than {} decimal places", ODDS_SCALE));
+    }
+    Ok(v.with_scale(ODDS_SCALE))
+}
+
+/// Decimal odds of a basis-point multiplier, exactly (18500 -> 1.85)
+pub fn odds_from_bps(bps: i32) -> BigDecimal {
+    BigDecimal::new(bps.into(), 4)
+}
+
+/// Outcome of settling one stake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settlement {
+    /// Credited to the user: stake * odds rounded down for a win, zero for a loss
+    pub payout: BigDecimal,
+    /// payout - stake, exactly
+    pub pnl: BigDecimal,
+}
+
+pub fn settle(amount: &BigDecimal, odds: &BigDecimal, won: bool, rounding: Rounding) -> Settlement {
+    let payout = if won { rounding.round_down(&(amount * odds)) } else { BigDecimal::zero().with_scale(rounding.decimals) };
+    let pnl = &payout - amount;
+    Settlement { payout, pnl }
+}
+
+/// `deserialize_with` for request amounts: a decimal string (preferred) or a JSON number.
+/// Numbers are read through their shortest representation, so `0.1` is exactly 0.1.
+pub fn lenient<'de, D: Deserializer<'de>>(d: D) -> Result<BigDecimal, D::Error> {
+    d.deserialize_any(LenientVisitor)
+}
+
+/// `lenient` for optional fields; null or absent is None
+pub fn lenient_opt<'de, D: Deserializer<'de>>(d: D) -> Result<Option<BigDecimal>, D::Error> {
+    d.deserialize_option(OptionalVisitor)
+}
+
+struct LenientVisitor;
+
+impl<'de> de::Visitor<'de> for LenientVisitor {
+    type Value = BigDecimal;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a decimal string or number")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<BigDecimal, E> {
+        BigDecimal::from_str(v.trim()).map_err(|_| E::custom(format!("invalid decimal: {}", v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<BigDecimal, E> {
+        Ok(BigDecimal::from(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<BigDecimal, E> {
+        Ok(BigDecimal::from(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<BigDecimal, E> {
+        if !v.is_finite() {
+            return Err(E::custom("invalid decimal"));
+        }
+        self.visit_str(&v.to_string())
+    }
+}
+
+struct OptionalVisitor;
+
+impl<'de> de::Visitor<'de> for OptionalVisitor {
+    type Value = Option<BigDecimal>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a decimal string, number or null")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        lenient(d).map(Some)
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use sqlx::{PgExecutor, PgPool, Row};
+
+use crate::models::event::{DomainEvent, OutboxEvent};
+
+/// Events claimed per dispatcher pass
+const BATCH_SIZE: i64 = 100;
+/// A claimed event is redelivered if the dispatcher dies before recording the outcome
+const CLAIM_LEASE_SECS: i64 = 60;
+/// After this many failed deliveries the event is parked as 'failed'
+pub const MAX_ATTEMPTS: i32 = 10;
+
+/// Write an event to outbox_events. Pass the transaction that makes the change, so the event
+/// exists if and only if the change commits.
+pub async fn enqueue<'e, E: PgExecutor<'e>>(executor: E, event: &DomainEvent) -> Result<i64, sqlx::Error> {
+    let (aggregate_type, aggregate_id) = event.aggregate();
+    let payload = serde_json::to_value(event).map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+    sqlx::query_scalar("INSERT INTO outbox_events (event_type, aggregate_type, aggregate_id, payload) VALUES ($1, $2, $3, $4) RETURNING id")
+        .bind(event.event_type())
+        .bind(aggregate_type)
+        .bind(aggregate_id)
+        .bind(payload)
+        .fetch_one(executor)
+        .await
+}
+
+/// Seconds before retry n (1-based): 2, 4, 8 ... capped at 5 minutes
+pub fn retry_delay_secs(attempts: i32) -> i64 {
+    2i64.saturating_pow(attempts.clamp(1, 30) as u32).min(300)
+}
+
+/// In-process consumer of domain events. Delivery is at-least-once: an event is retried to
+/// the subscribers that have not handled it (outbox_events.delivered_to, by name) until all of
+/// them succeed, so handlers must be idempotent. Names must be unique per dispatcher.
+#[async_trait]
+pub trait EventSubscriber: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), String>;
+}
+
+#[derive(Clone)]
+pub struct OutboxDispatcher {
+    pool: PgPool,
+    subscribers: Vec<Arc<dyn EventSubscriber>>,
+}
+
+impl OutboxDispatcher {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool, subscribers: Vec::new() }
+    }
+
+    pub fn subscribe(mut self, subscriber: Arc<dyn EventSubscriber>) -> Self {
+        self.subscribers.push(subscriber);
+        self
+    }
+
+    /// Claim due events (skipping rows another dispatcher holds), deliver them in id order and
+    /// record the outcome. Returns the number of events claimed.
+    pub async fn run_once(&self) -> Result<usize, sqlx::Error> {
+        let rows = sqlx::query(
+            "UPDATE outbox_events SET attempts = attempts + 1, next_attempt_at = NOW() + $2 * INTERVAL '1 second' \
+             WHERE id IN (SELECT id FROM outbox_events WHERE status = 'pending' AND next_attempt_at <= NOW() \
+                          ORDER BY id LIMIT $1 FOR UPDATE SKIP LOCKED) \
+             RETURNING id, payload, attempts, created_at, delivered_to"
+        )
+        .bind(BATCH_SIZE)
+        .bind(CLAIM_LEASE_SECS)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut events: Vec<(i64, Vec<String>, Result<OutboxEvent, String>)> = rows.into_iter().map(|r| {
+            let id: i64 = r.try_get("id").unwrap_or_default();
+            let delivered_to: Vec<String> = r.try_get("delivered_to").unwrap_or_default();
+            let event = serde_json::from_value::<DomainEvent>(r.try_get("payload").unwrap_or_default())
+                .map(|event| OutboxEvent {
+                    id,
+                    event,
+                    attempts: r.try_get("attempts").unwrap_or_default(),
+                    created_at: r.try_get("created_at").unwrap_or_else(|_| chrono::Utc::now()),
+                })
+                .map_err(|e| format!("undecodable payload: {}", e));
+            (id, delivered_to, event)
+        }).collect();
+        events.sort_by_key(|(id, _, _)| *id);
+        let claimed = events.len();
+
+        for (id, mut delivered_to, event) in events {
+            let outcome = match event {
+                Ok(event) => self.deliver(&event, &mut delivered_to).await.map_err(|e| (e, event.attempts)),
+                // a payload this build cannot read will not get better with retries
+                Err(e) => Err((e, MAX_ATTEMPTS)),
+            };
+            match outcome {
+                Ok(()) => {
+                    sqlx::query("UPDATE outbox_events SET status = 'delivered', delivered_at = NOW(), last_error = NULL, delivered_to = $2 WHERE id = $1")
+                        .bind(id)
+                        .bind(&delivered_to)
+                        .execute(&self.pool)
+                        .await?;
+                }
+                Err((error, attempts)) => {
+                    let status = if attempts >= MAX_ATTEMPTS { "failed" } else { "pending" };
+                    tracing::warn!(target: "kmarket_backend", "outbox event {} delivery failed (attempt {}): {}", id, attempts, error);
+                    sqlx::query("UPDATE outbox_events SET status = $2, last_error = $3, next_attempt_at = NOW() + $4 * INTERVAL '1 second', delivered_to = $5 WHERE id = $1")
+                        .bind(id)
+                        .bind(status)
+                        .bind(error)
+                        .bind(retry_delay_secs(attempts))
+                        .bind(&delivered_to)
+                        .execute(&self.pool)
+                        .await?;
+                }
+            }
+        }
+        Ok(claimed)
+    }
+
+    /// Hand the event to each subscriber not yet in `delivered_to`, adding those that succeed
+    async fn deliver(&self, event: &OutboxEvent, delivered_to: &mut Vec<String>) -> Result<(), String> {
+        let mut errors = Vec::new();
+        for s in &self.subscribers {
+            if delivered_to.iter().any(|name| name == s.name()) {
+                continue;
+            }
+            match s.handle(event).await {
+                Ok(()) => delivered_to.push(s.name().to_string()),
+                Err(e) => errors.push(format!("{}: {}", s.name(), e)),
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors.join("; ")) }
+    }
+
+    /// Background loop: drain due events, then wait `idle` before polling again
+    pub fn spawn(self, idle: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match self.run_once().await {
+                    Ok(n) if n > 0 => continue,
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!(target: "kmarket_backend", "outbox dispatcher: {}", e),
+                }
+                tokio::time::sleep(idle).await;
+            }
+        })
+    }
+}
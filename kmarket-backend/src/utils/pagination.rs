@@ -0,0 +1,76 @@
+use actix_web::HttpResponse;
+use chrono::{DateTime, Utc};
+
+use crate::utils::response::ApiResponse;
+
+/// Keyset position: sort timestamp and id of the last row of a page. Lists are ordered
+/// newest first, so the next page holds rows strictly below `(at, id)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub at: DateTime<Utc>,
+    pub id: i64,
+}
+
+impl Cursor {
+    /// Opaque to clients; microsecond precision matches TIMESTAMPTZ so no row is skipped
+    pub fn encode(&self) -> String {
+        hex::encode(format!("{}:{}", self.at.timestamp_micros(), self.id))
+    }
+
+    pub fn decode(s: &str) -> Option<Self> {
+        let raw = String::from_utf8(hex::decode(s).ok()?).ok()?;
+        let (micros, id) = raw.split_once(':')?;
+        let at = DateTime::<Utc>::from_timestamp_micros(micros.parse().ok()?)?;
+        Some(Self { at, id: id.parse().ok()? })
+    }
+}
+
+/// How a list request pages: `page` / `limit` with OFFSET (the default), or keyset after a cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageMode {
+    Offset { page: i64, offset: i64 },
+    /// None for the first page
+    After(Option<Cursor>),
+}
+
+impl PageMode {
+    /// Keyset mode when `cursor` is given (an empty value starts at the first page)
+    pub fn from_query(page: Option<i64>, limit: i64, cursor: Option<&str>) -> Result<Self, HttpResponse> {
+        match cursor.map(str::trim) {
+            None => {
+                let page = page.unwrap_or(1).max(1);
+                Ok(Self::Offset { page, offset: (page - 1) * limit })
+            }
+            Some("") => Ok(Self::After(None)),
+            Some(c) => Cursor::decode(c)
+                .map(|c| Self::After(Some(c)))
+                .ok_or_else(|| HttpResponse::BadRequest().json(ApiResponse::<()>::error("invalid_cursor", "cursor invalid"))),
+        }
+    }
+
+    /// A total costs a COUNT(*) over the whole filter: on by default for offset pages,
+    /// opt-in (`with_total=true`) for cursor pages
+    pub fn wants_total(&self, with_total: Option<bool>) -> bool {
+        with_total.unwrap_or(matches!(self, Self::Offset { .. }))
+    }
+}
+
+/// Trim a keyset page fetched with LIMIT limit + 1 and return the next page's cursor, if any
+pub fn next_cursor<T>(rows: &mut Vec<T>, limit: i64, key: impl Fn(&T) -> Option<Cursor>) -> Option<String> {
+    if rows.len() as i64 <= limit {
+        return None;
+    }
+    rows.truncate(limit as usize);
+    rows.last().and_then(key).map(|c| c.encode())
+}
+
+/// `pagination` object of a list response: page / total / totalPages for offset pages,
+/// next_cursor (null on the last page) for keyset pages; total is null when not counted
+pub fn pagination_json(mode: &PageMode, limit: i64, total: Option<i64>, next_cursor: Option<String>) -> serde_json::Value {
+    match mode {
+        PageMode::Offset { page, .. } => serde_json::json!({
+            "page": page, "limit": limit, "total": total, "totalPages": total.map(|t| (t + limit - 1) / limit),
+        }),
+        PageMode::After(_) => serde_json::json!({ "limit": limit, "total": total, "next_cursor": next_cursor }),
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::Method,
+    middleware::Next,
+    web, HttpRequest, HttpResponse,
+};
+
+use crate::state::AppState;
+use crate::utils::response::ApiResponse;
+
+/// `max` requests per `window_secs` for one subject (IP, account, wallet)
+#[derive(Clone, Debug)]
+pub struct RatePolicy {
+    /// Key namespace, e.g. "admin:login:ip" -> Redis key "rl:admin:login:ip:{subject}"
+    pub name: &'static str,
+    pub max: u32,
+    pub window_secs: u64,
+}
+
+impl RatePolicy {
+    pub const fn new(name: &'static str, max: u32, window_secs: u64) -> Self {
+        Self { name, max, window_secs }
+    }
+
+    /// Override from an env var formatted as "<max>/<window_secs>", e.g. RATE_LIMIT_LOGIN_IP=10/60
+    fn from_env(var: &str, default: RatePolicy) -> Self {
+        let parsed = std::env::var(var).ok().and_then(|v| {
+            let (max, window) = v.trim().split_once('/')?;
+            Some((max.trim().parse::<u32>().ok()?, window.trim().parse::<u64>().ok()?))
+        });
+        match parsed {
+            Some((max, window_secs)) if max > 0 && window_secs > 0 => RatePolicy { max, window_secs, ..default },
+            _ => default,
+        }
+    }
+}
+
+/// The limits applied across the API (see docs/REDIS_INTEGRATION.md §6)
+#[derive(Clone, Debug)]
+pub struct RatePolicies {
+    pub login_ip: RatePolicy,
+    pub login_account: RatePolicy,
+    pub order_wallet: RatePolicy,
+    pub public_read_ip: RatePolicy,
+}
+
+impl Default for RatePolicies {
+    fn default() -> Self {
+        Self {
+            login_ip: RatePolicy::new("admin:login:ip", 10, 60),
+            login_account: RatePolicy::new("admin:login:account", 5, 300),
+            order_wallet: RatePolicy::new("orders:wallet", 30, 60),
+            public_read_ip: RatePolicy::new("public:ip", 300, 60),
+        }
+    }
+}
+
+impl RatePolicies {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            login_ip: RatePolicy::from_env("RATE_LIMIT_LOGIN_IP", d.login_ip),
+            login_account: RatePolicy::from_env("RATE_LIMIT_LOGIN_ACCOUNT", d.login_account),
+            order_wallet: RatePolicy::from_env("RATE_LIMIT_ORDER_WALLET", d.order_wallet),
+            public_read_ip: RatePolicy::from_env("RATE_LIMIT_PUBLIC_READ", d.public_read_ip),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Decision {
+    Allowed,
+    Limited { retry_after_secs: u64 },
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// In-process token bucket; each subject refills `max` tokens per window
+pub struct MemoryBackend {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+/// Buckets are pruned once the map grows past this many subjects
+const MEMORY_PRUNE_THRESHOLD: usize = 10_000;
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self { buckets: Mutex::new(HashMap::new()) }
+    }
+
+    fn check(&self, key: &str, policy: &RatePolicy) -> Decision {
+        let capacity = policy.max as f64;
+        let rate = capacity / policy.window_secs as f64;
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() > MEMORY_PRUNE_THRESHOLD {
+            // a bucket that would be full again carries no state worth keeping
+            buckets.retain(|_, b| b.tokens + now.duration_since(b.updated).as_secs_f64() * rate < capacity);
+        }
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket { tokens: capacity, updated: now });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Decision::Allowed
+        } else {
+            let wait = ((1.0 - bucket.tokens) / rate).ceil() as u64;
+            Decision::Limited { retry_after_secs: wait.max(1) }
+        }
+    }
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self { Self::new() }
+}
+
+/// Fixed-window counter shared by all instances (INCR + EXPIRE, docs/REDIS_INTEGRATION.md §3)
+pub struct RedisBackend {
+    conn: redis::aio::ConnectionManager,
+}
+
+const REDIS_WINDOW_SCRIPT: &str = r#"
+local count = redis.call('INCR', KEYS[1])
+if count == 1 then redis.call('EXPIRE', KEYS[1], ARGV[1]) end
+return {count, redis.call('TTL', KEYS[1])}
+"#;
+
+impl RedisBackend {
+    pub async fn connect(url: &str) -> redis::RedisResult<Self> {
+        let client = redis::Client::open(url)?;
+        let conn = client.get_connection_manager().await?;
+        Ok(Self { conn })
+    }
+
+    async fn check(&self, key: &str, policy: &RatePolicy) -> Decision {
+        let mut conn = self.conn.clone();
+        let res: redis::RedisResult<(i64, i64)> = redis::Script::new(REDIS_WINDOW_SCRIPT)
+            .key(key)
+            .arg(policy.window_secs)
+            .invoke_async(&mut conn)
+            .await;
+        match res {
+            Ok((count, _)) if count <= policy.max as i64 => Decision::Allowed,
+            Ok((_, ttl)) => Decision::Limited { retry_after_secs: if ttl > 0 { ttl as u64 } else { policy.window_secs } },
+            Err(e) => {
+                // fail open: Redis outages degrade throttling rather than the API (§11)
+                tracing::warn!(target: "kmarket_backend", "rate limit redis error on {}: {}", key, e);
+                Decision::Allowed
+            }
+        }
+    }
+}
+
+pub enum RateLimitBackend {
+    Memory(MemoryBackend),
+    Redis(RedisBackend),
+    Disabled,
+}
+
+pub struct RateLimiter {
+    backend: RateLimitBackend,
+    pub policies: RatePolicies,
+}
+
+impl RateLimiter {
+    pub fn new(backend: RateLimitBackend, policies: RatePolicies) -> Self {
+        Self { backend, policies }
+    }
+
+    pub fn memory(policies: RatePolicies) -> Self {
+        Self::new(RateLimitBackend::Memory(MemoryBackend::new()), policies)
+    }
+
+    /// Build from RATE_LIMIT_BACKEND (memory | redis | off) and REDIS_URL.
+    /// Falls back to the in-memory backend when Redis is unreachable.
+    pub async fn from_env() -> Self {
+        let policies = RatePolicies::from_env();
+        let kind = std::env::var("RATE_LIMIT_BACKEND").unwrap_or_else(|_| "memory".to_string());
+        match kind.trim().to_lowercase().as_str() {
+            "off" | "disabled" | "none" => Self::new(RateLimitBackend::Disabled, policies),
+            "redis" => {
+                let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379/0".to_string());
+                match RedisBackend::connect(&url).await {
+                    Ok(b) => Self::new(RateLimitBackend::Redis(b), policies),
+                    Err(e) => {
+                        tracing::warn!(target: "kmarket_backend", "rate limit redis unavailable ({}), using in-memory limiter", e);
+                        Self::memory(policies)
+                    }
+                }
+            }
+            _ => Self::memory(policies),
+        }
+    }
+
+    /// Consume one request for `subject` under `policy`
+    pub async fn check(&self, policy: &RatePolicy, subject: &str) -> Decision {
+        let key = format!("rl:{}:{}", policy.name, subject);
+        match &self.backend {
+            RateLimitBackend::Memory(m) => m.check(&key, policy),
+            RateLimitBackend::Redis(r) => r.check(&key, policy).await,
+            RateLimitBackend::Disabled => Decision::Allowed,
+        }
+    }
+
+    /// Check several limits in order; the first one exceeded wins
+    pub async fn check_all(&self, checks: &[(&RatePolicy, &str)]) -> Decision {
+        for (policy, subject) in checks {
+            if let d @ Decision::Limited { .. } = self.check(policy, subject).await {
+                return d;
+            }
+        }
+        Decision::Allowed
+    }
+}
+
+/// 429 with Retry-After in the usual ApiResponse envelope
+pub fn too_many_requests(retry_after_secs: u64) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", retry_after_secs.to_string()))
+        .json(ApiResponse::<()>::error("rate_limited", "too many requests, retry later"))
+}
+
+/// Client IP for limiting. Forwarded headers are only trusted with RATE_LIMIT_TRUST_PROXY=true,
+/// otherwise any client could pick its own bucket.
+pub fn client_ip(req: &HttpRequest) -> String {
+    let trust_proxy = std::env::var("RATE_LIMIT_TRUST_PROXY")
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false);
+    let info = req.connection_info();
+    let ip = if trust_proxy { info.realip_remote_addr().map(str::to_string) } else { info.peer_addr().map(str::to_string) };
+    ip.unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Middleware for the public API: throttles GET/HEAD per client IP.
+/// Admin routes and partner routes (limited per API key) are excluded.
+pub async fn limit_public_reads(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let is_read = matches!(*req.method(), Method::GET | Method::HEAD);
+    if is_read && !req.path().contains("/admin/") && !req.path().contains("/partner/") {
+        if let Some(state) = req.app_data::<web::Data<AppState>>().cloned() {
+            let ip = client_ip(req.request());
+            let limiter = &state.rate_limiter;
+            if let Decision::Limited { retry_after_secs } = limiter.check(&limiter.policies.public_read_ip, &ip).await {
+                return Ok(req.into_response(too_many_requests(retry_after_secs)).map_into_right_body());
+            }
+        }
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use sqlx::{FromRow, PgPool, Row};
+use tokio::sync::broadcast;
+
+use crate::models::event::{DomainEvent, OutboxEvent};
+use crate::utils::match_state::MatchState;
+use crate::utils::outbox::EventSubscriber;
+
+/// All fixture/market changes, for list views
+pub const TOPIC_FIXTURES_LIVE: &str = "fixtures:live";
+/// Internal: pushes may have been missed on every topic; sessions should refetch
+pub const TOPIC_RESYNC: &str = "*";
+
+/// Pushes buffered per subscriber before it is told it lagged and should refetch
+const CHANNEL_CAPACITY: usize = 1024;
+/// Postgres NOTIFY channel every instance LISTENs on
+pub const NOTIFY_CHANNEL: &str = "kmarket_realtime";
+/// NOTIFY payloads are capped at 8000 bytes by Postgres
+const MAX_NOTIFY_PAYLOAD: usize = 7900;
+
+/// One event on a topic, fanned out to every socket subscribed to that topic
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Push {
+    pub topic: String,
+    pub event: String,
+    pub data: serde_json::Value,
+    pub ts: chrono::DateTime<chrono::Utc>,
+}
+
+pub fn market_topic(market_id: i64) -> String { format!("market:{}", market_id) }
+
+/// Topics clients may subscribe to: market:{market_id}, fixtures:live. Sockets are anonymous
+/// (wallets do not authenticate), so only public market data is pushed; a client follows its
+/// own orders over REST.
+pub fn is_valid_topic(topic: &str) -> bool {
+    if topic == TOPIC_FIXTURES_LIVE { return true; }
+    if let Some(id) = topic.strip_prefix("market:") {
+        return !id.is_empty() && id.len() <= 19 && id.bytes().all(|b| b.is_ascii_digit());
+    }
+    false
+}
+
+/// Pub/sub between code that changes state and connected WebSocket/SSE sessions.
+/// Publishing is best effort and never fails the request that triggered it.
+///
+/// With fan-out enabled, `market_changed`/`match_state_changed` NOTIFY the push on NOTIFY_CHANNEL
+/// instead of delivering it directly; every instance (this one included) receives it through
+/// `spawn_listener` and delivers it to its own sessions.
+pub struct RealtimeHub {
+    tx: broadcast::Sender<Arc<Push>>,
+    fanout: Option<PgPool>,
+}
+
+impl Default for RealtimeHub {
+    fn default() -> Self { Self::new() }
+}
+
+impl RealtimeHub {
+    /// Single-instance hub: pushes only reach this process
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { tx, fanout: None }
+    }
+
+    /// Hub that fans pushes out to every instance through Postgres LISTEN/NOTIFY
+    pub fn with_fanout(pool: PgPool) -> Self {
+        Self { fanout: Some(pool), ..Self::new() }
+    }
+
+    /// REALTIME_FANOUT=postgres (default) | local
+    pub fn from_env(pool: &PgPool) -> Self {
+        match std::env::var("REALTIME_FANOUT").unwrap_or_default().trim().to_lowercase().as_str() {
+            "local" | "off" => Self::new(),
+            _ => Self::with_fanout(pool.clone()),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Push>> { self.tx.subscribe() }
+
+    pub fn has_subscribers(&self) -> bool { self.tx.receiver_count() > 0 }
+
+    /// Deliver to this instance's sessions only
+    pub fn publish(&self, topic: String, event: &str, data: serde_json::Value) {
+        self.deliver(Push { topic, event: event.to_string(), data, ts: chrono::Utc::now() });
+    }
+
+    fn deliver(&self, push: Push) {
+        // Err only means nobody is listening
+        let _ = self.tx.send(Arc::new(push));
+    }
+
+    /// Deliver to every instance's sessions (this one only, without fan-out)
+    pub async fn broadcast(&self, topic: String, event: &str, data: serde_json::Value) {
+        let push = Push { topic, event: event.to_string(), data, ts: chrono::Utc::now() };
+        let Some(pool) = &self.fanout else { return self.deliver(push); };
+        let payload = serde_json::to_string(&push).unwrap_or_default();
+        if payload.len() > MAX_NOTIFY_PAYLOAD {
+            tracing::warn!(target: "kmarket_backend", "realtime: {} push on {} too large to fan out ({} bytes), delivering locally", push.event, push.topic, payload.len());
+            return self.deliver(push);
+        }
+        if let Err(e) = sqlx::query("SELECT pg_notify($1, $2)").bind(NOTIFY_CHANNEL).bind(&payload).execute(pool).await {
+            tracing::warn!(target: "kmarket_backend", "realtime: NOTIFY failed, delivering locally: {}", e);
+            self.deliver(push);
+        }
+    }
+
+    /// LISTEN on NOTIFY_CHANNEL and deliver what arrives to this instance's sessions.
+    /// No-op without fan-out. Notifications sent while the listener reconnects are lost;
+    /// sessions are told to refetch as if they had lagged.
+    pub fn spawn_listener(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let pool = self.fanout.clone()?;
+        let hub = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            let mut listener = loop {
+                match PgListener::connect_with(&pool).await {
+                    Ok(mut l) => match l.listen(NOTIFY_CHANNEL).await {
+                        Ok(()) => break l,
+                        Err(e) => tracing::warn!(target: "kmarket_backend", "realtime: LISTEN failed: {}", e),
+                    },
+                    Err(e) => tracing::warn!(target: "kmarket_backend", "realtime: listener connect failed: {}", e),
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            };
+            loop {
+                match listener.try_recv().await {
+                    Ok(Some(n)) => match serde_json::from_str::<Push>(n.payload()) {
+                        Ok(push) => hub.deliver(push),
+                        Err(e) => tracing::warn!(target: "kmarket_backend", "realtime: bad notification payload: {}", e),
+                    },
+                    // connection dropped; the next try_recv reconnects and re-LISTENs
+                    Ok(None) => {
+                        tracing::warn!(target: "kmarket_backend", "realtime: listener connection lost, reconnecting");
+                        hub.deliver(Push { topic: TOPIC_RESYNC.to_string(), event: "resync".into(), data: serde_json::json!({}), ts: chrono::Utc::now() });
+                    }
+                    Err(e) => {
+                        tracing::warn!(target: "kmarket_backend", "realtime: listener error: {}", e);
+                        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        }))
+    }
+
+    /// Push a market's current status and odds to market:{market_id} and fixtures:live.
+    /// `market_pk` is markets.id; topics use the public market_id.
+    pub async fn market_changed(&self, pool: &PgPool, market_pk: i64, event: &str) {
+        if self.fanout.is_none() && !self.has_subscribers() { return; }
+        let row = sqlx::query("SELECT market_id, status::TEXT AS status, state, odds_home_bps, odds_away_bps, winning_option FROM markets WHERE id = $1")
+            .bind(market_pk)
+            .fetch_optional(pool)
+            .await;
+        let row = match row {
+            Ok(Some(r)) => r,
+            Ok(None) => return,
+            Err(e) => { tracing::warn!(target: "kmarket_backend", "realtime: market {} lookup failed: {}", market_pk, e); return; }
+        };
+        let market_id: i64 = row.try_get("market_id").unwrap_or_default();
+        let data = serde_json::json!({
+            "market_id": market_id,
+            "status": row.try_get::<String, _>("status").unwrap_or_default(),
+            "state": row.try_get::<Option<i32>, _>("state").ok().flatten(),
+            "odds_home_bps": row.try_get::<Option<i32>, _>("odds_home_bps").ok().flatten(),
+            "odds_away_bps": row.try_get::<Option<i32>, _>("odds_away_bps").ok().flatten(),
+            "winning_option": row.try_get::<Option<i16>, _>("winning_option").ok().flatten(),
+        });
+        self.broadcast(market_topic(market_id), event, data.clone()).await;
+        self.broadcast(TOPIC_FIXTURES_LIVE.to_string(), event, data).await;
+    }
+
+    /// Push a fixture's live stats (match_state) to market:{market_id} and fixtures:live
+    pub async fn match_state_changed(&self, pool: &PgPool, market_pk: i64) {
+        if self.fanout.is_none() && !self.has_subscribers() { return; }
+        let row = sqlx::query(
+            "SELECT m.market_id AS public_market_id, s.market_id, s.phase, s.period, s.clock_seconds, s.home_score, s.away_score, \
+                    s.home_red_cards, s.away_red_cards, s.source, s.updated_at \
+             FROM markets m JOIN match_state s ON s.market_id = m.id WHERE m.id = $1"
+        )
+        .bind(market_pk)
+        .fetch_optional(pool)
+        .await;
+        let row = match row {
+            Ok(Some(r)) => r,
+            Ok(None) => return,
+            Err(e) => { tracing::warn!(target: "kmarket_backend", "realtime: match state {} lookup failed: {}", market_pk, e); return; }
+        };
+        let market_id: i64 = row.try_get("public_market_id").unwrap_or_default();
+        let Ok(state) = MatchState::from_row(&row) else { return };
+        let data = serde_json::json!({"market_id": market_id, "liveStats": state});
+        self.broadcast(market_topic(market_id), "match.state", data.clone()).await;
+        self.broadcast(TOPIC_FIXTURES_LIVE.to_string(), "match.state", data).await;
+    }
+}
+
+/// Outbox subscriber that turns committed domain events into pushes
+pub struct RealtimeSubscriber {
+    hub: Arc<RealtimeHub>,
+    pool: PgPool,
+}
+
+impl RealtimeSubscriber {
+    pub fn new(hub: Arc<RealtimeHub>, pool: PgPool) -> Self { Self { hub, pool } }
+}
+
+#[async_trait]
+impl EventSubscriber for RealtimeSubscriber {
+    fn name(&self) -> &'static str { "realtime" }
+
+    /// Pushes are best effort: a push nobody received is not worth redelivering
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), String> {
+        match &event.event {
+            DomainEvent::MarketCreated { id } => self.hub.market_changed(&self.pool, *id, "market.created").await,
+            DomainEvent::MarketUpdated { id, fields } => {
+                let push = if fields.iter().any(|f| f == "status") {
+                    "market.status"
+                } else if fields.iter().any(|f| f.starts_with("odds_")) {
+                    "market.odds"
+                } else {
+                    "market.updated"
+                };
+                self.hub.market_changed(&self.pool, *id, push).await
+            }
+            DomainEvent::MarketStatusChanged { id, .. } | DomainEvent::MarketSettled { id, .. } | DomainEvent::MarketClosed { id } => {
+                self.hub.market_changed(&self.pool, *id, "market.status").await
+            }
+            DomainEvent::MatchStateChanged { id, .. } => self.hub.match_state_changed(&self.pool, *id).await,
+            // order events carry a wallet's amounts and payouts; anonymous sockets do not get them
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+
+pub const HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Id of the request being handled: the one assigned by `assign`, or a fresh one outside it
+pub fn current() -> String {
+    REQUEST_ID.try_with(Clone::clone).unwrap_or_else(|_| generate())
+}
+
+fn generate() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// A client- or proxy-supplied id is kept when it is short and plain
+fn accept(raw: &str) -> bool {
+    !raw.is_empty() && raw.len() <= 64 && raw.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Middleware: gives every request an id (honouring a valid incoming X-Request-Id), exposes it
+/// to handlers and errors through `current`, and echoes it in the response header
+pub async fn assign(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let id = req.headers().get(HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| accept(v))
+        .map(str::to_string)
+        .unwrap_or_else(generate);
+    let mut resp = REQUEST_ID.scope(id.clone(), next.call(req)).await?;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        resp.headers_mut().insert(HeaderName::from_static(HEADER), value);
+    }
+    Ok(resp)
+}
@@ -12,6 +12,9 @@ pub struct ApiResponse<T> {
 pub struct ApiError {
     pub code: String,
     pub message: String,
+    /// Set on errors raised through `AppError`; quote it when reporting a failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl<T: Serialize> ApiResponse<T> {
@@ -28,7 +31,7 @@ impl<T: Serialize> ApiResponse<T> {
         ApiResponse {
             success: false,
             data: None,
-            error: Some(ApiError { code: code.to_string(), message: message.to_string() }),
+            error: Some(ApiError { code: code.to_string(), message: message.to_string(), request_id: None }),
             timestamp: chrono::Utc::now().timestamp(),
         }
     }
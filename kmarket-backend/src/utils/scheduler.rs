@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+use crate::models::event::DomainEvent;
+use crate::models::market::{MARKET_STATE_CLOSED, MARKET_STATE_OPEN};
+use crate::utils::{audit, outbox};
+use crate::utils::audit::SYSTEM_ACTOR_ID;
+
+/// attention_reason for markets whose end_time passed without a result
+pub const ATTENTION_UNRESOLVED: &str = "unresolved_after_end";
+
+/// Markets transitioned per step and pass
+const BATCH_SIZE: i64 = 100;
+
+/// What one scheduler pass changed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LifecycleReport {
+    pub opened: usize,
+    pub closed: usize,
+    pub flagged: usize,
+}
+
+/// Time-driven market transitions:
+/// - pending -> active once open_time has passed
+/// - betting closed (state = closed) once close_time has passed
+/// - flag markets still pending/active after end_time for admin attention
+///
+/// Each step locks its rows with SKIP LOCKED and re-checks the condition in the UPDATE, so
+/// several instances can run the scheduler at once without double transitions. Every
+/// transition is audited as SYSTEM_ACTOR_ID in the same transaction.
+#[derive(Clone)]
+pub struct MarketScheduler {
+    pool: PgPool,
+}
+
+impl MarketScheduler {
+    pub fn new(pool: PgPool) -> Self { Self { pool } }
+
+    pub async fn run_once(&self) -> Result<LifecycleReport, sqlx::Error> {
+        Ok(LifecycleReport {
+            opened: self.open_due().await?,
+            closed: self.close_due().await?,
+            flagged: self.flag_unresolved().await?,
+        })
+    }
+
+    async fn open_due(&self) -> Result<usize, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query(
+            "UPDATE markets SET status = 'active' \
+             WHERE id IN (SELECT id FROM markets WHERE status = 'pending' AND open_time <= NOW() ORDER BY open_time LIMIT $1 FOR UPDATE SKIP LOCKED) \
+             RETURNING id, market_id, open_time"
+        )
+        .bind(BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+        for r in &rows {
+            let id: i64 = r.try_get("id")?;
+            audit::record(&mut *tx, SYSTEM_ACTOR_ID, "system.market_open", "markets", Some(id), serde_json::json!({
+                "market_id": r.try_get::<i64, _>("market_id")?,
+                "open_time": r.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("open_time")?,
+            })).await?;
+            outbox::enqueue(&mut *tx, &DomainEvent::MarketStatusChanged { id, status: "active".into() }).await?;
+        }
+        tx.commit().await?;
+        Ok(rows.len())
+    }
+
+    async fn close_due(&self) -> Result<usize, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query(
+            "UPDATE markets SET state = $2 \
+             WHERE id IN (SELECT id FROM markets WHERE status = 'active' AND state = $3 AND close_time <= NOW() ORDER BY close_time LIMIT $1 FOR UPDATE SKIP LOCKED) \
+             RETURNING id, market_id, close_time"
+        )
+        .bind(BATCH_SIZE)
+        .bind(MARKET_STATE_CLOSED)
+        .bind(MARKET_STATE_OPEN)
+        .fetch_all(&mut *tx)
+        .await?;
+        for r in &rows {
+            let id: i64 = r.try_get("id")?;
+            audit::record(&mut *tx, SYSTEM_ACTOR_ID, "system.market_close", "markets", Some(id), serde_json::json!({
+                "market_id": r.try_get::<i64, _>("market_id")?,
+                "close_time": r.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("close_time")?,
+            })).await?;
+            outbox::enqueue(&mut *tx, &DomainEvent::MarketClosed { id }).await?;
+        }
+        tx.commit().await?;
+        Ok(rows.len())
+    }
+
+    async fn flag_unresolved(&self) -> Result<usize, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query(
+            "UPDATE markets SET attention_reason = $2, attention_at = NOW() \
+             WHERE id IN (SELECT id FROM markets WHERE status IN ('pending', 'active') AND end_time <= NOW() AND winning_option IS NULL \
+                          AND attention_at IS NULL ORDER BY end_time LIMIT $1 FOR UPDATE SKIP LOCKED) \
+             RETURNING id, market_id, end_time"
+        )
+        .bind(BATCH_SIZE)
+        .bind(ATTENTION_UNRESOLVED)
+        .fetch_all(&mut *tx)
+        .await?;
+        for r in &rows {
+            let id: i64 = r.try_get("id")?;
+            audit::record(&mut *tx, SYSTEM_ACTOR_ID, "system.market_flag", "markets", Some(id), serde_json::json!({
+                "market_id": r.try_get::<i64, _>("market_id")?,
+                "end_time": r.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("end_time")?,
+                "reason": ATTENTION_UNRESOLVED,
+            })).await?;
+        }
+        tx.commit().await?;
+        Ok(rows.len())
+    }
+
+    /// Background loop: one pass every `interval`
+    pub fn spawn(self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match self.run_once().await {
+                    Ok(r) if r != LifecycleReport::default() => {
+                        tracing::info!(target: "kmarket_backend", "market scheduler: opened={} closed={} flagged={}", r.opened, r.closed, r.flagged);
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!(target: "kmarket_backend", "market scheduler: {}", e),
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,435 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool, Row};
+
+use crate::models::event::DomainEvent;
+use crate::models::market::MARKET_STATE_CLOSED;
+use crate::utils::audit::SYSTEM_ACTOR_ID;
+use crate::utils::match_state::{self, MatchStateUpdate};
+use crate::utils::realtime::{market_topic, RealtimeHub, TOPIC_FIXTURES_LIVE};
+use crate::utils::{audit, currency, fees, outbox};
+
+pub const SIMULATION_RUNNING: &str = "running";
+pub const SIMULATION_SETTLED: &str = "settled";
+pub const SIMULATION_STOPPED: &str = "stopped";
+
+pub const DEFAULT_TICK_SECS: i32 = 3;
+/// Wall-clock length of a simulated match; the demo flow runs for at most 30 minutes
+pub const MAX_DURATION_SECS: i32 = 1800;
+/// Odds used when the market has none (evens, no margin)
+pub const DEFAULT_ODDS_BPS: i32 = 20000;
+
+/// Simulations advanced per pass
+const BATCH_SIZE: usize = 100;
+
+pub const SIMULATION_COLUMNS: &str = "id, market_id, sport, status, seed, tick_secs, duration_secs, ticks, progress, period, minute, second, phase, \
+    home_score, away_score, pre_odds_home_bps, pre_odds_away_bps, odds_home_bps, odds_away_bps, winning_option, settled_orders, \
+    started_by, started_at, next_tick_at, finished_at, updated_at";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sport {
+    Football,
+    Basketball,
+    AmericanFootball,
+}
+
+impl Sport {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "football" | "soccer" => Some(Sport::Football),
+            "basketball" | "nba" => Some(Sport::Basketball),
+            "american_football" | "american football" | "nfl" => Some(Sport::AmericanFootball),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sport::Football => "football",
+            Sport::Basketball => "basketball",
+            Sport::AmericanFootball => "american_football",
+        }
+    }
+
+    /// (periods, seconds of play per period)
+    fn periods(&self) -> (i32, i32) {
+        match self {
+            Sport::Football => (2, 45 * 60),
+            Sport::Basketball => (4, 12 * 60),
+            Sport::AmericanFootball => (4, 15 * 60),
+        }
+    }
+
+    /// Expected scoring plays per team over a whole match
+    fn scoring_rate(&self) -> f64 {
+        match self {
+            Sport::Football => 1.4,
+            Sport::Basketball => 42.0,
+            Sport::AmericanFootball => 4.5,
+        }
+    }
+
+    /// Points for one scoring play: goals, 2/3-pointers, field goals/touchdowns
+    fn score_increment(&self, rng: &mut StdRng) -> i32 {
+        match self {
+            Sport::Football => 1,
+            Sport::Basketball => if rng.gen_bool(0.3) { 3 } else { 2 },
+            Sport::AmericanFootball => if rng.gen_bool(0.4) { 6 } else { 3 },
+        }
+    }
+
+    fn mean_increment(&self) -> f64 {
+        match self {
+            Sport::Football => 1.0,
+            Sport::Basketball => 2.3,
+            Sport::AmericanFootball => 4.2,
+        }
+    }
+
+    fn final_phase(&self) -> &'static str {
+        match self {
+            Sport::Football => "Full Time",
+            _ => "Final",
+        }
+    }
+}
+
+/// A match_simulations row
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MatchSimulation {
+    pub id: i64,
+    /// markets.id
+    pub market_id: i64,
+    pub sport: String,
+    pub status: String,
+    #[serde(skip)]
+    pub seed: i64,
+    pub tick_secs: i32,
+    pub duration_secs: i32,
+    pub ticks: i32,
+    pub progress: f64,
+    pub period: i32,
+    pub minute: i32,
+    pub second: i32,
+    pub phase: String,
+    pub home_score: i32,
+    pub away_score: i32,
+    pub pre_odds_home_bps: i32,
+    pub pre_odds_away_bps: i32,
+    pub odds_home_bps: i32,
+    pub odds_away_bps: i32,
+    pub winning_option: Option<i16>,
+    pub settled_orders: Option<i32>,
+    pub started_by: Option<i64>,
+    pub started_at: DateTime<Utc>,
+    pub next_tick_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl MatchSimulation {
+    fn sport(&self) -> Sport { Sport::parse(&self.sport).unwrap_or(Sport::Football) }
+
+    /// (pre-match home win probability, bookmaker overround) implied by the kick-off odds
+    fn pricing(&self) -> (f64, f64) {
+        let home = 10000.0 / self.pre_odds_home_bps.max(10001) as f64;
+        let away = 10000.0 / self.pre_odds_away_bps.max(10001) as f64;
+        (home / (home + away), home + away)
+    }
+}
+
+/// Match clock at `progress` (0..=1 of the match played): (period, minute, second, phase).
+/// Football counts minutes across the match (0'..90'), basketball counts up within the quarter
+/// and American football counts down, like the frontend liveDataSimulator.
+pub fn match_clock(sport: Sport, progress: f64) -> (i32, i32, i32, String) {
+    let (periods, period_secs) = sport.periods();
+    if progress >= 1.0 {
+        let (minute, second) = match sport {
+            Sport::Football => (90, 0),
+            Sport::Basketball => (12, 0),
+            Sport::AmericanFootball => (0, 0),
+        };
+        return (periods, minute, second, sport.final_phase().to_string());
+    }
+    let played = (progress.max(0.0) * (periods * period_secs) as f64) as i32;
+    let period = (played / period_secs + 1).min(periods);
+    let in_period = played - (period - 1) * period_secs;
+    match sport {
+        Sport::Football => {
+            let phase = if period == 1 { "First Half" } else { "Second Half" };
+            (period, played / 60, played % 60, phase.to_string())
+        }
+        Sport::Basketball => (period, in_period / 60, in_period % 60, format!("Q{}", period)),
+        Sport::AmericanFootball => {
+            let left = period_secs - in_period;
+            (period, left / 60, left % 60, format!("Q{}", period))
+        }
+    }
+}
+
+/// Probability that the home side wins, from the pre-match probability `base`, the current
+/// `lead` (home minus away points) and the share of the match still to play. The remaining
+/// score difference is treated as roughly normal, so a lead counts for more as time runs out.
+pub fn home_win_probability(sport: Sport, base: f64, lead: i32, remaining: f64) -> f64 {
+    let base = base.clamp(0.02, 0.98);
+    let remaining = remaining.clamp(0.0, 1.0);
+    let prior = (base / (1.0 - base)).ln() * remaining.sqrt();
+    let spread = sport.mean_increment() * ((2.0 * sport.scoring_rate() * remaining).sqrt() + 0.3);
+    let z = prior + 1.7 * lead as f64 / spread;
+    (1.0 / (1.0 + (-z).exp())).clamp(0.01, 0.99)
+}
+
+/// Decimal odds in basis points for a win probability, keeping the market's margin
+pub fn odds_bps(probability: f64, overround: f64) -> i32 {
+    ((10000.0 / (probability * overround)).round() as i32).clamp(10100, 1_000_000)
+}
+
+/// Advance the match to `now`: scoring plays since the last tick (stronger side scores more
+/// often), then clock and odds. Returns true once the full match has been played.
+fn advance(sim: &mut MatchSimulation, now: DateTime<Utc>) -> bool {
+    let sport = sim.sport();
+    let (base, overround) = sim.pricing();
+    let elapsed_ms = (now - sim.started_at).num_milliseconds().max(0) as f64;
+    let progress = (elapsed_ms / (sim.duration_secs as f64 * 1000.0)).clamp(sim.progress, 1.0);
+    let delta = progress - sim.progress;
+    let mut rng = StdRng::seed_from_u64(sim.seed.wrapping_add(sim.ticks as i64) as u64);
+    for (share, score) in [(base, &mut sim.home_score), (1.0 - base, &mut sim.away_score)] {
+        let expected = 2.0 * sport.scoring_rate() * share * delta;
+        let plays = expected.floor() as i32 + i32::from(rng.gen_bool(expected.fract()));
+        for _ in 0..plays {
+            *score += sport.score_increment(&mut rng);
+        }
+    }
+    let (period, minute, second, phase) = match_clock(sport, progress);
+    let p = home_win_probability(sport, base, sim.home_score - sim.away_score, 1.0 - progress);
+    sim.period = period;
+    sim.minute = minute;
+    sim.second = second;
+    sim.phase = phase;
+    sim.odds_home_bps = odds_bps(p, overround);
+    sim.odds_away_bps = odds_bps(1.0 - p, overround);
+    sim.progress = progress;
+    sim.ticks += 1;
+    sim.next_tick_at = now + chrono::Duration::seconds(sim.tick_secs as i64);
+    progress >= 1.0
+}
+
+/// Winning option (0 = home, 1 = away) from the final score. A level game goes to penalties
+/// (football) or a deciding overtime score, since the market has no draw outcome.
+fn decide(sim: &mut MatchSimulation) -> i16 {
+    if sim.home_score != sim.away_score {
+        return if sim.home_score > sim.away_score { 0 } else { 1 };
+    }
+    let sport = sim.sport();
+    let mut rng = StdRng::seed_from_u64(sim.seed.wrapping_add(sim.ticks as i64).wrapping_add(1) as u64);
+    let home_wins = rng.gen_bool(0.5);
+    if sport == Sport::Football {
+        sim.phase = "Penalties".into();
+    } else {
+        let points = sport.score_increment(&mut rng);
+        if home_wins { sim.home_score += points } else { sim.away_score += points }
+        sim.phase = "Overtime".into();
+    }
+    if home_wins { 0 } else { 1 }
+}
+
+async fn save(conn: &mut PgConnection, sim: &MatchSimulation) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE match_simulations SET status = $2, ticks = $3, progress = $4, period = $5, minute = $6, second = $7, phase = $8, \
+         home_score = $9, away_score = $10, odds_home_bps = $11, odds_away_bps = $12, winning_option = $13, settled_orders = $14, \
+         next_tick_at = $15, finished_at = $16 WHERE id = $1"
+    )
+    .bind(sim.id).bind(&sim.status).bind(sim.ticks).bind(sim.progress).bind(sim.period).bind(sim.minute).bind(sim.second).bind(&sim.phase)
+    .bind(sim.home_score).bind(sim.away_score).bind(sim.odds_home_bps).bind(sim.odds_away_bps).bind(sim.winning_option).bind(sim.settled_orders)
+    .bind(sim.next_tick_at).bind(sim.finished_at)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Mirror the simulated clock and score into match_state (the fixtures API liveStats)
+pub async fn record_match_state(conn: &mut PgConnection, sim: &MatchSimulation, phase: &str) -> Result<(), sqlx::Error> {
+    let update = MatchStateUpdate {
+        phase: Some(phase.to_string()),
+        period: Some(sim.period),
+        clock_seconds: Some(sim.minute * 60 + sim.second),
+        home_score: Some(sim.home_score),
+        away_score: Some(sim.away_score),
+        ..Default::default()
+    };
+    match_state::upsert(conn, sim.market_id, &update, "simulator").await?;
+    Ok(())
+}
+
+/// End a running simulation at its current score: settle the market with the winner, settle
+/// every open order on it (winners are paid amount * odds rounded down to the currency, as
+/// `money::settle`), less settlement fees, and credit users' total_pnl, overall and per currency.
+/// Audited as `actor_id` (SYSTEM_ACTOR_ID when the match ran to full time). Pass the
+/// transaction holding the simulation row lock.
+pub async fn finish(conn: &mut PgConnection, sim: &mut MatchSimulation, actor_id: i64) -> Result<(), sqlx::Error> {
+    if sim.progress < 1.0 {
+        sim.phase = sim.sport().final_phase().to_string();
+    }
+    let winning_option = decide(sim);
+    let settled = sqlx::query(
+        "UPDATE markets SET status = 'settled', winning_option = $2, result = $2 + 1, resolved_at = NOW(), state = $3, \
+         odds_home_bps = $4, odds_away_bps = $5 WHERE id = $1 AND status IN ('pending', 'active') RETURNING market_id"
+    )
+    .bind(sim.market_id)
+    .bind(winning_option)
+    .bind(MARKET_STATE_CLOSED)
+    .bind(sim.odds_home_bps)
+    .bind(sim.odds_away_bps)
+    .fetch_optional(&mut *conn)
+    .await?;
+    sim.finished_at = Some(Utc::now());
+    if settled.is_none() {
+        // settled or cancelled by hand meanwhile: the market's result stands
+        sim.status = SIMULATION_STOPPED.into();
+        return save(conn, sim).await;
+    }
+
+    // a placement fee came out of the stake, so the odds pay on the rest; payouts are truncated to
+    // each order's currency decimals
+    let orders = sqlx::query(
+        "UPDATE orders o SET status = 'settled', closed_at = NOW(), \
+             close_price = CASE WHEN o.option = $2 THEN TRUNC((o.amount - o.fee_at_placement) * o.odds, c.decimals) ELSE 0 END, \
+             close_pnl = CASE WHEN o.option = $2 THEN TRUNC((o.amount - o.fee_at_placement) * o.odds, c.decimals) - o.amount ELSE -o.amount END \
+         FROM currencies c WHERE c.code = o.currency AND o.market_id = $1 AND o.status = 'placed' RETURNING o.id, o.user_id"
+    )
+    .bind(sim.market_id)
+    .bind(winning_option)
+    .fetch_all(&mut *conn)
+    .await?;
+    let order_ids: Vec<i64> = orders.iter().map(|r| r.try_get("id")).collect::<Result<_, _>>()?;
+    fees::charge_at_settlement(conn, &order_ids).await?;
+    sqlx::query(
+        "UPDATE users u SET total_pnl = COALESCE(u.total_pnl, 0) + s.pnl \
+         FROM (SELECT user_id, SUM(close_pnl) AS pnl FROM orders WHERE id = ANY($1) GROUP BY user_id) s WHERE u.id = s.user_id"
+    )
+    .bind(&order_ids)
+    .execute(&mut *conn)
+    .await?;
+    currency::add_settled_pnl(conn, &order_ids).await?;
+
+    sim.status = SIMULATION_SETTLED.into();
+    sim.winning_option = Some(winning_option);
+    sim.settled_orders = Some(orders.len() as i32);
+    save(conn, sim).await?;
+    record_match_state(conn, sim, match_state::PHASE_FINISHED).await?;
+
+    let action = if actor_id == SYSTEM_ACTOR_ID { "system.market_settle" } else { "admin.market_settle" };
+    audit::record(&mut *conn, actor_id, action, "markets", Some(sim.market_id), serde_json::json!({
+        "winning_option": winning_option,
+        "simulation_id": sim.id,
+        "home_score": sim.home_score,
+        "away_score": sim.away_score,
+        "phase": sim.phase,
+        "settled_orders": orders.len(),
+    })).await?;
+    outbox::enqueue(&mut *conn, &DomainEvent::MarketSettled { id: sim.market_id, winning_option }).await?;
+    for r in &orders {
+        let id: i64 = r.try_get("id")?;
+        outbox::enqueue(&mut *conn, &DomainEvent::OrderSettled { id, market_id: sim.market_id, user_id: r.try_get("user_id")? }).await?;
+    }
+    Ok(())
+}
+
+/// Drives running simulations: each due simulation is locked (SKIP LOCKED, so several
+/// instances can run the simulator), advanced by one tick, persisted together with the
+/// market's live odds, and pushed as `match.tick` to market:{market_id} and fixtures:live.
+/// When the match is over the market and its orders are settled in the same transaction.
+#[derive(Clone)]
+pub struct MatchSimulator {
+    pool: PgPool,
+    hub: Arc<RealtimeHub>,
+}
+
+impl MatchSimulator {
+    pub fn new(pool: PgPool, hub: Arc<RealtimeHub>) -> Self { Self { pool, hub } }
+
+    /// Advance every due simulation once. Returns the number advanced.
+    pub async fn run_once(&self) -> Result<usize, sqlx::Error> {
+        let mut advanced = 0;
+        while advanced < BATCH_SIZE {
+            let mut tx = self.pool.begin().await?;
+            let sim = sqlx::query_as::<_, MatchSimulation>(&format!(
+                "SELECT {} FROM match_simulations WHERE status = $1 AND next_tick_at <= NOW() ORDER BY next_tick_at LIMIT 1 FOR UPDATE SKIP LOCKED",
+                SIMULATION_COLUMNS
+            ))
+            .bind(SIMULATION_RUNNING)
+            .fetch_optional(&mut *tx)
+            .await?;
+            let Some(mut sim) = sim else { break };
+            self.tick(&mut tx, &mut sim).await?;
+            tx.commit().await?;
+            advanced += 1;
+            self.push(&sim).await;
+        }
+        Ok(advanced)
+    }
+
+    async fn tick(&self, conn: &mut PgConnection, sim: &mut MatchSimulation) -> Result<(), sqlx::Error> {
+        let status: Option<String> = sqlx::query_scalar("SELECT status::TEXT FROM markets WHERE id = $1 FOR UPDATE")
+            .bind(sim.market_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        if !matches!(status.as_deref(), Some("pending") | Some("active")) {
+            sim.status = SIMULATION_STOPPED.into();
+            sim.finished_at = Some(Utc::now());
+            return save(conn, sim).await;
+        }
+        if advance(sim, Utc::now()) {
+            return finish(conn, sim, SYSTEM_ACTOR_ID).await;
+        }
+        let repriced = sqlx::query(
+            "UPDATE markets SET odds_home_bps = $2, odds_away_bps = $3 \
+             WHERE id = $1 AND (odds_home_bps, odds_away_bps) IS DISTINCT FROM ($2, $3)"
+        )
+            .bind(sim.market_id)
+            .bind(sim.odds_home_bps)
+            .bind(sim.odds_away_bps)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+        // cached market lists, ETags and market:{id} subscribers follow odds through the outbox
+        if repriced > 0 {
+            let fields = vec!["odds_home_bps".to_string(), "odds_away_bps".to_string()];
+            outbox::enqueue(&mut *conn, &DomainEvent::MarketUpdated { id: sim.market_id, fields }).await?;
+        }
+        record_match_state(conn, sim, match_state::PHASE_LIVE).await?;
+        save(conn, sim).await
+    }
+
+    /// Live stats are best effort; settlement itself is announced through the outbox
+    async fn push(&self, sim: &MatchSimulation) {
+        let market_id: i64 = match sqlx::query_scalar("SELECT market_id FROM markets WHERE id = $1").bind(sim.market_id).fetch_optional(&self.pool).await {
+            Ok(Some(id)) => id,
+            _ => return,
+        };
+        let mut data = serde_json::to_value(sim).unwrap_or_default();
+        data["simulation_id"] = serde_json::json!(sim.id);
+        data["market_id"] = serde_json::json!(market_id);
+        self.hub.broadcast(market_topic(market_id), "match.tick", data.clone()).await;
+        self.hub.broadcast(TOPIC_FIXTURES_LIVE.to_string(), "match.tick", data).await;
+    }
+
+    /// Background loop: one pass every `interval`
+    pub fn spawn(self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_once().await {
+                    tracing::warn!(target: "kmarket_backend", "match simulator: {}", e);
+                }
+            }
+        })
+    }
+}
+
@@ -0,0 +1,277 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::{PgPool, Row};
+
+use crate::models::event::{DomainEvent, OutboxEvent};
+use crate::utils::outbox::EventSubscriber;
+
+/// Market starts taking bets (status -> active)
+pub const EVENT_MARKET_OPENED: &str = "market.opened";
+/// Market stops taking bets: close_time passed, or deactivated (status cancelled)
+pub const EVENT_MARKET_CLOSED: &str = "market.closed";
+pub const EVENT_MARKET_SETTLED: &str = "market.settled";
+/// Order placed; partner-scoped subscriptions only receive their referred users' orders
+pub const EVENT_ORDER_PLACED: &str = "order.placed";
+pub const WEBHOOK_EVENT_TYPES: [&str; 4] = [EVENT_MARKET_OPENED, EVENT_MARKET_CLOSED, EVENT_MARKET_SETTLED, EVENT_ORDER_PLACED];
+
+pub const HEADER_SIGNATURE: &str = "X-Kmarket-Signature";
+pub const HEADER_EVENT: &str = "X-Kmarket-Event";
+pub const HEADER_DELIVERY: &str = "X-Kmarket-Delivery";
+
+/// After this many failed attempts a delivery is dead-lettered
+pub const MAX_ATTEMPTS: i32 = 8;
+const BATCH_SIZE: i64 = 50;
+const CLAIM_LEASE_SECS: i64 = 120;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Response bodies are kept in last_error only up to this length
+const MAX_ERROR_LEN: usize = 500;
+
+/// Hex HMAC-SHA256 over "{timestamp}.{body}"
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// X-Kmarket-Signature value: "t=<unix seconds>,v1=<hex signature>". Receivers recompute the
+/// signature and should reject timestamps far from their clock to limit replays.
+pub fn signature_header(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!("t={},v1={}", timestamp, sign_payload(secret, timestamp, body))
+}
+
+/// Seconds before retry n (1-based): 30s, 1m, 2m ... capped at 1 hour
+pub fn retry_delay_secs(attempts: i32) -> i64 {
+    (30i64 << (attempts.clamp(1, 20) - 1)).min(3600)
+}
+
+/// Outbox subscriber that records a pending delivery for every matching webhook subscription.
+/// Idempotent per (subscription, outbox event), so outbox redelivery does not duplicate webhooks.
+pub struct WebhookSubscriber {
+    pool: PgPool,
+}
+
+impl WebhookSubscriber {
+    pub fn new(pool: PgPool) -> Self { Self { pool } }
+
+    /// Webhook event type, data and (for order events) the referring partner key
+    async fn describe(&self, event: &DomainEvent) -> Result<Option<(&'static str, serde_json::Value, Option<i64>)>, sqlx::Error> {
+        let market_pk = match event {
+            DomainEvent::MarketCreated { id }
+            | DomainEvent::MarketUpdated { id, .. }
+            | DomainEvent::MarketStatusChanged { id, .. }
+            | DomainEvent::MarketSettled { id, .. }
+            | DomainEvent::MarketClosed { id } => Some(*id),
+            DomainEvent::OrderPlaced { id, .. } => return self.describe_order(*id).await,
+            _ => None,
+        };
+        let Some(market_pk) = market_pk else { return Ok(None) };
+        if matches!(event, DomainEvent::MarketUpdated { fields, .. } if !fields.iter().any(|f| f == "status")) {
+            return Ok(None);
+        }
+        let row = sqlx::query(
+            "SELECT market_id, title, option_a, option_b, status::TEXT AS status, state, close_time, start_time, end_time, odds_home_bps, odds_away_bps, winning_option \
+             FROM markets WHERE id = $1"
+        )
+        .bind(market_pk)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(row) = row else { return Ok(None) };
+        let status: String = row.try_get("status").unwrap_or_default();
+        let event_type = match (event, status.as_str()) {
+            (DomainEvent::MarketSettled { .. }, _) | (_, "settled") => EVENT_MARKET_SETTLED,
+            (DomainEvent::MarketClosed { .. }, _) => EVENT_MARKET_CLOSED,
+            (_, "active") => EVENT_MARKET_OPENED,
+            (_, "cancelled") => EVENT_MARKET_CLOSED,
+            _ => return Ok(None),
+        };
+        let data = serde_json::json!({
+            "market_id": row.try_get::<i64, _>("market_id").unwrap_or_default(),
+            "title": row.try_get::<String, _>("title").unwrap_or_default(),
+            "option_a": row.try_get::<String, _>("option_a").unwrap_or_default(),
+            "option_b": row.try_get::<String, _>("option_b").unwrap_or_default(),
+            "status": status,
+            "state": row.try_get::<Option<i32>, _>("state").ok().flatten(),
+            "close_time": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("close_time").ok().flatten(),
+            "start_time": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("start_time").ok().flatten(),
+            "end_time": row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>("end_time").ok().flatten(),
+            "odds_home_bps": row.try_get::<Option<i32>, _>("odds_home_bps").ok().flatten(),
+            "odds_away_bps": row.try_get::<Option<i32>, _>("odds_away_bps").ok().flatten(),
+            "winning_option": row.try_get::<Option<i16>, _>("winning_option").ok().flatten(),
+        });
+        Ok(Some((event_type, data, None)))
+    }
+
+    async fn describe_order(&self, order_pk: i64) -> Result<Option<(&'static str, serde_json::Value, Option<i64>)>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT o.id, o.order_id, o.status::TEXT AS status, o.amount::TEXT AS amount, o.odds::TEXT AS odds, o.option, o.created_at, \
+                    u.address, u.referrer_api_key_id, m.market_id \
+             FROM orders o JOIN users u ON u.id = o.user_id JOIN markets m ON m.id = o.market_id WHERE o.id = $1"
+        )
+        .bind(order_pk)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(row) = row else { return Ok(None) };
+        let data = serde_json::json!({
+            "id": row.try_get::<i64, _>("id").unwrap_or_default(),
+            "order_id": row.try_get::<i64, _>("order_id").unwrap_or_default(),
+            "market_id": row.try_get::<i64, _>("market_id").unwrap_or_default(),
+            "wallet_address": row.try_get::<String, _>("address").unwrap_or_default(),
+            "amount": row.try_get::<String, _>("amount").unwrap_or_default(),
+            "odds": row.try_get::<String, _>("odds").unwrap_or_default(),
+            "option": row.try_get::<i16, _>("option").unwrap_or_default(),
+            "status": row.try_get::<String, _>("status").unwrap_or_default(),
+            "created_at": row.try_get::<chrono::DateTime<chrono::Utc>, _>("created_at").ok(),
+        });
+        Ok(Some((EVENT_ORDER_PLACED, data, row.try_get::<Option<i64>, _>("referrer_api_key_id").ok().flatten())))
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for WebhookSubscriber {
+    fn name(&self) -> &'static str { "webhooks" }
+
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), String> {
+        let Some((event_type, data, referrer)) = self.describe(&event.event).await.map_err(|e| e.to_string())? else { return Ok(()) };
+        let payload = serde_json::json!({
+            "id": format!("evt_{}", event.id),
+            "type": event_type,
+            "created_at": event.created_at,
+            "data": data,
+        });
+        // Partner-scoped subscriptions see order events for their referred users only
+        sqlx::query(
+            "INSERT INTO webhook_deliveries (subscription_id, outbox_event_id, event_type, payload) \
+             SELECT s.id, $1, $2, $3 FROM webhook_subscriptions s \
+             WHERE s.active AND $2 = ANY(s.event_types) AND (s.api_key_id IS NULL OR $2 <> $5 OR s.api_key_id = $4) \
+             ON CONFLICT (subscription_id, outbox_event_id) DO NOTHING"
+        )
+        .bind(event.id)
+        .bind(event_type)
+        .bind(payload)
+        .bind(referrer)
+        .bind(EVENT_ORDER_PLACED)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+/// Sends pending deliveries, retrying failures with exponential backoff until MAX_ATTEMPTS
+#[derive(Clone)]
+pub struct WebhookWorker {
+    pool: PgPool,
+    client: reqwest::Client,
+}
+
+impl WebhookWorker {
+    pub fn new(pool: PgPool) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .user_agent("kmarket-webhooks/1")
+            .build()
+            .expect("reqwest client");
+        Self { pool, client }
+    }
+
+    /// Claim due deliveries (skipping rows another worker holds), send them and record the
+    /// outcome. Returns the number of deliveries attempted.
+    pub async fn run_once(&self) -> Result<usize, sqlx::Error> {
+        let rows = sqlx::query(
+            "WITH claimed AS ( \
+                UPDATE webhook_deliveries SET attempts = attempts + 1, last_attempt_at = NOW(), next_attempt_at = NOW() + $2 * INTERVAL '1 second' \
+                WHERE id IN (SELECT id FROM webhook_deliveries WHERE status = 'pending' AND next_attempt_at <= NOW() \
+                             ORDER BY id LIMIT $1 FOR UPDATE SKIP LOCKED) \
+                RETURNING id, subscription_id, event_type, payload, attempts) \
+             SELECT c.id, c.event_type, c.payload, c.attempts, s.url, s.secret, s.active \
+             FROM claimed c JOIN webhook_subscriptions s ON s.id = c.subscription_id ORDER BY c.id"
+        )
+        .bind(BATCH_SIZE)
+        .bind(CLAIM_LEASE_SECS)
+        .fetch_all(&self.pool)
+        .await?;
+        let claimed = rows.len();
+
+        for r in rows {
+            let id: i64 = r.try_get("id").unwrap_or_default();
+            let attempts: i32 = r.try_get("attempts").unwrap_or_default();
+            let outcome = if r.try_get::<bool, _>("active").unwrap_or(false) {
+                self.send(
+                    id,
+                    &r.try_get::<String, _>("url").unwrap_or_default(),
+                    &r.try_get::<String, _>("secret").unwrap_or_default(),
+                    &r.try_get::<String, _>("event_type").unwrap_or_default(),
+                    &r.try_get::<serde_json::Value, _>("payload").unwrap_or_default(),
+                ).await
+            } else {
+                (None, Err("subscription disabled".to_string()))
+            };
+            match outcome {
+                (code, Ok(())) => {
+                    sqlx::query("UPDATE webhook_deliveries SET status = 'delivered', delivered_at = NOW(), last_status_code = $2, last_error = NULL WHERE id = $1")
+                        .bind(id)
+                        .bind(code)
+                        .execute(&self.pool)
+                        .await?;
+                }
+                (code, Err(error)) => {
+                    let status = if attempts >= MAX_ATTEMPTS { "dead" } else { "pending" };
+                    tracing::warn!(target: "kmarket_backend", "webhook delivery {} failed (attempt {}): {}", id, attempts, error);
+                    sqlx::query("UPDATE webhook_deliveries SET status = $2, last_status_code = $3, last_error = $4, next_attempt_at = NOW() + $5 * INTERVAL '1 second' WHERE id = $1")
+                        .bind(id)
+                        .bind(status)
+                        .bind(code)
+                        .bind(error)
+                        .bind(retry_delay_secs(attempts))
+                        .execute(&self.pool)
+                        .await?;
+                }
+            }
+        }
+        Ok(claimed)
+    }
+
+    /// POST one delivery; any 2xx is success
+    async fn send(&self, id: i64, url: &str, secret: &str, event_type: &str, payload: &serde_json::Value) -> (Option<i32>, Result<(), String>) {
+        let body = payload.to_string();
+        let timestamp = chrono::Utc::now().timestamp();
+        let resp = self.client.post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(HEADER_SIGNATURE, signature_header(secret, timestamp, body.as_bytes()))
+            .header(HEADER_EVENT, event_type)
+            .header(HEADER_DELIVERY, id.to_string())
+            .body(body)
+            .send()
+            .await;
+        match resp {
+            Ok(resp) => {
+                let code = resp.status().as_u16() as i32;
+                if resp.status().is_success() { return (Some(code), Ok(())); }
+                let mut text = resp.text().await.unwrap_or_default();
+                text.truncate(text.char_indices().nth(MAX_ERROR_LEN).map(|(i, _)| i).unwrap_or(text.len()));
+                (Some(code), Err(format!("HTTP {}: {}", code, text)))
+            }
+            Err(e) => (None, Err(e.to_string())),
+        }
+    }
+
+    /// Background loop: drain due deliveries, then wait `idle` before polling again
+    pub fn spawn(self, idle: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match self.run_once().await {
+                    Ok(n) if n > 0 => continue,
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!(target: "kmarket_backend", "webhook worker: {}", e),
+                }
+                tokio::time::sleep(idle).await;
+            }
+        })
+    }
+}
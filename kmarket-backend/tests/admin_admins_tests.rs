@@ -0,0 +1,103 @@
+use actix_web::{test, web, App, http::StatusCode};
+use kmarket_backend::routes::{admin_admins, admin_auth};
+use kmarket_backend::state::AppState;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+async fn seed_admin(pool: &sqlx::PgPool, email: &str, password: &str, role: &str) -> i64 {
+    use argon2::{Argon2, password_hash::{SaltString, PasswordHasher}};
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(password.as_bytes(), &salt).unwrap().to_string();
+    sqlx::query_scalar("INSERT INTO admin_users (email, password_hash, salt, status, role) VALUES ($1, $2, $3, 'active', $4) RETURNING id")
+        .bind(email).bind(hash).bind(salt.to_string()).bind(role)
+        .fetch_one(pool).await.unwrap()
+}
+
+#[actix_rt::test]
+async fn test_invite_forced_reset_and_role_enforcement() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let root_email = format!("root{}@kmarket.local", suffix);
+    seed_admin(&pool, &root_email, "rootpass1", "admin").await;
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/login", web::post().to(admin_auth::login))
+            .route("/admins", web::get().to(admin_admins::list_admins))
+            .route("/admins", web::post().to(admin_admins::invite_admin))
+            .route("/admins/login-attempts", web::get().to(admin_admins::list_login_attempts))
+            .route("/admins/me/password", web::post().to(admin_admins::change_own_password))
+            .route("/admins/{id}/status", web::put().to(admin_admins::set_admin_status))
+    ).await;
+
+    let login = |email: String, password: &'static str| test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": email, "password": password})).to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, login(root_email.clone(), "rootpass1")).await;
+    let root_token = resp["data"]["token"].as_str().unwrap().to_string();
+
+    // invite an operator
+    let new_email = format!("ops{}@kmarket.local", suffix);
+    let req = test::TestRequest::post().uri("/admins")
+        .insert_header(("Authorization", format!("Bearer {}", root_token)))
+        .set_json(serde_json::json!({"email": new_email, "role": "operator"})).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["data"]["must_reset_password"], true);
+    let new_id = body["data"]["id"].as_i64().unwrap();
+    let temp = body["data"]["temporary_password"].as_str().unwrap().to_string();
+
+    // duplicate invite is a conflict
+    let req = test::TestRequest::post().uri("/admins")
+        .insert_header(("Authorization", format!("Bearer {}", root_token)))
+        .set_json(serde_json::json!({"email": new_email, "role": "operator"})).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+
+    // invitee logs in with the temporary password but is blocked until it is changed
+    let req = test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": new_email, "password": temp})).to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["password_reset_required"], true);
+    let ops_token = resp["data"]["token"].as_str().unwrap().to_string();
+    let req = test::TestRequest::get().uri("/admins").insert_header(("Authorization", format!("Bearer {}", ops_token))).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    let req = test::TestRequest::post().uri("/admins/me/password")
+        .insert_header(("Authorization", format!("Bearer {}", ops_token)))
+        .set_json(serde_json::json!({"current_password": "wrong", "new_password": "operator-pass"})).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+    let req = test::TestRequest::post().uri("/admins/me/password")
+        .insert_header(("Authorization", format!("Bearer {}", ops_token)))
+        .set_json(serde_json::json!({"current_password": temp, "new_password": "operator-pass"})).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    // reads now work, but an operator cannot manage admins
+    let req = test::TestRequest::get().uri("/admins").insert_header(("Authorization", format!("Bearer {}", ops_token))).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    let req = test::TestRequest::post().uri("/admins")
+        .insert_header(("Authorization", format!("Bearer {}", ops_token)))
+        .set_json(serde_json::json!({"email": format!("x{}@kmarket.local", suffix)})).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    // disabling the operator revokes access immediately
+    let req = test::TestRequest::put().uri(&format!("/admins/{}/status", new_id))
+        .insert_header(("Authorization", format!("Bearer {}", root_token)))
+        .set_json(serde_json::json!({"status": "disabled"})).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    let req = test::TestRequest::get().uri("/admins").insert_header(("Authorization", format!("Bearer {}", ops_token))).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    // login attempts are listed from the audit log
+    let req = test::TestRequest::get().uri(&format!("/admins/login-attempts?email={}", new_email))
+        .insert_header(("Authorization", format!("Bearer {}", root_token))).to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let items = resp["data"]["items"].as_array().unwrap();
+    assert!(items.iter().any(|i| i["outcome"] == "login_success" && i["admin_id"] == new_id));
+
+    let actions: Vec<String> = sqlx::query_scalar("SELECT action FROM audit_logs WHERE resource = 'admin_users' AND resource_id = $1")
+        .bind(new_id).fetch_all(&pool).await.unwrap();
+    for a in ["admin.admin_invite", "admin.password_change", "admin.admin_status"] {
+        assert!(actions.iter().any(|x| x == a), "missing audit {}", a);
+    }
+}
@@ -0,0 +1,114 @@
+use actix_web::{http::StatusCode, test, web, App};
+use kmarket_backend::routes::{admin_api_keys, admin_auth, partner};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::api_keys::{sign_request, string_to_sign};
+#[path = "common/helpers.rs"]
+mod helpers;
+
+#[actix_rt::test]
+async fn test_signature_is_bound_to_request() {
+    let sig = sign_request("secret", "post", "/partner/orders?x=1", 1_700_000_000, "nonce-123", b"{}");
+    assert_eq!(sig.len(), 64);
+    assert_eq!(sig, sign_request("secret", "POST", "/partner/orders?x=1", 1_700_000_000, "nonce-123", b"{}"));
+    assert_ne!(sig, sign_request("secret", "POST", "/partner/orders?x=2", 1_700_000_000, "nonce-123", b"{}"));
+    assert_ne!(sig, sign_request("secret", "POST", "/partner/orders?x=1", 1_700_000_001, "nonce-123", b"{}"));
+    assert_ne!(sig, sign_request("secret", "POST", "/partner/orders?x=1", 1_700_000_000, "nonce-123", b"{ }"));
+    assert_ne!(sig, sign_request("other", "POST", "/partner/orders?x=1", 1_700_000_000, "nonce-123", b"{}"));
+    assert!(string_to_sign("get", "/p", 1, "n", b"").starts_with("GET\n/p\n1\nn\n"));
+}
+
+fn signed(method: &str, uri: &str, key_id: &str, secret: &str, nonce: &str, body: &[u8]) -> test::TestRequest {
+    let ts = chrono::Utc::now().timestamp();
+    let sig = sign_request(secret, method, uri, ts, nonce, body);
+    let req = if method == "POST" { test::TestRequest::post() } else { test::TestRequest::get() };
+    req.uri(uri)
+        .insert_header(("X-Api-Key", key_id.to_string()))
+        .insert_header(("X-Api-Timestamp", ts.to_string()))
+        .insert_header(("X-Api-Nonce", nonce.to_string()))
+        .insert_header(("X-Api-Signature", sig))
+        .insert_header(("Content-Type", "application/json"))
+        .set_payload(body.to_vec())
+}
+
+#[actix_rt::test]
+async fn test_partner_key_lifecycle() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    use argon2::{Argon2, password_hash::{SaltString, PasswordHasher}};
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let email = format!("keys{}@kmarket.local", suffix);
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(b"keyspass1", &salt).unwrap().to_string();
+    sqlx::query("INSERT INTO admin_users (email, password_hash, salt, status, role) VALUES ($1, $2, $3, 'active', 'admin')")
+        .bind(&email).bind(hash).bind(salt.to_string()).execute(&pool).await.unwrap();
+    let market_id = suffix % 1_000_000_000;
+    sqlx::query("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status) VALUES ($1, 'Partner', 'A', 'B', NOW(), NOW() + INTERVAL '1 day', 'active')")
+        .bind(market_id).execute(&pool).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/login", web::post().to(admin_auth::login))
+            .route("/admin/api-keys", web::post().to(admin_api_keys::create_key))
+            .route("/admin/api-keys/{id}/revoke", web::post().to(admin_api_keys::revoke_key))
+            .route("/admin/api-keys/{id}/usage", web::get().to(admin_api_keys::get_key_usage))
+            .route("/partner/markets", web::get().to(partner::list_markets))
+            .route("/partner/orders", web::post().to(partner::place_order))
+            .route("/partner/reports/summary", web::get().to(partner::report_summary))
+    ).await;
+
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": email, "password": "keyspass1"})).to_request()).await;
+    let bearer = format!("Bearer {}", resp["data"]["token"].as_str().unwrap());
+
+    let req = test::TestRequest::post().uri("/admin/api-keys").insert_header(("Authorization", bearer.clone()))
+        .set_json(serde_json::json!({"name": "bot", "scopes": ["markets:read", "orders:write"]})).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let id = body["data"]["id"].as_i64().unwrap();
+    let key_id = body["data"]["key_id"].as_str().unwrap().to_string();
+    let secret = body["data"]["secret"].as_str().unwrap().to_string();
+
+    // signed read succeeds; replaying the same nonce does not
+    let req = signed("GET", "/partner/markets?page=1", &key_id, &secret, "nonce-0001", b"").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    let req = signed("GET", "/partner/markets?page=1", &key_id, &secret, "nonce-0001", b"").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+    // wrong secret and missing scope are rejected
+    let req = signed("GET", "/partner/markets", &key_id, "not-the-secret", "nonce-0002", b"").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+    let req = signed("GET", "/partner/reports/summary", &key_id, &secret, "nonce-0003", b"").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    // order on behalf of a wallet; the signature covers the body
+    let order = serde_json::to_vec(&serde_json::json!({
+        "wallet_address": format!("0xpartner{}", suffix), "market_id": market_id, "amount": 5.0, "odds": 1.8, "option": 0, "client_order_id": "ref-1"
+    })).unwrap();
+    let req = signed("POST", "/partner/orders", &key_id, &secret, "nonce-0004", &order).to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["success"], true);
+    let tampered = signed("POST", "/partner/orders", &key_id, &secret, "nonce-0005", &order).set_payload(b"{}".to_vec()).to_request();
+    assert_eq!(test::call_service(&app, tampered).await.status(), StatusCode::UNAUTHORIZED);
+    // a retry gets the same order back; the reference on a different order is a conflict
+    let req = signed("POST", "/partner/orders", &key_id, &secret, "nonce-0006", &order).to_request();
+    let retried: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((retried["data"]["id"].clone(), retried["data"]["order_id"].clone()), (resp["data"]["id"].clone(), resp["data"]["order_id"].clone()));
+    let other = serde_json::to_vec(&serde_json::json!({
+        "wallet_address": format!("0xpartner{}", suffix), "market_id": market_id, "amount": 6.0, "odds": 1.8, "option": 0, "client_order_id": "ref-1"
+    })).unwrap();
+    let req = signed("POST", "/partner/orders", &key_id, &secret, "nonce-0008", &other).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "DUPLICATE_ORDER");
+
+    let req = test::TestRequest::get().uri(&format!("/admin/api-keys/{}/usage", id)).insert_header(("Authorization", bearer.clone())).to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["key"]["request_count"], 4);
+    assert_eq!(resp["data"]["daily"][0]["requests"], 4);
+
+    let req = test::TestRequest::post().uri(&format!("/admin/api-keys/{}/revoke", id)).insert_header(("Authorization", bearer)).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    let req = signed("GET", "/partner/markets", &key_id, &secret, "nonce-0007", b"").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+}
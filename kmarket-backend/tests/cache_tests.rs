@@ -0,0 +1,104 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::{test, web, App};
+use kmarket_backend::models::event::{DomainEvent, OutboxEvent};
+use kmarket_backend::routes::{compat, sports};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::cache::{self, CacheSubscriber, ResponseCache, NS_FIXTURES, NS_MARKETS};
+use kmarket_backend::utils::conditional::Representation;
+use kmarket_backend::utils::outbox::EventSubscriber;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+#[actix_rt::test]
+async fn test_memory_cache_ttl_lru_and_namespaces() {
+    let cache = ResponseCache::memory(2, Duration::from_secs(60));
+    let rep = |body: &str| Representation::new(body.to_string(), None);
+    let body = |r: Option<Representation>| r.map(|r| r.body);
+    let (a, b, c) = (cache::key(NS_FIXTURES, &1), cache::key(NS_FIXTURES, &2), cache::key(NS_MARKETS, &3));
+    assert_eq!(cache::key(NS_FIXTURES, &serde_json::json!({"page": 1})), cache::key(NS_FIXTURES, &serde_json::json!({"page": 1})));
+    assert_ne!(cache::key(NS_FIXTURES, &1), cache::key(NS_MARKETS, &1));
+
+    cache.set(&a, rep("a")).await;
+    cache.set(&b, rep("b")).await;
+    // touching a leaves b as the least recently used entry
+    assert_eq!(body(cache.get(&a).await).as_deref(), Some("a"));
+    cache.set(&c, rep("c")).await;
+    assert!(cache.get(&b).await.is_none());
+    assert_eq!((body(cache.get(&a).await).as_deref(), body(cache.get(&c).await).as_deref()), (Some("a"), Some("c")));
+
+    cache.invalidate(NS_FIXTURES).await;
+    assert_eq!((body(cache.get(&a).await), body(cache.get(&c).await).as_deref()), (None, Some("c")));
+
+    let short = ResponseCache::memory(10, Duration::from_millis(50));
+    short.set(&a, rep("a")).await;
+    assert!(short.get(&a).await.is_some());
+    tokio::time::sleep(Duration::from_millis(80)).await;
+    assert!(short.get(&a).await.is_none());
+
+    let off = ResponseCache::disabled();
+    off.set(&a, rep("a")).await;
+    assert!(!off.is_enabled() && off.get(&a).await.is_none());
+}
+
+#[actix_rt::test]
+async fn test_fixture_and_market_lists_are_cached_until_invalidated() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let market_id = suffix % 1_000_000_000;
+    let title = format!("Cache Cup {}", suffix);
+    let market_pk: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status, odds_home_bps, odds_away_bps) VALUES ($1, $2, 'A', 'B', NOW() + INTERVAL '1 day', NOW() + INTERVAL '2 days', 'active', 18000, 20000) RETURNING id")
+        .bind(market_id).bind(&title).fetch_one(&pool).await.unwrap();
+
+    let state = AppState { cache: Arc::new(ResponseCache::memory(100, Duration::from_secs(60))), ..AppState::from_pool(pool.clone()) };
+    let subscriber = CacheSubscriber::new(state.cache.clone());
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(state))
+            .route("/sports/fixtures", web::get().to(sports::get_fixtures))
+            .route("/compat/markets", web::get().to(compat::get_frontend_markets))
+    ).await;
+    let get = |uri: String| {
+        let app = &app;
+        async move {
+            let resp = test::call_service(app, test::TestRequest::get().uri(&uri).to_request()).await;
+            let cache_header = resp.headers().get("X-Cache").and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            (cache_header, body)
+        }
+    };
+    let odds = |body: &serde_json::Value| body["data"]["fixtures"][0]["preOdds"].clone();
+    let q = format!("q={}", title.replace(' ', "%20"));
+
+    let (hit, first) = get(format!("/sports/fixtures?{}", q)).await;
+    assert_eq!((hit.as_str(), first["data"]["fixtures"].as_array().unwrap().len()), ("MISS", 1));
+    // defaults resolve to the same entry
+    let (hit, again) = get(format!("/sports/fixtures?{}&page=1&limit=20", q)).await;
+    assert_eq!((hit.as_str(), &again), ("HIT", &first));
+
+    // an update is not visible until its event invalidates the namespace
+    sqlx::query("UPDATE markets SET odds_home_bps = 25000 WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    let (hit, stale) = get(format!("/sports/fixtures?{}", q)).await;
+    assert_eq!((hit.as_str(), odds(&stale)), ("HIT", odds(&first)));
+    let (hit, _) = get("/compat/markets?page_size=100".into()).await;
+    assert_eq!(hit, "MISS");
+    let (hit, _) = get("/compat/markets?page_size=100".into()).await;
+    assert_eq!(hit, "HIT");
+
+    let event = DomainEvent::MarketUpdated { id: market_pk, fields: vec!["odds_home_bps".into()] };
+    subscriber.handle(&OutboxEvent { id: 0, event, attempts: 1, created_at: chrono::Utc::now() }).await.unwrap();
+    let (hit, fresh) = get(format!("/sports/fixtures?{}", q)).await;
+    assert_eq!(hit, "MISS");
+    assert_ne!(odds(&fresh), odds(&first));
+    let (hit, _) = get("/compat/markets?page_size=100".into()).await;
+    assert_eq!(hit, "MISS");
+
+    // match state only touches fixtures
+    let event = DomainEvent::MatchStateChanged { id: market_pk, phase: "live".into() };
+    subscriber.handle(&OutboxEvent { id: 0, event, attempts: 1, created_at: chrono::Utc::now() }).await.unwrap();
+    assert_eq!(get(format!("/sports/fixtures?{}", q)).await.0, "MISS");
+    assert_eq!(get("/compat/markets?page_size=100".into()).await.0, "HIT");
+
+    sqlx::query("DELETE FROM markets WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+}
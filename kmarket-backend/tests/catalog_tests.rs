@@ -0,0 +1,167 @@
+use actix_web::{http::StatusCode, test, web, App};
+use kmarket_backend::routes::{admin_auth, admin_catalog, admin_markets, sports};
+use kmarket_backend::state::AppState;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+#[actix_rt::test]
+async fn test_catalog_crud_and_fixture_filters() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    use argon2::{Argon2, password_hash::{SaltString, PasswordHasher}};
+    let suffix = chrono::Utc::now().timestamp_micros();
+
+    let email = format!("catalog{}@kmarket.local", suffix);
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(b"catalogpass", &salt).unwrap().to_string();
+    sqlx::query("INSERT INTO admin_users (email, password_hash, salt, status, role) VALUES ($1, $2, $3, 'active', 'operator')")
+        .bind(&email).bind(hash).bind(salt.to_string()).execute(&pool).await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/login", web::post().to(admin_auth::login))
+            .route("/admin/sports", web::get().to(admin_catalog::list_sports))
+            .route("/admin/sports", web::post().to(admin_catalog::create_sport))
+            .route("/admin/sports/{id}", web::put().to(admin_catalog::update_sport))
+            .route("/admin/sports/{id}", web::delete().to(admin_catalog::delete_sport))
+            .route("/admin/leagues", web::get().to(admin_catalog::list_leagues))
+            .route("/admin/leagues", web::post().to(admin_catalog::create_league))
+            .route("/admin/leagues/{id}", web::put().to(admin_catalog::update_league))
+            .route("/admin/leagues/{id}", web::delete().to(admin_catalog::delete_league))
+            .route("/admin/teams", web::get().to(admin_catalog::list_teams))
+            .route("/admin/teams", web::post().to(admin_catalog::create_team))
+            .route("/admin/teams/{id}", web::put().to(admin_catalog::update_team))
+            .route("/admin/teams/{id}", web::delete().to(admin_catalog::delete_team))
+            .route("/admin/markets", web::post().to(admin_markets::create_market))
+            .route("/admin/markets/{id}", web::put().to(admin_markets::update_market))
+            .route("/sports/fixtures", web::get().to(sports::get_fixtures))
+    ).await;
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": email, "password": "catalogpass"})).to_request()).await;
+    let bearer = format!("Bearer {}", resp["data"]["token"].as_str().unwrap());
+    let call = |method: &str, uri: String, body: Option<serde_json::Value>| {
+        let req = match method {
+            "POST" => test::TestRequest::post(),
+            "PUT" => test::TestRequest::put(),
+            "DELETE" => test::TestRequest::delete(),
+            _ => test::TestRequest::get(),
+        }.uri(&uri).insert_header(("Authorization", bearer.clone()));
+        let req = match body { Some(b) => req.set_json(b), None => req };
+        test::call_service(&app, req.to_request())
+    };
+    let json = |resp: actix_web::dev::ServiceResponse| async move {
+        let status = resp.status();
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        (status, body)
+    };
+
+    // seeded catalog
+    let (_, body) = json(call("GET", "/admin/sports".into(), None).await).await;
+    assert!(body["data"]["items"].as_array().unwrap().iter().any(|s| s["code"] == "basketball"));
+
+    // sport -> league -> teams
+    let (status, body) = json(call("POST", "/admin/sports".into(), Some(serde_json::json!({"code": format!("Curling{}", suffix), "name": format!("Curling {}", suffix)}))).await).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let sport_id = body["data"]["id"].as_i64().unwrap();
+    assert_eq!(body["data"]["code"], format!("curling{}", suffix));
+    let (status, _) = json(call("POST", "/admin/sports".into(), Some(serde_json::json!({"code": format!("curling{}", suffix), "name": "Other"}))).await).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let league_code = format!("cl{}", suffix % 1_000_000_000);
+    let league_name = format!("Curling League {}", suffix);
+    let (status, body) = json(call("POST", "/admin/leagues".into(), Some(serde_json::json!({
+        "sport_id": sport_id, "code": league_code, "name": league_name, "country": "Scotland", "logo_url": "https://cdn.example.com/cl.png",
+    }))).await).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let league_id = body["data"]["id"].as_i64().unwrap();
+    assert_eq!(body["data"]["code"], league_code.to_uppercase());
+    let (status, _) = json(call("POST", "/admin/leagues".into(), Some(serde_json::json!({"sport_id": -1, "code": "X", "name": "X"}))).await).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = json(call("POST", "/admin/leagues".into(), Some(serde_json::json!({"sport_id": sport_id, "code": "Y", "name": "Y", "logo_url": "not a url"}))).await).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let mut team_ids = Vec::new();
+    for (name, code) in [("Stones", "STN"), ("Brooms", "BRM")] {
+        let (status, body) = json(call("POST", "/admin/teams".into(), Some(serde_json::json!({
+            "sport_id": sport_id, "name": format!("{} {}", name, suffix), "code": code, "logo_url": format!("https://cdn.example.com/{}.png", code),
+        }))).await).await;
+        assert_eq!(status, StatusCode::CREATED);
+        team_ids.push(body["data"]["id"].as_i64().unwrap());
+    }
+    let (home, away) = (team_ids[0], team_ids[1]);
+    let (_, body) = json(call("PUT", format!("/admin/teams/{}", home), Some(serde_json::json!({"name": format!("Granite Stones {}", suffix)}))).await).await;
+    assert_eq!((body["data"]["name"].as_str(), body["data"]["code"].as_str()), (Some(format!("Granite Stones {}", suffix).as_str()), Some("STN")));
+    let (_, body) = json(call("GET", format!("/admin/teams?sport_id={}&q=brm", sport_id), None).await).await;
+    assert_eq!(body["data"]["items"].as_array().unwrap().len(), 1);
+    let (_, body) = json(call("GET", format!("/admin/leagues?sport_id={}", sport_id), None).await).await;
+    assert_eq!(body["data"]["items"][0]["id"].as_i64(), Some(league_id));
+
+    // a basketball team cannot play in the curling league
+    let nba_team: i64 = sqlx::query_scalar("INSERT INTO teams (sport_id, name) SELECT id, $1 FROM sports WHERE code = 'basketball' RETURNING id")
+        .bind(format!("Hoopers {}", suffix)).fetch_one(&pool).await.unwrap();
+    let market = |market_id: i64, home_team: i64| serde_json::json!({
+        "market_id": market_id, "title": format!("Curling Final {}", suffix), "option_a": "Home", "option_b": "Away",
+        "start_time": chrono::Utc::now() + chrono::Duration::days(3), "end_time": chrono::Utc::now() + chrono::Duration::days(3) + chrono::Duration::hours(3),
+        "status": "pending", "league_id": league_id, "home_team_id": home_team, "away_team_id": away,
+    });
+    let market_id = suffix % 1_000_000_000;
+    let (status, body) = json(call("POST", "/admin/markets".into(), Some(market(market_id, nba_team))).await).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_catalog_ref")));
+    let (status, _) = json(call("POST", "/admin/markets".into(), Some(market(market_id, away))).await).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = json(call("POST", "/admin/markets".into(), Some(market(market_id, home))).await).await;
+    assert_eq!(status, StatusCode::OK);
+    let market_pk = body["data"]["id"].as_i64().unwrap();
+    let home_name: Option<String> = sqlx::query_scalar("SELECT home_name FROM markets WHERE id = $1").bind(market_pk).fetch_one(&pool).await.unwrap();
+    assert_eq!(home_name, Some(format!("Granite Stones {}", suffix)));
+    let (status, _) = json(call("PUT", format!("/admin/markets/{}", market_pk), Some(serde_json::json!({"away_team_id": nba_team}))).await).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // fixtures carry catalog ids and filter on ids or on the old sport / league strings
+    let fixtures = |query: String| {
+        let app = &app;
+        async move {
+            let req = test::TestRequest::get().uri(&format!("/sports/fixtures?{}", query)).to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(app, req).await;
+            body["data"]["fixtures"].as_array().unwrap().clone()
+        }
+    };
+    let found = fixtures(format!("league_id={}", league_id)).await;
+    assert_eq!(found.len(), 1);
+    let f = &found[0];
+    assert_eq!((f["sport"].as_str(), f["league"].as_str()), (Some(format!("Curling {}", suffix).as_str()), Some(league_name.as_str())));
+    assert_eq!((f["sportId"].as_i64(), f["leagueId"].as_i64(), f["homeTeamId"].as_i64(), f["awayTeamId"].as_i64()), (Some(sport_id), Some(league_id), Some(home), Some(away)));
+    assert_eq!((f["homeTeam"].as_str(), f["homeTeamLogo"].as_str()), (Some(format!("Granite Stones {}", suffix).as_str()), Some("https://cdn.example.com/STN.png")));
+    for query in [
+        format!("sport_id={}", sport_id),
+        format!("team_id={}", away),
+        format!("sport=CURLING{}", suffix),
+        format!("sport={}", league_code),
+        format!("league={}", league_name.replace(' ', "%20")),
+        format!("league={}&status=pre", league_code.to_uppercase()),
+    ] {
+        assert_eq!(fixtures(query.clone()).await.len(), 1, "{}", query);
+    }
+    assert!(fixtures(format!("team_id={}", nba_team)).await.is_empty());
+    assert!(fixtures(format!("league={}&sport=basketball", league_code)).await.is_empty());
+
+    // in-use catalog rows cannot be deleted
+    let (status, body) = json(call("DELETE", format!("/admin/teams/{}", home), None).await).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::CONFLICT, Some("in_use")));
+    let (status, _) = json(call("DELETE", format!("/admin/leagues/{}", league_id), None).await).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = json(call("PUT", format!("/admin/leagues/{}", league_id), Some(serde_json::json!({"sport_id": 1}))).await).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    sqlx::query("DELETE FROM markets WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    for uri in [format!("/admin/teams/{}", home), format!("/admin/teams/{}", away), format!("/admin/leagues/{}", league_id), format!("/admin/sports/{}", sport_id)] {
+        let (status, _) = json(call("DELETE", uri.clone(), None).await).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+    }
+    let (status, _) = json(call("DELETE", format!("/admin/sports/{}", sport_id), None).await).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let audits: Vec<String> = sqlx::query_scalar("SELECT action FROM audit_logs WHERE resource IN ('sports', 'leagues', 'teams') AND resource_id = ANY($1) ORDER BY id")
+        .bind(vec![sport_id, league_id, home, away]).fetch_all(&pool).await.unwrap();
+    for action in ["admin.sport_create", "admin.league_create", "admin.team_create", "admin.team_update", "admin.team_delete", "admin.league_delete", "admin.sport_delete"] {
+        assert!(audits.iter().any(|a| a == action), "{} missing from {:?}", action, audits);
+    }
+}
@@ -0,0 +1,142 @@
+use actix_web::{http::StatusCode, test, web, App};
+use bigdecimal::BigDecimal;
+use kmarket_backend::repository::order_repo::OrderRepository;
+use kmarket_backend::routes::{admin_fees, admin_orders, compat};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::errors::DataAccessError;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+fn dec(v: &serde_json::Value) -> BigDecimal {
+    v.as_str().unwrap_or_else(|| panic!("not a decimal string: {}", v)).parse().unwrap()
+}
+
+fn d(s: &str) -> BigDecimal {
+    s.parse().unwrap()
+}
+
+#[actix_rt::test]
+async fn test_claims_credit_once_and_unclaimed_liabilities() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let code = format!("K{}", suffix % 1_000_000_000);
+    let bearer = helpers::admin_bearer(&pool, "claims", suffix).await;
+
+    sqlx::query("INSERT INTO currencies (code, name, decimals) VALUES ($1, 'Claim test', 2)").bind(&code).execute(&pool).await.unwrap();
+    let market_pk: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status, currency) VALUES ($1, 'Claim Cup', 'A', 'B', NOW() - INTERVAL '2 days', NOW() - INTERVAL '1 day', 'settled', $2) RETURNING id")
+        .bind(suffix % 1_000_000_000).bind(&code).fetch_one(&pool).await.unwrap();
+    let address = format!("0xclaims{}", suffix);
+    let user_id: i64 = sqlx::query_scalar("INSERT INTO users (address) VALUES ($1) RETURNING id").bind(&address).fetch_one(&pool).await.unwrap();
+    // won 15.00, lost, still open, won 8.00 and won 4.00; closed an hour apart, oldest first
+    let orders = [
+        ("10.00", "settled", Some("15.00")),
+        ("10.00", "settled", Some("0")),
+        ("10.00", "placed", None),
+        ("5.00", "settled", Some("8.00")),
+        ("2.00", "settled", Some("4.00")),
+    ];
+    let mut ids = Vec::new();
+    for (i, (amount, status, close_price)) in orders.into_iter().enumerate() {
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO orders (order_id, user_id, market_id, amount, odds, option, status, close_price, close_pnl, closed_at) \
+             VALUES ($1, $2, $3, $4, 1.5, 0, $5::order_status, $6, $6 - $4, NOW() - make_interval(hours => $7)) RETURNING id"
+        )
+        .bind(suffix % 1_000_000_000 * 10 + i as i64).bind(user_id).bind(market_pk).bind(d(amount)).bind(status)
+        .bind(close_price.map(d)).bind(10 - i as i32)
+        .fetch_one(&pool).await.unwrap();
+        ids.push(id);
+    }
+    let (won, lost, open, won_onchain, won_last) = (ids[0], ids[1], ids[2], ids[3], ids[4]);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/compat/positions/{id}/claim", web::post().to(compat::claim_frontend_position))
+            .route("/compat/users/{address}/positions", web::get().to(compat::get_frontend_positions))
+            .route("/admin/claims/unclaimed", web::get().to(admin_orders::list_unclaimed))
+            .route("/admin/orders/{id}/settle", web::post().to(admin_orders::settle_order))
+            .route("/admin/orders/{id}/claim", web::post().to(admin_orders::claim_order))
+            .route("/admin/ledger", web::get().to(admin_fees::list_ledger))
+    ).await;
+    let send = |req: test::TestRequest| {
+        let (app, bearer) = (&app, bearer.clone());
+        async move {
+            let resp = test::call_service(app, req.insert_header(("Authorization", bearer)).to_request()).await;
+            let status = resp.status();
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            (status, body)
+        }
+    };
+    let claim = |id: i64, wallet: &str| send(test::TestRequest::post().uri(&format!("/compat/positions/{}/claim", id))
+        .set_json(serde_json::json!({"wallet_address": wallet})));
+
+    // liabilities: the three winning positions, oldest first
+    let (status, body) = send(test::TestRequest::get().uri(&format!("/admin/claims/unclaimed?currency={}&with_total=true", code.to_lowercase()))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!((body["data"]["totals"][0]["positions"].as_i64(), dec(&body["data"]["totals"][0]["amount"])), (Some(3), d("27.00")));
+    let listed: Vec<i64> = body["data"]["items"].as_array().unwrap().iter().map(|i| i["id"].as_i64().unwrap()).collect();
+    assert_eq!(listed, [won, won_onchain, won_last]);
+
+    // only the owner's settled winners are claimable
+    let (status, body) = claim(won, "0xsomeoneelse").await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::NOT_FOUND, Some("POSITION_NOT_FOUND")));
+    for id in [lost, open] {
+        let (status, body) = claim(id, &address).await;
+        assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::CONFLICT, Some("NOT_CLAIMABLE")));
+    }
+
+    // a claim credits the balance in the order's currency, once
+    let (status, body) = claim(won, &address).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!((dec(&body["data"]["amount"]), dec(&body["data"]["balance"]), body["data"]["currency"].as_str()), (d("15.00"), d("15.00"), Some(code.as_str())));
+    let (status, body) = claim(won, &address).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::CONFLICT, Some("ALREADY_CLAIMED")));
+
+    // only an admin records an on-chain payout, which leaves the balance alone
+    let signature = format!("sig{}", suffix);
+    let (status, body) = send(test::TestRequest::post().uri(&format!("/compat/positions/{}/claim", won_onchain))
+        .set_json(serde_json::json!({"wallet_address": address, "transaction_signature": signature}))).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::BAD_REQUEST, Some("INVALID_ARGS")));
+    let onchain = |id: i64| test::TestRequest::post().uri(&format!("/admin/orders/{}/claim", id)).set_json(serde_json::json!({"transaction_signature": signature}));
+    let resp = test::call_service(&app, onchain(won_onchain).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let (status, body) = send(onchain(won_onchain)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!((dec(&body["data"]["amount"]), body["data"]["balance"].is_null()), (d("8.00"), true));
+    let audited: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_logs WHERE action = 'admin.order_claim' AND resource_id = $1").bind(won_onchain).fetch_one(&pool).await.unwrap();
+    assert_eq!(audited, 1);
+    // one payout transaction pays one claim
+    let (status, body) = send(onchain(won_last)).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::CONFLICT, Some("duplicate_key")));
+
+    // racing claims: one wins, the other sees it claimed
+    let repo = OrderRepository::new(pool.clone());
+    let (a, b) = tokio::join!(repo.claim(won_last, &address), repo.claim(won_last, &address));
+    assert_eq!([&a, &b].iter().filter(|r| r.is_ok()).count(), 1);
+    assert!([a, b].into_iter().any(|r| matches!(r, Err(DataAccessError::AlreadyClaimed(id)) if id == won_last)));
+    let balance: BigDecimal = sqlx::query_scalar("SELECT balance FROM user_balances WHERE user_id = $1 AND currency = $2").bind(user_id).bind(&code).fetch_one(&pool).await.unwrap();
+    assert_eq!(balance, d("19.00"));
+
+    // a paid order cannot be settled again
+    let (status, body) = send(test::TestRequest::post().uri(&format!("/admin/orders/{}/settle", won)).set_json(serde_json::json!({"close_price": "20.00"}))).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::CONFLICT, Some("already_claimed")));
+
+    let (_, body) = send(test::TestRequest::get().uri(&format!("/compat/users/{}/positions", address))).await;
+    let claimed: Vec<i64> = body["data"]["positions"].as_array().unwrap().iter()
+        .filter(|p| p["is_claimed"] == true).map(|p| p["id"].as_i64().unwrap()).collect();
+    assert_eq!(claimed.len(), 3);
+    assert!(claimed.contains(&won) && claimed.contains(&won_onchain) && claimed.contains(&won_last));
+
+    let (_, body) = send(test::TestRequest::get().uri(&format!("/admin/ledger?user_id={}&kind=payout", user_id))).await;
+    let entries = body["data"]["items"].as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert!(entries.iter().any(|e| e["order_id"] == won_onchain && e["detail"]["transaction_signature"] == signature.as_str()));
+
+    let (_, body) = send(test::TestRequest::get().uri(&format!("/admin/claims/unclaimed?currency={}", code))).await;
+    assert!(body["data"]["totals"].as_array().unwrap().is_empty() && body["data"]["items"].as_array().unwrap().is_empty(), "{}", body);
+
+    sqlx::query("DELETE FROM orders WHERE user_id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM markets WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM currencies WHERE code = $1").bind(&code).execute(&pool).await.unwrap();
+}
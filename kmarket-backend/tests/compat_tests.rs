@@ -1,5 +1,4 @@
 use sqlx::Row;
-use actix_rt;
 
 #[actix_rt::test]
 async fn test_compat_markets_and_positions() {
@@ -0,0 +1,89 @@
+use actix_web::{http::{header, StatusCode}, test, web, App};
+use kmarket_backend::routes::{admin_carousel, compat, markets, sports};
+use kmarket_backend::state::AppState;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+#[actix_rt::test]
+async fn test_read_endpoints_honour_etags_and_last_modified() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let market_id = suffix % 1_000_000_000;
+    let title = format!("Etag Derby {}", suffix);
+    let market_pk: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status, odds_home_bps, odds_away_bps) VALUES ($1, $2, 'A', 'B', NOW() + INTERVAL '1 day', NOW() + INTERVAL '2 days', 'active', 18000, 20000) RETURNING id")
+        .bind(market_id).bind(&title).fetch_one(&pool).await.unwrap();
+    let carousel_id = format!("etag{}", suffix);
+    sqlx::query("INSERT INTO carousel_items (id, title, image_url, href, \"order\", enabled) VALUES ($1, 'Banner', 'https://cdn.example.com/b.png', '/', 1, true)")
+        .bind(&carousel_id).execute(&pool).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/markets", web::get().to(markets::get_markets))
+            .route("/markets/{id}", web::get().to(markets::get_market_detail))
+            .route("/sports/fixtures", web::get().to(sports::get_fixtures))
+            .route("/compat/markets", web::get().to(compat::get_frontend_markets))
+            .route("/admin/carousel", web::get().to(admin_carousel::list_items))
+    ).await;
+    // (status, ETag, Last-Modified, Cache-Control, body length)
+    let get = |uri: String, headers: Vec<(header::HeaderName, String)>| {
+        let app = &app;
+        async move {
+            let mut req = test::TestRequest::get().uri(&uri);
+            for h in headers { req = req.insert_header(h); }
+            let resp = test::call_service(app, req.to_request()).await;
+            let h = |name: header::HeaderName| resp.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+            let meta = (resp.status(), h(header::ETAG), h(header::LAST_MODIFIED), h(header::CACHE_CONTROL));
+            let body = test::read_body(resp).await;
+            (meta.0, meta.1, meta.2, meta.3, body.len())
+        }
+    };
+
+    let detail = format!("/markets/{}", market_id);
+    let (status, etag, modified, cache_control, len) = get(detail.clone(), vec![]).await;
+    assert_eq!((status, cache_control.as_str()), (StatusCode::OK, "public, max-age=10"));
+    assert!(etag.starts_with('"') && !modified.is_empty() && len > 0);
+
+    let weak = format!("W/{}", etag);
+    for validator in [etag.clone(), weak, "*".to_string(), format!("\"other\", {}", etag)] {
+        let (status, same_etag, _, cache_control, len) = get(detail.clone(), vec![(header::IF_NONE_MATCH, validator.clone())]).await;
+        assert_eq!((status, len), (StatusCode::NOT_MODIFIED, 0), "{}", validator);
+        assert_eq!((same_etag.as_str(), cache_control.as_str()), (etag.as_str(), "public, max-age=10"));
+    }
+    assert_eq!(get(detail.clone(), vec![(header::IF_NONE_MATCH, "\"other\"".into())]).await.0, StatusCode::OK);
+    assert_eq!(get(detail.clone(), vec![(header::IF_MODIFIED_SINCE, modified.clone())]).await.0, StatusCode::NOT_MODIFIED);
+    let earlier = header::HttpDate::from(std::time::SystemTime::now() - std::time::Duration::from_secs(3600)).to_string();
+    assert_eq!(get(detail.clone(), vec![(header::IF_MODIFIED_SINCE, earlier.clone())]).await.0, StatusCode::OK);
+    // If-None-Match wins over If-Modified-Since
+    assert_eq!(get(detail.clone(), vec![(header::IF_NONE_MATCH, "\"other\"".into()), (header::IF_MODIFIED_SINCE, modified.clone())]).await.0, StatusCode::OK);
+
+    // a change yields a new validator
+    sqlx::query("UPDATE markets SET title = title || ' (updated)' WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    let (status, new_etag, ..) = get(detail.clone(), vec![(header::IF_NONE_MATCH, etag.clone())]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(new_etag, etag);
+
+    // fixtures, lists and the carousel
+    let fixtures = format!("/sports/fixtures?q={}", title.replace(' ', "%20"));
+    for (uri, expected_cache_control) in [
+        (fixtures.as_str(), "public, max-age=5"),
+        ("/markets?page_size=100", "public, max-age=10"),
+        ("/compat/markets?page_size=100", "public, max-age=10"),
+        ("/admin/carousel", "public, max-age=60"),
+    ] {
+        let (status, etag, modified, cache_control, _) = get(uri.to_string(), vec![]).await;
+        assert_eq!((status, cache_control.as_str()), (StatusCode::OK, expected_cache_control), "{}", uri);
+        assert!(!etag.is_empty() && !modified.is_empty(), "{}", uri);
+        assert_eq!(get(uri.to_string(), vec![(header::IF_NONE_MATCH, etag)]).await.0, StatusCode::NOT_MODIFIED, "{}", uri);
+        assert_eq!(get(uri.to_string(), vec![(header::IF_MODIFIED_SINCE, earlier.clone())]).await.0, StatusCode::OK, "{}", uri);
+    }
+
+    // live state changes the fixture representation
+    let (_, fixture_etag, ..) = get(fixtures.clone(), vec![]).await;
+    sqlx::query("INSERT INTO match_state (market_id, phase, home_score, away_score, source) VALUES ($1, 'live', 1, 0, 'admin')").bind(market_pk).execute(&pool).await.unwrap();
+    assert_eq!(get(fixtures.clone(), vec![(header::IF_NONE_MATCH, fixture_etag)]).await.0, StatusCode::OK);
+
+    sqlx::query("DELETE FROM match_state WHERE market_id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM markets WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM carousel_items WHERE id = $1").bind(&carousel_id).execute(&pool).await.unwrap();
+}
@@ -29,7 +29,7 @@ async fn test_crud_users_markets_orders() {
     // Create order
     let orepo = OrderRepository::new(pool.clone());
     let order = orepo.create_with_audit(CreateOrderRequest {
-        order_id: 660001, user_id: user.id, market_id: market.id, amount: 2.5, odds: 1.8, option: 0
+        order_id: 660001, user_id: user.id, market_id: market.id, amount: "2.5".parse().unwrap(), odds: "1.8".parse().unwrap(), option: 0
     }).await.unwrap();
 
     // Read order by id
@@ -0,0 +1,249 @@
+use actix_web::{http::StatusCode, test, web, App};
+use bigdecimal::BigDecimal;
+use kmarket_backend::repository::order_repo::{CreateOrderRequest, OrderRepository};
+use kmarket_backend::routes::{admin_currencies, admin_markets, admin_orders, admin_users, compat, orders};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::errors::DataAccessError;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+fn dec(v: &serde_json::Value) -> BigDecimal {
+    v.as_str().unwrap_or_else(|| panic!("not a decimal string: {}", v)).parse().unwrap()
+}
+
+fn d(s: &str) -> BigDecimal {
+    s.parse().unwrap()
+}
+
+#[actix_rt::test]
+async fn test_market_currency_decimals_and_exposure_limits() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let code = format!("C{}", suffix % 1_000_000_000);
+    let bearer = helpers::admin_bearer(&pool, "currency", suffix).await;
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/admin/currencies", web::post().to(admin_currencies::create_currency))
+            .route("/admin/currencies/{code}", web::put().to(admin_currencies::update_currency))
+            .route("/admin/markets", web::post().to(admin_markets::create_market))
+            .route("/admin/markets/{id}", web::put().to(admin_markets::update_market))
+            .route("/admin/users/{id}/balances", web::get().to(admin_users::get_user_balances))
+            .route("/admin/users/{id}/balances/{currency}/exposure-limit", web::put().to(admin_users::set_exposure_limit))
+            .route("/compat/users/{address}/positions", web::get().to(compat::get_frontend_positions))
+            .route("/users/{address}/stats", web::get().to(orders::get_user_stats))
+    ).await;
+    let send = |req: test::TestRequest| {
+        let (app, bearer) = (&app, bearer.clone());
+        async move {
+            let resp = test::call_service(app, req.insert_header(("Authorization", bearer)).to_request()).await;
+            let status = resp.status();
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            (status, body)
+        }
+    };
+
+    // a currency with 2 decimals and a default cap of 10 open per user
+    let currency = serde_json::json!({"code": code.to_lowercase(), "name": "Cents", "decimals": 2, "max_user_exposure": "10"});
+    let (status, body) = send(test::TestRequest::post().uri("/admin/currencies").set_json(&currency)).await;
+    assert_eq!((status, body["data"]["code"].as_str()), (StatusCode::CREATED, Some(code.as_str())), "{}", body);
+    let (status, _) = send(test::TestRequest::post().uri("/admin/currencies").set_json(&currency)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let market = |market_id: i64, currency: &str| serde_json::json!({
+        "market_id": market_id, "title": "Currency Cup", "option_a": "A", "option_b": "B",
+        "start_time": chrono::Utc::now() + chrono::Duration::days(1), "end_time": chrono::Utc::now() + chrono::Duration::days(2),
+        "status": "active", "currency": currency,
+    });
+    let (status, body) = send(test::TestRequest::post().uri("/admin/markets").set_json(market(suffix % 1_000_000_000 + 1, "DOGE"))).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_currency")));
+    let (_, body) = send(test::TestRequest::post().uri("/admin/markets").set_json(market(suffix % 1_000_000_000, &code.to_lowercase()))).await;
+    let market_pk = body["data"]["id"].as_i64().unwrap();
+
+    let address = format!("0xcurrency{}", suffix);
+    let user_id: i64 = sqlx::query_scalar("INSERT INTO users (address) VALUES ($1) RETURNING id").bind(&address).fetch_one(&pool).await.unwrap();
+    let repo = OrderRepository::new(pool.clone());
+    let place = |n: i64, amount: &str| repo.create_with_audit(CreateOrderRequest {
+        order_id: suffix % 1_000_000_000 * 10 + n, user_id, market_id: market_pk, amount: d(amount), odds: d("1.5"), option: 0,
+    });
+
+    // stakes must fit the market currency's decimals; the order carries the currency
+    assert!(matches!(place(1, "1.234").await, Err(DataAccessError::InvalidArgument(_))));
+    let order = place(1, "1.23").await.unwrap();
+    assert_eq!((order.currency.as_str(), order.amount), (code.as_str(), d("1.23")));
+    place(2, "8.77").await.unwrap();
+    assert!(matches!(place(3, "0.01").await, Err(DataAccessError::ExposureLimitExceeded(c)) if c == code));
+
+    // a per-user limit overrides the currency default
+    let (status, body) = send(test::TestRequest::put().uri(&format!("/admin/users/{}/balances/{}/exposure-limit", user_id, code))
+        .set_json(serde_json::json!({"exposure_limit": "0.001"}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    send(test::TestRequest::put().uri(&format!("/admin/users/{}/balances/{}/exposure-limit", user_id, code))
+        .set_json(serde_json::json!({"exposure_limit": "20"}))).await;
+    place(3, "0.01").await.unwrap();
+    let (_, body) = send(test::TestRequest::get().uri(&format!("/admin/users/{}/balances", user_id))).await;
+    let item = body["data"]["items"].as_array().unwrap().iter().find(|i| i["currency"] == code.as_str()).unwrap().clone();
+    assert_eq!((dec(&item["open_exposure"]), dec(&item["effective_limit"])), (d("10.01"), d("20")));
+
+    let (_, body) = send(test::TestRequest::get().uri(&format!("/compat/users/{}/positions", address))).await;
+    assert!(body["data"]["positions"].as_array().unwrap().iter().all(|p| p["currency"] == code.as_str()));
+    let (_, body) = send(test::TestRequest::get().uri(&format!("/users/{}/stats", address))).await;
+    assert_eq!((body["data"]["placed"].as_i64(), body["data"]["by_currency"][0]["currency"].as_str()), (Some(3), Some(code.as_str())));
+    assert_eq!(dec(&body["data"]["by_currency"][0]["amount_placed"]), d("10.01"));
+    // the scalar amounts keep meaning the default currency only
+    assert_eq!((body["data"]["amount_placed"].as_str(), body["data"]["amount_settled"].as_str()), (Some("0"), Some("0")));
+
+    // orders pin the market's currency and the currency's decimals
+    let (status, body) = send(test::TestRequest::put().uri(&format!("/admin/markets/{}", market_pk)).set_json(serde_json::json!({"currency": "USDC"}))).await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+    let (status, body) = send(test::TestRequest::put().uri(&format!("/admin/currencies/{}", code)).set_json(serde_json::json!({"decimals": 4}))).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::CONFLICT, Some("currency_in_use")));
+    let (status, body) = send(test::TestRequest::put().uri(&format!("/admin/currencies/{}", code)).set_json(serde_json::json!({"max_user_exposure": null, "enabled": false}))).await;
+    assert_eq!((status, body["data"]["max_user_exposure"].is_null(), body["data"]["enabled"].as_bool()), (StatusCode::OK, true, Some(false)));
+    let mismatched = sqlx::query("INSERT INTO orders (order_id, user_id, market_id, amount, odds, option, currency) VALUES ($1, $2, $3, 1, 1.5, 0, 'SOL')")
+        .bind(suffix % 1_000_000_000 * 10 + 9).bind(user_id).bind(market_pk).execute(&pool).await;
+    assert!(mismatched.is_err());
+
+    sqlx::query("DELETE FROM orders WHERE user_id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM markets WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM currencies WHERE code = $1").bind(&code).execute(&pool).await.unwrap();
+}
+
+#[actix_rt::test]
+async fn test_currency_report_and_fx_consolidation() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let (priced, unpriced) = (format!("P{}", suffix % 1_000_000_000), format!("U{}", suffix % 1_000_000_000));
+    let bearer = helpers::admin_bearer(&pool, "fxreport", suffix).await;
+
+    let user_id: i64 = sqlx::query_scalar("INSERT INTO users (address) VALUES ($1) RETURNING id").bind(format!("0xfx{}", suffix)).fetch_one(&pool).await.unwrap();
+    let mut markets = Vec::new();
+    for (i, (code, decimals)) in [(&priced, 2i16), (&unpriced, 0)].into_iter().enumerate() {
+        sqlx::query("INSERT INTO currencies (code, name, decimals) VALUES ($1, 'Report test', $2)").bind(code).bind(decimals).execute(&pool).await.unwrap();
+        let id: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status, currency) VALUES ($1, 'FX Final', 'A', 'B', NOW() + INTERVAL '1 day', NOW() + INTERVAL '2 days', 'active', $2) RETURNING id")
+            .bind(suffix % 1_000_000_000 * 10 + i as i64).bind(code).fetch_one(&pool).await.unwrap();
+        markets.push(id);
+    }
+    // priced: 10.50 open, 4.00 settled for 7.20, 2.00 cancelled; unpriced: 100 open
+    let orders = [
+        (markets[0], "10.50", "placed", None, None),
+        (markets[0], "4.00", "settled", Some("7.20"), Some("3.20")),
+        (markets[0], "2.00", "cancelled", None, None),
+        (markets[1], "100", "placed", None, None),
+    ];
+    for (i, (market, amount, status, close_price, close_pnl)) in orders.into_iter().enumerate() {
+        sqlx::query("INSERT INTO orders (order_id, user_id, market_id, amount, odds, option, status, close_price, close_pnl) VALUES ($1, $2, $3, $4, 1.8, 0, $5::order_status, $6, $7)")
+            .bind(suffix % 1_000_000_000 * 10 + i as i64).bind(user_id).bind(market).bind(d(amount)).bind(status)
+            .bind(close_price.map(d)).bind(close_pnl.map(d))
+            .execute(&pool).await.unwrap();
+    }
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/admin/fx-rates/{code}", web::put().to(admin_currencies::set_fx_rate))
+            .route("/admin/reports/currencies", web::get().to(admin_currencies::currency_report))
+    ).await;
+    let send = |req: test::TestRequest| {
+        let (app, bearer) = (&app, bearer.clone());
+        async move {
+            let resp = test::call_service(app, req.insert_header(("Authorization", bearer)).to_request()).await;
+            let status = resp.status();
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            (status, body)
+        }
+    };
+
+    let (status, _) = send(test::TestRequest::put().uri(&format!("/admin/fx-rates/{}", priced)).set_json(serde_json::json!({"rate": "0"}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(test::TestRequest::put().uri("/admin/fx-rates/NOPE1").set_json(serde_json::json!({"rate": "1"}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, body) = send(test::TestRequest::put().uri(&format!("/admin/fx-rates/{}", priced.to_lowercase())).set_json(serde_json::json!({"rate": "2.5"}))).await;
+    assert_eq!((status, body["data"]["quote"].as_str()), (StatusCode::OK, Some("USD")), "{}", body);
+
+    let (status, body) = send(test::TestRequest::get().uri("/admin/reports/currencies")).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let rows = body["data"]["currencies"].as_array().unwrap();
+    let row = |code: &str| rows.iter().find(|r| r["currency"] == code).unwrap();
+    let p = row(&priced);
+    assert_eq!((p["orders"].as_i64(), p["markets"].as_i64(), dec(&p["fx_rate"])), (Some(3), Some(1), d("2.5")));
+    assert_eq!(
+        (dec(&p["volume"]), dec(&p["open_stake"]), dec(&p["payouts"]), dec(&p["house_pnl"])),
+        (d("14.50"), d("10.50"), d("7.20"), d("-3.20")),
+    );
+    assert!(row(&unpriced)["fx_rate"].is_null() && dec(&row(&unpriced)["volume"]) == d("100"));
+
+    // the consolidation adds up each priced currency's figures at its rate, and names the rest
+    let consolidated = &body["data"]["consolidated"];
+    assert!(consolidated["unpriced"].as_array().unwrap().iter().any(|c| c == unpriced.as_str()));
+    assert!(!consolidated["unpriced"].as_array().unwrap().iter().any(|c| c == priced.as_str()));
+    for column in ["volume", "open_stake", "payouts", "house_pnl", "fees"] {
+        let expected: BigDecimal = rows.iter().filter(|r| !r["fx_rate"].is_null()).map(|r| dec(&r[column]) * dec(&r["fx_rate"])).sum();
+        assert_eq!(dec(&consolidated[column]), expected, "{}", column);
+    }
+
+    let from = (chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339().replace('+', "%2B");
+    let (_, body) = send(test::TestRequest::get().uri(&format!("/admin/reports/currencies?from={}", from))).await;
+    let p = body["data"]["currencies"].as_array().unwrap().iter().find(|r| r["currency"] == priced.as_str()).unwrap().clone();
+    assert_eq!((p["orders"].as_i64(), dec(&p["volume"])), (Some(0), d("0")));
+
+    sqlx::query("DELETE FROM orders WHERE user_id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM markets WHERE id = ANY($1)").bind(&markets).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM currencies WHERE code = ANY($1)").bind(vec![priced, unpriced]).execute(&pool).await.unwrap();
+}
+
+#[actix_rt::test]
+async fn test_settling_twice_leaves_pnl_and_ledger_unchanged() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let code = format!("S{}", suffix % 1_000_000_000);
+    let bearer = helpers::admin_bearer(&pool, "resettle", suffix).await;
+
+    sqlx::query("INSERT INTO currencies (code, name, decimals) VALUES ($1, 'Resettle test', 2)").bind(&code).execute(&pool).await.unwrap();
+    let market_pk: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status, currency) VALUES ($1, 'Resettle Cup', 'A', 'B', NOW() + INTERVAL '1 day', NOW() + INTERVAL '2 days', 'active', $2) RETURNING id")
+        .bind(suffix % 1_000_000_000).bind(&code).fetch_one(&pool).await.unwrap();
+    // fees charged at settlement, so a second settlement would show up in the ledger
+    sqlx::query("INSERT INTO fee_schedules (scope, market_id, currency, stake_rate, winnings_rate, charge_at) VALUES ('market', $1, $2, 0.02, 0.1, 'settlement')")
+        .bind(market_pk).bind(&code).execute(&pool).await.unwrap();
+    let user_id: i64 = sqlx::query_scalar("INSERT INTO users (address) VALUES ($1) RETURNING id").bind(format!("0xresettle{}", suffix)).fetch_one(&pool).await.unwrap();
+    let order = OrderRepository::new(pool.clone()).create_with_audit(CreateOrderRequest {
+        order_id: suffix % 1_000_000_000 * 10, user_id, market_id: market_pk, amount: d("10.00"), odds: d("1.5"), option: 0,
+    }).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/admin/orders/{id}/settle", web::post().to(admin_orders::settle_order))
+    ).await;
+    let settle = |close_price: &str| test::TestRequest::post().uri(&format!("/admin/orders/{}/settle", order.id))
+        .insert_header(("Authorization", bearer.clone()))
+        .set_json(serde_json::json!({"close_price": close_price})).to_request();
+    let totals = || async {
+        let user_pnl: BigDecimal = sqlx::query_scalar("SELECT total_pnl FROM users WHERE id = $1").bind(user_id).fetch_one(&pool).await.unwrap();
+        let balance_pnl: BigDecimal = sqlx::query_scalar("SELECT total_pnl FROM user_balances WHERE user_id = $1 AND currency = $2")
+            .bind(user_id).bind(&code).fetch_one(&pool).await.unwrap();
+        let (entries, ledger): (i64, Option<BigDecimal>) = sqlx::query_as("SELECT COUNT(*), SUM(amount) FROM ledger_entries WHERE order_id = $1")
+            .bind(order.id).fetch_one(&pool).await.unwrap();
+        (user_pnl, balance_pnl, entries, ledger)
+    };
+
+    // 15.00 back: fee 0.20 on the stake plus 0.50 on the winnings
+    let body: serde_json::Value = test::call_and_read_body_json(&app, settle("15.00")).await;
+    assert_eq!((dec(&body["data"]["close_pnl"]), dec(&body["data"]["fee_paid"])), (d("4.30"), d("0.70")), "{}", body);
+    let settled = totals().await;
+    assert_eq!((settled.0.clone(), settled.1.clone(), settled.2), (d("4.30"), d("4.30"), 1));
+
+    let resp = test::call_service(&app, settle("15.00")).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"].as_str(), Some("order_not_open"));
+    assert_eq!(totals().await, settled);
+
+    sqlx::query("DELETE FROM orders WHERE user_id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM markets WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM currencies WHERE code = $1").bind(&code).execute(&pool).await.unwrap();
+}
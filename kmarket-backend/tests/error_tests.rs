@@ -0,0 +1,97 @@
+use actix_web::{http::StatusCode, middleware, test, web, App, ResponseError};
+use kmarket_backend::routes::{admin_users, markets, users};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::errors::{AppError, DataAccessError};
+use kmarket_backend::utils::request_id;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+#[actix_rt::test]
+async fn test_data_access_errors_map_to_status_and_code() {
+    for (err, status, code) in [
+        (DataAccessError::InvalidArgument("id".into()), StatusCode::BAD_REQUEST, "invalid_argument"),
+        (DataAccessError::NotFound("user".into()), StatusCode::NOT_FOUND, "not_found"),
+        (DataAccessError::DuplicateKey("users_address_key".into()), StatusCode::CONFLICT, "duplicate_key"),
+        (DataAccessError::ReferentialIntegrity("orders_market_id_fkey".into()), StatusCode::CONFLICT, "referential_integrity"),
+        (DataAccessError::ConstraintViolation("chk".into()), StatusCode::BAD_REQUEST, "constraint_violation"),
+        (DataAccessError::NotNullViolation("title".into()), StatusCode::BAD_REQUEST, "missing_field"),
+        (DataAccessError::ConcurrencyConflict("users".into()), StatusCode::CONFLICT, "version_conflict"),
+        (DataAccessError::MarketClosed(7), StatusCode::CONFLICT, "market_closed"),
+        (DataAccessError::NotClaimable(8), StatusCode::CONFLICT, "not_claimable"),
+        (DataAccessError::AlreadyClaimed(9), StatusCode::CONFLICT, "already_claimed"),
+        (DataAccessError::Database("relation \"secret_table\" does not exist".into()), StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+    ] {
+        let app_err = AppError::from(err);
+        assert_eq!((app_err.status_code(), app_err.code()), (status, code));
+        let resp = app_err.error_response();
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!((body["success"].as_bool(), body["error"]["code"].as_str()), (Some(false), Some(code)));
+        assert!(!body["error"]["request_id"].as_str().unwrap_or_default().is_empty());
+        // constraint names and SQL never reach the client
+        let message = body["error"]["message"].as_str().unwrap();
+        assert!(!message.contains("secret_table") && !message.contains("_key") && !message.contains("fkey"), "{}", message);
+    }
+    assert_eq!(AppError::internal("connection refused").error_response().status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[actix_rt::test]
+async fn test_handler_errors_use_envelope_and_request_id() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .wrap(middleware::from_fn(request_id::assign))
+            .route("/users", web::post().to(users::create_user))
+            .route("/users/{id}", web::put().to(users::update_user_email))
+            .route("/users/{id}", web::delete().to(users::delete_user))
+            .route("/markets/{id}", web::get().to(markets::get_market_detail))
+            .route("/admin/users", web::get().to(admin_users::list_users))
+    ).await;
+    let call = |req: test::TestRequest| {
+        let app = &app;
+        async move {
+            let resp = test::call_service(app, req.to_request()).await;
+            let status = resp.status();
+            let header = resp.headers().get(request_id::HEADER).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(body["error"]["request_id"].as_str(), Some(header.as_str()), "{}", body);
+            (status, header, body)
+        }
+    };
+    let code = |body: &serde_json::Value| body["error"]["code"].as_str().map(str::to_string);
+
+    // deleting a missing row is a 404, not a database error
+    let (status, id, body) = call(test::TestRequest::delete().uri("/users/999999999999")).await;
+    assert_eq!((status, code(&body).as_deref()), (StatusCode::NOT_FOUND, Some("not_found")));
+    assert_eq!(id.len(), 32);
+    let (status, _, body) = call(test::TestRequest::get().uri("/markets/999999999999")).await;
+    assert_eq!((status, code(&body).as_deref()), (StatusCode::NOT_FOUND, Some("not_found")));
+
+    // a valid incoming id is kept, an unsafe one replaced
+    let (_, id, _) = call(test::TestRequest::delete().uri("/users/999999999999").insert_header((request_id::HEADER, "trace-abc_123"))).await;
+    assert_eq!(id, "trace-abc_123");
+    let (_, id, _) = call(test::TestRequest::delete().uri("/users/999999999999").insert_header((request_id::HEADER, "bad id;drop"))).await;
+    assert_ne!(id, "bad id;drop");
+
+    // a duplicate is a 409 without the constraint name; a stale version is a 409 too
+    let address = format!("0xerrors{}", suffix);
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/users")
+        .set_json(serde_json::json!({"address": address})).to_request()).await;
+    let user_id = resp["data"]["id"].as_i64().unwrap();
+    let (status, _, body) = call(test::TestRequest::post().uri("/users").set_json(serde_json::json!({"address": address}))).await;
+    assert_eq!((status, code(&body).as_deref()), (StatusCode::CONFLICT, Some("duplicate_key")));
+    assert!(!body["error"]["message"].as_str().unwrap().contains("constraint"));
+    let (status, _, body) = call(test::TestRequest::put().uri(&format!("/users/{}", user_id))
+        .set_json(serde_json::json!({"expected_version": 99, "email": "x@example.com"}))).await;
+    assert_eq!((status, code(&body).as_deref()), (StatusCode::CONFLICT, Some("version_conflict")));
+
+    // auth failures share the envelope
+    let (status, _, body) = call(test::TestRequest::get().uri("/admin/users")).await;
+    assert_eq!((status, code(&body).as_deref()), (StatusCode::UNAUTHORIZED, Some("missing_bearer")));
+    let (status, _, body) = call(test::TestRequest::get().uri("/admin/users").insert_header(("Authorization", "Bearer nope"))).await;
+    assert_eq!((status, code(&body).as_deref()), (StatusCode::UNAUTHORIZED, Some("invalid_token")));
+
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await.unwrap();
+}
@@ -4,9 +4,10 @@ mod helpers;
 #[actix_rt::test]
 async fn test_index_usage_explain() {
     let Some(pool) = helpers::maybe_init_test_db().await else { return; };
-    // Force planner to prefer indexes
+    // Force planner to prefer indexes; SET is per-session, so keep one connection for it and the EXPLAIN
+    let mut conn = pool.acquire().await.unwrap();
     sqlx::query("SET enable_seqscan = off")
-        .execute(&pool)
+        .execute(&mut *conn)
         .await
         .ok();
 
@@ -32,7 +33,7 @@ async fn test_index_usage_explain() {
 
     let plan: String = sqlx::query_scalar("EXPLAIN SELECT * FROM orders WHERE market_id = $1")
         .bind(mid)
-        .fetch_one(&pool)
+        .fetch_one(&mut *conn)
         .await
         .unwrap();
     assert!(plan.contains("Index") || plan.contains("Bitmap Index"));
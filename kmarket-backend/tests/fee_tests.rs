@@ -0,0 +1,204 @@
+use actix_web::{http::StatusCode, test, web, App};
+use bigdecimal::BigDecimal;
+use kmarket_backend::repository::order_repo::{CreateOrderRequest, OrderRepository};
+use kmarket_backend::routes::{admin_currencies, admin_fees, admin_orders, admin_simulations, admin_users, compat};
+use kmarket_backend::state::AppState;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+fn dec(v: &serde_json::Value) -> BigDecimal {
+    v.as_str().unwrap_or_else(|| panic!("not a decimal string: {}", v)).parse().unwrap()
+}
+
+fn d(s: &str) -> BigDecimal {
+    s.parse().unwrap()
+}
+
+#[actix_rt::test]
+async fn test_fee_schedules_charged_on_orders_and_recorded_in_ledger() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    // a currency of our own, so the global schedule below applies to no other test's orders
+    let code = format!("F{}", suffix % 1_000_000_000);
+    let tier = format!("vip{}", suffix);
+    let bearer = helpers::admin_bearer(&pool, "fees", suffix).await;
+
+    sqlx::query("INSERT INTO currencies (code, name, decimals) VALUES ($1, 'Fee test', 2)").bind(&code).execute(&pool).await.unwrap();
+    let mut markets = Vec::new();
+    for i in 0..2 {
+        let id: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status, currency) VALUES ($1, 'Fee Cup', 'A', 'B', NOW() + INTERVAL '1 day', NOW() + INTERVAL '2 days', 'active', $2) RETURNING id")
+            .bind(suffix % 1_000_000_000 * 10 + i).bind(&code).fetch_one(&pool).await.unwrap();
+        markets.push(id);
+    }
+    let (plain, vip) = (format!("0xfees{}", suffix), format!("0xfeesvip{}", suffix));
+    let mut users = Vec::new();
+    for address in [&plain, &vip] {
+        let id: i64 = sqlx::query_scalar("INSERT INTO users (address) VALUES ($1) RETURNING id").bind(address).fetch_one(&pool).await.unwrap();
+        users.push(id);
+    }
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/admin/fees", web::get().to(admin_fees::list_fee_schedules))
+            .route("/admin/fees", web::post().to(admin_fees::create_fee_schedule))
+            .route("/admin/fees/{id}", web::put().to(admin_fees::update_fee_schedule))
+            .route("/admin/ledger", web::get().to(admin_fees::list_ledger))
+            .route("/admin/users/{id}/fee-tier", web::put().to(admin_users::set_fee_tier))
+            .route("/admin/orders/{id}/settle", web::post().to(admin_orders::settle_order))
+            .route("/admin/reports/currencies", web::get().to(admin_currencies::currency_report))
+            .route("/compat/users/{address}/positions", web::get().to(compat::get_frontend_positions))
+    ).await;
+    let send = |req: test::TestRequest| {
+        let (app, bearer) = (&app, bearer.clone());
+        async move {
+            let resp = test::call_service(app, req.insert_header(("Authorization", bearer)).to_request()).await;
+            let status = resp.status();
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            (status, body)
+        }
+    };
+    let create = |body: serde_json::Value| send(test::TestRequest::post().uri("/admin/fees").set_json(body));
+
+    // invalid schedules
+    for body in [
+        serde_json::json!({"scope": "global", "flat_fee": "1"}),
+        serde_json::json!({"scope": "global", "currency": code, "stake_rate": "1.5"}),
+        serde_json::json!({"scope": "global", "currency": code, "charge_at": "later"}),
+        serde_json::json!({"scope": "tier", "currency": code}),
+        serde_json::json!({"scope": "market", "market_id": markets[0], "currency": "SOL"}),
+        serde_json::json!({"scope": "global", "currency": code, "flat_fee": "0.001"}),
+    ] {
+        let (status, resp) = create(body.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} -> {}", body, resp);
+    }
+
+    // global: 2% of the stake plus 0.10 at placement; the tier: 1%; the second market: 1% of the
+    // stake and 5% of the winnings, at settlement
+    let (status, body) = create(serde_json::json!({"scope": "global", "currency": code, "stake_rate": "0.02", "flat_fee": "0.10"})).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let global_id = body["data"]["id"].as_i64().unwrap();
+    let (status, _) = create(serde_json::json!({"scope": "global", "currency": code})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = create(serde_json::json!({"scope": "tier", "tier": tier, "currency": code, "stake_rate": 0.01})).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, body) = create(serde_json::json!({"scope": "market", "market_id": markets[1], "stake_rate": "0.01", "winnings_rate": "0.05", "charge_at": "settlement"})).await;
+    assert_eq!((status, body["data"]["currency"].as_str()), (StatusCode::CREATED, Some(code.as_str())), "{}", body);
+    let (_, body) = send(test::TestRequest::get().uri(&format!("/admin/fees?tier={}", tier))).await;
+    assert_eq!(body["data"]["items"].as_array().unwrap().len(), 1);
+
+    let (status, body) = send(test::TestRequest::put().uri(&format!("/admin/users/{}/fee-tier", users[1])).set_json(serde_json::json!({"fee_tier": tier}))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let repo = OrderRepository::new(pool.clone());
+    let place = |n: i64, user_id: i64, market_id: i64, option: i16| repo.create_with_audit(CreateOrderRequest {
+        order_id: suffix % 1_000_000_000 * 10 + n, user_id, market_id, amount: d("10.00"), odds: d("1.5"), option,
+    });
+
+    // placement fees: global for the plain user, the tier's for the vip; none yet on the second market
+    let plain_order = place(1, users[0], markets[0], 0).await.unwrap();
+    assert_eq!(plain_order.fee_paid, d("0.30"));
+    assert_eq!(place(2, users[1], markets[0], 0).await.unwrap().fee_paid, d("0.10"));
+    let winner = place(3, users[0], markets[1], 0).await.unwrap();
+    let loser = place(4, users[0], markets[1], 1).await.unwrap();
+    assert_eq!((winner.fee_paid.clone(), loser.fee_paid.clone()), (d("0"), d("0")));
+
+    // a changed schedule applies to new orders only
+    let (status, _) = send(test::TestRequest::put().uri(&format!("/admin/fees/{}", global_id)).set_json(serde_json::json!({"stake_rate": "0"}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(place(5, users[0], markets[0], 0).await.unwrap().fee_paid, d("0.10"));
+
+    // settlement fees are withheld from the payout: 0.10 deferred + 5% of 5.00 won
+    let (status, body) = send(test::TestRequest::post().uri(&format!("/admin/orders/{}/settle", winner.id)).set_json(serde_json::json!({"close_price": "15.00"}))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!((dec(&body["data"]["close_pnl"]), dec(&body["data"]["fee_paid"])), (d("4.65"), d("0.35")));
+    let close_price: BigDecimal = sqlx::query_scalar("SELECT close_price FROM orders WHERE id = $1").bind(winner.id).fetch_one(&pool).await.unwrap();
+    assert_eq!(close_price, d("14.65"));
+    // nothing to withhold from a loss
+    let (_, body) = send(test::TestRequest::post().uri(&format!("/admin/orders/{}/settle", loser.id)).set_json(serde_json::json!({"close_price": "0"}))).await;
+    assert_eq!((dec(&body["data"]["close_pnl"]), dec(&body["data"]["fee_paid"])), (d("-10.00"), d("0")));
+    let pnl: BigDecimal = sqlx::query_scalar("SELECT total_pnl FROM user_balances WHERE user_id = $1 AND currency = $2").bind(users[0]).bind(&code).fetch_one(&pool).await.unwrap();
+    assert_eq!(pnl, d("-5.35"));
+
+    // fee_paid on positions, and every charge in the ledger
+    let (_, body) = send(test::TestRequest::get().uri(&format!("/compat/users/{}/positions", plain))).await;
+    let positions = body["data"]["positions"].as_array().unwrap();
+    let fee_of = |id: i64| dec(&positions.iter().find(|p| p["id"] == id).unwrap()["fee_paid"]);
+    assert_eq!((fee_of(plain_order.id), fee_of(winner.id), fee_of(loser.id)), (d("0.30"), d("0.35"), d("0")));
+    let (_, body) = send(test::TestRequest::get().uri(&format!("/admin/ledger?user_id={}", users[0]))).await;
+    let entries = body["data"]["items"].as_array().unwrap();
+    assert_eq!(entries.len(), 3, "{}", body);
+    assert!(entries.iter().all(|e| e["kind"] == "fee" && e["currency"] == code.as_str()));
+    assert_eq!(entries.iter().map(|e| dec(&e["amount"])).sum::<BigDecimal>(), d("-0.75"));
+    let settlement = entries.iter().find(|e| e["order_id"] == winner.id).unwrap();
+    assert_eq!((dec(&settlement["amount"]), settlement["detail"]["charged_at"].as_str()), (d("-0.35"), Some("settlement")));
+
+    let (_, body) = send(test::TestRequest::get().uri("/admin/reports/currencies")).await;
+    let row = body["data"]["currencies"].as_array().unwrap().iter().find(|r| r["currency"] == code.as_str()).unwrap().clone();
+    assert_eq!(dec(&row["fees"]), d("0.85"));
+
+    sqlx::query("DELETE FROM orders WHERE user_id = ANY($1)").bind(&users).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = ANY($1)").bind(&users).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM markets WHERE id = ANY($1)").bind(&markets).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM currencies WHERE code = $1").bind(&code).execute(&pool).await.unwrap();
+}
+
+#[actix_rt::test]
+async fn test_placement_fee_comes_out_of_the_stake() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let code = format!("G{}", suffix % 1_000_000_000);
+    let bearer = helpers::admin_bearer(&pool, "stakefee", suffix).await;
+
+    sqlx::query("INSERT INTO currencies (code, name, decimals) VALUES ($1, 'Stake fee test', 2)").bind(&code).execute(&pool).await.unwrap();
+    let market_pk: i64 = sqlx::query_scalar(
+        "INSERT INTO markets (market_id, title, option_a, option_b, status, start_time, end_time, odds_home_bps, odds_away_bps, currency) \
+         VALUES ($1, 'Stake Fee Derby', 'Home', 'Away', 'pending', NOW(), NOW() + INTERVAL '2 hours', 18000, 18000, $2) RETURNING id"
+    ).bind(suffix % 1_000_000_000).bind(&code).fetch_one(&pool).await.unwrap();
+    sqlx::query("INSERT INTO fee_schedules (scope, market_id, currency, stake_rate) VALUES ('market', $1, $2, 0.03)")
+        .bind(market_pk).bind(&code).execute(&pool).await.unwrap();
+    let user_id: i64 = sqlx::query_scalar("INSERT INTO users (address) VALUES ($1) RETURNING id").bind(format!("0xstakefee{}", suffix)).fetch_one(&pool).await.unwrap();
+    let repo = OrderRepository::new(pool.clone());
+    let mut orders = Vec::new();
+    for option in [0i16, 1] {
+        let order = repo.create_with_audit(CreateOrderRequest {
+            order_id: suffix % 1_000_000_000 * 10 + option as i64, user_id, market_id: market_pk, amount: d("10.00"), odds: d("1.8"), option,
+        }).await.unwrap();
+        assert_eq!(order.fee_paid, d("0.30"));
+        orders.push(order.id);
+    }
+    let expected: Option<BigDecimal> = sqlx::query_scalar("SELECT payout_expected FROM positions_v WHERE id = $1").bind(orders[0]).fetch_one(&pool).await.unwrap();
+    assert_eq!(expected, Some(d("17.46")));
+
+    // settle from the simulated score; a basketball game always has a winner
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/admin/markets/{id}/simulation", web::post().to(admin_simulations::start_simulation))
+            .route("/admin/markets/{id}/simulation/stop", web::post().to(admin_simulations::stop_simulation))
+    ).await;
+    for (uri, body) in [("simulation", serde_json::json!({"sport": "basketball"})), ("simulation/stop", serde_json::json!({}))] {
+        let resp = test::call_service(&app, test::TestRequest::post().uri(&format!("/admin/markets/{}/{}", market_pk, uri))
+            .insert_header(("Authorization", bearer.clone())).set_json(body).to_request()).await;
+        assert!(resp.status().is_success(), "{}", resp.status());
+    }
+
+    // 0.30 of each 10.00 stake is the fee and 9.70 is at risk: the winner is paid 1.8 x 9.70, the
+    // loser loses it, and payout / odds + fee adds back up to the stake
+    let settled: Vec<(BigDecimal, BigDecimal, BigDecimal, BigDecimal)> = sqlx::query_as(
+        "SELECT amount, fee_paid, close_price, close_pnl FROM orders WHERE id = ANY($1) AND status = 'settled' ORDER BY close_price DESC"
+    ).bind(&orders).fetch_all(&pool).await.unwrap();
+    assert_eq!(settled.iter().map(|o| o.2.clone()).collect::<Vec<_>>(), [d("17.46"), d("0")]);
+    let (amount, fee_paid, payout, pnl) = &settled[0];
+    assert_eq!((payout / d("1.8") + fee_paid, pnl.clone()), (amount.clone(), payout - amount));
+    assert_eq!((settled[1].1.clone(), settled[1].3.clone()), (d("0.30"), d("-10.00")));
+    let fees: BigDecimal = sqlx::query_scalar("SELECT SUM(amount) FROM ledger_entries WHERE order_id = ANY($1)").bind(&orders).fetch_one(&pool).await.unwrap();
+    assert_eq!(fees, d("-0.60"));
+    let pnl: BigDecimal = sqlx::query_scalar("SELECT total_pnl FROM user_balances WHERE user_id = $1 AND currency = $2").bind(user_id).bind(&code).fetch_one(&pool).await.unwrap();
+    assert_eq!(pnl, d("-2.54"));
+
+    sqlx::query("DELETE FROM orders WHERE user_id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM markets WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM currencies WHERE code = $1").bind(&code).execute(&pool).await.unwrap();
+}
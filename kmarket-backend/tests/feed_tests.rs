@@ -0,0 +1,221 @@
+use actix_web::{http::StatusCode, test, web, App, HttpResponse, HttpServer};
+use kmarket_backend::routes::{admin_auth, admin_feeds};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::feeds::{self, FeedIngestor};
+#[path = "common/helpers.rs"]
+mod helpers;
+
+#[actix_rt::test]
+async fn test_parse_csv_fixture_sheet() {
+    let csv = "external_id,title,league,home_id,home_name,away_id,away_name,start_time,end_time,odds_home,odds_away,home_score,away_score,result\n\
+               ev1,,EPL,t1,\"Arsenal, London\",t2,Chelsea,2026-05-01T14:00:00Z,,1.85,2.10,,,\n\
+               \n\
+               ev2,\"Cup \"\"Final\"\"\",,t3,Leeds,t4,Hull,2026-05-02T14:00:00Z,2026-05-02T16:00:00Z,,,2,1,final\n";
+    let doc = feeds::parse_csv(csv).unwrap();
+    assert_eq!(doc.fixtures.len(), 2);
+    assert_eq!(doc.fixtures[0].home.name, "Arsenal, London");
+    assert_eq!((doc.fixtures[0].title.as_deref(), doc.fixtures[0].league.as_deref(), doc.fixtures[0].end_time), (None, Some("EPL"), None));
+    assert_eq!(doc.fixtures[1].title.as_deref(), Some("Cup \"Final\""));
+    assert_eq!(doc.odds.len(), 1);
+    assert_eq!((doc.odds[0].fixture_id.as_str(), doc.odds[0].home, doc.odds[0].away), ("ev1", 1.85, 2.10));
+    assert_eq!(doc.results.len(), 1);
+    assert_eq!((doc.results[0].fixture_id.as_str(), doc.results[0].home_score, doc.results[0].away_score, doc.results[0].status.as_str()), ("ev2", 2, 1, "final"));
+
+    assert!(feeds::parse_csv("external_id,home_id\nx,y\n").unwrap_err().contains("missing column"));
+    assert!(feeds::parse_csv("external_id,home_id,home_name,away_id,away_name,start_time\nx,a,A,b,B,tomorrow\n").unwrap_err().contains("row 2"));
+
+    assert!(feeds::valid_source("file", "fixtures.json"));
+    assert!(!feeds::valid_source("file", "../secrets.json"));
+    assert!(!feeds::valid_source("file", "/etc/passwd"));
+    assert!(feeds::valid_source("http", "http://127.0.0.1:9000/feed"));
+    assert!(!feeds::valid_source("http", "ftp://example.com"));
+}
+
+#[actix_rt::test]
+async fn test_feed_sync_upserts_markets_odds_and_results() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    use argon2::{Argon2, password_hash::{SaltString, PasswordHasher}};
+    let suffix = chrono::Utc::now().timestamp_micros();
+
+    let dir = std::env::temp_dir().join(format!("kmarket_feeds_{}", suffix));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("FEED_DIR", &dir);
+    let write_feed = |doc: serde_json::Value| std::fs::write(dir.join("feed.json"), doc.to_string()).unwrap();
+    let kickoff = (chrono::Utc::now() + chrono::Duration::days(2)).to_rfc3339();
+    let fixture = |id: &str, home: &str, away: &str| serde_json::json!({
+        "external_id": id, "league": "EPL",
+        "home": {"id": format!("{}-home", id), "name": home}, "away": {"id": format!("{}-away", id), "name": away},
+        "start_time": kickoff,
+    });
+    write_feed(serde_json::json!({
+        "fixtures": [fixture("ev1", "Feed United", "Sync City"), fixture("ev2", "Odds Rovers", "History Town")],
+        "odds": [{"fixture_id": "ev1", "home": 1.8, "away": 2.2}],
+    }));
+
+    let email = format!("feeds{}@kmarket.local", suffix);
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(b"feedpass", &salt).unwrap().to_string();
+    sqlx::query("INSERT INTO admin_users (email, password_hash, salt, status, role) VALUES ($1, $2, $3, 'active', 'admin')")
+        .bind(&email).bind(hash).bind(salt.to_string()).execute(&pool).await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/login", web::post().to(admin_auth::login))
+            .route("/admin/feeds", web::get().to(admin_feeds::list_providers))
+            .route("/admin/feeds", web::post().to(admin_feeds::create_provider))
+            .route("/admin/feeds/{id}", web::put().to(admin_feeds::update_provider))
+            .route("/admin/feeds/{id}/sync", web::post().to(admin_feeds::sync_provider))
+            .route("/admin/feeds/{id}/runs", web::get().to(admin_feeds::list_runs))
+    ).await;
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": email, "password": "feedpass"})).to_request()).await;
+    let bearer = format!("Bearer {}", resp["data"]["token"].as_str().unwrap());
+
+    let name = format!("file{}", suffix);
+    let create = |body: serde_json::Value| test::call_service(&app, test::TestRequest::post().uri("/admin/feeds")
+        .insert_header(("Authorization", bearer.clone())).set_json(body).to_request());
+    assert_eq!(create(serde_json::json!({"name": name, "kind": "file", "source": "../feed.json"})).await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(create(serde_json::json!({"name": name, "kind": "ftp", "source": "feed.json"})).await.status(), StatusCode::BAD_REQUEST);
+    let resp = create(serde_json::json!({"name": name, "kind": "file", "source": "feed.json", "enabled": false})).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let provider_id = body["data"]["id"].as_i64().unwrap();
+    assert_eq!(body["data"]["last_status"], "never");
+    assert_eq!(create(serde_json::json!({"name": name, "kind": "file", "source": "feed.json"})).await.status(), StatusCode::CONFLICT);
+
+    let sync = || async {
+        let req = test::TestRequest::post().uri(&format!("/admin/feeds/{}/sync", provider_id))
+            .insert_header(("Authorization", bearer.clone())).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        body["data"].clone()
+    };
+    let markets = || sqlx::query_as::<_, (i64, i64, String, String, Option<i32>, Option<i32>)>(
+        "SELECT id, market_id, external_id, title, odds_home_bps, odds_away_bps FROM markets WHERE feed_provider = $1 ORDER BY external_id"
+    ).bind(&name).fetch_all(&pool);
+
+    // first sync creates both markets and records the opening odds
+    let report = sync().await;
+    assert_eq!((report["status"].as_str(), report["fixtures"].as_i64(), report["odds"].as_i64()), (Some("ok"), Some(2), Some(1)));
+    let created = markets().await.unwrap();
+    assert_eq!(created.len(), 2);
+    let (ev1, ev2) = (created[0].0, created[1].0);
+    assert!(created[0].1 >= 10_000_000_000);
+    assert_eq!(created[0].3, "EPL: Feed United vs Sync City");
+    let league: Option<String> = sqlx::query_scalar("SELECT l.code FROM markets m JOIN leagues l ON l.id = m.league_id WHERE m.id = $1")
+        .bind(created[0].0).fetch_optional(&pool).await.unwrap();
+    assert_eq!(league.as_deref(), Some("EPL"));
+    assert_eq!((created[0].4, created[0].5), (Some(18000), Some(22000)));
+    let created_events: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM outbox_events WHERE event_type = 'market.created' AND aggregate_id = ANY($1)")
+        .bind(vec![ev1, ev2]).fetch_one(&pool).await.unwrap();
+    assert_eq!(created_events, 2);
+
+    // an unchanged feed is a no-op: no duplicates, no new history
+    let report = sync().await;
+    assert_eq!((report["status"].as_str(), report["fixtures"].as_i64(), report["odds"].as_i64()), (Some("ok"), Some(0), Some(0)));
+    assert_eq!(markets().await.unwrap().len(), 2);
+
+    // renamed team (admin mapping wins), moved odds, a result and an unknown fixture
+    sqlx::query("UPDATE feed_team_mappings SET name = 'Feed Utd', code = 7 WHERE provider = $1 AND external_id = 'ev1-home'")
+        .bind(&name).execute(&pool).await.unwrap();
+    write_feed(serde_json::json!({
+        "fixtures": [fixture("ev1", "Feed United", "Sync City"), fixture("ev2", "Odds Rovers", "History Town")],
+        "odds": [{"fixture_id": "ev1", "home": 1.8, "away": 2.2}, {"fixture_id": "ev2", "home": 2.5, "away": 1.5}, {"fixture_id": "nope", "home": 2.0, "away": 2.0}],
+        "results": [{"fixture_id": "ev1", "home_score": 3, "away_score": 1, "status": "final"}, {"fixture_id": "ev2", "home_score": 0, "away_score": 0, "status": "abandoned"}],
+    }));
+    let report = sync().await;
+    assert_eq!(report["status"], "partial");
+    assert_eq!((report["fixtures"].as_i64(), report["odds"].as_i64(), report["results"].as_i64()), (Some(1), Some(1), Some(1)));
+    let errors = report["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 2);
+    assert_eq!((errors[0]["stage"].as_str(), errors[0]["external_id"].as_str(), errors[0]["error"].as_str()), (Some("odds"), Some("nope"), Some("unknown fixture")));
+    assert_eq!((errors[1]["stage"].as_str(), errors[1]["external_id"].as_str()), (Some("results"), Some("ev2")));
+
+    let after = markets().await.unwrap();
+    assert_eq!(after.len(), 2);
+    assert_eq!(after[0].3, "EPL: Feed Utd vs Sync City");
+    let home_code: Option<i32> = sqlx::query_scalar("SELECT home_code FROM markets WHERE id = $1").bind(ev1).fetch_one(&pool).await.unwrap();
+    assert_eq!(home_code, Some(7));
+    let history: Vec<(i64, String, i32)> = sqlx::query_as("SELECT market_id, source, odds_home_bps FROM odds_history WHERE market_id = ANY($1) ORDER BY id")
+        .bind(vec![ev1, ev2]).fetch_all(&pool).await.unwrap();
+    assert_eq!(history, [(ev1, name.clone(), 18000), (ev2, name.clone(), 25000)]);
+    let result: (i32, i32, String) = sqlx::query_as("SELECT home_score, away_score, status FROM feed_results WHERE provider = $1 AND market_id = $2")
+        .bind(&name).bind(ev1).fetch_one(&pool).await.unwrap();
+    assert_eq!(result, (3, 1, "final".to_string()));
+    let state: (String, i32, i32, String) = sqlx::query_as("SELECT phase, home_score, away_score, source FROM match_state WHERE market_id = $1")
+        .bind(ev1).fetch_one(&pool).await.unwrap();
+    assert_eq!(state, ("finished".to_string(), 3, 1, name.clone()));
+    // results never settle the market; they flag it for an admin
+    let (status, attention): (String, Option<String>) = sqlx::query_as("SELECT status::TEXT, attention_reason FROM markets WHERE id = $1")
+        .bind(ev1).fetch_one(&pool).await.unwrap();
+    assert_eq!((status.as_str(), attention.as_deref()), ("pending", Some(feeds::ATTENTION_FEED_RESULT)));
+
+    // provider status and run log
+    let req = test::TestRequest::get().uri("/admin/feeds").insert_header(("Authorization", bearer.clone())).to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let listed = body["data"]["items"].as_array().unwrap().iter().find(|p| p["id"].as_i64() == Some(provider_id)).unwrap().clone();
+    assert_eq!(listed["last_status"], "partial");
+    assert!(listed["last_error"].as_str().unwrap().starts_with("odds: unknown fixture"));
+    assert!(listed["last_success_at"].is_string());
+    let req = test::TestRequest::get().uri(&format!("/admin/feeds/{}/runs?limit=2", provider_id)).insert_header(("Authorization", bearer.clone())).to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let runs = body["data"]["items"].as_array().unwrap();
+    assert_eq!((runs[0]["status"].as_str(), runs[1]["status"].as_str()), (Some("partial"), Some("ok")));
+    let cursor = body["data"]["next_cursor"].as_str().unwrap().to_string();
+    let req = test::TestRequest::get().uri(&format!("/admin/feeds/{}/runs?cursor={}", provider_id, cursor)).insert_header(("Authorization", bearer.clone())).to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["data"]["items"].as_array().unwrap().len(), 1);
+
+    // a missing file fails every stage
+    let req = test::TestRequest::put().uri(&format!("/admin/feeds/{}", provider_id)).insert_header(("Authorization", bearer.clone()))
+        .set_json(serde_json::json!({"source": "missing.csv", "interval_secs": 120})).to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((body["data"]["source"].as_str(), body["data"]["interval_secs"].as_i64()), (Some("missing.csv"), Some(120)));
+    let report = sync().await;
+    assert_eq!((report["status"].as_str(), report["errors"].as_array().unwrap().len()), (Some("error"), 3));
+
+    let audits: Vec<String> = sqlx::query_scalar("SELECT action FROM audit_logs WHERE resource = 'feed_providers' AND resource_id = $1 ORDER BY id")
+        .bind(provider_id).fetch_all(&pool).await.unwrap();
+    assert_eq!(audits, ["admin.feed_create", "admin.feed_sync", "admin.feed_sync", "admin.feed_sync", "admin.feed_update", "admin.feed_sync"]);
+
+    // HTTP provider: fixtures and odds from a local stub, /results failing
+    let stub_kickoff = kickoff.clone();
+    let server = HttpServer::new(move || {
+        let kickoff = stub_kickoff.clone();
+        App::new()
+            .route("/feed/fixtures", web::get().to(move || {
+                let kickoff = kickoff.clone();
+                async move {
+                    HttpResponse::Ok().json(serde_json::json!([{
+                        "external_id": "h1", "title": "NBA: Stub Hawks vs Local Bulls",
+                        "home": {"id": "hawks", "name": "Stub Hawks"}, "away": {"id": "bulls", "name": "Local Bulls"}, "start_time": kickoff,
+                    }]))
+                }
+            }))
+            .route("/feed/odds", web::get().to(|| async { HttpResponse::Ok().json(serde_json::json!([{"fixture_id": "h1", "home": 1.5, "away": 2.6}])) }))
+            .route("/feed/results", web::get().to(|| async { HttpResponse::ServiceUnavailable().finish() }))
+    })
+    .workers(1)
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+    let base = format!("http://{}/feed", server.addrs()[0]);
+    let server = server.run();
+    let server_handle = server.handle();
+    actix_rt::spawn(server);
+
+    let http_name = format!("http{}", suffix);
+    let http_id: i64 = sqlx::query_scalar("INSERT INTO feed_providers (name, kind, source, enabled) VALUES ($1, 'http', $2, FALSE) RETURNING id")
+        .bind(&http_name).bind(&base).fetch_one(&pool).await.unwrap();
+    let provider = feeds::provider_for(&http_name, "http", &base).unwrap();
+    let report = FeedIngestor::new(pool.clone()).sync(http_id, provider.as_ref()).await.unwrap();
+    assert_eq!((report.status, report.fixtures, report.odds, report.results), ("partial", 1, 1, 0));
+    assert_eq!((report.errors[0].stage, report.errors[0].external_id.as_deref()), ("results", None));
+    assert!(report.errors[0].error.contains("HTTP 503"));
+    let (title, odds_home): (String, Option<i32>) = sqlx::query_as("SELECT title, odds_home_bps FROM markets WHERE feed_provider = $1 AND external_id = 'h1'")
+        .bind(&http_name).fetch_one(&pool).await.unwrap();
+    assert_eq!((title.as_str(), odds_home), ("NBA: Stub Hawks vs Local Bulls", Some(15000)));
+
+    server_handle.stop(true).await;
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,94 @@
+use std::pin::Pin;
+use std::time::Duration;
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::{http::StatusCode, test, web, App};
+use kmarket_backend::routes::sports;
+use kmarket_backend::state::AppState;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+/// Read SSE chunks until `done` holds for the accumulated text (10s budget)
+async fn read_until(body: &mut BoxBody, text: &mut String, done: impl Fn(&str) -> bool) {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+    while !done(text) {
+        let chunk = tokio::time::timeout_at(deadline, futures_util::future::poll_fn(|cx| Pin::new(&mut *body).poll_next(cx)))
+            .await
+            .expect("timed out waiting for stream events")
+            .expect("stream ended")
+            .unwrap();
+        text.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+}
+
+/// (id, event, data) of every complete event in `text` for the given fixture id
+fn events_for(text: &str, fixture_id: &str) -> Vec<(String, String, serde_json::Value)> {
+    text.split("\n\n").filter_map(|block| {
+        let mut id = String::new();
+        let mut event = String::new();
+        let mut data = None;
+        for line in block.lines() {
+            if let Some(v) = line.strip_prefix("id: ") { id = v.to_string(); }
+            if let Some(v) = line.strip_prefix("event: ") { event = v.to_string(); }
+            if let Some(v) = line.strip_prefix("data: ") { data = serde_json::from_str::<serde_json::Value>(v).ok(); }
+        }
+        let data = data?;
+        let matches = data["id"] == fixture_id
+            || data["fixtures"].as_array().is_some_and(|a| a.iter().any(|f| f["id"] == fixture_id));
+        matches.then_some((id, event, data))
+    }).collect()
+}
+
+#[actix_rt::test]
+async fn test_fixture_stream_updates_and_resume() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let market_id = chrono::Utc::now().timestamp_micros() % 1_000_000_000;
+    let fixture_id = market_id.to_string();
+    sqlx::query("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status, odds_home_bps, odds_away_bps, league_id) VALUES ($1, 'Tennis SSE', 'A', 'B', NOW() + INTERVAL '50 years', NOW() + INTERVAL '51 years', 'active', 18000, 20000, (SELECT id FROM leagues WHERE code = 'ATP'))")
+        .bind(market_id).execute(&pool).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/sports/fixtures/stream", web::get().to(sports::stream_fixtures))
+    ).await;
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/sports/fixtures/stream?status=live&sport=Tennis").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("content-type").unwrap(), "text/event-stream");
+    let mut body = resp.into_body();
+    let mut text = String::new();
+    read_until(&mut body, &mut text, |t| t.contains("event: snapshot")).await;
+    let snapshot = events_for(&text, &fixture_id);
+    assert_eq!(snapshot.len(), 1, "{}", text);
+    assert_eq!(snapshot[0].1, "snapshot");
+    let snapshot_id = snapshot[0].0.clone();
+
+    // odds change -> fixture event with the same shape as get_fixtures
+    sqlx::query("UPDATE markets SET odds_home_bps = 25000 WHERE market_id = $1").bind(market_id).execute(&pool).await.unwrap();
+    read_until(&mut body, &mut text, |t| events_for(t, &fixture_id).len() >= 2).await;
+    let (_, event, data) = events_for(&text, &fixture_id).pop().unwrap();
+    assert_eq!(event, "fixture");
+    assert_eq!(data["liveOdds"]["home"].as_f64(), Some(2.5));
+    assert_eq!(data["homeTeam"], "A");
+
+    // leaving the status filter -> fixture_removed
+    sqlx::query("UPDATE markets SET status = 'cancelled' WHERE market_id = $1").bind(market_id).execute(&pool).await.unwrap();
+    read_until(&mut body, &mut text, |t| events_for(t, &fixture_id).len() >= 3).await;
+    let (_, event, _) = events_for(&text, &fixture_id).pop().unwrap();
+    assert_eq!(event, "fixture_removed");
+
+    // resuming from the snapshot replays the market's latest state, without a new snapshot
+    let req = test::TestRequest::get().uri("/sports/fixtures/stream?status=live&sport=Tennis")
+        .insert_header(("Last-Event-ID", snapshot_id)).to_request();
+    let mut body = test::call_service(&app, req).await.into_body();
+    let mut text = String::new();
+    read_until(&mut body, &mut text, |t| !events_for(t, &fixture_id).is_empty()).await;
+    assert!(!text.contains("event: snapshot"));
+    let (id, event, _) = events_for(&text, &fixture_id).pop().unwrap();
+    assert_eq!(event, "fixture_removed");
+    assert!(id.parse::<i64>().unwrap() >= snapshot[0].0.parse::<i64>().unwrap());
+
+    let req = test::TestRequest::get().uri("/sports/fixtures/stream").insert_header(("Last-Event-ID", "abc")).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,130 @@
+use actix_web::{http::StatusCode, middleware, test, web, App};
+use kmarket_backend::routes::{admin_fees, compat};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::idempotency;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+#[actix_rt::test]
+async fn test_idempotency_keys_replay_writes_and_reject_reuse() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let code = format!("I{}", suffix % 1_000_000_000);
+    let (first, second) = (helpers::admin_bearer(&pool, "idem", suffix).await, helpers::admin_bearer(&pool, "idem2", suffix).await);
+
+    sqlx::query("INSERT INTO currencies (code, name, decimals) VALUES ($1, 'Idempotency test', 2)").bind(&code).execute(&pool).await.unwrap();
+    let fixture_id = suffix % 1_000_000_000;
+    let market_pk: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status, currency) VALUES ($1, 'Retry Cup', 'A', 'B', NOW() + INTERVAL '1 day', NOW() + INTERVAL '2 days', 'active', $2) RETURNING id")
+        .bind(fixture_id).bind(&code).fetch_one(&pool).await.unwrap();
+    let address = format!("0xidem{}", suffix);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .service(
+                web::scope("/api/v1")
+                    .wrap(middleware::from_fn(idempotency::handle))
+                    .route("/compat/positions", web::post().to(compat::create_frontend_position))
+                    .route("/admin/fees", web::post().to(admin_fees::create_fee_schedule))
+            )
+    ).await;
+    let send = |req: test::TestRequest, key: Option<&str>| {
+        let app = &app;
+        let req = match key {
+            Some(k) => req.insert_header((idempotency::HEADER, k.to_string())),
+            None => req,
+        };
+        async move {
+            let resp = test::call_service(app, req.to_request()).await;
+            let (status, replayed) = (resp.status(), resp.headers().contains_key(idempotency::REPLAYED_HEADER));
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            (status, replayed, body)
+        }
+    };
+    let place_as = |wallet: &str, amount: &str| test::TestRequest::post().uri("/api/v1/compat/positions")
+        .set_json(serde_json::json!({"wallet_address": wallet, "fixture_id": fixture_id, "selected_team": 1, "amount": amount, "multiplier_bps": 15000}));
+    let place = |amount: &str| place_as(&address, amount);
+    // test requests carry no peer address
+    let scope = format!("ip:unknown:wallet:{}", address);
+    let orders = || sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM orders WHERE market_id = $1").bind(market_pk).fetch_one(&pool);
+
+    // a retried placement returns the original order instead of placing another
+    let key = format!("order-{}", suffix);
+    let (status, replayed, original) = send(place("10.00"), Some(&key)).await;
+    assert_eq!((status, replayed), (StatusCode::OK, false), "{}", original);
+    let (status, replayed, retried) = send(place("10.00"), Some(&key)).await;
+    assert_eq!((status, replayed), (StatusCode::OK, true));
+    assert_eq!(retried, original);
+    assert_eq!(orders().await.unwrap(), 1);
+
+    // the same key on a different request is rejected
+    let (status, _, body) = send(place("20.00"), Some(&key)).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::CONFLICT, Some("idempotency_key_reused")));
+    let (status, _, body) = send(place("10.00"), Some(&"k".repeat(idempotency::MAX_KEY_LEN + 1))).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_idempotency_key")));
+    assert_eq!(orders().await.unwrap(), 1);
+
+    // public keys belong to the wallet: another wallet's same key runs on its own
+    let other = format!("0xidemother{}", suffix);
+    let (status, replayed, body) = send(place_as(&other, "10.00"), Some(&key)).await;
+    assert_eq!((status, replayed), (StatusCode::OK, false), "{}", body);
+    assert_ne!(body["data"]["order_id"], original["data"]["order_id"]);
+    assert_eq!(orders().await.unwrap(), 2);
+
+    // the body's wallet is unverified, so it only narrows the caller's IP: a client elsewhere
+    // naming the same wallet and key neither replays nor blocks the original
+    let (status, replayed, body) = send(place("10.00").peer_addr("10.9.8.7:5000".parse().unwrap()), Some(&key)).await;
+    assert_eq!((status, replayed), (StatusCode::OK, false), "{}", body);
+    assert_ne!(body["data"]["order_id"], original["data"]["order_id"]);
+    assert_eq!(orders().await.unwrap(), 3);
+
+    // without a key every request runs
+    send(place("10.00"), None).await;
+    send(place("10.00"), None).await;
+    assert_eq!(orders().await.unwrap(), 5);
+
+    // a key whose first request is still running; an abandoned one is taken over
+    let busy = format!("busy-{}", suffix);
+    send(place("10.00"), Some(&busy)).await;
+    sqlx::query("UPDATE idempotency_keys SET response_status = NULL, response_body = NULL, completed_at = NULL WHERE scope = $1 AND idempotency_key = $2")
+        .bind(&scope).bind(&busy).execute(&pool).await.unwrap();
+    let (status, _, body) = send(place("10.00"), Some(&busy)).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::CONFLICT, Some("idempotency_key_in_progress")));
+    sqlx::query("UPDATE idempotency_keys SET created_at = NOW() - INTERVAL '1 hour' WHERE scope = $1 AND idempotency_key = $2").bind(&scope).bind(&busy).execute(&pool).await.unwrap();
+    let (status, replayed, _) = send(place("10.00"), Some(&busy)).await;
+    assert_eq!((status, replayed), (StatusCode::OK, false));
+    assert_eq!(orders().await.unwrap(), 7);
+
+    // a placement transaction pays for one order; the id is the server's, not the signature's
+    let signed = |sig: &str| test::TestRequest::post().uri("/api/v1/compat/positions")
+        .set_json(serde_json::json!({"wallet_address": address, "fixture_id": fixture_id, "selected_team": 1, "amount": "10.00", "multiplier_bps": 15000, "transaction_signature": sig}));
+    let sig = format!("sig{}", suffix);
+    let (status, _, body) = send(signed(&sig), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let stored: Option<String> = sqlx::query_scalar("SELECT transaction_signature FROM orders WHERE id = $1").bind(body["data"]["id"].as_i64().unwrap()).fetch_one(&pool).await.unwrap();
+    assert_eq!(stored.as_deref(), Some(sig.as_str()));
+    let (status, _, body) = send(signed(&sig), None).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::CONFLICT, Some("DUPLICATE_ORDER")));
+    let (status, _, _) = send(signed(&"s".repeat(129)), None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(orders().await.unwrap(), 8);
+
+    // admin keys belong to the admin: a retry replays, another admin's same key runs on its own
+    let fee = || test::TestRequest::post().uri("/api/v1/admin/fees").set_json(serde_json::json!({"scope": "global", "currency": code, "stake_rate": "0.01"}));
+    let admin_key = format!("fee-{}", suffix);
+    let (status, replayed, created) = send(fee().insert_header(("Authorization", first.clone())), Some(&admin_key)).await;
+    assert_eq!((status, replayed), (StatusCode::CREATED, false), "{}", created);
+    let (status, replayed, body) = send(fee().insert_header(("Authorization", first.clone())), Some(&admin_key)).await;
+    assert_eq!((status, replayed, body["data"]["id"].clone()), (StatusCode::CREATED, true, created["data"]["id"].clone()));
+    let (status, replayed, body) = send(fee().insert_header(("Authorization", second.clone())), Some(&admin_key)).await;
+    assert_eq!((status, replayed, body["error"]["code"].as_str()), (StatusCode::CONFLICT, false, Some("duplicate_key")));
+    let schedules: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM fee_schedules WHERE currency = $1").bind(&code).fetch_one(&pool).await.unwrap();
+    assert_eq!(schedules, 1);
+
+    sqlx::query("DELETE FROM idempotency_keys WHERE idempotency_key = ANY($1)").bind([key, busy, admin_key]).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM fee_schedules WHERE currency = $1").bind(&code).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM orders WHERE market_id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE address = ANY($1)").bind([address, other]).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM markets WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM currencies WHERE code = $1").bind(&code).execute(&pool).await.unwrap();
+}
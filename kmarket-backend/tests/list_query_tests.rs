@@ -0,0 +1,112 @@
+use actix_web::{http::StatusCode, test, web, App};
+use kmarket_backend::routes::{admin_auth, admin_markets, compat};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::list_query::{push_template, ListQuery, Sort, SortField};
+use kmarket_backend::utils::pagination::{Cursor, PageMode};
+use sqlx::{Postgres, QueryBuilder};
+#[path = "common/helpers.rs"]
+mod helpers;
+
+const SORTS: &[SortField] = &[
+    SortField { name: "created_at", column: "t.created_at", keyset: true },
+    SortField { name: "title", column: "t.title", keyset: false },
+];
+
+#[actix_rt::test]
+async fn test_builder_sql_and_sort_whitelist() {
+    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new("");
+    push_template(&mut qb, "(a = {} OR b = {})", &["x".into()]);
+    push_template(&mut qb, " AND c BETWEEN {} AND {}", &[1i64.into(), 2i64.into()]);
+    assert_eq!(qb.sql(), "(a = $1 OR b = $2) AND c BETWEEN $3 AND $4");
+
+    let mut list = ListQuery::new("t.id, t.title", "things t", "t.id");
+    list.filter_opt("t.status = {}", Some("open"))
+        .filter_opt("t.owner = {}", None::<i64>)
+        .filter("t.title ILIKE {} OR t.body ILIKE {}", "%x%")
+        .condition("t.deleted_at IS NULL");
+    assert_eq!(list.count_query().sql(), "SELECT COUNT(*) FROM things t WHERE TRUE AND (t.status = $1) AND (t.title ILIKE $2 OR t.body ILIKE $3) AND (t.deleted_at IS NULL)");
+
+    let offset = PageMode::from_query(Some(3), 10, None).unwrap();
+    let sort = Sort::parse(SORTS, Some("title"), Some("ASC"), &offset).unwrap();
+    assert!(list.page_query(sort, 10, &offset).sql().ends_with("ORDER BY t.title ASC NULLS LAST, t.id ASC LIMIT $4 OFFSET $5"));
+
+    let at = chrono::Utc::now();
+    let after = PageMode::from_query(None, 10, Some(&Cursor { at, id: 7 }.encode())).unwrap();
+    let sort = Sort::parse(SORTS, None, None, &after).unwrap();
+    let sql = list.page_query(sort, 10, &after).sql().to_string();
+    assert!(sql.starts_with("SELECT t.id, t.title, t.created_at AS list_sort_at, t.id AS list_sort_id FROM things t"), "{}", sql);
+    assert!(sql.ends_with("AND (t.created_at, t.id) < ($4, $5) ORDER BY t.created_at DESC NULLS LAST, t.id DESC LIMIT $6"), "{}", sql);
+    let sort = Sort::parse(SORTS, Some("created_at"), Some("asc"), &after).unwrap();
+    assert!(list.page_query(sort, 10, &after).sql().contains("(t.created_at, t.id) > ($4, $5)"));
+
+    assert!(Sort::parse(SORTS, Some("password"), None, &offset).is_err());
+    assert!(Sort::parse(SORTS, Some("title; DROP TABLE things"), None, &offset).is_err());
+    assert!(Sort::parse(SORTS, None, Some("sideways"), &offset).is_err());
+    // keyset paging needs a timestamp sort key
+    assert!(Sort::parse(SORTS, Some("title"), None, &after).is_err());
+}
+
+#[actix_rt::test]
+async fn test_position_status_filters_and_sorting() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    use argon2::{Argon2, password_hash::{SaltString, PasswordHasher}};
+    let suffix = chrono::Utc::now().timestamp_micros();
+
+    let market_pk: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status) VALUES ($1, $2, 'A', 'B', NOW() + INTERVAL '1 day', NOW() + INTERVAL '2 days', 'active') RETURNING id")
+        .bind(suffix % 1_000_000_000).bind(format!("Builder Bowl {}", suffix)).fetch_one(&pool).await.unwrap();
+    let address = format!("0xbuilder{}", suffix);
+    let user_id: i64 = sqlx::query_scalar("INSERT INTO users (address) VALUES ($1) RETURNING id").bind(&address).fetch_one(&pool).await.unwrap();
+    for (i, (amount, status)) in [(5, "placed"), (7, "placed"), (3, "settled"), (9, "cancelled")].into_iter().enumerate() {
+        sqlx::query("INSERT INTO orders (order_id, user_id, market_id, amount, odds, option, status) VALUES ($1, $2, $3, $4, 1.8, 0, $5::order_status)")
+            .bind(suffix % 1_000_000_000 * 10 + i as i64).bind(user_id).bind(market_pk).bind(amount).bind(status)
+            .execute(&pool).await.unwrap();
+    }
+
+    let email = format!("builder{}@kmarket.local", suffix);
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(b"builderpass", &salt).unwrap().to_string();
+    sqlx::query("INSERT INTO admin_users (email, password_hash, salt, status, role) VALUES ($1, $2, $3, 'active', 'admin')")
+        .bind(&email).bind(hash).bind(salt.to_string()).execute(&pool).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/login", web::post().to(admin_auth::login))
+            .route("/admin/markets", web::get().to(admin_markets::list_markets))
+            .route("/compat/users/{address}/positions", web::get().to(compat::get_frontend_positions))
+    ).await;
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": email, "password": "builderpass"})).to_request()).await;
+    let bearer = format!("Bearer {}", resp["data"]["token"].as_str().unwrap());
+    let get = |uri: String| {
+        let (app, bearer) = (&app, bearer.clone());
+        async move {
+            let resp = test::call_service(app, test::TestRequest::get().uri(&uri).insert_header(("Authorization", bearer)).to_request()).await;
+            let status = resp.status();
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            (status, body)
+        }
+    };
+    let amounts = |body: &serde_json::Value| -> Vec<f64> {
+        body["data"]["positions"].as_array().unwrap().iter().map(|p| p["amount"].as_str().unwrap().parse::<f64>().unwrap()).collect()
+    };
+
+    let base = format!("/compat/users/{}/positions", address);
+    let (_, body) = get(format!("{}?status=current&sort=amount&order=asc", base)).await;
+    assert_eq!((amounts(&body), body["data"]["pagination"]["total"].as_i64()), (vec![5.0, 7.0], Some(2)));
+    let (_, body) = get(format!("{}?status=history&sort=amount&order=desc", base)).await;
+    assert_eq!((amounts(&body), body["data"]["pagination"]["total"].as_i64()), (vec![9.0, 3.0], Some(2)));
+    let (_, body) = get(format!("{}?status=all&fixture_id={}&sort=amount", base, market_pk)).await;
+    assert_eq!(amounts(&body), vec![9.0, 7.0, 5.0, 3.0]);
+    let (status, body) = get(format!("{}?sort=wallet_address", base)).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_sort")));
+
+    let (_, body) = get(format!("/admin/markets?q=Builder%20Bowl%20{}&sort=title&order=asc", suffix)).await;
+    assert_eq!(body["data"]["items"][0]["id"].as_i64(), Some(market_pk));
+    let (status, body) = get("/admin/markets?sort=title&cursor=".into()).await;
+    assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_sort")));
+
+    sqlx::query("DELETE FROM orders WHERE user_id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM markets WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+}
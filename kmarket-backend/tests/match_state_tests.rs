@@ -0,0 +1,83 @@
+use actix_web::{http::StatusCode, test, web, App};
+use kmarket_backend::routes::{admin_auth, admin_markets, sports};
+use kmarket_backend::state::AppState;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+#[actix_rt::test]
+async fn test_match_state_drives_fixture_status_and_live_stats() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    use argon2::{Argon2, password_hash::{SaltString, PasswordHasher}};
+    let suffix = chrono::Utc::now().timestamp_micros();
+
+    let email = format!("state{}@kmarket.local", suffix);
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(b"statepass", &salt).unwrap().to_string();
+    sqlx::query("INSERT INTO admin_users (email, password_hash, salt, status, role) VALUES ($1, $2, $3, 'active', 'operator')")
+        .bind(&email).bind(hash).bind(salt.to_string()).execute(&pool).await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/login", web::post().to(admin_auth::login))
+            .route("/admin/markets/{id}/match-state", web::put().to(admin_markets::update_match_state))
+            .route("/sports/fixtures", web::get().to(sports::get_fixtures))
+    ).await;
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": email, "password": "statepass"})).to_request()).await;
+    let bearer = format!("Bearer {}", resp["data"]["token"].as_str().unwrap());
+
+    // kick-off is in the past and the market active: without a state row it shows as live
+    let title = format!("EPL State{} FC vs Clock Town", suffix);
+    let market_pk: i64 = sqlx::query_scalar(
+        "INSERT INTO markets (market_id, title, option_a, option_b, status, start_time, end_time) \
+         VALUES ($1, $2, 'State FC', 'Clock Town', 'active', NOW() - INTERVAL '10 minutes', NOW() + INTERVAL '2 hours') RETURNING id"
+    ).bind(suffix % 1_000_000_000).bind(&title).fetch_one(&pool).await.unwrap();
+    let fixture = || async {
+        let req = test::TestRequest::get().uri(&format!("/sports/fixtures?q=State{}", suffix)).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        body["data"]["fixtures"][0].clone()
+    };
+    let update = |market: i64, body: serde_json::Value| {
+        let req = test::TestRequest::put().uri(&format!("/admin/markets/{}/match-state", market))
+            .insert_header(("Authorization", bearer.clone())).set_json(body).to_request();
+        test::call_service(&app, req)
+    };
+    let f = fixture().await;
+    assert_eq!((f["status"].as_str(), f["liveStats"].is_null()), (Some("live"), true));
+
+    // the feed says the match has not started yet
+    let resp = update(market_pk, serde_json::json!({"phase": "pre", "source": "feed:test"})).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let f = fixture().await;
+    assert_eq!(f["status"], "pre");
+    assert_eq!((f["liveStats"]["phase"].as_str(), f["liveStats"]["homeScore"].as_i64(), f["liveStats"]["source"].as_str()), (Some("pre"), Some(0), Some("feed:test")));
+
+    let resp = update(market_pk, serde_json::json!({"phase": "live", "period": 1, "clock_seconds": 1260, "home_score": 1, "away_red_cards": 1})).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!((body["data"]["clockSeconds"].as_i64(), body["data"]["source"].as_str()), (Some(1260), Some("admin")));
+    let resp = update(market_pk, serde_json::json!({"phase": "half_time", "clock_seconds": 2700})).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let f = fixture().await;
+    assert_eq!(f["status"], "live");
+    let stats = &f["liveStats"];
+    assert_eq!((stats["phase"].as_str(), stats["period"].as_i64(), stats["clockSeconds"].as_i64()), (Some("half_time"), Some(1), Some(2700)));
+    assert_eq!((stats["homeScore"].as_i64(), stats["awayScore"].as_i64(), stats["awayRedCards"].as_i64()), (Some(1), Some(0), Some(1)));
+
+    // finished before the market is settled: the fixture is final
+    let resp = update(market_pk, serde_json::json!({"phase": "finished", "period": 2, "clock_seconds": 5400, "away_score": 1})).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let f = fixture().await;
+    assert_eq!((f["status"].as_str(), f["liveStats"]["awayScore"].as_i64()), (Some("final"), Some(1)));
+
+    assert_eq!(update(market_pk, serde_json::json!({"phase": "extra_time"})).await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(update(market_pk, serde_json::json!({"home_score": -1})).await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(update(-1, serde_json::json!({"phase": "live"})).await.status(), StatusCode::NOT_FOUND);
+
+    let events: Vec<String> = sqlx::query_scalar("SELECT payload->>'phase' FROM outbox_events WHERE event_type = 'match.state_changed' AND aggregate_id = $1 ORDER BY id")
+        .bind(market_pk).fetch_all(&pool).await.unwrap();
+    assert_eq!(events, ["pre", "live", "half_time", "finished"]);
+    let audits: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_logs WHERE action = 'admin.match_state_update' AND resource_id = $1")
+        .bind(market_pk).fetch_one(&pool).await.unwrap();
+    assert_eq!(audits, 4);
+}
@@ -0,0 +1,96 @@
+use actix_web::{test, web, App};
+use kmarket_backend::routes::admin_auth;
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::mfa::{generate_code, generate_recovery_codes, generate_secret, hash_recovery_code, otpauth_uri, verify_code, TOTP_STEP};
+#[path = "common/helpers.rs"]
+mod helpers;
+
+#[actix_rt::test]
+async fn test_totp_verify_with_skew() {
+    let secret = generate_secret();
+    let now = 1_700_000_000u64;
+    let code = generate_code(&secret, now).unwrap();
+    assert_eq!(verify_code(&secret, &code, now), Some((now / TOTP_STEP) as i64));
+    // one step either side is tolerated
+    assert!(verify_code(&secret, &code, now + TOTP_STEP).is_some());
+    assert!(verify_code(&secret, &code, now - TOTP_STEP).is_some());
+    // beyond the skew window it is rejected
+    assert!(verify_code(&secret, &code, now + 3 * TOTP_STEP).is_none());
+    assert!(verify_code(&secret, "abcdef", now).is_none());
+}
+
+#[actix_rt::test]
+async fn test_otpauth_uri_and_recovery_codes() {
+    let secret = generate_secret();
+    let uri = otpauth_uri(&secret, "ops@kmarket.local").unwrap();
+    assert!(uri.starts_with("otpauth://totp/"));
+    assert!(uri.contains(&secret));
+    assert!(uri.contains("issuer=KMarket"));
+
+    let codes = generate_recovery_codes(10);
+    assert_eq!(codes.len(), 10);
+    assert!(codes.iter().all(|c| c.len() == 11 && c.as_bytes()[5] == b'-'));
+    assert_eq!(hash_recovery_code(&codes[0]), hash_recovery_code(&format!(" {} ", codes[0].to_uppercase())));
+}
+
+#[actix_rt::test]
+async fn test_login_requires_totp_when_enabled() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    use argon2::{Argon2, password_hash::{SaltString, PasswordHasher}};
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(b"s3cret!", &salt).unwrap().to_string();
+    let secret = generate_secret();
+    let email = format!("mfa{}@kmarket.local", chrono::Utc::now().timestamp_micros());
+    sqlx::query("INSERT INTO admin_users (email, password_hash, salt, status, totp_secret, totp_enabled) VALUES ($1, $2, $3, 'active', $4, true)")
+        .bind(&email).bind(hash).bind(salt.to_string()).bind(&secret)
+        .execute(&pool).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/login", web::post().to(admin_auth::login))
+            .route("/verify", web::post().to(admin_auth::verify_mfa))
+    ).await;
+
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": email, "password": "s3cret!"})).to_request()).await;
+    assert_eq!(resp["data"]["mfa_required"], true);
+    assert!(resp["data"].get("token").is_none());
+    let mfa_token = resp["data"]["mfa_token"].as_str().unwrap().to_string();
+
+    let code = generate_code(&secret, chrono::Utc::now().timestamp() as u64).unwrap();
+    let req = test::TestRequest::post().uri("/verify").set_json(serde_json::json!({"mfa_token": mfa_token, "code": code})).to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(resp["data"]["token"].is_string());
+
+    // the same code cannot be replayed
+    let req = test::TestRequest::post().uri("/verify").set_json(serde_json::json!({"mfa_token": mfa_token, "code": code})).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+}
+
+#[actix_rt::test]
+async fn test_activation_codes_are_rate_limited() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let bearer = helpers::admin_bearer(&pool, "mfaact", chrono::Utc::now().timestamp_micros()).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/enroll", web::post().to(admin_auth::enroll_mfa))
+            .route("/activate", web::post().to(admin_auth::activate_mfa))
+    ).await;
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/enroll")
+        .insert_header(("Authorization", bearer.clone())).to_request()).await;
+    let secret = resp["data"]["secret"].as_str().unwrap().to_string();
+
+    // a stolen access token cannot brute-force the code: guesses share the account's login budget
+    let activate = |code: String| test::TestRequest::post().uri("/activate")
+        .insert_header(("Authorization", bearer.clone())).set_json(serde_json::json!({"code": code})).to_request();
+    let mut statuses = Vec::new();
+    for _ in 0..6 {
+        statuses.push(test::call_service(&app, activate("abcdef".into())).await.status().as_u16());
+    }
+    assert_eq!(statuses.last(), Some(&429), "{:?}", statuses);
+    let code = generate_code(&secret, chrono::Utc::now().timestamp() as u64).unwrap();
+    assert_eq!(test::call_service(&app, activate(code)).await.status(), actix_web::http::StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,183 @@
+use actix_web::{test, web, App};
+use bigdecimal::{BigDecimal, Zero};
+use kmarket_backend::routes::{admin_auth, admin_orders};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::money::{self, Rounding};
+#[path = "common/helpers.rs"]
+mod helpers;
+
+/// Property tests; kept apart from actix_web::test, which would shadow #[test]
+mod properties {
+    use bigdecimal::{BigDecimal, Zero};
+    use kmarket_backend::utils::money::{self, Rounding};
+    use proptest::prelude::*;
+
+    fn rounding() -> impl Strategy<Value = Rounding> {
+        prop_oneof![
+            Just(Rounding::new(6)), // USDC
+            Just(Rounding::new(9)), // SOL
+            Just(Rounding::new(0)), // POINTS
+            Just(Rounding::DEFAULT),
+        ]
+    }
+
+    /// A stake that fits the currency: up to 10^12 minor units at any scale it allows
+    fn stake(r: Rounding) -> impl Strategy<Value = BigDecimal> {
+        (1u64..1_000_000_000_000, 0..=r.decimals).prop_map(|(units, scale)| BigDecimal::new(units.into(), scale))
+    }
+
+    /// Decimal odds above 1 with up to 8 places
+    fn odds() -> impl Strategy<Value = BigDecimal> {
+        (100_000_001i64..10_000_000_000).prop_map(|n| BigDecimal::new(n.into(), 8))
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Body {
+        #[serde(deserialize_with = "money::lenient")]
+        v: BigDecimal,
+    }
+
+    proptest! {
+        #[test]
+        fn settlement_rounds_down_and_reconciles((r, amount) in rounding().prop_flat_map(|r| (Just(r), stake(r))), odds in odds(), won: bool) {
+            let s = money::settle(&amount, &odds, won, r);
+            prop_assert!(money::decimal_places(&s.payout) <= r.decimals);
+            prop_assert_eq!(&s.pnl + &amount, s.payout.clone());
+            if won {
+                let exact = &amount * &odds;
+                let unit = BigDecimal::new(1.into(), r.decimals);
+                prop_assert!(s.payout <= exact && exact - &s.payout < unit);
+            } else {
+                prop_assert!(s.payout.is_zero());
+                prop_assert_eq!(s.pnl, -amount);
+            }
+        }
+
+        #[test]
+        fn market_totals_reconcile(r in rounding(), bets in prop::collection::vec((1u64..1_000_000_000, odds(), 0i16..2), 1..30), winner in 0i16..2) {
+            let (mut staked, mut paid, mut pnl) = (BigDecimal::zero(), BigDecimal::zero(), BigDecimal::zero());
+            for (units, odds, option) in &bets {
+                let amount = BigDecimal::new((*units).into(), r.decimals.min(6));
+                let s = money::settle(&amount, odds, *option == winner, r);
+                staked += &amount;
+                paid += &s.payout;
+                pnl += &s.pnl;
+            }
+            // what users won is exactly what the house lost: no value appears or vanishes in rounding
+            let house = &staked - &paid;
+            prop_assert_eq!(pnl + house, BigDecimal::zero());
+            prop_assert!(money::decimal_places(&paid) <= r.decimals);
+        }
+
+        #[test]
+        fn stakes_beyond_currency_decimals_are_rejected(r in rounding(), units in 1u64..1_000_000_000) {
+            // trailing 1 one place beyond the currency's decimals
+            let too_fine = BigDecimal::new((units * 10 + 1).into(), r.decimals + 1);
+            prop_assert!(r.stake(&too_fine).is_err());
+            prop_assert!(r.stake(&-BigDecimal::new(units.into(), 0)).is_err());
+        }
+
+        #[test]
+        fn amounts_serialize_as_strings_and_read_numbers_exactly(units in 0u64..10_000_000_000_000, scale in 0i64..=2) {
+            let v = BigDecimal::new(units.into(), scale);
+            let json = serde_json::to_value(&v).unwrap();
+            prop_assert_eq!(json.as_str().map(|s| s.parse::<BigDecimal>().unwrap()), Some(v.clone()));
+            // the frontend may still send numbers: 0.1 must read as 0.1, not its binary neighbour
+            let text = v.to_string();
+            let from_number: Body = serde_json::from_str(&format!("{{\"v\": {}}}", text)).unwrap();
+            let from_string: Body = serde_json::from_str(&format!("{{\"v\": \"{}\"}}", text)).unwrap();
+            prop_assert_eq!(&from_number.v, &v);
+            prop_assert_eq!(from_string.v, v);
+        }
+    }
+}
+
+#[actix_rt::test]
+async fn test_rounding_rules() {
+    let d = |s: &str| s.parse::<BigDecimal>().unwrap();
+    let usdc = Rounding::new(6);
+    assert_eq!(money::odds_from_bps(18500), d("1.85"));
+    assert!(money::odds(&d("1.123456789")).is_err());
+    assert!(money::odds(&d("0")).is_err());
+    // 3.333333 * 1.5 = 4.9999995: truncated, never rounded up
+    let s = money::settle(&d("3.333333"), &d("1.5"), true, usdc);
+    assert_eq!((s.payout.to_string(), s.pnl.to_string()), ("4.999999".to_string(), "1.666666".to_string()));
+    assert_eq!(money::settle(&d("2"), &d("1.5"), false, usdc).pnl, d("-2"));
+}
+
+#[actix_rt::test]
+async fn test_settlement_reconciles_in_database() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    use argon2::{Argon2, password_hash::{SaltString, PasswordHasher}};
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let d = |s: &str| s.parse::<BigDecimal>().unwrap();
+
+    // a currency at the full column scale, so the smallest representable stakes are exercised
+    let code = format!("D{}", suffix % 1_000_000_000);
+    sqlx::query("INSERT INTO currencies (code, name, decimals) VALUES ($1, 'Decimal test', 18)").bind(&code).execute(&pool).await.unwrap();
+    let market_pk: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status, currency) VALUES ($1, 'Decimal Derby', 'A', 'B', NOW() + INTERVAL '1 day', NOW() + INTERVAL '2 days', 'active', $2) RETURNING id")
+        .bind(suffix % 1_000_000_000).bind(&code).fetch_one(&pool).await.unwrap();
+    let user_id: i64 = sqlx::query_scalar("INSERT INTO users (address) VALUES ($1) RETURNING id").bind(format!("0xdecimal{}", suffix)).fetch_one(&pool).await.unwrap();
+    let bets = [
+        ("0.1", "1.85", 0i16), ("0.2", "2.3333", 0), ("0.000000000000000001", "1.00000001", 0),
+        ("1234.567891234567891234", "3.14159265", 1), ("7", "1.5", 0), ("0.3", "1.1", 1),
+    ];
+    let mut order_ids = Vec::new();
+    for (i, (amount, odds, option)) in bets.iter().enumerate() {
+        let id: i64 = sqlx::query_scalar("INSERT INTO orders (order_id, user_id, market_id, amount, odds, option) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id")
+            .bind(suffix % 1_000_000_000 * 10 + i as i64).bind(user_id).bind(market_pk).bind(d(amount)).bind(d(odds)).bind(option)
+            .fetch_one(&pool).await.unwrap();
+        order_ids.push(id);
+    }
+
+    // the simulator settles in SQL: TRUNC must agree with money::settle to the last digit
+    let sql_payouts: Vec<BigDecimal> = sqlx::query_scalar("SELECT TRUNC(o.amount * o.odds, c.decimals) FROM orders o JOIN currencies c ON c.code = o.currency WHERE o.id = ANY($1) ORDER BY o.id")
+        .bind(&order_ids).fetch_all(&pool).await.unwrap();
+    for ((amount, odds, _), sql) in bets.iter().zip(&sql_payouts) {
+        assert_eq!(money::settle(&d(amount), &d(odds), true, Rounding::DEFAULT).payout, *sql);
+    }
+
+    let email = format!("decimal{}@kmarket.local", suffix);
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(b"decimalpass", &salt).unwrap().to_string();
+    sqlx::query("INSERT INTO admin_users (email, password_hash, salt, status, role) VALUES ($1, $2, $3, 'active', 'admin')")
+        .bind(&email).bind(hash).bind(salt.to_string()).execute(&pool).await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/login", web::post().to(admin_auth::login))
+            .route("/admin/orders/{id}/settle", web::post().to(admin_orders::settle_order))
+    ).await;
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": email, "password": "decimalpass"})).to_request()).await;
+    let bearer = format!("Bearer {}", resp["data"]["token"].as_str().unwrap());
+
+    // settle each order (option 0 wins) with the payout money::settle computes
+    let mut expected_pnl = BigDecimal::zero();
+    for ((amount, odds, option), id) in bets.iter().zip(&order_ids) {
+        let s = money::settle(&d(amount), &d(odds), *option == 0, Rounding::DEFAULT);
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri(&format!("/admin/orders/{}/settle", id))
+            .insert_header(("Authorization", bearer.clone()))
+            .set_json(serde_json::json!({"close_price": s.payout.to_string()})).to_request()).await;
+        assert_eq!(resp["data"]["close_pnl"].as_str().map(|v| v.parse::<BigDecimal>().unwrap()), Some(s.pnl.clone()), "{}", resp);
+        expected_pnl += &s.pnl;
+    }
+
+    let (mismatched, total_pnl, currency_pnl): (i64, BigDecimal, BigDecimal) = sqlx::query_as(
+        "SELECT (SELECT COUNT(*) FROM orders WHERE id = ANY($1) AND close_price - amount <> close_pnl), \
+                (SELECT total_pnl FROM users WHERE id = $2), \
+                (SELECT total_pnl FROM user_balances WHERE user_id = $2 AND currency = $3)"
+    ).bind(&order_ids).bind(user_id).bind(&code).fetch_one(&pool).await.unwrap();
+    assert_eq!((mismatched, &total_pnl, currency_pnl), (0, &expected_pnl, expected_pnl.clone()));
+
+    // more decimals than the column holds is refused rather than rounded
+    let resp = test::call_service(&app, test::TestRequest::post().uri(&format!("/admin/orders/{}/settle", order_ids[0]))
+        .insert_header(("Authorization", bearer.clone()))
+        .set_json(serde_json::json!({"close_price": "0.0000000000000000001"})).to_request()).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+    sqlx::query("DELETE FROM orders WHERE user_id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM markets WHERE id = $1").bind(market_pk).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM currencies WHERE code = $1").bind(&code).execute(&pool).await.unwrap();
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use kmarket_backend::models::event::{DomainEvent, OutboxEvent};
+use kmarket_backend::repository::market_repo::{CreateMarketRequest, MarketRepository};
+use kmarket_backend::utils::outbox::{self, EventSubscriber, OutboxDispatcher, MAX_ATTEMPTS};
+#[path = "common/helpers.rs"]
+mod helpers;
+
+/// Records deliveries of the watched aggregate ids and fails the first n of each;
+/// every other event (from concurrent test binaries) is accepted untouched.
+struct Flaky {
+    name: &'static str,
+    fail_times: HashMap<i64, i32>,
+    seen: Mutex<HashMap<i64, i32>>,
+}
+
+#[async_trait]
+impl EventSubscriber for Flaky {
+    fn name(&self) -> &'static str { self.name }
+
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), String> {
+        let (_, id) = event.event.aggregate();
+        let Some(&fail_times) = self.fail_times.get(&id) else { return Ok(()); };
+        let mut seen = self.seen.lock().unwrap();
+        let n = seen.entry(id).or_insert(0);
+        *n += 1;
+        if *n <= fail_times { Err(format!("boom {}", n)) } else { Ok(()) }
+    }
+}
+
+async fn row(pool: &sqlx::PgPool, id: i64) -> (String, i32, Option<String>) {
+    sqlx::query_as("SELECT status, attempts, last_error FROM outbox_events WHERE id = $1")
+        .bind(id).fetch_one(pool).await.unwrap()
+}
+
+/// Run passes until the event leaves the due set (bounded, other binaries may be enqueueing too)
+async fn drain_until_settled(pool: &sqlx::PgPool, dispatcher: &OutboxDispatcher, id: i64) {
+    for _ in 0..50 {
+        dispatcher.run_once().await.unwrap();
+        let due: bool = sqlx::query_scalar("SELECT status = 'pending' AND next_attempt_at <= NOW() FROM outbox_events WHERE id = $1")
+            .bind(id).fetch_one(pool).await.unwrap();
+        if !due { return; }
+    }
+    panic!("outbox event {} still due", id);
+}
+
+#[actix_rt::test]
+async fn test_enqueue_is_transactional() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let marker = -chrono::Utc::now().timestamp_micros();
+    let count = |pool: sqlx::PgPool| async move {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM outbox_events WHERE aggregate_type = 'markets' AND aggregate_id = $1")
+            .bind(marker).fetch_one(&pool).await.unwrap()
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+    outbox::enqueue(&mut *tx, &DomainEvent::MarketDeleted { id: marker }).await.unwrap();
+    tx.rollback().await.unwrap();
+    assert_eq!(count(pool.clone()).await, 0);
+
+    let mut tx = pool.begin().await.unwrap();
+    outbox::enqueue(&mut *tx, &DomainEvent::MarketDeleted { id: marker }).await.unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!(count(pool.clone()).await, 1);
+
+    // repository writes record their event in the same transaction
+    let market_id = chrono::Utc::now().timestamp_micros() % 1_000_000_000;
+    let market = MarketRepository::new(pool.clone()).create(CreateMarketRequest {
+        market_id,
+        title: "Outbox".into(),
+        description: None,
+        option_a: "A".into(),
+        option_b: "B".into(),
+        start_time: chrono::Utc::now(),
+        end_time: chrono::Utc::now() + chrono::Duration::hours(1),
+    }).await.unwrap();
+    let (event_type, payload): (String, serde_json::Value) = sqlx::query_as(
+        "SELECT event_type, payload FROM outbox_events WHERE aggregate_type = 'markets' AND aggregate_id = $1"
+    )
+    .bind(market.id).fetch_one(&pool).await.unwrap();
+    assert_eq!(event_type, "market.created");
+    assert_eq!(serde_json::from_value::<DomainEvent>(payload).unwrap(), DomainEvent::MarketCreated { id: market.id });
+}
+
+#[actix_rt::test]
+async fn test_dispatch_retries_then_dead_letters() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let retried = -chrono::Utc::now().timestamp_micros();
+    let doomed = retried - 1;
+    let retried_id = outbox::enqueue(&pool, &DomainEvent::MarketDeleted { id: retried }).await.unwrap();
+    let doomed_id = outbox::enqueue(&pool, &DomainEvent::MarketDeleted { id: doomed }).await.unwrap();
+
+    let flaky = Arc::new(Flaky { name: "flaky", fail_times: HashMap::from([(retried, 1), (doomed, i32::MAX)]), seen: Mutex::new(HashMap::new()) });
+    let dispatcher = OutboxDispatcher::new(pool.clone()).subscribe(flaky.clone());
+
+    // first delivery fails: stays pending with the error, backed off
+    drain_until_settled(&pool, &dispatcher, retried_id).await;
+    let (status, attempts, last_error) = row(&pool, retried_id).await;
+    assert_eq!((status.as_str(), attempts), ("pending", 1));
+    assert!(last_error.unwrap().contains("flaky: boom 1"));
+
+    // once due again it is redelivered and succeeds
+    sqlx::query("UPDATE outbox_events SET next_attempt_at = NOW() WHERE id = $1").bind(retried_id).execute(&pool).await.unwrap();
+    drain_until_settled(&pool, &dispatcher, retried_id).await;
+    let (status, attempts, last_error) = row(&pool, retried_id).await;
+    assert_eq!((status.as_str(), attempts, last_error), ("delivered", 2, None));
+    assert_eq!(flaky.seen.lock().unwrap()[&retried], 2);
+
+    // a failure on the last allowed attempt parks the event as 'failed' for good
+    sqlx::query("UPDATE outbox_events SET attempts = $2, next_attempt_at = NOW() WHERE id = $1")
+        .bind(doomed_id).bind(MAX_ATTEMPTS - 1).execute(&pool).await.unwrap();
+    drain_until_settled(&pool, &dispatcher, doomed_id).await;
+    let (status, attempts, _) = row(&pool, doomed_id).await;
+    assert_eq!((status.as_str(), attempts), ("failed", MAX_ATTEMPTS));
+    dispatcher.run_once().await.unwrap();
+    assert_eq!(flaky.seen.lock().unwrap()[&doomed], 2);
+}
+
+#[actix_rt::test]
+async fn test_retry_reaches_only_failed_subscribers() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let marker = -chrono::Utc::now().timestamp_micros();
+    let id = outbox::enqueue(&pool, &DomainEvent::MarketDeleted { id: marker }).await.unwrap();
+
+    let steady = Arc::new(Flaky { name: "steady", fail_times: HashMap::from([(marker, 0)]), seen: Mutex::new(HashMap::new()) });
+    let flaky = Arc::new(Flaky { name: "flaky", fail_times: HashMap::from([(marker, 1)]), seen: Mutex::new(HashMap::new()) });
+    let dispatcher = OutboxDispatcher::new(pool.clone()).subscribe(steady.clone()).subscribe(flaky.clone());
+    let delivered_to = || sqlx::query_scalar::<_, Vec<String>>("SELECT delivered_to FROM outbox_events WHERE id = $1").bind(id).fetch_one(&pool);
+
+    // one subscriber fails: the one that succeeded is recorded and not handed the retry
+    drain_until_settled(&pool, &dispatcher, id).await;
+    assert_eq!(row(&pool, id).await.0, "pending");
+    assert_eq!(delivered_to().await.unwrap(), ["steady"]);
+    sqlx::query("UPDATE outbox_events SET next_attempt_at = NOW() WHERE id = $1").bind(id).execute(&pool).await.unwrap();
+    drain_until_settled(&pool, &dispatcher, id).await;
+    assert_eq!(row(&pool, id).await, ("delivered".to_string(), 2, None));
+    assert_eq!(delivered_to().await.unwrap(), ["steady", "flaky"]);
+    assert_eq!((steady.seen.lock().unwrap()[&marker], flaky.seen.lock().unwrap()[&marker]), (1, 2));
+}
+
+#[actix_rt::test]
+async fn test_retry_backoff() {
+    assert_eq!(outbox::retry_delay_secs(1), 2);
+    assert_eq!(outbox::retry_delay_secs(3), 8);
+    assert_eq!(outbox::retry_delay_secs(9), 300);
+    assert_eq!(outbox::retry_delay_secs(i32::MAX), 300);
+}
@@ -0,0 +1,123 @@
+use actix_web::{http::StatusCode, test, web, App};
+use kmarket_backend::routes::{admin_auth, admin_orders, admin_users, compat, sports};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::pagination::Cursor;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+#[actix_rt::test]
+async fn test_cursor_round_trip() {
+    let at = chrono::DateTime::<chrono::Utc>::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+    let cursor = Cursor { at, id: 42 };
+    assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+    for garbage in ["", "zz", "6869", &hex::encode("x:1"), &hex::encode("1:")] {
+        assert_eq!(Cursor::decode(garbage), None, "{}", garbage);
+    }
+}
+
+#[actix_rt::test]
+async fn test_list_endpoints_page_by_cursor() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    use argon2::{Argon2, password_hash::{SaltString, PasswordHasher}};
+    let suffix = chrono::Utc::now().timestamp_micros();
+
+    let email = format!("pager{}@kmarket.local", suffix);
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(b"pagerpass", &salt).unwrap().to_string();
+    sqlx::query("INSERT INTO admin_users (email, password_hash, salt, status, role) VALUES ($1, $2, $3, 'active', 'admin')")
+        .bind(&email).bind(hash).bind(salt.to_string()).execute(&pool).await.unwrap();
+
+    // five markets (fixtures), five users and five orders; some rows share their sort timestamp
+    let token = format!("Pager{}", suffix);
+    let tie = chrono::Utc::now() + chrono::Duration::days(10);
+    let mut market_pks = Vec::new();
+    for i in 0..5i64 {
+        let kickoff = if i < 3 { tie } else { tie + chrono::Duration::hours(i) };
+        let pk: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status) VALUES ($1, $2, 'A', 'B', $3, $3 + INTERVAL '2 hours', 'active') RETURNING id")
+            .bind(suffix % 1_000_000_000 * 10 + i).bind(format!("{} match {}", token, i)).bind(kickoff).fetch_one(&pool).await.unwrap();
+        market_pks.push(pk);
+    }
+    let address = format!("0xpager{}", suffix);
+    let mut user_ids = Vec::new();
+    for i in 0..5 {
+        let id: i64 = sqlx::query_scalar("INSERT INTO users (address, created_at) VALUES ($1, $2) RETURNING id")
+            .bind(if i == 0 { address.clone() } else { format!("{}_{}", address, i) }).bind(tie).fetch_one(&pool).await.unwrap();
+        user_ids.push(id);
+    }
+    for i in 0..5i64 {
+        sqlx::query("INSERT INTO orders (order_id, user_id, market_id, amount, odds, option, created_at) VALUES ($1, $2, $3, 5, 1.8, 0, $4)")
+            .bind(suffix % 1_000_000_000 * 10 + i).bind(user_ids[0]).bind(market_pks[0]).bind(if i < 3 { tie } else { tie + chrono::Duration::seconds(i) })
+            .execute(&pool).await.unwrap();
+    }
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/login", web::post().to(admin_auth::login))
+            .route("/admin/orders", web::get().to(admin_orders::list_orders))
+            .route("/admin/users", web::get().to(admin_users::list_users))
+            .route("/compat/users/{address}/positions", web::get().to(compat::get_frontend_positions))
+            .route("/sports/fixtures", web::get().to(sports::get_fixtures))
+    ).await;
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": email, "password": "pagerpass"})).to_request()).await;
+    let bearer = format!("Bearer {}", resp["data"]["token"].as_str().unwrap());
+    let get = |uri: String| {
+        let (app, bearer) = (&app, bearer.clone());
+        async move {
+            let req = test::TestRequest::get().uri(&uri).insert_header(("Authorization", bearer)).to_request();
+            let resp = test::call_service(app, req).await;
+            let status = resp.status();
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            (status, body)
+        }
+    };
+    let ids = |items: &serde_json::Value| -> Vec<String> { items.as_array().unwrap().iter().map(|i| i["id"].to_string()).collect() };
+
+    for (base, key) in [
+        (format!("/admin/users?q={}", address), "items"),
+        (format!("/compat/users/{}/positions?status=all", address), "positions"),
+        (format!("/sports/fixtures?q={}", token), "fixtures"),
+        // last: adds an order mid-walk
+        (format!("/admin/orders?user={}", address), "items"),
+    ] {
+        let (status, body) = get(format!("{}&limit=100", base)).await;
+        assert_eq!(status, StatusCode::OK, "{}", base);
+        let expected = ids(&body["data"][key]);
+        assert_eq!((expected.len(), body["data"]["pagination"]["total"].as_i64()), (5, Some(5)), "{}", base);
+
+        // two rows per page; the first page starts from an empty cursor
+        let (mut seen, mut cursor, mut pages) = (Vec::new(), String::new(), 0);
+        loop {
+            let (status, body) = get(format!("{}&limit=2&cursor={}", base, cursor)).await;
+            assert_eq!(status, StatusCode::OK, "{}", base);
+            let pagination = &body["data"]["pagination"];
+            assert!(pagination["total"].is_null() && pagination.get("page").is_none(), "{}", base);
+            seen.extend(ids(&body["data"][key]));
+            pages += 1;
+            match pagination["next_cursor"].as_str() {
+                Some(next) => cursor = next.to_string(),
+                None => break,
+            }
+            if pages == 1 && base.starts_with("/admin/orders") {
+                // a newer order arriving mid-walk does not shift later pages
+                sqlx::query("INSERT INTO orders (order_id, user_id, market_id, amount, odds, option, created_at) VALUES ($1, $2, $3, 5, 1.8, 0, $4)")
+                    .bind(suffix % 1_000_000_000 * 10 + 9).bind(user_ids[0]).bind(market_pks[0]).bind(tie + chrono::Duration::days(1))
+                    .execute(&pool).await.unwrap();
+            }
+        }
+        assert_eq!((seen, pages), (expected, 3), "{}", base);
+
+        let (_, body) = get(format!("{}&limit=2&cursor=&with_total=true", base)).await;
+        let expected_total = if base.starts_with("/admin/orders") { 6 } else { 5 };
+        assert_eq!(body["data"]["pagination"]["total"].as_i64(), Some(expected_total), "{}", base);
+        let (_, body) = get(format!("{}&limit=2&page=1&with_total=false", base)).await;
+        assert!(body["data"]["pagination"]["total"].is_null(), "{}", base);
+        let (status, body) = get(format!("{}&cursor=not-a-cursor", base)).await;
+        assert_eq!((status, body["error"]["code"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_cursor")), "{}", base);
+    }
+
+    sqlx::query("DELETE FROM orders WHERE user_id = $1").bind(user_ids[0]).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = ANY($1)").bind(&user_ids).execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM markets WHERE id = ANY($1)").bind(&market_pks).execute(&pool).await.unwrap();
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use actix_web::{http::StatusCode, middleware, test, web, App, HttpResponse};
+use kmarket_backend::routes::admin_auth;
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::rate_limit::{limit_public_reads, Decision, RateLimiter, RatePolicies, RatePolicy};
+#[path = "common/helpers.rs"]
+mod helpers;
+
+fn state_with(pool: sqlx::PgPool, policies: RatePolicies) -> AppState {
+    AppState { rate_limiter: Arc::new(RateLimiter::memory(policies)), ..AppState::from_pool(pool) }
+}
+
+fn lazy_pool() -> sqlx::PgPool {
+    sqlx::postgres::PgPoolOptions::new().connect_lazy("postgresql://postgres@localhost:5432/unused").unwrap()
+}
+
+#[actix_rt::test]
+async fn test_memory_token_bucket() {
+    let limiter = RateLimiter::memory(RatePolicies::default());
+    let policy = RatePolicy::new("test", 3, 60);
+    for _ in 0..3 {
+        assert_eq!(limiter.check(&policy, "a").await, Decision::Allowed);
+    }
+    // one token refills every 20s
+    assert_eq!(limiter.check(&policy, "a").await, Decision::Limited { retry_after_secs: 20 });
+    // subjects are independent
+    assert_eq!(limiter.check(&policy, "b").await, Decision::Allowed);
+}
+
+#[actix_rt::test]
+async fn test_public_reads_throttled_per_ip() {
+    let policies = RatePolicies { public_read_ip: RatePolicy::new("public:ip", 2, 60), ..RatePolicies::default() };
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(state_with(lazy_pool(), policies)))
+            .service(
+                web::scope("/api/v1")
+                    .wrap(middleware::from_fn(limit_public_reads))
+                    .route("/markets", web::get().to(HttpResponse::Ok))
+                    .route("/markets", web::post().to(HttpResponse::Ok))
+                    .route("/admin/markets", web::get().to(HttpResponse::Ok))
+            )
+    ).await;
+
+    let get = |ip: &str| test::TestRequest::get().uri("/api/v1/markets").peer_addr(format!("{}:5000", ip).parse().unwrap()).to_request();
+    assert_eq!(test::call_service(&app, get("10.0.0.1")).await.status(), StatusCode::OK);
+    assert_eq!(test::call_service(&app, get("10.0.0.1")).await.status(), StatusCode::OK);
+    let resp = test::call_service(&app, get("10.0.0.1")).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers().get("Retry-After").unwrap(), "30");
+    // other clients, writes and admin reads are unaffected
+    assert_eq!(test::call_service(&app, get("10.0.0.2")).await.status(), StatusCode::OK);
+    let post = test::TestRequest::post().uri("/api/v1/markets").peer_addr("10.0.0.1:5000".parse().unwrap()).to_request();
+    assert_eq!(test::call_service(&app, post).await.status(), StatusCode::OK);
+    let admin = test::TestRequest::get().uri("/api/v1/admin/markets").peer_addr("10.0.0.1:5000".parse().unwrap()).to_request();
+    assert_eq!(test::call_service(&app, admin).await.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn test_login_throttled_per_account() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let policies = RatePolicies { login_account: RatePolicy::new("admin:login:account", 3, 300), ..RatePolicies::default() };
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(state_with(pool.clone(), policies)))
+            .route("/login", web::post().to(admin_auth::login))
+    ).await;
+
+    let email = format!("brute{}@kmarket.local", chrono::Utc::now().timestamp_micros());
+    // rotating source IPs does not escape the per-account limit
+    for i in 0..3 {
+        let req = test::TestRequest::post().uri("/login").peer_addr(format!("10.1.0.{}:4000", i).parse().unwrap())
+            .set_json(serde_json::json!({"email": email, "password": "guess"})).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+    }
+    let req = test::TestRequest::post().uri("/login").peer_addr("10.1.0.9:4000".parse().unwrap())
+        .set_json(serde_json::json!({"email": email.to_uppercase(), "password": "guess"})).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(resp.headers().contains_key("Retry-After"));
+
+    let throttled: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_logs WHERE action = 'admin.login_throttled' AND payload_json->>'email' = $1")
+        .bind(email.to_uppercase()).fetch_one(&pool).await.unwrap();
+    assert_eq!(throttled, 1);
+}
+
+#[actix_rt::test]
+async fn test_redis_fixed_window_if_available() {
+    let Ok(url) = std::env::var("REDIS_URL") else { return; };
+    let Ok(backend) = kmarket_backend::utils::rate_limit::RedisBackend::connect(&url).await else { return; };
+    let limiter = RateLimiter::new(kmarket_backend::utils::rate_limit::RateLimitBackend::Redis(backend), RatePolicies::default());
+    let policy = RatePolicy::new("test:redis", 2, 60);
+    let subject = format!("s{}", chrono::Utc::now().timestamp_micros());
+    assert_eq!(limiter.check(&policy, &subject).await, Decision::Allowed);
+    assert_eq!(limiter.check(&policy, &subject).await, Decision::Allowed);
+    assert!(matches!(limiter.check(&policy, &subject).await, Decision::Limited { retry_after_secs } if retry_after_secs <= 60));
+}
@@ -0,0 +1,175 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::{test, web, App, HttpServer};
+use futures_util::{SinkExt, StreamExt};
+use kmarket_backend::routes::{compat, ws};
+use kmarket_backend::state::AppState;
+use kmarket_backend::models::event::{DomainEvent, OutboxEvent};
+use kmarket_backend::utils::outbox::EventSubscriber;
+use kmarket_backend::utils::realtime::{is_valid_topic, market_topic, Push, RealtimeHub, RealtimeSubscriber};
+use tokio_tungstenite::tungstenite::Message;
+#[path = "common/helpers.rs"]
+mod helpers;
+
+fn lazy_pool() -> sqlx::PgPool {
+    sqlx::postgres::PgPoolOptions::new().connect_lazy("postgresql://postgres@localhost:5432/unused").unwrap()
+}
+
+#[actix_rt::test]
+async fn test_topic_validation() {
+    assert!(is_valid_topic("fixtures:live"));
+    assert!(is_valid_topic("market:12345"));
+    assert!(!is_valid_topic("market:"));
+    assert!(!is_valid_topic("market:12a"));
+    // sockets are anonymous: a wallet's orders are not pushed
+    assert!(!is_valid_topic("user:0xAbC123"));
+    assert!(!is_valid_topic("orders:all"));
+}
+
+async fn next_json<S>(socket: &mut S) -> serde_json::Value
+where
+    S: futures_util::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    loop {
+        let msg = tokio::time::timeout(Duration::from_secs(5), socket.next()).await.expect("timed out").unwrap().unwrap();
+        if let Message::Text(text) = msg { return serde_json::from_str(&text).unwrap(); }
+    }
+}
+
+#[actix_rt::test]
+async fn test_ws_subscribe_and_receive_pushes() {
+    let state = web::Data::new(AppState::from_pool(lazy_pool()));
+    let hub = state.realtime.clone();
+    let app_state = state.clone();
+    let server = HttpServer::new(move || App::new().app_data(app_state.clone()).route("/ws", web::get().to(ws::connect)))
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+    let addr = server.addrs()[0];
+    let handle = server.run();
+    let server_handle = handle.handle();
+    actix_rt::spawn(handle);
+
+    // topics in the query string resubscribe in one step; invalid ones are reported back
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?topics=market:42,bogus", addr)).await.unwrap();
+    let msg = next_json(&mut socket).await;
+    assert_eq!(msg["type"], "subscribed");
+    assert_eq!(msg["topics"], serde_json::json!(["market:42"]));
+    assert_eq!(msg["rejected"], serde_json::json!(["bogus"]));
+
+    socket.send(Message::Text(r#"{"op":"subscribe","topics":["fixtures:live","user:0xabc"]}"#.into())).await.unwrap();
+    let msg = next_json(&mut socket).await;
+    assert_eq!(msg["topics"], serde_json::json!(["fixtures:live", "market:42"]));
+    assert_eq!(msg["rejected"], serde_json::json!(["user:0xabc"]));
+
+    // only subscribed topics are delivered
+    hub.publish("market:43".into(), "market.odds", serde_json::json!({"market_id": 43}));
+    hub.publish("market:42".into(), "market.odds", serde_json::json!({"market_id": 42, "odds_home_bps": 18000}));
+    let msg = next_json(&mut socket).await;
+    assert_eq!(msg["type"], "event");
+    assert_eq!(msg["topic"], "market:42");
+    assert_eq!(msg["event"], "market.odds");
+    assert_eq!(msg["data"]["odds_home_bps"], 18000);
+
+    socket.send(Message::Text(r#"{"op":"unsubscribe","topics":["market:42"]}"#.into())).await.unwrap();
+    let msg = next_json(&mut socket).await;
+    assert_eq!(msg["topics"], serde_json::json!(["fixtures:live"]));
+    hub.publish("market:42".into(), "market.status", serde_json::json!({}));
+    hub.publish("fixtures:live".into(), "market.status", serde_json::json!({"market_id": 44}));
+    let msg = next_json(&mut socket).await;
+    assert_eq!((msg["topic"].as_str(), msg["data"]["market_id"].as_i64()), (Some("fixtures:live"), Some(44)));
+
+    socket.send(Message::Text(r#"{"op":"ping"}"#.into())).await.unwrap();
+    assert_eq!(next_json(&mut socket).await["type"], "pong");
+    socket.send(Message::Text("nonsense".into())).await.unwrap();
+    assert_eq!(next_json(&mut socket).await["type"], "error");
+
+    socket.close(None).await.unwrap();
+    server_handle.stop(true).await;
+}
+
+/// Hand the outbox event recorded for `order_pk` to the realtime subscriber, as the dispatcher would.
+/// (Running a dispatcher here could race other test binaries for the row.)
+async fn deliver_order_event(pool: &sqlx::PgPool, subscriber: &RealtimeSubscriber, order_pk: i64, event_type: &str) {
+    let (id, payload): (i64, serde_json::Value) = sqlx::query_as(
+        "SELECT id, payload FROM outbox_events WHERE aggregate_type = 'orders' AND aggregate_id = $1 AND event_type = $2 ORDER BY id DESC LIMIT 1"
+    )
+    .bind(order_pk).bind(event_type).fetch_one(pool).await.unwrap();
+    let event: DomainEvent = serde_json::from_value(payload).unwrap();
+    subscriber.handle(&OutboxEvent { id, event, attempts: 1, created_at: chrono::Utc::now() }).await.unwrap();
+}
+
+#[actix_rt::test]
+async fn test_order_events_are_not_pushed() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let suffix = chrono::Utc::now().timestamp_micros();
+    let market_id = suffix % 1_000_000_000;
+    let wallet = format!("0xws{}", suffix);
+    sqlx::query("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status) VALUES ($1, 'WS', 'A', 'B', NOW(), NOW() + INTERVAL '1 day', 'active')")
+        .bind(market_id).execute(&pool).await.unwrap();
+
+    let state = AppState::from_pool(pool.clone());
+    let mut rx = state.realtime.subscribe();
+    let subscriber = RealtimeSubscriber::new(state.realtime.clone(), pool.clone());
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(state))
+            .route("/positions", web::post().to(compat::create_frontend_position))
+    ).await;
+
+    let req = test::TestRequest::post().uri("/positions")
+        .set_json(serde_json::json!({"wallet_address": wallet, "fixture_id": market_id, "selected_team": 1, "amount": 3.0, "multiplier_bps": 19000}))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let id = resp["data"]["id"].as_i64().unwrap();
+
+    // a wallet's amounts and payouts never reach anonymous sockets
+    deliver_order_event(&pool, &subscriber, id, "order.placed").await;
+    assert!(rx.try_recv().is_err());
+}
+
+/// Next push on `topic`, skipping others; None after `wait`
+async fn recv_topic(rx: &mut tokio::sync::broadcast::Receiver<Arc<Push>>, topic: &str, wait: Duration) -> Option<Arc<Push>> {
+    tokio::time::timeout(wait, async {
+        loop {
+            let p = rx.recv().await.unwrap();
+            if p.topic == topic { return p; }
+        }
+    }).await.ok()
+}
+
+#[actix_rt::test]
+async fn test_pushes_fan_out_across_instances() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let market_id = chrono::Utc::now().timestamp_micros() % 1_000_000_000;
+    let pk: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status, odds_home_bps) VALUES ($1, 'Fanout', 'A', 'B', NOW(), NOW() + INTERVAL '1 day', 'active', 17000) RETURNING id")
+        .bind(market_id).fetch_one(&pool).await.unwrap();
+
+    // two "instances" sharing only the database
+    let a = Arc::new(RealtimeHub::with_fanout(pool.clone()));
+    let b = Arc::new(RealtimeHub::with_fanout(pool.clone()));
+    a.spawn_listener().unwrap();
+    b.spawn_listener().unwrap();
+    let mut rx_a = a.subscribe();
+    let mut rx_b = b.subscribe();
+    let topic = market_topic(market_id);
+
+    // the listeners LISTEN asynchronously; repeat until the first notification lands
+    let mut got = None;
+    for _ in 0..25 {
+        a.market_changed(&pool, pk, "market.odds").await;
+        got = recv_topic(&mut rx_b, &topic, Duration::from_millis(200)).await;
+        if got.is_some() { break; }
+    }
+    let push = got.expect("push raised on instance a never reached instance b");
+    assert_eq!(push.event, "market.odds");
+    assert_eq!(push.data["odds_home_bps"], 17000);
+
+    // the raising instance gets it back through its own listener, not a direct publish
+    let own = recv_topic(&mut rx_a, &topic, Duration::from_secs(5)).await.expect("no push on instance a");
+    assert_eq!(own.data["market_id"], market_id);
+
+    // a local-only hub has nothing to listen to
+    assert!(Arc::new(RealtimeHub::new()).spawn_listener().is_none());
+}
@@ -45,8 +45,8 @@ async fn test_market_repo_create_and_stats() {
         order_id: 9001,
         user_id: 1,
         market_id: market.id,
-        amount: 5.0,
-        odds: 1.5,
+        amount: "5.0".parse().unwrap(),
+        odds: "1.5".parse().unwrap(),
         option: 0,
     }).await.expect("create order");
 
@@ -87,8 +87,8 @@ async fn test_order_repo_version_conflict() {
         order_id: 9100,
         user_id: user.id,
         market_id: market.id,
-        amount: 10.0,
-        odds: 2.0,
+        amount: "10.0".parse().unwrap(),
+        odds: "2.0".parse().unwrap(),
         option: 1,
     }).await.expect("create order");
 
@@ -98,8 +98,7 @@ async fn test_order_repo_version_conflict() {
 
     let err = orepo.update_status_with_version(order.id, order.version + 1, OrderStatus::Cancelled)
         .await
-        .err()
-        .expect("should fail");
+        .expect_err("should fail");
     match err {
         DataAccessError::ConcurrencyConflict(_) => {},
         _ => panic!("unexpected error"),
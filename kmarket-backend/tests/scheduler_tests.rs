@@ -0,0 +1,69 @@
+use kmarket_backend::repository::order_repo::{CreateOrderRequest, OrderRepository};
+use kmarket_backend::repository::user_repo::{CreateUserRequest, UserRepository};
+use kmarket_backend::utils::errors::DataAccessError;
+use kmarket_backend::utils::scheduler::{MarketScheduler, ATTENTION_UNRESOLVED};
+#[path = "common/helpers.rs"]
+mod helpers;
+
+/// `schedule`: SQL for start_time, end_time, open_time, close_time
+async fn insert_market(pool: &sqlx::PgPool, market_id: i64, status: &str, schedule: &str) -> i64 {
+    sqlx::query_scalar(&format!(
+        "INSERT INTO markets (market_id, title, option_a, option_b, status, start_time, end_time, open_time, close_time) \
+         VALUES ($1, 'Sched', 'A', 'B', $2::market_status, {}) RETURNING id",
+        schedule
+    ))
+    .bind(market_id).bind(status).fetch_one(pool).await.unwrap()
+}
+
+/// (audit rows by the system actor, outbox event types) recorded for a market
+async fn trail(pool: &sqlx::PgPool, pk: i64) -> (Vec<String>, Vec<String>) {
+    let audits = sqlx::query_scalar("SELECT action FROM audit_logs WHERE resource = 'markets' AND resource_id = $1 AND actor_id = 0 ORDER BY id")
+        .bind(pk).fetch_all(pool).await.unwrap();
+    let events = sqlx::query_scalar("SELECT event_type FROM outbox_events WHERE aggregate_type = 'markets' AND aggregate_id = $1 ORDER BY id")
+        .bind(pk).fetch_all(pool).await.unwrap();
+    (audits, events)
+}
+
+#[actix_rt::test]
+async fn test_scheduler_opens_closes_and_flags() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    let base = chrono::Utc::now().timestamp_micros() % 1_000_000_000;
+    let opening = insert_market(&pool, base, "pending", "NOW(), NOW() + INTERVAL '2 hours', NOW() - INTERVAL '1 minute', NOW() + INTERVAL '1 hour'").await;
+    let not_yet = insert_market(&pool, base + 1, "pending", "NOW(), NOW() + INTERVAL '2 hours', NOW() + INTERVAL '1 hour', NOW() + INTERVAL '1 hour'").await;
+    let closing = insert_market(&pool, base + 2, "active", "NOW() - INTERVAL '1 hour', NOW() + INTERVAL '1 hour', NULL, NOW() - INTERVAL '1 second'").await;
+    let overdue = insert_market(&pool, base + 3, "active", "NOW() - INTERVAL '3 hours', NOW() - INTERVAL '1 hour', NULL, NOW() - INTERVAL '2 hours'").await;
+
+    // two instances racing: every transition happens exactly once
+    let (a, b) = (MarketScheduler::new(pool.clone()), MarketScheduler::new(pool.clone()));
+    let (ra, rb) = tokio::join!(a.run_once(), b.run_once());
+    let (ra, rb) = (ra.unwrap(), rb.unwrap());
+    assert!(ra.opened + rb.opened >= 1 && ra.closed + rb.closed >= 2 && ra.flagged + rb.flagged >= 1);
+    a.run_once().await.unwrap();
+
+    let (status, state): (String, i32) = sqlx::query_as("SELECT status::TEXT, state FROM markets WHERE id = $1").bind(opening).fetch_one(&pool).await.unwrap();
+    assert_eq!((status.as_str(), state), ("active", 1));
+    assert_eq!(trail(&pool, opening).await, (vec!["system.market_open".to_string()], vec!["market.status_changed".to_string()]));
+
+    let status: String = sqlx::query_scalar("SELECT status::TEXT FROM markets WHERE id = $1").bind(not_yet).fetch_one(&pool).await.unwrap();
+    assert_eq!(status, "pending");
+    assert_eq!(trail(&pool, not_yet).await, (vec![], vec![]));
+
+    let (status, state): (String, i32) = sqlx::query_as("SELECT status::TEXT, state FROM markets WHERE id = $1").bind(closing).fetch_one(&pool).await.unwrap();
+    assert_eq!((status.as_str(), state), ("active", 2));
+    assert_eq!(trail(&pool, closing).await, (vec!["system.market_close".to_string()], vec!["market.closed".to_string()]));
+
+    // past end_time without a result: closed and flagged once, status left for an admin to decide
+    let (state, reason): (i32, Option<String>) = sqlx::query_as("SELECT state, attention_reason FROM markets WHERE id = $1").bind(overdue).fetch_one(&pool).await.unwrap();
+    assert_eq!((state, reason.as_deref()), (2, Some(ATTENTION_UNRESOLVED)));
+    assert_eq!(trail(&pool, overdue).await.0, vec!["system.market_close".to_string(), "system.market_flag".to_string()]);
+
+    // closed markets take no more bets; open ones still do
+    let user = UserRepository::new(pool.clone()).create(CreateUserRequest {
+        address: format!("0xsched{}", base), username: None, email: None, password_hash: None, salt: None, status: None,
+    }).await.unwrap();
+    let repo = OrderRepository::new(pool.clone());
+    let order = |market_id: i64, order_id: i64| CreateOrderRequest { order_id, user_id: user.id, market_id, amount: "2.0".parse().unwrap(), odds: "1.9".parse().unwrap(), option: 0 };
+    assert!(matches!(repo.create_with_audit(order(closing, base * 10)).await, Err(DataAccessError::MarketClosed(id)) if id == closing));
+    assert!(matches!(repo.create(order(closing, base * 10 + 1)).await, Err(DataAccessError::MarketClosed(_))));
+    assert!(repo.create_with_audit(order(opening, base * 10 + 2)).await.is_ok());
+}
@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use actix_web::{http::StatusCode, test, web, App};
+use kmarket_backend::repository::order_repo::{CreateOrderRequest, OrderRepository};
+use kmarket_backend::repository::user_repo::{CreateUserRequest, UserRepository};
+use kmarket_backend::routes::{admin_auth, admin_simulations};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::realtime::{market_topic, RealtimeHub};
+use kmarket_backend::utils::simulator::{home_win_probability, match_clock, odds_bps, MatchSimulator, Sport};
+#[path = "common/helpers.rs"]
+mod helpers;
+
+#[actix_rt::test]
+async fn test_clock_and_odds_model() {
+    assert_eq!(match_clock(Sport::Football, 0.5), (2, 45, 0, "Second Half".to_string()));
+    assert_eq!(match_clock(Sport::Football, 0.25).0, 1);
+    assert_eq!(match_clock(Sport::Basketball, 0.3), (2, 2, 24, "Q2".to_string()));
+    assert_eq!(match_clock(Sport::AmericanFootball, 0.0), (1, 15, 0, "Q1".to_string()));
+    assert_eq!(match_clock(Sport::AmericanFootball, 1.0), (4, 0, 0, "Final".to_string()));
+
+    // a lead counts for more as time runs out; pre-match strength fades with it
+    let early = home_win_probability(Sport::Football, 0.5, 1, 0.9);
+    let late = home_win_probability(Sport::Football, 0.5, 1, 0.05);
+    assert!(0.5 < early && early < late && late > 0.9);
+    assert!(home_win_probability(Sport::Basketball, 0.7, 0, 1.0) > 0.69);
+    assert!((home_win_probability(Sport::Basketball, 0.7, 0, 0.0) - 0.5).abs() < 1e-9);
+    assert_eq!(odds_bps(0.5, 1.0), 20000);
+    assert_eq!(odds_bps(0.5, 1.05), 19048);
+    assert_eq!(odds_bps(0.999, 1.0), 10100);
+}
+
+#[actix_rt::test]
+async fn test_simulated_match_runs_and_settles() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    use argon2::{Argon2, password_hash::{SaltString, PasswordHasher}};
+    let suffix = chrono::Utc::now().timestamp_micros();
+
+    let email = format!("sim{}@kmarket.local", suffix);
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(b"simpass", &salt).unwrap().to_string();
+    let admin_id: i64 = sqlx::query_scalar("INSERT INTO admin_users (email, password_hash, salt, status, role) VALUES ($1, $2, $3, 'active', 'admin') RETURNING id")
+        .bind(&email).bind(hash).bind(salt.to_string()).fetch_one(&pool).await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/login", web::post().to(admin_auth::login))
+            .route("/admin/markets/{id}/simulation", web::get().to(admin_simulations::get_simulation))
+            .route("/admin/markets/{id}/simulation", web::post().to(admin_simulations::start_simulation))
+            .route("/admin/markets/{id}/simulation/stop", web::post().to(admin_simulations::stop_simulation))
+    ).await;
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": email, "password": "simpass"})).to_request()).await;
+    let bearer = format!("Bearer {}", resp["data"]["token"].as_str().unwrap());
+    let post = |uri: String, body: serde_json::Value| {
+        test::call_service(&app, test::TestRequest::post().uri(&uri).insert_header(("Authorization", bearer.clone())).set_json(body).to_request())
+    };
+
+    let base = suffix % 1_000_000_000;
+    let mut markets = Vec::new();
+    for i in 0..2 {
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO markets (market_id, title, option_a, option_b, status, start_time, end_time, odds_home_bps, odds_away_bps) \
+             VALUES ($1, 'EPL Sim FC vs Replay United', 'Sim FC', 'Replay United', 'pending', NOW(), NOW() + INTERVAL '2 hours', 18000, 21000) RETURNING id"
+        ).bind(base + i).fetch_one(&pool).await.unwrap();
+        markets.push(id);
+    }
+    let (full_time, stopped) = (markets[0], markets[1]);
+
+    // bets on both sides of the match that runs to full time
+    let users = UserRepository::new(pool.clone());
+    let orders = OrderRepository::new(pool.clone());
+    let mut bets = Vec::new();
+    for option in [0i16, 1] {
+        let user = users.create(CreateUserRequest {
+            address: format!("0xsim{}{}", base, option), username: None, email: None, password_hash: None, salt: None, status: None,
+        }).await.unwrap();
+        let order = orders.create(CreateOrderRequest { order_id: base * 10 + option as i64, user_id: user.id, market_id: full_time, amount: "10.0".parse().unwrap(), odds: "1.8".parse().unwrap(), option }).await.unwrap();
+        bets.push((order.id, user.id, option));
+    }
+
+    let resp = post(format!("/admin/markets/{}/simulation", full_time), serde_json::json!({"sport": "cricket"})).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = post(format!("/admin/markets/{}/simulation", full_time), serde_json::json!({"duration_secs": 3600})).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = post(format!("/admin/markets/{}/simulation", full_time), serde_json::json!({"duration_secs": 2, "tick_secs": 1, "seed": 7})).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["data"]["sport"], "football");
+    assert_eq!(body["data"]["phase"], "First Half");
+    assert_eq!((body["data"]["odds_home_bps"].as_i64(), body["data"]["odds_away_bps"].as_i64()), (Some(18000), Some(21000)));
+    let resp = post(format!("/admin/markets/{}/simulation", full_time), serde_json::json!({})).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let status: String = sqlx::query_scalar("SELECT status::TEXT FROM markets WHERE id = $1").bind(full_time).fetch_one(&pool).await.unwrap();
+    assert_eq!(status, "active");
+
+    // tick until full time; every tick is persisted and pushed to market:{market_id}
+    let hub = Arc::new(RealtimeHub::new());
+    let mut rx = hub.subscribe();
+    let simulator = MatchSimulator::new(pool.clone(), hub);
+    let mut settled = false;
+    for _ in 0..40 {
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        simulator.run_once().await.unwrap();
+        let status: String = sqlx::query_scalar("SELECT status FROM match_simulations WHERE market_id = $1").bind(full_time).fetch_one(&pool).await.unwrap();
+        if status == "settled" { settled = true; break; }
+    }
+    assert!(settled, "simulation did not reach full time");
+    let push = rx.try_recv().unwrap();
+    assert_eq!((push.topic.as_str(), push.event.as_str()), (market_topic(base).as_str(), "match.tick"));
+    assert_eq!(push.data["market_id"], base);
+    // repricing reaches caches and market:{id} through the outbox, like any other market update
+    let repriced: Vec<serde_json::Value> = sqlx::query_scalar("SELECT payload FROM outbox_events WHERE aggregate_type = 'markets' AND aggregate_id = $1 AND event_type = 'market.updated'")
+        .bind(full_time).fetch_all(&pool).await.unwrap();
+    assert!(!repriced.is_empty());
+    assert!(repriced.iter().all(|p| p["fields"] == serde_json::json!(["odds_home_bps", "odds_away_bps"])), "{:?}", repriced);
+
+    let req = test::TestRequest::get().uri(&format!("/admin/markets/{}/simulation", full_time)).insert_header(("Authorization", bearer.clone())).to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let sim = &body["data"];
+    let (home, away) = (sim["home_score"].as_i64().unwrap(), sim["away_score"].as_i64().unwrap());
+    let winner = sim["winning_option"].as_i64().unwrap() as i16;
+    match home.cmp(&away) {
+        std::cmp::Ordering::Greater => assert_eq!(winner, 0),
+        std::cmp::Ordering::Less => assert_eq!(winner, 1),
+        std::cmp::Ordering::Equal => assert_eq!(sim["phase"], "Penalties"),
+    }
+    assert_eq!((sim["minute"].as_i64(), sim["settled_orders"].as_i64(), sim["progress"].as_f64()), (Some(90), Some(2), Some(1.0)));
+    let live_stats: (String, i32, i32, String) = sqlx::query_as("SELECT phase, home_score, away_score, source FROM match_state WHERE market_id = $1")
+        .bind(full_time).fetch_one(&pool).await.unwrap();
+    assert_eq!(live_stats, ("finished".to_string(), home as i32, away as i32, "simulator".to_string()));
+
+    let (status, winning_option, result): (String, Option<i16>, i32) = sqlx::query_as("SELECT status::TEXT, winning_option, result FROM markets WHERE id = $1")
+        .bind(full_time).fetch_one(&pool).await.unwrap();
+    assert_eq!((status.as_str(), winning_option, result), ("settled", Some(winner), winner as i32 + 1));
+    for (order_pk, user_pk, option) in &bets {
+        let (status, pnl): (String, f64) = sqlx::query_as("SELECT status::TEXT, close_pnl::DOUBLE PRECISION FROM orders WHERE id = $1")
+            .bind(order_pk).fetch_one(&pool).await.unwrap();
+        let expected = if *option == winner { 8.0 } else { -10.0 };
+        assert_eq!((status.as_str(), pnl), ("settled", expected));
+        let total: f64 = sqlx::query_scalar("SELECT total_pnl::DOUBLE PRECISION FROM users WHERE id = $1").bind(user_pk).fetch_one(&pool).await.unwrap();
+        assert_eq!(total, expected);
+    }
+    let audits: Vec<(String, i64)> = sqlx::query_as("SELECT action, actor_id FROM audit_logs WHERE resource = 'markets' AND resource_id = $1 ORDER BY id")
+        .bind(full_time).fetch_all(&pool).await.unwrap();
+    assert_eq!(audits, vec![("admin.simulation_start".to_string(), admin_id), ("system.market_settle".to_string(), 0)]);
+    let events: Vec<String> = sqlx::query_scalar(
+        "SELECT event_type FROM outbox_events WHERE (aggregate_type = 'markets' AND aggregate_id = $1) OR (aggregate_type = 'orders' AND aggregate_id = ANY($2)) ORDER BY id"
+    ).bind(full_time).bind(bets.iter().map(|b| b.0).collect::<Vec<_>>()).fetch_all(&pool).await.unwrap();
+    // repricing happens on any tick, so odds updates are checked apart from the lifecycle sequence
+    let lifecycle: Vec<&str> = events.iter().map(String::as_str).filter(|e| *e != "market.updated").collect();
+    assert_eq!(lifecycle, ["order.placed", "order.placed", "market.status_changed", "market.settled", "order.settled", "order.settled"]);
+
+    // stopping without settlement leaves the market live; stopping with it settles from the current score
+    let resp = post(format!("/admin/markets/{}/simulation/stop", stopped), serde_json::json!({})).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = post(format!("/admin/markets/{}/simulation", stopped), serde_json::json!({"sport": "nba"})).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri(&format!("/admin/markets/{}/simulation/stop", stopped))
+        .insert_header(("Authorization", bearer.clone())).set_json(serde_json::json!({"settle": false})).to_request()).await;
+    assert_eq!((body["data"]["status"].as_str(), body["data"]["sport"].as_str()), (Some("stopped"), Some("basketball")));
+    let status: String = sqlx::query_scalar("SELECT status::TEXT FROM markets WHERE id = $1").bind(stopped).fetch_one(&pool).await.unwrap();
+    assert_eq!(status, "active");
+
+    let resp = post(format!("/admin/markets/{}/simulation", stopped), serde_json::json!({"sport": "basketball"})).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri(&format!("/admin/markets/{}/simulation/stop", stopped))
+        .insert_header(("Authorization", bearer.clone())).set_json(serde_json::json!({})).to_request()).await;
+    assert_eq!(body["data"]["status"], "settled");
+    // a level basketball game is decided in overtime, so the final score always has a winner
+    assert_ne!(body["data"]["home_score"], body["data"]["away_score"]);
+    let (status, winning_option): (String, Option<i16>) = sqlx::query_as("SELECT status::TEXT, winning_option FROM markets WHERE id = $1")
+        .bind(stopped).fetch_one(&pool).await.unwrap();
+    assert_eq!((status.as_str(), winning_option.map(i64::from)), ("settled", body["data"]["winning_option"].as_i64()));
+    let actions: Vec<String> = sqlx::query_scalar("SELECT action FROM audit_logs WHERE resource = 'markets' AND resource_id = $1 AND actor_id = $2 ORDER BY id")
+        .bind(stopped).bind(admin_id).fetch_all(&pool).await.unwrap();
+    assert_eq!(actions, ["admin.simulation_start", "admin.simulation_stop", "admin.simulation_start", "admin.simulation_stop", "admin.market_settle"]);
+}
@@ -21,17 +21,19 @@ async fn test_create_order_with_audit_tx() {
     }).await.unwrap();
 
     let orepo = OrderRepository::new(pool.clone());
+    let last_audit: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM order_audits").fetch_one(&pool).await.unwrap();
     let order = orepo.create_with_audit(CreateOrderRequest {
         order_id: 700001,
         user_id: user.id,
         market_id: market.id,
-        amount: 1.0,
-        odds: 2.0,
+        amount: "1.0".parse().unwrap(),
+        odds: "2.0".parse().unwrap(),
         option: 0,
     }).await.unwrap();
 
-    let audit_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM order_audits WHERE order_id = $1")
+    let audit_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM order_audits WHERE order_id = $1 AND id > $2")
         .bind(order.order_id)
+        .bind(last_audit)
         .fetch_one(&pool)
         .await
         .unwrap();
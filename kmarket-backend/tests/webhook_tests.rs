@@ -0,0 +1,178 @@
+use std::sync::{Arc, Mutex};
+
+use actix_web::{http::StatusCode, test, web, App, HttpRequest, HttpResponse, HttpServer};
+use kmarket_backend::models::event::{DomainEvent, OutboxEvent};
+use kmarket_backend::routes::{admin_auth, admin_webhooks};
+use kmarket_backend::state::AppState;
+use kmarket_backend::utils::outbox::{self, EventSubscriber};
+use kmarket_backend::utils::webhooks::{self, WebhookSubscriber, WebhookWorker, MAX_ATTEMPTS};
+#[path = "common/helpers.rs"]
+mod helpers;
+
+/// (path, signature header, event header, body) of every request the stub received
+type Received = Arc<Mutex<Vec<(String, String, String, String)>>>;
+
+/// Local receiver: /ok answers 200, /fail 500, /flaky 500 on its first call and 200 afterwards
+async fn stub(req: HttpRequest, body: web::Bytes, received: web::Data<Received>) -> HttpResponse {
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+    let mut log = received.lock().unwrap();
+    let path = req.path().to_string();
+    let earlier = log.iter().filter(|(p, ..)| *p == path).count();
+    log.push((path.clone(), header(webhooks::HEADER_SIGNATURE), header(webhooks::HEADER_EVENT), String::from_utf8_lossy(&body).into_owned()));
+    match path.as_str() {
+        "/ok" => HttpResponse::Ok().finish(),
+        "/flaky" if earlier > 0 => HttpResponse::NoContent().finish(),
+        _ => HttpResponse::InternalServerError().body("receiver down"),
+    }
+}
+
+/// Enqueue a committed event and return it as the dispatcher would hand it over
+async fn emit(pool: &sqlx::PgPool, event: DomainEvent) -> OutboxEvent {
+    let id = outbox::enqueue(pool, &event).await.unwrap();
+    OutboxEvent { id, event, attempts: 1, created_at: chrono::Utc::now() }
+}
+
+/// (status, attempts, last_status_code) of the delivery of `event_id` to `subscription_id`
+async fn delivery(pool: &sqlx::PgPool, subscription_id: i64, event_id: i64) -> Option<(String, i32, Option<i32>)> {
+    sqlx::query_as("SELECT status, attempts, last_status_code FROM webhook_deliveries WHERE subscription_id = $1 AND outbox_event_id = $2")
+        .bind(subscription_id).bind(event_id).fetch_optional(pool).await.unwrap()
+}
+
+async fn drain(worker: &WebhookWorker) {
+    while worker.run_once().await.unwrap() > 0 {}
+}
+
+#[actix_rt::test]
+async fn test_signature_format() {
+    let body = br#"{"id":"evt_1"}"#;
+    let sig = webhooks::sign_payload("secret", 1_700_000_000, body);
+    assert_eq!(sig.len(), 64);
+    assert_eq!(webhooks::signature_header("secret", 1_700_000_000, body), format!("t=1700000000,v1={}", sig));
+    assert_ne!(sig, webhooks::sign_payload("secret", 1_700_000_001, body));
+    assert_ne!(sig, webhooks::sign_payload("other", 1_700_000_000, body));
+    assert_eq!(webhooks::retry_delay_secs(1), 30);
+    assert_eq!(webhooks::retry_delay_secs(3), 120);
+    assert_eq!(webhooks::retry_delay_secs(MAX_ATTEMPTS), 3600);
+}
+
+#[actix_rt::test]
+async fn test_webhook_delivery_retry_and_dead_letter() {
+    let Some(pool) = helpers::maybe_init_test_db().await else { return; };
+    use argon2::{Argon2, password_hash::{SaltString, PasswordHasher}};
+    let suffix = chrono::Utc::now().timestamp_micros();
+
+    let received: Received = Arc::default();
+    let stub_data = web::Data::new(received.clone());
+    let server = HttpServer::new(move || App::new().app_data(stub_data.clone()).default_service(web::to(stub)))
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+    let base = format!("http://{}", server.addrs()[0]);
+    let server = server.run();
+    let server_handle = server.handle();
+    actix_rt::spawn(server);
+
+    let email = format!("hooks{}@kmarket.local", suffix);
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    let hash = Argon2::default().hash_password(b"hookspass", &salt).unwrap().to_string();
+    sqlx::query("INSERT INTO admin_users (email, password_hash, salt, status, role) VALUES ($1, $2, $3, 'active', 'admin')")
+        .bind(&email).bind(hash).bind(salt.to_string()).execute(&pool).await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::from_pool(pool.clone())))
+            .route("/login", web::post().to(admin_auth::login))
+            .route("/admin/webhooks", web::post().to(admin_webhooks::create_subscription))
+            .route("/admin/webhooks/{id}", web::delete().to(admin_webhooks::delete_subscription))
+            .route("/admin/webhooks/{id}/deliveries", web::get().to(admin_webhooks::list_deliveries))
+            .route("/admin/webhooks/{id}/deliveries/{delivery_id}/retry", web::post().to(admin_webhooks::retry_delivery))
+    ).await;
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, test::TestRequest::post().uri("/login")
+        .set_json(serde_json::json!({"email": email, "password": "hookspass"})).to_request()).await;
+    let bearer = format!("Bearer {}", resp["data"]["token"].as_str().unwrap());
+
+    let partner_keys: Vec<i64> = sqlx::query_scalar("INSERT INTO api_keys (key_id, secret, name) VALUES ($1, 's', 'p1'), ($2, 's', 'p2') RETURNING id")
+        .bind(format!("pk_a{}", suffix)).bind(format!("pk_b{}", suffix)).fetch_all(&pool).await.unwrap();
+    let subscribe = |url: String, types: &[&str], api_key_id: Option<i64>| {
+        let req = test::TestRequest::post().uri("/admin/webhooks").insert_header(("Authorization", bearer.clone()))
+            .set_json(serde_json::json!({"url": url, "event_types": types, "api_key_id": api_key_id})).to_request();
+        test::call_service(&app, req)
+    };
+    let resp = subscribe(format!("{}/ok", base), &["market.exploded"], None).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = subscribe("ftp://example.com".into(), &["market.opened"], None).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let mut subs = Vec::new();
+    for (path, types, key) in [("flaky", ["market.opened"], None), ("fail", ["market.opened"], None), ("ok", ["order.placed"], Some(partner_keys[0])), ("ok", ["order.placed"], Some(partner_keys[1]))] {
+        let resp = subscribe(format!("{}/{}", base, path), &types, key).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        subs.push((body["data"]["id"].as_i64().unwrap(), body["data"]["secret"].as_str().unwrap().to_string()));
+    }
+    let [(flaky, flaky_secret), (fail, _), (partner_a, _), (partner_b, _)] = &subs[..] else { unreachable!() };
+
+    // market opens -> one delivery per market.opened subscription, however often the outbox redelivers
+    let market_id = suffix % 1_000_000_000;
+    let market_pk: i64 = sqlx::query_scalar("INSERT INTO markets (market_id, title, option_a, option_b, start_time, end_time, status) VALUES ($1, 'Hooks', 'A', 'B', NOW(), NOW() + INTERVAL '1 day', 'active') RETURNING id")
+        .bind(market_id).fetch_one(&pool).await.unwrap();
+    let opened = emit(&pool, DomainEvent::MarketStatusChanged { id: market_pk, status: "active".into() }).await;
+    let subscriber = WebhookSubscriber::new(pool.clone());
+    subscriber.handle(&opened).await.unwrap();
+    subscriber.handle(&opened).await.unwrap();
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM webhook_deliveries WHERE outbox_event_id = $1 AND subscription_id = ANY($2)")
+        .bind(opened.id).bind(vec![*flaky, *fail, *partner_a, *partner_b]).fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 2);
+
+    // first attempt: both receivers fail -> retried later
+    let worker = WebhookWorker::new(pool.clone());
+    drain(&worker).await;
+    assert_eq!(delivery(&pool, *flaky, opened.id).await, Some(("pending".into(), 1, Some(500))));
+    assert_eq!(delivery(&pool, *fail, opened.id).await, Some(("pending".into(), 1, Some(500))));
+
+    // the payload is signed with the subscription secret
+    let (_, signature, event, body) = received.lock().unwrap().iter().find(|(p, ..)| p == "/flaky").cloned().unwrap();
+    assert_eq!(event, "market.opened");
+    let (t, v1) = signature.strip_prefix("t=").and_then(|s| s.split_once(",v1=")).unwrap();
+    assert_eq!(v1, webhooks::sign_payload(flaky_secret, t.parse().unwrap(), body.as_bytes()));
+    let payload: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(payload["id"], format!("evt_{}", opened.id));
+    assert_eq!(payload["type"], "market.opened");
+    assert_eq!(payload["data"]["market_id"], market_id);
+
+    // once due again the flaky receiver accepts it
+    sqlx::query("UPDATE webhook_deliveries SET next_attempt_at = NOW() WHERE subscription_id = $1").bind(flaky).execute(&pool).await.unwrap();
+    drain(&worker).await;
+    assert_eq!(delivery(&pool, *flaky, opened.id).await, Some(("delivered".into(), 2, Some(204))));
+
+    // failing the last allowed attempt dead-letters the delivery
+    sqlx::query("UPDATE webhook_deliveries SET attempts = $2, next_attempt_at = NOW() WHERE subscription_id = $1").bind(fail).bind(MAX_ATTEMPTS - 1).execute(&pool).await.unwrap();
+    drain(&worker).await;
+    assert_eq!(delivery(&pool, *fail, opened.id).await, Some(("dead".into(), MAX_ATTEMPTS, Some(500))));
+
+    // delivery log, then a manual retry requeues it with a fresh budget
+    let req = test::TestRequest::get().uri(&format!("/admin/webhooks/{}/deliveries?status=dead", fail)).insert_header(("Authorization", bearer.clone())).to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let items = resp["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["last_error"], "HTTP 500: receiver down");
+    let delivery_id = items[0]["id"].as_i64().unwrap();
+    let req = test::TestRequest::post().uri(&format!("/admin/webhooks/{}/deliveries/{}/retry", fail, delivery_id)).insert_header(("Authorization", bearer.clone())).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    assert_eq!(delivery(&pool, *fail, opened.id).await, Some(("pending".into(), 0, Some(500))));
+
+    // partner-scoped order webhooks only fire for that partner's referred users
+    let user_id: i64 = sqlx::query_scalar("INSERT INTO users (address, referrer_api_key_id) VALUES ($1, $2) RETURNING id")
+        .bind(format!("0xhook{}", suffix)).bind(partner_keys[0]).fetch_one(&pool).await.unwrap();
+    let order_pk: i64 = sqlx::query_scalar("INSERT INTO orders (order_id, user_id, market_id, amount, odds, option) VALUES ($1, $2, $3, 5, 1.8, 0) RETURNING id")
+        .bind(suffix).bind(user_id).bind(market_pk).fetch_one(&pool).await.unwrap();
+    let placed = emit(&pool, DomainEvent::OrderPlaced { id: order_pk, market_id: market_pk, user_id }).await;
+    subscriber.handle(&placed).await.unwrap();
+    assert_eq!(delivery(&pool, *partner_a, placed.id).await.map(|d| d.0), Some("pending".into()));
+    assert_eq!(delivery(&pool, *partner_b, placed.id).await, None);
+
+    for (id, _) in &subs {
+        let req = test::TestRequest::delete().uri(&format!("/admin/webhooks/{}", id)).insert_header(("Authorization", bearer.clone())).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    }
+    server_handle.stop(true).await;
+}